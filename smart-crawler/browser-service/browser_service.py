@@ -37,6 +37,9 @@ FIREFOX_DRIVER_PATH = os.path.join(DRIVER_DIR, 'geckodriver')
 SCRIPTS_DIR = os.environ.get('SCRIPTS_DIR', os.path.join(os.path.dirname(__file__), 'scripts'))
 os.makedirs(SCRIPTS_DIR, exist_ok=True)
 
+# axe-core build injected for accessibility audits
+AXE_CORE_URL = os.environ.get('AXE_CORE_URL', 'https://cdnjs.cloudflare.com/ajax/libs/axe-core/4.8.2/axe.min.js')
+
 # Initialize pools
 driver_pools = {
     'chrome': [],
@@ -80,6 +83,10 @@ class BrowserUtils:
             if os.environ.get('HEADLESS', 'true').lower() == 'true':
                 options.add_argument('--headless')
             
+            # Keep browser console logs so failure traces can include them, and
+            # network events so response headers can be read back
+            options.set_capability('goog:loggingPrefs', {'browser': 'ALL', 'performance': 'ALL'})
+            
             # Additional privacy/fingerprinting prevention
             options.add_argument('--disable-dev-shm-usage')
             options.add_argument('--no-sandbox')
@@ -275,6 +282,272 @@ class BrowserUtils:
             except:
                 pass
         return links
+    
+    @staticmethod
+    def run_accessibility_audit(driver):
+        """Inject axe-core into the page and collect its violations"""
+        driver.set_script_timeout(60)
+        return driver.execute_async_script("""
+            var axeUrl = arguments[0];
+            var done = arguments[arguments.length - 1];
+            function run() {
+                axe.run(document, { resultTypes: ['violations'] }).then(function(results) {
+                    done({
+                        'violations': results.violations.map(function(v) {
+                            return {
+                                'id': v.id,
+                                'impact': v.impact,
+                                'description': v.description,
+                                'help_url': v.helpUrl,
+                                'nodes': v.nodes.map(function(n) { return n.target.join(' '); })
+                            };
+                        })
+                    });
+                }).catch(function(err) {
+                    done({ 'error': String(err) });
+                });
+            }
+            if (window.axe) {
+                run();
+                return;
+            }
+            var script = document.createElement('script');
+            script.src = axeUrl;
+            script.onload = run;
+            script.onerror = function() { done({ 'error': 'Failed to load axe-core' }); };
+            document.head.appendChild(script);
+        """, AXE_CORE_URL)
+    
+    @staticmethod
+    def read_network_log(driver):
+        """Read the network events logged since the last read (Chrome only)"""
+        try:
+            entries = driver.get_log('performance')
+        except Exception:
+            return None
+        
+        messages = []
+        for entry in entries:
+            try:
+                messages.append(json.loads(entry['message'])['message'])
+            except (KeyError, ValueError):
+                continue
+        return messages
+    
+    @staticmethod
+    def get_document_response(driver, url, network_log):
+        """Find the main document response in the network log"""
+        if network_log is None:
+            return None
+        
+        response = None
+        for message in network_log:
+            if message.get('method') != 'Network.responseReceived':
+                continue
+            
+            params = message.get('params', {})
+            if params.get('type') != 'Document':
+                continue
+            
+            # Keep the last document response for the page, which follows redirects
+            candidate = params.get('response', {})
+            if candidate.get('url') in (url, driver.current_url) or response is None:
+                response = {
+                    'url': candidate.get('url'),
+                    'status': candidate.get('status'),
+                    'mime_type': candidate.get('mimeType'),
+                    'headers': {k.lower(): v for k, v in candidate.get('headers', {}).items()},
+                    'request_id': params.get('requestId')
+                }
+        
+        return response
+    
+    @staticmethod
+    def get_document_text(driver, document_response):
+        """Body of a document that isn't HTML as Chrome received it, rather than
+        the viewer page Chrome renders it in. None for binary documents."""
+        if not document_response or not document_response.get('request_id'):
+            return None
+        
+        try:
+            body = driver.execute_cdp_cmd('Network.getResponseBody', {'requestId': document_response['request_id']})
+        except Exception as e:
+            logger.debug(f"Failed to read document body: {e}")
+            return None
+        
+        if body.get('base64Encoded'):
+            return None
+        return body.get('body')
+    
+    @staticmethod
+    def get_redirect_chain(network_log):
+        """List the redirects followed to load the page, each with the URL that
+        redirected and its status"""
+        chain = []
+        main_frame = None
+        for message in network_log or []:
+            if message.get('method') != 'Network.requestWillBeSent':
+                continue
+            
+            params = message.get('params', {})
+            if params.get('type') != 'Document':
+                continue
+            
+            # The first document request is the page's, later ones in other
+            # frames are iframes
+            if main_frame is None:
+                main_frame = params.get('frameId')
+            if params.get('frameId') != main_frame:
+                continue
+            
+            redirect = params.get('redirectResponse')
+            if redirect and redirect.get('url') and redirect.get('status'):
+                chain.append({'url': redirect['url'], 'status': int(redirect['status'])})
+        
+        return chain
+    
+    @staticmethod
+    def collect_resources(driver):
+        """List the sub-resources loaded by the page"""
+        return driver.execute_script("""
+            return performance.getEntriesByType('resource').map(function(entry) {
+                return { 'url': entry.name, 'type': entry.initiatorType };
+            });
+        """)
+    
+    @staticmethod
+    def collect_brand_assets(driver):
+        """Find the favicon and og:image URLs declared by the page"""
+        return driver.execute_script("""
+            var favicons = Array.prototype.map.call(
+                document.querySelectorAll('link[rel~="icon"], link[rel="apple-touch-icon"]'),
+                function(link) { return link.href; }).filter(Boolean);
+            if (favicons.length === 0) {
+                favicons.push(location.origin + '/favicon.ico');
+            }
+            
+            var ogImage = null;
+            var meta = document.querySelector('meta[property="og:image"], meta[name="og:image"]');
+            if (meta && meta.content) {
+                try {
+                    ogImage = new URL(meta.content, location.href).href;
+                } catch (e) {}
+            }
+            
+            return { 'favicons': favicons, 'og_image': ogImage };
+        """)
+    
+    @staticmethod
+    def collect_security_info(driver, response):
+        """Collect security-relevant response headers and cookie flags"""
+        headers = (response or {}).get('headers', {})
+        security_headers = [
+            'content-security-policy',
+            'strict-transport-security',
+            'x-frame-options',
+            'x-content-type-options',
+            'referrer-policy',
+            'permissions-policy',
+        ]
+        
+        cookies = []
+        for cookie in driver.get_cookies():
+            cookies.append({
+                'name': cookie.get('name'),
+                'secure': cookie.get('secure', False),
+                'http_only': cookie.get('httpOnly', False),
+                'same_site': cookie.get('sameSite')
+            })
+        
+        return {
+            'headers_available': response is not None,
+            'headers': {name: headers.get(name) for name in security_headers},
+            'cookies': cookies
+        }
+    
+    @staticmethod
+    def detect_captcha(driver):
+        """Detect an unsolved reCAPTCHA or hCaptcha widget on the current page"""
+        return driver.execute_script("""
+            var solved = Array.prototype.some.call(
+                document.querySelectorAll('textarea[name="g-recaptcha-response"], textarea[name="h-captcha-response"]'),
+                function(field) { return field.value.length > 0; });
+            if (solved) {
+                return null;
+            }
+            var recaptcha = document.querySelector('.g-recaptcha[data-sitekey], [data-sitekey].g-recaptcha');
+            if (recaptcha) {
+                return {
+                    'captcha_type': 'recaptcha_v2',
+                    'site_key': recaptcha.getAttribute('data-sitekey'),
+                    'page_url': window.location.href
+                };
+            }
+            var hcaptcha = document.querySelector('.h-captcha[data-sitekey]');
+            if (hcaptcha) {
+                return {
+                    'captcha_type': 'hcaptcha',
+                    'site_key': hcaptcha.getAttribute('data-sitekey'),
+                    'page_url': window.location.href
+                };
+            }
+            return null;
+        """)
+    
+    @staticmethod
+    def inject_captcha_token(driver, token):
+        """Inject a solved captcha token into the page and submit the challenge"""
+        driver.execute_script("""
+            var token = arguments[0];
+            var fields = document.querySelectorAll(
+                'textarea[name="g-recaptcha-response"], textarea[name="h-captcha-response"]');
+            fields.forEach(function(field) {
+                field.style.display = 'block';
+                field.value = token;
+            });
+            var widget = document.querySelector('[data-sitekey][data-callback]');
+            if (widget && typeof window[widget.getAttribute('data-callback')] === 'function') {
+                window[widget.getAttribute('data-callback')](token);
+                return;
+            }
+            if (fields.length > 0 && fields[0].form) {
+                fields[0].form.submit();
+            }
+        """, token)
+
+
+def capture_trace(driver, fingerprint):
+    """Capture a debug bundle for a failed crawl"""
+    trace = {
+        'screenshot': None,
+        'html': None,
+        'console_logs': [],
+        'proxy': fingerprint.get('proxy')
+    }
+    
+    if driver is None:
+        return trace
+    
+    try:
+        trace['screenshot'] = driver.get_screenshot_as_base64()
+    except Exception as e:
+        logger.warning(f"Failed to capture screenshot for trace: {e}")
+    
+    try:
+        trace['html'] = driver.page_source
+    except Exception as e:
+        logger.warning(f"Failed to capture HTML for trace: {e}")
+    
+    try:
+        trace['console_logs'] = [
+            f"{entry.get('level')}: {entry.get('message')}"
+            for entry in driver.get_log('browser')
+        ]
+    except Exception:
+        # Not every driver exposes console logs
+        pass
+    
+    return trace
 
 
 def get_driver(browser_type, fingerprint):
@@ -323,7 +596,7 @@ def load_custom_script(domain):
     return None
 
 
-def default_crawl(driver, url, behavior):
+def default_crawl(driver, url, behavior, captcha_token=None):
     """Default crawling behavior when no custom script exists"""
     # Navigate to URL
     driver.get(url)
@@ -332,6 +605,11 @@ def default_crawl(driver, url, behavior):
     wait_time = random.uniform(1, 3)
     time.sleep(wait_time)
     
+    # Submit a solved captcha if the caller provided one
+    if captcha_token:
+        BrowserUtils.inject_captcha_token(driver, captcha_token)
+        BrowserUtils.random_wait(2.0, 4.0)
+    
     # Get page title and metrics
     title = driver.title
     metrics = BrowserUtils.get_page_metrics(driver)
@@ -392,6 +670,12 @@ def crawl():
         # Set page load timeout
         driver.set_page_load_timeout(30)
         
+        # Drop network events left over from the previous page on a pooled driver
+        try:
+            driver.get_log('performance')
+        except Exception:
+            pass
+        
         # Try to load a custom script for this domain
         custom_script = load_custom_script(domain)
         
@@ -402,7 +686,55 @@ def crawl():
         else:
             # Use default crawling behavior
             logger.info(f"Using default crawl behavior for {domain}")
-            result = default_crawl(driver, url, behavior)
+            result = default_crawl(driver, url, behavior, data.get('captcha_token'))
+        
+        # Report any captcha still blocking the page
+        captcha = BrowserUtils.detect_captcha(driver)
+        
+        # Run the accessibility audit if requested
+        accessibility = None
+        if data.get('accessibility_audit', False) and not captcha:
+            try:
+                accessibility = BrowserUtils.run_accessibility_audit(driver)
+            except Exception as e:
+                logger.warning(f"Accessibility audit failed for {url}: {e}")
+                accessibility = {'error': str(e)}
+        
+        # Follow the redirects of the page from the network log, read only once
+        network_log = BrowserUtils.read_network_log(driver)
+        redirects = BrowserUtils.get_redirect_chain(network_log)
+        document_response = BrowserUtils.get_document_response(driver, url, network_log)
+        
+        # Record security headers if requested
+        security = None
+        if data.get('security_audit', False):
+            security = BrowserUtils.collect_security_info(driver, document_response)
+        
+        # Inventory loaded resources if requested
+        resources = None
+        if data.get('resource_inventory', False):
+            try:
+                resources = BrowserUtils.collect_resources(driver)
+            except Exception as e:
+                logger.warning(f"Resource inventory failed for {url}: {e}")
+        
+        # Report favicon and og:image URLs if requested
+        brand_assets = None
+        if data.get('brand_assets', False):
+            try:
+                brand_assets = BrowserUtils.collect_brand_assets(driver)
+            except Exception as e:
+                logger.warning(f"Brand asset lookup failed for {url}: {e}")
+        
+        final_url = driver.current_url
+        status_code = (document_response or {}).get('status')
+        mime_type = (document_response or {}).get('mime_type')
+        
+        # Documents other than HTML are sent as the text Chrome received, and
+        # binary ones without content
+        content = result.get('content', '')
+        if mime_type and mime_type not in ('text/html', 'application/xhtml+xml'):
+            content = BrowserUtils.get_document_text(driver, document_response) or ''
         
         # Take screenshot if requested
         screenshot = None
@@ -418,10 +750,20 @@ def crawl():
             'success': True,
             'url': url,
             'title': result.get('title', ''),
-            'content': result.get('content', ''),
+            'content': content,
             'links': result.get('links', []),
             'screenshot': screenshot,
-            'metrics': result.get('metrics', {})
+            'metrics': result.get('metrics', {}),
+            'captcha': captcha,
+            'accessibility': accessibility,
+            'security': security,
+            'resources': resources,
+            'brand_assets': brand_assets,
+            'redirects': redirects,
+            'final_url': final_url,
+            'headers': (document_response or {}).get('headers'),
+            'status_code': int(status_code) if status_code else None,
+            'mime_type': mime_type
         }
         
         return jsonify(response)
@@ -435,7 +777,8 @@ def crawl():
             'url': url,
             'title': '',
             'content': '',
-            'links': []
+            'links': [],
+            'trace': capture_trace(driver, fingerprint) if data.get('capture_trace') else None
         })
         
     except Exception as e:
@@ -447,7 +790,8 @@ def crawl():
             'url': url,
             'title': '',
             'content': '',
-            'links': []
+            'links': [],
+            'trace': capture_trace(driver, fingerprint) if data.get('capture_trace') else None
         })
         
     finally:
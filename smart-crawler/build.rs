@@ -0,0 +1,5 @@
+fn main() {
+    // The gRPC service is only generated when the grpc feature is enabled, it needs protoc
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/crawler.proto").expect("Failed to compile proto/crawler.proto");
+}
\ No newline at end of file
@@ -0,0 +1,402 @@
+use anyhow::Context;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::api::{ApiError, ApiResult};
+use crate::cli::config::{CrawlerConfig, JobPriority};
+use crate::crawler::controller::CrawlerController;
+use crate::crawler::task::{RecordPreview, TaskResult};
+use crate::storage::export::{ExportCompression, ExportFormat, ExportOptions, ExportTarget};
+use crate::storage::naming::NamingRules;
+use crate::storage::processed::{PageVersion, RowFilter};
+use crate::storage::raw::{JobLimits, JobStatus};
+use crate::storage::scheduler_state::DomainStats;
+
+/// Pages returned by one request when no limit is given
+const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// Most pages returned by one request
+const MAX_PAGE_LIMIT: usize = 1000;
+
+/// Record previews returned when no limit is given
+const DEFAULT_PREVIEW_LIMIT: usize = 5;
+
+type Controller = State<Arc<CrawlerController>>;
+
+/// Body of `POST /jobs`
+#[derive(Debug, Deserialize)]
+pub struct StartJobRequest {
+    pub url: String,
+    #[serde(default)]
+    pub more_urls: Vec<String>, // Other entry points of the site, crawled in the same job
+    #[serde(default = "default_profile")]
+    pub profile: String,
+    pub depth: Option<u32>,
+    pub limit: Option<u32>,
+    pub priority: Option<JobPriority>,
+}
+
+fn default_profile() -> String {
+    "general".to_string()
+}
+
+#[derive(Debug, Serialize)]
+pub struct StartJobResponse {
+    pub job_id: String,
+}
+
+/// Start a job with a site profile, overriding its depth, page limit and priority
+pub async fn start_job(Json(request): Json<StartJobRequest>) -> ApiResult<(StatusCode, Json<StartJobResponse>)> {
+    // Profiles are files under config/sites, their names must not reach other paths
+    if !NamingRules::FILESYSTEM.accepts(&request.profile) {
+        return Err(ApiError::bad_request(format!("Invalid profile name '{}'", request.profile)));
+    }
+    let mut config = CrawlerConfig::load_profile(&request.profile)
+        .map_err(ApiError::bad_request)?;
+    if let Some(depth) = request.depth {
+        config.crawler.max_depth = depth;
+    }
+    if let Some(limit) = request.limit {
+        config.crawler.max_pages = limit;
+    }
+    if let Some(priority) = request.priority {
+        config.crawler.priority = priority;
+    }
+    
+    let controller = CrawlerController::new(config).await?;
+    let mut seed_urls = vec![request.url.clone()];
+    seed_urls.extend(request.more_urls);
+    let job_id = controller.start_job(seed_urls).await?;
+    info!("Started job {} on {} with profile {}", job_id, request.url, request.profile);
+    
+    Ok((StatusCode::CREATED, Json(StartJobResponse { job_id })))
+}
+
+/// Query of `GET /jobs`
+#[derive(Debug, Deserialize)]
+pub struct ListJobsQuery {
+    pub state: Option<String>,
+}
+
+/// List jobs, most recently started first
+pub async fn list_jobs(State(controller): Controller, Query(query): Query<ListJobsQuery>) -> ApiResult<Json<Vec<JobStatus>>> {
+    let mut jobs = controller.list_jobs().await?;
+    if let Some(state) = &query.state {
+        jobs.retain(|job| job.state == *state);
+    }
+    jobs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    
+    Ok(Json(jobs))
+}
+
+pub async fn job_status(State(controller): Controller, Path(job_id): Path<String>) -> ApiResult<Json<JobStatus>> {
+    Ok(Json(controller.get_job_status(&job_id).await?))
+}
+
+pub async fn pause_job(State(controller): Controller, Path(job_id): Path<String>) -> ApiResult<Json<JobStatus>> {
+    controller.pause_job(&job_id).await?;
+    Ok(Json(controller.get_job_status(&job_id).await?))
+}
+
+pub async fn resume_job(State(controller): Controller, Path(job_id): Path<String>) -> ApiResult<Json<JobStatus>> {
+    controller.resume_job(&job_id).await?;
+    Ok(Json(controller.get_job_status(&job_id).await?))
+}
+
+pub async fn cancel_job(State(controller): Controller, Path(job_id): Path<String>) -> ApiResult<Json<JobStatus>> {
+    controller.cancel_job(&job_id).await?;
+    Ok(Json(controller.get_job_status(&job_id).await?))
+}
+
+/// Body of `PATCH /jobs/{job_id}/limits`, limits left out are kept
+#[derive(Debug, Deserialize)]
+pub struct UpdateLimitsRequest {
+    pub max_pages: Option<u32>,
+    pub max_depth: Option<u32>,
+    pub concurrency: Option<usize>,
+    pub weight: Option<u32>,
+    pub priority: Option<JobPriority>,
+}
+
+/// Change the limits of a running job, like `crawler jobs update`
+pub async fn update_job_limits(
+    State(controller): Controller,
+    Path(job_id): Path<String>,
+    Json(request): Json<UpdateLimitsRequest>,
+) -> ApiResult<Json<JobLimits>> {
+    check_concurrency(request.concurrency)?;
+    let limits = controller.update_job_limits(
+        &job_id,
+        request.max_pages,
+        request.max_depth,
+        request.concurrency,
+        request.weight,
+        request.priority,
+    ).await?;
+    info!("Updated limits of job {}: {:?}", job_id, limits);
+    
+    Ok(Json(limits))
+}
+
+fn check_concurrency(concurrency: Option<usize>) -> ApiResult<()> {
+    match concurrency {
+        Some(concurrency) if !(1..=JobLimits::MAX_CONCURRENCY).contains(&concurrency) => {
+            Err(ApiError::bad_request(format!("Concurrency must be between 1 and {}", JobLimits::MAX_CONCURRENCY)))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Query of `GET /jobs/{job_id}/pages`
+#[derive(Debug, Deserialize)]
+pub struct ListPagesQuery {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub include_url: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListPagesResponse {
+    /// Pages matching the query, before paging
+    pub total: usize,
+    pub offset: usize,
+    pub pages: Vec<TaskResult>,
+}
+
+/// Page results of a job, ordered by URL so pages stay stable across requests
+pub async fn list_pages(
+    State(controller): Controller,
+    Path(job_id): Path<String>,
+    Query(query): Query<ListPagesQuery>,
+) -> ApiResult<Json<ListPagesResponse>> {
+    let filter = RowFilter::parse(query.include_url.as_deref(), query.since.as_deref(), query.until.as_deref())
+        .map_err(ApiError::bad_request)?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    
+    // Fail on unknown jobs rather than returning an empty list
+    controller.get_job_status(&job_id).await?;
+    
+    let mut pages = controller.list_page_results(&job_id).await?;
+    pages.retain(|page| filter.matches(&page.url, page.crawled_at));
+    pages.sort_by(|a, b| a.url.cmp(&b.url));
+    
+    let total = pages.len();
+    let pages = pages.into_iter().skip(query.offset).take(limit).collect();
+    
+    Ok(Json(ListPagesResponse { total, offset: query.offset, pages }))
+}
+
+/// Query of `GET /jobs/{job_id}/preview`
+#[derive(Debug, Deserialize)]
+pub struct PreviewQuery {
+    pub limit: Option<usize>,
+}
+
+/// Fields of the pages a job crawled last, newest first
+pub async fn preview_records(
+    State(controller): Controller,
+    Path(job_id): Path<String>,
+    Query(query): Query<PreviewQuery>,
+) -> ApiResult<Json<Vec<RecordPreview>>> {
+    controller.get_job_status(&job_id).await?;
+    
+    Ok(Json(controller.recent_records(&job_id, query.limit.unwrap_or(DEFAULT_PREVIEW_LIMIT)).await?))
+}
+
+/// Query of `GET /pages/history`
+#[derive(Debug, Deserialize)]
+pub struct PageHistoryQuery {
+    pub url: String,
+    pub limit: Option<usize>,
+}
+
+/// Stored versions of a URL across jobs, newest first
+pub async fn page_history(State(controller): Controller, Query(query): Query<PageHistoryQuery>) -> ApiResult<Json<Vec<PageVersion>>> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    
+    Ok(Json(controller.page_history(&query.url, limit).await?))
+}
+
+/// Statistics of the domains requested recently across jobs, most requested first
+pub async fn list_domain_stats(State(controller): Controller) -> ApiResult<Json<Vec<DomainStats>>> {
+    Ok(Json(controller.domain_stats().await?))
+}
+
+/// Statistics of a domain across jobs
+pub async fn domain_stats(State(controller): Controller, Path(domain): Path<String>) -> ApiResult<Json<DomainStats>> {
+    let domain = domain.to_lowercase();
+    controller.domain_stats().await?
+        .into_iter()
+        .find(|stats| stats.domain == domain)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("No statistics for domain {}", domain)))
+}
+
+/// Body of `POST /jobs/{job_id}/exports`, the same options as the export command
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    /// Export formats, comma-separated
+    pub format: String,
+    /// Output path on the server, relative to the export directory, defaults to the job ID
+    pub output: Option<String>,
+    #[serde(default)]
+    pub resume: bool,
+    pub columns: Option<Vec<String>>,
+    pub include_url: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub compress: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedFile {
+    pub format: String,
+    pub path: String,
+    pub records: usize,
+}
+
+/// Export a job to files on the server
+pub async fn export_job(
+    State(controller): Controller,
+    Path(job_id): Path<String>,
+    Json(request): Json<ExportRequest>,
+) -> ApiResult<Json<Vec<ExportedFile>>> {
+    Ok(Json(run_export(&controller, &job_id, request).await?))
+}
+
+/// Check an export request and write its files, shared with the gRPC service
+pub async fn run_export(controller: &CrawlerController, job_id: &str, request: ExportRequest) -> ApiResult<Vec<ExportedFile>> {
+    let formats = ExportFormat::parse_list(&request.format).map_err(ApiError::bad_request)?;
+    let filter = RowFilter::parse(request.include_url.as_deref(), request.since.as_deref(), request.until.as_deref())
+        .map_err(ApiError::bad_request)?;
+    let compression = request.compress.as_deref()
+        .map(ExportCompression::parse)
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    if compression.is_some() && formats.contains(&ExportFormat::Parquet) {
+        return Err(ApiError::bad_request("Parquet exports are compressed internally, export them separately without compress"));
+    }
+    let targets = export_targets(controller.export_dir(), job_id, &formats, request.output.as_deref(), compression)?;
+    
+    // Fail on unknown jobs before creating any file
+    controller.get_job_status(job_id).await?;
+    
+    for parent in targets.iter().filter_map(|target| target.output_path.parent()) {
+        tokio::fs::create_dir_all(parent).await
+            .context(format!("Failed to create export directory {}", parent.display()))?;
+    }
+    let options = ExportOptions {
+        resume: request.resume,
+        data_columns: request.columns,
+        filter,
+        compression,
+        progress: None,
+        threads: 0,
+    };
+    let counts = controller.export_job_data(job_id, &targets, &options).await
+        .context(format!("Failed to export job {}", job_id))?;
+    
+    let exported = targets.iter()
+        .zip(counts)
+        .map(|(target, records)| ExportedFile {
+            format: target.format.extension().to_string(),
+            path: target.output_path.display().to_string(),
+            records,
+        })
+        .collect();
+    
+    Ok(exported)
+}
+
+/// Files of an export, kept inside the export directory
+///
+/// Outputs are relative paths without `..`, and the default name is the job
+/// ID made safe for a file name.
+fn export_targets(
+    export_dir: &std::path::Path,
+    job_id: &str,
+    formats: &[ExportFormat],
+    output: Option<&str>,
+    compression: Option<ExportCompression>,
+) -> ApiResult<Vec<ExportTarget>> {
+    if output == Some("-") {
+        return Err(ApiError::bad_request("The API only exports to files"));
+    }
+    if let Some(output) = output {
+        let relative = !output.is_empty()
+            && PathBuf::from(output).components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !relative {
+            return Err(ApiError::bad_request(format!("Export output '{}' must be a relative path inside the export directory", output)));
+        }
+    }
+    
+    let name = NamingRules::FILESYSTEM.job_identifier("", job_id, "");
+    let mut targets = ExportTarget::for_formats(&name, formats, output, compression);
+    for target in &mut targets {
+        target.output_path = export_dir.join(&target.output_path);
+    }
+    
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_start_job_rejects_profile_paths() {
+        for profile in ["../../etc/passwd", "/etc/passwd", "sites/general", ""] {
+            let request = StartJobRequest {
+                url: "https://example.com".to_string(),
+                more_urls: vec![],
+                profile: profile.to_string(),
+                depth: None,
+                limit: None,
+                priority: None,
+            };
+            let error = start_job(Json(request)).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::BAD_REQUEST, "{}", profile);
+            assert!(error.message().starts_with("Invalid profile name"));
+        }
+    }
+    
+    #[test]
+    fn test_check_concurrency() {
+        assert!(check_concurrency(None).is_ok());
+        assert!(check_concurrency(Some(1)).is_ok());
+        assert!(check_concurrency(Some(JobLimits::MAX_CONCURRENCY)).is_ok());
+        for concurrency in [0, JobLimits::MAX_CONCURRENCY + 1] {
+            assert_eq!(check_concurrency(Some(concurrency)).unwrap_err().status(), StatusCode::BAD_REQUEST);
+        }
+    }
+    
+    #[test]
+    fn test_export_targets() {
+        let export_dir = std::path::Path::new("/srv/exports");
+        let formats = [ExportFormat::Json, ExportFormat::Csv];
+        
+        let targets = export_targets(export_dir, "job-1", &formats, None, None).unwrap();
+        let paths: Vec<_> = targets.iter().map(|target| target.output_path.clone()).collect();
+        assert_eq!(paths, [PathBuf::from("/srv/exports/job-1.json"), PathBuf::from("/srv/exports/job-1.csv")]);
+        
+        let targets = export_targets(export_dir, "job-1", &formats[..1], Some("daily/shop.json"), None).unwrap();
+        assert_eq!(targets[0].output_path, PathBuf::from("/srv/exports/daily/shop.json"));
+        
+        // Job IDs don't escape the export directory either
+        let targets = export_targets(export_dir, "../../etc/cron.d/job", &formats[..1], None, None).unwrap();
+        assert_eq!(targets[0].output_path.parent(), Some(export_dir));
+        
+        for output in ["/etc/cron.d/job", "../job.json", "daily/../../job.json", "", "-"] {
+            let error = export_targets(export_dir, "job-1", &formats, Some(output), None).unwrap_err();
+            assert_eq!(error.status(), StatusCode::BAD_REQUEST, "{}", output);
+        }
+    }
+}
\ No newline at end of file
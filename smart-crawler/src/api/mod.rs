@@ -0,0 +1,122 @@
+pub mod handlers;
+
+use anyhow::{Result, Context};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
+use std::fmt::Display;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::crawler::controller::CrawlerController;
+use crate::utils::shutdown;
+
+/// Build the API routes around a shared controller
+pub fn router(controller: Arc<CrawlerController>) -> Router {
+    Router::new()
+        .route("/jobs", get(handlers::list_jobs).post(handlers::start_job))
+        .route("/jobs/:job_id", get(handlers::job_status))
+        .route("/jobs/:job_id/pause", post(handlers::pause_job))
+        .route("/jobs/:job_id/resume", post(handlers::resume_job))
+        .route("/jobs/:job_id/cancel", post(handlers::cancel_job))
+        .route("/jobs/:job_id/limits", patch(handlers::update_job_limits))
+        .route("/jobs/:job_id/pages", get(handlers::list_pages))
+        .route("/jobs/:job_id/preview", get(handlers::preview_records))
+        .route("/jobs/:job_id/exports", post(handlers::export_job))
+        .route("/pages/history", get(handlers::page_history))
+        .route("/domains", get(handlers::list_domain_stats))
+        .route("/domains/:domain", get(handlers::domain_stats))
+        .with_state(controller)
+}
+
+/// Serve the REST API until interrupted
+pub async fn serve(host: &str, port: u16) -> Result<()> {
+    let controller = Arc::new(CrawlerController::connect().await?);
+    
+    let listener = tokio::net::TcpListener::bind((host, port)).await
+        .context(format!("Failed to listen on {}:{}", host, port))?;
+    info!("API listening on http://{}", listener.local_addr()?);
+    
+    // Jobs of crawl schedules are started by the servers while they run
+    let schedules = tokio::spawn({
+        let controller = controller.clone();
+        async move { controller.run_schedules().await }
+    });
+    
+    axum::serve(listener, router(controller))
+        .with_graceful_shutdown(async {
+            shutdown::requested().await;
+            info!("Shutting down API server");
+        })
+        .await
+        .context("API server failed")?;
+    schedules.await.context("Schedule runner failed")?;
+    
+    // Workers of jobs started through the API finish or requeue their tasks
+    shutdown::wait_for_workers().await;
+    
+    Ok(())
+}
+
+/// Error returned by the API as `{"error": "..."}`
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+impl ApiError {
+    /// Invalid request parameters
+    pub fn bad_request(message: impl Display) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+    
+    /// Requested resource that doesn't exist
+    pub fn not_found(message: impl Display) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: message.to_string(),
+        }
+    }
+    
+    /// HTTP status of the error
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+    
+    /// Message returned to the client
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        let message = format!("{:#}", e);
+        
+        // Controller errors only carry a message, map the expected ones to client errors
+        let cause = e.root_cause().to_string();
+        let status = if cause.starts_with("Job not found") {
+            StatusCode::NOT_FOUND
+        } else if cause.starts_with("Cannot ") {
+            StatusCode::CONFLICT
+        } else {
+            error!("API request failed: {}", message);
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        
+        Self { status, message }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "error": self.message }))).into_response()
+    }
+}
\ No newline at end of file
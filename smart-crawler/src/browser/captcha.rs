@@ -0,0 +1,203 @@
+use anyhow::{Result, Context};
+use reqwest::Client;
+use serde::{Serialize, Deserialize};
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::cli::config::CaptchaSettings;
+
+/// Captcha challenge detected by the browser service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptchaChallenge {
+    /// Captcha type ("recaptcha_v2", "hcaptcha")
+    pub captcha_type: String,
+    
+    /// Site key embedded in the page
+    pub site_key: String,
+    
+    /// URL of the page showing the challenge
+    pub page_url: String,
+}
+
+/// Client for external captcha solving services
+pub struct CaptchaSolver {
+    client: Client,
+    settings: CaptchaSettings,
+}
+
+impl CaptchaSolver {
+    /// Create a new captcha solver
+    pub fn new(settings: CaptchaSettings) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+        
+        Ok(Self { client, settings })
+    }
+    
+    /// Create a solver if captcha solving is enabled in the settings
+    pub fn from_settings(settings: &CaptchaSettings) -> Result<Option<Self>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+        
+        if settings.api_key.is_empty() {
+            anyhow::bail!("Captcha solving is enabled but no API key is configured");
+        }
+        
+        Ok(Some(Self::new(settings.clone())?))
+    }
+    
+    /// Cost of a single solve
+    pub fn cost_per_solve(&self) -> f64 {
+        self.settings.cost_per_solve
+    }
+    
+    /// Spend limit per job, 0 for none
+    pub fn max_spend_per_job(&self) -> f64 {
+        self.settings.max_spend_per_job
+    }
+    
+    /// Solve a captcha challenge and return the response token
+    pub async fn solve(&self, challenge: &CaptchaChallenge) -> Result<String> {
+        info!("Submitting {} captcha for {}", challenge.captcha_type, challenge.page_url);
+        
+        match self.settings.provider.as_str() {
+            "2captcha" => self.solve_2captcha(challenge).await,
+            "anticaptcha" => self.solve_anticaptcha(challenge).await,
+            _ => anyhow::bail!("Unsupported captcha provider: {}", self.settings.provider),
+        }
+    }
+    
+    /// Solve using the 2captcha in.php/res.php API
+    async fn solve_2captcha(&self, challenge: &CaptchaChallenge) -> Result<String> {
+        let method = match challenge.captcha_type.as_str() {
+            "recaptcha_v2" => "userrecaptcha",
+            "hcaptcha" => "hcaptcha",
+            other => anyhow::bail!("Unsupported captcha type: {}", other),
+        };
+        let key_param = if method == "hcaptcha" { "sitekey" } else { "googlekey" };
+        
+        let submit: serde_json::Value = self.client.post(format!("{}/in.php", self.settings.api_url))
+            .form(&[
+                ("key", self.settings.api_key.as_str()),
+                ("method", method),
+                (key_param, challenge.site_key.as_str()),
+                ("pageurl", challenge.page_url.as_str()),
+                ("json", "1"),
+            ])
+            .send()
+            .await
+            .context("Failed to submit captcha")?
+            .json()
+            .await
+            .context("Failed to parse captcha submission response")?;
+        
+        if submit["status"].as_i64() != Some(1) {
+            anyhow::bail!("Captcha submission rejected: {}", submit["request"]);
+        }
+        
+        let request_id = submit["request"].as_str()
+            .context("Captcha submission response has no request id")?
+            .to_string();
+        
+        debug!("Captcha submitted with id: {}", request_id);
+        
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.settings.solve_timeout);
+        
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            
+            let result: serde_json::Value = self.client.get(format!("{}/res.php", self.settings.api_url))
+                .query(&[
+                    ("key", self.settings.api_key.as_str()),
+                    ("action", "get"),
+                    ("id", request_id.as_str()),
+                    ("json", "1"),
+                ])
+                .send()
+                .await
+                .context("Failed to poll captcha result")?
+                .json()
+                .await
+                .context("Failed to parse captcha result")?;
+            
+            if result["status"].as_i64() == Some(1) {
+                return result["request"].as_str()
+                    .map(|token| token.to_string())
+                    .context("Captcha result has no token");
+            }
+            
+            if result["request"].as_str() != Some("CAPCHA_NOT_READY") {
+                anyhow::bail!("Captcha solving failed: {}", result["request"]);
+            }
+        }
+        
+        anyhow::bail!("Timed out waiting for captcha solution")
+    }
+    
+    /// Solve using the anti-captcha createTask/getTaskResult API
+    async fn solve_anticaptcha(&self, challenge: &CaptchaChallenge) -> Result<String> {
+        let task_type = match challenge.captcha_type.as_str() {
+            "recaptcha_v2" => "RecaptchaV2TaskProxyless",
+            "hcaptcha" => "HCaptchaTaskProxyless",
+            other => anyhow::bail!("Unsupported captcha type: {}", other),
+        };
+        
+        let submit: serde_json::Value = self.client.post(format!("{}/createTask", self.settings.api_url))
+            .json(&serde_json::json!({
+                "clientKey": self.settings.api_key,
+                "task": {
+                    "type": task_type,
+                    "websiteURL": challenge.page_url,
+                    "websiteKey": challenge.site_key,
+                }
+            }))
+            .send()
+            .await
+            .context("Failed to submit captcha")?
+            .json()
+            .await
+            .context("Failed to parse captcha submission response")?;
+        
+        if submit["errorId"].as_i64() != Some(0) {
+            anyhow::bail!("Captcha submission rejected: {}", submit["errorDescription"]);
+        }
+        
+        let task_id = submit["taskId"].as_i64()
+            .context("Captcha submission response has no task id")?;
+        
+        debug!("Captcha submitted with id: {}", task_id);
+        
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.settings.solve_timeout);
+        
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            
+            let result: serde_json::Value = self.client.post(format!("{}/getTaskResult", self.settings.api_url))
+                .json(&serde_json::json!({
+                    "clientKey": self.settings.api_key,
+                    "taskId": task_id,
+                }))
+                .send()
+                .await
+                .context("Failed to poll captcha result")?
+                .json()
+                .await
+                .context("Failed to parse captcha result")?;
+            
+            if result["errorId"].as_i64() != Some(0) {
+                anyhow::bail!("Captcha solving failed: {}", result["errorDescription"]);
+            }
+            
+            if result["status"].as_str() == Some("ready") {
+                return result["solution"]["gRecaptchaResponse"].as_str()
+                    .map(|token| token.to_string())
+                    .context("Captcha result has no token");
+            }
+        }
+        
+        anyhow::bail!("Timed out waiting for captcha solution")
+    }
+}
\ No newline at end of file
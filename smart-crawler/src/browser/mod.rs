@@ -1,9 +1,11 @@
 // src/browser/mod.rs
+pub mod captcha;
 pub mod fingerprint;
 pub mod remote;
 pub mod script;
 
 // Re-export common types
+pub use captcha::CaptchaSolver;
 pub use fingerprint::FingerprintManager;
 pub use remote::RemoteBrowserService;
 pub use script::ScriptManager;
\ No newline at end of file
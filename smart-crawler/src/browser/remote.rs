@@ -1,12 +1,16 @@
 use anyhow::{Result, Context};
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
 use std::time::Duration;
-use tracing::{debug, error};
+use thiserror::Error;
+use tracing::{debug, error, instrument};
 use url::Url;
 
+use crate::browser::captcha::CaptchaChallenge;
 use crate::browser::fingerprint::CompleteFingerprint;
 use crate::cli::config::BrowserBehavior;
+use crate::utils::trace_headers;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrowserServiceRequest {
@@ -15,6 +19,13 @@ pub struct BrowserServiceRequest {
     pub fingerprint: serde_json::Value,
     pub behavior: serde_json::Value,
     pub take_screenshot: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captcha_token: Option<String>,
+    pub capture_trace: bool,
+    pub accessibility_audit: bool,
+    pub security_audit: bool,
+    pub resource_inventory: bool,
+    pub brand_assets: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +37,94 @@ pub struct BrowserServiceResponse {
     pub links: Vec<String>,
     pub screenshot: Option<String>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub captcha: Option<CaptchaChallenge>,
+    #[serde(default)]
+    pub trace: Option<BrowserTrace>,
+    #[serde(default)]
+    pub accessibility: Option<serde_json::Value>,
+    #[serde(default)]
+    pub security: Option<serde_json::Value>,
+    #[serde(default)]
+    pub resources: Option<Vec<LoadedResource>>,
+    #[serde(default)]
+    pub brand_assets: Option<BrandAssetLinks>,
+    #[serde(default)]
+    pub redirects: Vec<RedirectHop>,
+    #[serde(default)]
+    pub final_url: Option<String>, // URL the page ended on, after redirects and client-side navigation
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>, // Headers of the document response, by lowercase name
+    #[serde(default)]
+    pub status_code: Option<u16>, // HTTP status of the document response, after redirects
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// Redirect followed while loading a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectHop {
+    /// URL that redirected
+    pub url: String,
+    
+    /// Redirect status, like 301 or 302
+    pub status: u16,
+}
+
+/// Favicon and og:image URLs declared by a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrandAssetLinks {
+    #[serde(default)]
+    pub favicons: Vec<String>,
+    pub og_image: Option<String>,
+}
+
+/// Sub-resource loaded by a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedResource {
+    pub url: String,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+}
+
+/// Debug information captured by the browser service when a crawl fails
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserTrace {
+    pub screenshot: Option<String>,
+    pub html: Option<String>,
+    #[serde(default)]
+    pub console_logs: Vec<String>,
+    pub proxy: Option<String>,
+}
+
+/// Error returned when the browser service reports a failed crawl
+#[derive(Debug, Error)]
+#[error("Browser service error: {message}")]
+pub struct BrowserServiceError {
+    pub message: String,
+    pub trace: Option<BrowserTrace>,
+}
+
+/// Per-request options for the browser service
+#[derive(Debug, Clone, Default)]
+pub struct CrawlOptions {
+    /// Solved captcha token to inject before extracting the page
+    pub captcha_token: Option<String>,
+    
+    /// Capture a debug trace if the crawl fails
+    pub capture_trace: bool,
+    
+    /// Run an axe-core accessibility scan on the page
+    pub accessibility_audit: bool,
+    
+    /// Record security headers and cookie flags for the page
+    pub security_audit: bool,
+    
+    /// List the sub-resources loaded by the page
+    pub resource_inventory: bool,
+    
+    /// Report the favicon and og:image URLs of the page
+    pub brand_assets: bool,
 }
 
 use crate::browser::script::ScriptManager;
@@ -40,60 +139,73 @@ impl RemoteBrowserService {
         // Get URL from environment variable or use default
         let base_url = std::env::var("BROWSER_SERVICE_URL")
             .unwrap_or_else(|_| "http://browser-service:5000".to_string());
-            
+        
         let client = Client::builder()
             .timeout(Duration::from_secs(120))
             .build()
             .expect("Failed to create HTTP client");
-            
+        
         Self {
             client,
             base_url,
         }
     }
     
+    #[instrument(skip_all, fields(url = %url, browser = %browser_type))]
     pub async fn crawl_url(
         &self, 
         url: &str, 
         browser_type: &str,
         fingerprint: &CompleteFingerprint,
-        behavior: &BrowserBehavior
+        behavior: &BrowserBehavior,
+        options: &CrawlOptions,
     ) -> Result<BrowserServiceResponse> {
         let endpoint = format!("{}/crawl", self.base_url);
         
         // Convert fingerprint and behavior to JSON
         let fingerprint_json = serde_json::to_value(fingerprint)
             .context("Failed to serialize fingerprint")?;
-            
+        
         let behavior_json = serde_json::to_value(behavior)
             .context("Failed to serialize behavior")?;
-            
+        
         let request = BrowserServiceRequest {
             url: url.to_string(),
             browser_type: browser_type.to_string(),
             fingerprint: fingerprint_json,
             behavior: behavior_json,
             take_screenshot: false,
+            captcha_token: options.captcha_token.clone(),
+            capture_trace: options.capture_trace,
+            accessibility_audit: options.accessibility_audit,
+            security_audit: options.security_audit,
+            resource_inventory: options.resource_inventory,
+            brand_assets: options.brand_assets,
         };
         
         debug!("Sending request to browser service: {}", url);
         
-        let response = self.client.post(&endpoint)
-            .json(&request)
+        let mut http_request = self.client.post(&endpoint).json(&request);
+        for (name, value) in trace_headers() {
+            http_request = http_request.header(name, value);
+        }
+        
+        let response = http_request
             .send()
             .await
             .context("Failed to send request to browser service")?
             .json::<BrowserServiceResponse>()
             .await
             .context("Failed to parse browser service response")?;
-            
+        
         if !response.success {
-            if let Some(error) = &response.error {
-                error!("Browser service error: {}", error);
-                anyhow::bail!("Browser service error: {}", error);
-            } else {
-                anyhow::bail!("Browser service crawl failed with unknown error");
-            }
+            let message = response.error
+                .unwrap_or_else(|| "crawl failed with unknown error".to_string());
+            error!("Browser service error: {}", message);
+            return Err(BrowserServiceError {
+                message,
+                trace: response.trace,
+            }.into());
         }
         
         debug!("Successfully crawled URL: {}", url);
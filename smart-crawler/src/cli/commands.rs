@@ -1,11 +1,87 @@
 use anyhow::{Result, Context};
 use tracing::{info, warn};
+use crate::crawler::audit::{AccessibilityReport, ResourceInventoryReport, SecurityHeaderReport};
+use crate::crawler::compare::JobComparison;
 use crate::crawler::controller::CrawlerController;
-use crate::cli::config::CrawlerConfig;
+use crate::crawler::pipeline::handoff_urls;
+use crate::crawler::schedule::CrawlSchedule;
+use crate::crawler::suggest::{sample_pages, suggest_selectors};
+use crate::crawler::templates::TemplateReport;
+use crate::cli::config::{CrawlerConfig, ExecutionMode, JobPriority};
+use crate::cli::daemon::{self, DaemonService, DaemonSpec, RestartPolicy};
+use crate::storage::export::{
+    ExportCompression, ExportFormat, ExportManifest, ExportOptions, ExportTarget, NDJSON_RECOMMENDED_PAGES,
+};
+use crate::storage::models::RecordModel;
+use crate::storage::processed::RowFilter;
+use crate::storage::progress::RowProgress;
+use crate::storage::query::{QueryFormat, QueryResult};
+use crate::cli::progress::{format_duration, progress_bar, RateTracker, RowProgressBar};
+use chrono::{DateTime, Utc};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::path::PathBuf;
 
-/// Start a new crawling job
-pub async fn crawl(url: String, profile: String, depth: Option<u32>, limit: Option<u32>) -> Result<()> {
+/// Where a new job's seed URLs come from
+pub enum Seeds {
+    /// URLs given on the command line
+    Urls(Vec<String>),
+    /// The URLs listed in a file
+    File(PathBuf),
+    /// The URLs in a field extracted by a previous job
+    FromJob { job_id: String, field: String },
+    /// The pages of sitemaps, with the ones of the profile
+    Sitemaps(Vec<String>),
+    /// The entries of feeds, with the ones of the profile
+    Feeds(Vec<String>),
+}
+
+/// URLs of a seeds file, one per line, skipping blank lines and `#` comments
+/// and keeping the first of duplicates
+fn read_seeds_file(path: &std::path::Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read seeds file {}", path.display()))?;
+    
+    let mut urls: Vec<String> = Vec::new();
+    let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+    for (number, line) in content.lines().enumerate() {
+        // `#` only starts a comment at the start of a line or after a space,
+        // URL fragments keep theirs
+        let line = match line.find(" #") {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        
+        let url = url::Url::parse(line)
+            .context(format!("Invalid URL on line {} of {}: {}", number + 1, path.display(), line))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("Line {} of {} is not an HTTP URL: {}", number + 1, path.display(), line);
+        }
+        if seen.insert(line.to_string()) {
+            urls.push(line.to_string());
+        }
+    }
+    
+    if urls.is_empty() {
+        anyhow::bail!("Seeds file {} lists no URLs", path.display());
+    }
+    Ok(urls)
+}
+
+/// Start a new crawling job, then poll its feeds every `poll` seconds when given
+pub async fn crawl(
+    seeds: Seeds,
+    profile: String,
+    depth: Option<u32>,
+    limit: Option<u32>,
+    priority: Option<String>,
+    poll: Option<u64>,
+) -> Result<()> {
     // Load the specified profile configuration
     let mut config = CrawlerConfig::load_profile(&profile)
         .context(format!("Failed to load profile: {}", profile))?;
@@ -19,33 +95,154 @@ pub async fn crawl(url: String, profile: String, depth: Option<u32>, limit: Opti
         config.crawler.max_pages = l;
     }
     
+    if let Some(p) = priority {
+        config.crawler.priority = JobPriority::parse(&p)?;
+    }
+    
+    if let Seeds::Sitemaps(sitemaps) = &seeds {
+        config.crawler.sitemaps.extend(sitemaps.iter().cloned());
+    }
+    
+    if let Seeds::Feeds(feeds) = &seeds {
+        config.crawler.feeds.extend(feeds.iter().cloned());
+    }
+    
+    if let Some(poll) = poll {
+        config.crawler.feed_poll_interval = poll;
+    }
+    let poll = Some(config.crawler.feed_poll_interval).filter(|poll| *poll > 0 && !config.crawler.feeds.is_empty());
+    
     // Initialize the crawler controller
     let controller = CrawlerController::new(config).await?;
     
     // Start the crawling job
-    let job_id = controller.start_job(url).await?;
+    let job_id = match seeds {
+        Seeds::Urls(urls) => controller.start_job(urls).await?,
+        Seeds::FromJob { job_id, field } => {
+            // Fail on unknown jobs rather than on their missing results
+            controller.get_job_status(&job_id).await?;
+            
+            let results = controller.list_page_results(&job_id).await
+                .context(format!("Failed to read the results of job {}", job_id))?;
+            let urls = handoff_urls(&results, &field);
+            if urls.is_empty() {
+                anyhow::bail!("Job {} extracted no URLs in field '{}'", job_id, field);
+            }
+            
+            info!("Seeding the job with {} URL(s) from job {}", urls.len(), job_id);
+            controller.start_seeded_job(urls, Some(job_id), Vec::new()).await?
+        },
+        Seeds::File(path) => {
+            let urls = read_seeds_file(&path)?;
+            info!("Seeding the job with {} URL(s) from {}", urls.len(), path.display());
+            controller.start_seeded_job(urls, None, Vec::new()).await?
+        },
+        Seeds::Sitemaps(_) | Seeds::Feeds(_) => controller.start_seeded_job(Vec::new(), None, Vec::new()).await?,
+    };
     
     info!("Crawling job started with ID: {}", job_id);
     info!("Use `crawler status {}` to check the job status", job_id);
     
+    if let Some(poll) = poll {
+        info!("Reading the feeds again every {} seconds until Ctrl-C", poll);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(poll)) => {},
+                _ = crate::utils::shutdown::requested() => break,
+            }
+            match controller.poll_feeds(&job_id).await {
+                Ok(Some(0)) => {},
+                Ok(Some(queued)) => info!("Queued {} new feed entries for job {}", queued, job_id),
+                Ok(None) => break,
+                Err(e) => warn!("Failed to read the feeds of job {}: {:#}", job_id, e),
+            }
+        }
+    }
+    
+    // Standalone workers run in this process, on SIGINT or SIGTERM they
+    // finish or requeue their tasks before it exits
+    crate::utils::shutdown::wait_for_workers().await;
+    
+    Ok(())
+}
+
+/// Run a pipeline of linked jobs, each stage seeded by the one before
+pub async fn pipeline(name: String, url: String) -> Result<()> {
+    let pipeline = CrawlerConfig::load_pipeline(&name)
+        .context(format!("Failed to load pipeline: {}", name))?;
+    
+    let job_ids = crate::crawler::pipeline::run_pipeline(&pipeline, url).await?;
+    for (index, job_id) in job_ids.iter().enumerate() {
+        println!("Stage {} ({}): job {}", index + 1, pipeline.stages[index].profile, job_id);
+    }
+    
+    crate::utils::shutdown::wait_for_workers().await;
+    
     Ok(())
 }
 
 /// Check the status of a crawling job
-pub async fn status(job_id: String) -> Result<()> {
+pub async fn status(job_id: String, watch: bool, interval: u64, skips: bool, preview: Option<usize>) -> Result<()> {
     // Load the controller
     let controller = CrawlerController::connect().await?;
     
+    if watch {
+        return watch_status(&controller, &job_id, interval, preview).await;
+    }
+    
     // Get the job status
     let status = controller.get_job_status(&job_id).await?;
     
     // Display status information
     println!("Job ID: {}", job_id);
     println!("Status: {}", status.state);
+    if let Some(parent_job_id) = &status.parent_job_id {
+        println!("Seeded By: job {}", parent_job_id);
+    }
+    if !status.tags.is_empty() {
+        println!("Tags: {}", status.tags.join(", "));
+    }
     println!("Pages Crawled: {}/{}", status.pages_crawled, status.pages_total);
+    if let Some(recrawl) = &status.recrawl {
+        println!(
+            "Recrawl Of: job {}, {} page(s) changed, {} unchanged, {} new",
+            recrawl.previous_job_id, recrawl.pages_changed, recrawl.pages_unchanged, recrawl.pages_new
+        );
+    }
+    if status.seeds.len() > 1 {
+        for seed in &status.seeds {
+            println!("  {}: {}/{}", seed.url, seed.pages_crawled, seed.pages_total);
+        }
+    }
+    if let Some(limits) = &status.limits {
+        println!(
+            "Limits: {} pages, depth {}, {} worker(s), weight {}, {} priority",
+            limits.max_pages, limits.max_depth, limits.concurrency, limits.weight, limits.priority.as_str()
+        );
+    }
+    if let Some(reason) = &status.stop_reason {
+        println!("Stopped By: {}", reason);
+    }
+    if status.pages_per_minute > 0.0 {
+        println!(
+            "Crawl Rate: {:.1} pages/min, {} ms per page",
+            status.pages_per_minute, status.avg_page_ms
+        );
+    }
+    if let (Some(eta), "running") = (status.eta_seconds, status.state.as_str()) {
+        println!("Estimated Time Left: {}", format_duration(Duration::from_secs(eta)));
+    }
     println!("Started: {}", status.started_at);
     println!("Last Updated: {}", status.updated_at);
     
+    let domains = controller.get_domain_counts(&job_id).await?;
+    if domains.len() > 1 {
+        println!("Top Domains:");
+        for (domain, pages) in domains.iter().take(5) {
+            println!("  - {}: {} page(s)", domain, pages);
+        }
+    }
+    
     if !status.errors.is_empty() {
         println!("Recent Errors:");
         for error in &status.errors {
@@ -53,11 +250,691 @@ pub async fn status(job_id: String) -> Result<()> {
         }
     }
     
+    let skip_counts = controller.get_skip_counts(&job_id).await?;
+    if !skip_counts.is_empty() {
+        println!("Skipped URLs:");
+        for (reason, count) in &skip_counts {
+            println!("  - {}: {}", reason, count);
+        }
+    }
+    
+    if skips {
+        if skip_counts.is_empty() {
+            println!("No skipped URLs recorded, set crawler.record_skips in the profile to record them");
+        }
+        for (url, reason) in controller.list_skipped_urls(&job_id).await? {
+            println!("{:<12} {}", reason, url);
+        }
+    }
+    
+    if let Some(count) = preview {
+        print_preview(&controller, &job_id, count).await?;
+    }
+    
+    Ok(())
+}
+
+/// Print the fields of the pages a job crawled last, and the fields none of
+/// them has, which usually means a broken selector
+async fn print_preview(controller: &CrawlerController, job_id: &str, count: usize) -> Result<()> {
+    let records = controller.recent_records(job_id, count).await?;
+    if records.is_empty() {
+        println!("Recent Records: none yet");
+        return Ok(());
+    }
+    
+    println!("Recent Records:");
+    for record in &records {
+        println!("  - {} ({})", record.url, record.crawled_at.format("%H:%M:%S"));
+        if record.fields.is_empty() {
+            println!("      title: {}", record.title);
+        }
+        for (name, value) in &record.fields {
+            println!("      {}: {}", name, value.as_deref().unwrap_or("(empty)"));
+        }
+    }
+    
+    let empty: Vec<&str> = records[0].fields.keys()
+        .filter(|name| records.iter().all(|record| record.fields.get(*name).map_or(true, Option::is_none)))
+        .map(String::as_str)
+        .collect();
+    if !empty.is_empty() {
+        println!("Empty in every recent record, check their selectors: {}", empty.join(", "));
+    }
+    
+    Ok(())
+}
+
+/// States a job doesn't leave on its own
+const FINAL_STATES: [&str; 3] = ["completed", "failed", "cancelled"];
+
+/// Period the crawl rate is measured over while watching a job
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Redraw a compact progress display of a job until it finishes or Ctrl-C
+async fn watch_status(controller: &CrawlerController, job_id: &str, interval: u64, preview: Option<usize>) -> Result<()> {
+    let interval = Duration::from_secs(interval.max(1));
+    let redraw = std::io::stdout().is_terminal();
+    let mut rates = RateTracker::new(RATE_WINDOW);
+    
+    loop {
+        let status = controller.get_job_status(job_id).await?;
+        let counts = controller.get_queue_counts(job_id).await?;
+        rates.record(Instant::now(), status.pages_crawled);
+        
+        // Pages still to crawl, bounded by the page limit when the job has one
+        let mut remaining = counts.pending + counts.processing;
+        if let Some(limits) = &status.limits {
+            remaining = remaining.min((limits.max_pages as usize).saturating_sub(status.pages_crawled));
+        }
+        let target = status.pages_crawled + remaining;
+        let finished = FINAL_STATES.contains(&status.state.as_str());
+        
+        if redraw {
+            print!("\x1b[2J\x1b[H");
+        }
+        println!("Job {}  {}  {}", job_id, status.state, Utc::now().format("%H:%M:%S"));
+        println!(
+            "Pages  {}/{}  {}",
+            status.pages_crawled,
+            target,
+            progress_bar(status.pages_crawled, target, 30)
+        );
+        println!(
+            "Queue  {} pending, {} processing, {} completed, {} failed",
+            counts.pending, counts.processing, counts.completed, counts.failed
+        );
+        println!("Workers  {} alive", counts.workers);
+        let rate = rates.rate()
+            .map(|rate| format!("{:.1} pages/s", rate))
+            .unwrap_or_else(|| "measuring...".to_string());
+        let eta = match rates.eta(remaining) {
+            _ if finished || status.state == "paused" => "-".to_string(),
+            Some(eta) => format_duration(eta),
+            None => "unknown".to_string(),
+        };
+        println!("Rate   {}  ETA {}", rate, eta);
+        if let Some(reason) = &status.stop_reason {
+            println!("Stopped By: {}", reason);
+        }
+        if let Some(count) = preview {
+            print_preview(controller, job_id, count).await?;
+        }
+        if !redraw {
+            println!();
+        }
+        
+        if finished {
+            return Ok(());
+        }
+        
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {},
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Pause a running crawling job
+pub async fn pause(job_id: String) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    controller.pause_job(&job_id).await?;
+    
+    println!("Job {} paused", job_id);
+    info!("Use `crawler resume {}` to continue crawling", job_id);
+    
+    Ok(())
+}
+
+/// Resume a paused or stalled crawling job
+pub async fn resume(job_id: String) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    controller.resume_job(&job_id).await?;
+    
+    println!("Job {} resumed", job_id);
+    
     Ok(())
 }
 
+/// Restart workers for the interrupted jobs of a profile and run them
+/// until they finish
+pub async fn resume_all(profile: String) -> Result<()> {
+    let mut config = CrawlerConfig::load_profile(&profile)
+        .context(format!("Failed to load profile: {}", profile))?;
+    config.mode = ExecutionMode::Standalone;
+    let controller = CrawlerController::new(config).await?;
+    
+    let resumed = controller.resume_interrupted().await?;
+    if resumed.is_empty() {
+        println!("No interrupted jobs to resume");
+        return Ok(());
+    }
+    for job_id in &resumed {
+        println!("Job {} resumed", job_id);
+    }
+    
+    // The workers run in this process, on SIGINT or SIGTERM they finish or
+    // requeue their tasks before it exits
+    crate::utils::shutdown::wait_for_workers().await;
+    
+    Ok(())
+}
+
+/// Cancel a crawling job
+pub async fn cancel(job_id: String) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    controller.cancel_job(&job_id).await?;
+    
+    println!("Job {} cancelled", job_id);
+    
+    Ok(())
+}
+
+/// Requeue the failed URLs of a job
+pub async fn retry_failed(job_id: String) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    let requeued = controller.retry_failed(&job_id, None).await?;
+    
+    if requeued == 0 {
+        println!("Job {} has no failed URLs to retry", job_id);
+    } else {
+        println!("Requeued {} failed URL(s) for job {}", requeued, job_id);
+    }
+    
+    Ok(())
+}
+
+/// List the dead-letter tasks of a job or put them back into the queue
+pub async fn dlq(job_id: String, requeue: bool, url: Option<String>, json: bool) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    
+    if requeue {
+        let requeued = controller.retry_failed(&job_id, url.as_deref()).await?;
+        println!("Requeued {} dead-letter task(s) for job {}", requeued, job_id);
+        return Ok(());
+    }
+    
+    let mut dead_letters = controller.list_dead_letters(&job_id).await?;
+    if let Some(url) = &url {
+        dead_letters.retain(|dead_letter| &dead_letter.task.url == url);
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&dead_letters)
+            .context("Failed to serialize dead-letter tasks")?);
+        return Ok(());
+    }
+    
+    if dead_letters.is_empty() {
+        println!("No dead-letter tasks for job {}.", job_id);
+        return Ok(());
+    }
+    
+    println!("Dead-letter tasks for job {}: {}", job_id, dead_letters.len());
+    for dead_letter in &dead_letters {
+        println!(
+            "  - {} (depth {}, {} attempt(s), failed {})",
+            dead_letter.task.url,
+            dead_letter.task.depth,
+            dead_letter.task.attempt + 1,
+            dead_letter.failed_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        if dead_letter.error_type.is_empty() {
+            println!("    {}", dead_letter.error);
+        } else {
+            println!("    [{}] {}", dead_letter.error_type, dead_letter.error);
+        }
+    }
+    
+    Ok(())
+}
+
+/// List the failure traces of a job, writing their screenshots and HTML
+/// snapshots to the `save` directory
+pub async fn traces(job_id: String, url: Option<String>, save: Option<String>, json: bool) -> Result<()> {
+    use base64::Engine;
+    
+    let controller = CrawlerController::connect().await?;
+    
+    let mut traces = controller.list_task_traces(&job_id).await?;
+    if let Some(url) = &url {
+        traces.retain(|trace| &trace.url == url);
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&traces)
+            .context("Failed to serialize failure traces")?);
+        return Ok(());
+    }
+    
+    if traces.is_empty() {
+        println!("No failure traces for job {}.", job_id);
+        return Ok(());
+    }
+    
+    let save = save.map(PathBuf::from);
+    if let Some(dir) = &save {
+        std::fs::create_dir_all(dir)
+            .context(format!("Failed to create trace directory: {}", dir.display()))?;
+    }
+    
+    println!("Failure traces for job {}: {}", job_id, traces.len());
+    for (index, trace) in traces.iter().enumerate() {
+        match &trace.proxy {
+            Some(proxy) => println!("  - {} (failed {}, through {})", trace.url, trace.captured_at.format("%Y-%m-%d %H:%M:%S"), proxy),
+            None => println!("  - {} (failed {})", trace.url, trace.captured_at.format("%Y-%m-%d %H:%M:%S")),
+        }
+        println!("    {}", trace.error);
+        for line in &trace.console_logs {
+            println!("    console: {}", line);
+        }
+        
+        let Some(dir) = &save else {
+            continue;
+        };
+        // Numbered in capture order, a URL can fail more than once
+        let stem = format!("trace-{}", index + 1);
+        if let Some(screenshot) = &trace.screenshot {
+            let path = dir.join(format!("{}.png", stem));
+            let png = base64::engine::general_purpose::STANDARD.decode(screenshot)
+                .context(format!("Failed to decode the screenshot of trace {}", index + 1))?;
+            std::fs::write(&path, png)
+                .context(format!("Failed to write screenshot: {}", path.display()))?;
+            println!("    screenshot: {}", path.display());
+        }
+        if let Some(html) = &trace.html {
+            let path = dir.join(format!("{}.html", stem));
+            std::fs::write(&path, html)
+                .context(format!("Failed to write HTML snapshot: {}", path.display()))?;
+            println!("    html: {}", path.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Change the limits of a running job
+pub async fn update_job(
+    job_id: String,
+    limit: Option<u32>,
+    depth: Option<u32>,
+    concurrency: Option<usize>,
+    weight: Option<u32>,
+    priority: Option<String>,
+) -> Result<()> {
+    if limit.is_none() && depth.is_none() && concurrency.is_none() && weight.is_none() && priority.is_none() {
+        anyhow::bail!("Nothing to update, give --limit, --depth, --concurrency, --weight or --priority");
+    }
+    let priority = priority.as_deref().map(JobPriority::parse).transpose()?;
+    
+    let controller = CrawlerController::connect().await?;
+    let limits = controller.update_job_limits(&job_id, limit, depth, concurrency, weight, priority).await?;
+    
+    println!(
+        "Job {} limits: {} pages, depth {}, {} worker(s), weight {}, {} priority",
+        job_id, limits.max_pages, limits.max_depth, limits.concurrency, limits.weight, limits.priority.as_str()
+    );
+    
+    Ok(())
+}
+
+/// List all crawling jobs, optionally filtered by state
+pub async fn list_jobs(state: Option<String>, json: bool) -> Result<()> {
+    // Load the controller
+    let controller = CrawlerController::connect().await?;
+    
+    // Get all jobs and apply the state filter
+    let mut jobs = controller.list_jobs().await?;
+    if let Some(state) = &state {
+        jobs.retain(|job| job.state == *state);
+    }
+    
+    // Show the most recently started jobs first
+    jobs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&jobs)
+            .context("Failed to serialize job list")?);
+        return Ok(());
+    }
+    
+    if jobs.is_empty() {
+        println!("No jobs found.");
+        return Ok(());
+    }
+    
+    println!("{:<36}  {:<10}  {:>15}  {}", "JOB ID", "STATE", "PAGES", "STARTED");
+    for job in &jobs {
+        println!(
+            "{:<36}  {:<10}  {:>15}  {}",
+            job.job_id,
+            job.state,
+            format!("{}/{}", job.pages_crawled, job.pages_total),
+            job.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    
+    Ok(())
+}
+
+/// List or purge the quarantined pages of a job
+pub async fn quarantine(job_id: String, purge: bool, json: bool) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    
+    if purge {
+        let purged = controller.purge_quarantine(&job_id, true).await?;
+        println!("Purged {} quarantined page(s) for job {}", purged, job_id);
+        return Ok(());
+    }
+    
+    // Expired pages are dropped rather than listed
+    controller.purge_quarantine(&job_id, false).await?;
+    let pages = controller.list_quarantined_pages(&job_id).await?;
+    
+    if json {
+        let summary: Vec<serde_json::Value> = pages.iter()
+            .map(|page| serde_json::json!({
+                "url": page.result.url,
+                "missing_fields": page.missing_fields,
+                "quarantined_at": page.quarantined_at,
+                "expires_at": page.expires_at,
+            }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&summary)
+            .context("Failed to serialize quarantined pages")?);
+        return Ok(());
+    }
+    
+    if pages.is_empty() {
+        println!("No quarantined pages for job {}.", job_id);
+        return Ok(());
+    }
+    
+    println!("Quarantined pages for job {}: {}", job_id, pages.len());
+    for page in &pages {
+        println!(
+            "  - {} (missing {}, expires {})",
+            page.result.url,
+            page.missing_fields.join(", "),
+            page.expires_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    
+    Ok(())
+}
+
+/// Report the main page templates of a job
+pub async fn templates(job_id: String, json: bool) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    
+    let results = controller.list_page_results(&job_id).await?;
+    let report = TemplateReport::from_results(&results);
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)
+            .context("Failed to serialize template report")?);
+        return Ok(());
+    }
+    
+    if report.templates.is_empty() {
+        println!("No HTML pages found for job {}.", job_id);
+        return Ok(());
+    }
+    
+    println!("Page templates for job {}", job_id);
+    println!("Pages analyzed: {}", report.pages_analyzed);
+    println!();
+    
+    for (i, template) in report.templates.iter().enumerate() {
+        println!(
+            "Template {} ({}) - {} page(s), {}",
+            i + 1, template.kind.name(), template.pages, template.url_pattern
+        );
+        for url in &template.example_urls {
+            println!("    - {}", url);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Compare two jobs over the same site
+pub async fn compare(job_a: String, job_b: String, details: bool, json: bool, output: Option<String>, format: String) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    
+    let results_a = controller.list_page_results(&job_a).await?;
+    let results_b = controller.list_page_results(&job_b).await?;
+    let comparison = JobComparison::from_results(&job_a, &results_a, &job_b, &results_b);
+    
+    if let Some(path) = output {
+        let path = PathBuf::from(path);
+        let report = match format.to_lowercase().as_str() {
+            "json" => serde_json::to_string_pretty(&comparison)
+                .context("Failed to serialize comparison report")?,
+            "csv" => comparison.to_csv(),
+            _ => anyhow::bail!("Unsupported comparison report format: {}", format),
+        };
+        std::fs::write(&path, report)
+            .context(format!("Failed to write comparison report: {}", path.display()))?;
+        info!("Comparison report written to: {}", path.display());
+        return Ok(());
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&comparison)
+            .context("Failed to serialize comparison report")?);
+        return Ok(());
+    }
+    
+    println!("Comparison of job {} to job {}", job_b, job_a);
+    println!("Pages: {} -> {}", comparison.pages_a, comparison.pages_b);
+    println!("Pages unchanged: {}", comparison.pages_unchanged);
+    println!("Pages gained: {}", comparison.pages_gained.len());
+    println!("Pages lost: {}", comparison.pages_lost.len());
+    println!("Status code changes: {}", comparison.status_changes.len());
+    println!(
+        "Field changes: {} on {} page(s)",
+        comparison.field_changes.len(),
+        comparison.pages_with_field_changes()
+    );
+    
+    if !details {
+        return Ok(());
+    }
+    
+    if !comparison.pages_gained.is_empty() {
+        println!();
+        println!("Gained:");
+        for url in &comparison.pages_gained {
+            println!("    + {}", url);
+        }
+    }
+    if !comparison.pages_lost.is_empty() {
+        println!();
+        println!("Lost:");
+        for url in &comparison.pages_lost {
+            println!("    - {}", url);
+        }
+    }
+    if !comparison.status_changes.is_empty() {
+        println!();
+        println!("Status codes:");
+        for change in &comparison.status_changes {
+            println!("    {} {} -> {}", change.url, change.before, change.after);
+        }
+    }
+    if !comparison.field_changes.is_empty() {
+        println!();
+        println!("Fields:");
+        let text = |value: &Option<serde_json::Value>| value.as_ref().map_or("(none)".to_string(), |value| value.to_string());
+        for change in &comparison.field_changes {
+            println!("    {} {}: {} -> {}", change.url, change.field, text(&change.before), text(&change.after));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Print or export a site-wide audit report for a job
+pub async fn audit(job_id: String, kind: String, json: bool, output: Option<String>) -> Result<()> {
+    // Load the controller
+    let controller = CrawlerController::connect().await?;
+    
+    // Aggregate the per-page audit results
+    let results = controller.list_page_results(&job_id).await?;
+    let report = match kind.as_str() {
+        "accessibility" => serde_json::to_value(AccessibilityReport::from_results(&results))?,
+        "security" => serde_json::to_value(SecurityHeaderReport::from_results(&results))?,
+        "resources" => serde_json::to_value(ResourceInventoryReport::from_results(&results))?,
+        _ => anyhow::bail!("Unsupported audit kind: {}", kind),
+    };
+    
+    if let Some(path) = output {
+        let path = PathBuf::from(path);
+        let file = std::fs::File::create(&path)
+            .context(format!("Failed to create output file: {}", path.display()))?;
+        serde_json::to_writer_pretty(file, &report)
+            .context("Failed to write audit report")?;
+        info!("Audit report written to: {}", path.display());
+        return Ok(());
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)
+            .context("Failed to serialize audit report")?);
+        return Ok(());
+    }
+    
+    if report["pages_audited"].as_u64() == Some(0) {
+        println!("No {} results found for job {}.", kind, job_id);
+        println!("Enable the matching option under `audits` in the profile and re-run the crawl.");
+        return Ok(());
+    }
+    
+    match kind.as_str() {
+        "accessibility" => print_accessibility_report(&job_id, &serde_json::from_value(report)?),
+        "security" => print_security_report(&job_id, &serde_json::from_value(report)?),
+        _ => print_resource_report(&job_id, &serde_json::from_value(report)?),
+    }
+    
+    Ok(())
+}
+
+/// Print an accessibility report as text
+fn print_accessibility_report(job_id: &str, report: &AccessibilityReport) {
+    println!("Accessibility report for job {}", job_id);
+    println!("Pages audited: {}", report.pages_audited);
+    println!("Pages with violations: {}", report.pages_with_violations);
+    println!();
+    
+    for rule in &report.rules {
+        println!("[{}] {} - {} page(s), {} element(s)", rule.impact, rule.id, rule.pages, rule.nodes);
+        println!("  {}", rule.description);
+        if !rule.help_url.is_empty() {
+            println!("  {}", rule.help_url);
+        }
+        for url in &rule.example_urls {
+            println!("    - {}", url);
+        }
+    }
+}
+
+/// Print a security header report as text
+fn print_security_report(job_id: &str, report: &SecurityHeaderReport) {
+    println!("Security header report for job {}", job_id);
+    println!("Pages audited: {}", report.pages_audited);
+    if report.pages_without_headers > 0 {
+        println!("Pages without header information: {}", report.pages_without_headers);
+    }
+    println!();
+    
+    for coverage in &report.headers {
+        println!("{:<28} present on {}, missing on {}", coverage.header, coverage.pages_present, coverage.pages_missing);
+        for url in &coverage.example_missing_urls {
+            println!("    - {}", url);
+        }
+    }
+    
+    if !report.cookies.is_empty() {
+        println!();
+        println!("Cookies missing security flags:");
+        for cookie in &report.cookies {
+            let mut missing = Vec::new();
+            if cookie.missing_secure {
+                missing.push("Secure");
+            }
+            if cookie.missing_http_only {
+                missing.push("HttpOnly");
+            }
+            println!("  - {} (missing {}) on {} page(s)", cookie.name, missing.join(", "), cookie.pages);
+        }
+    }
+}
+
+/// Print a resource inventory report as text
+fn print_resource_report(job_id: &str, report: &ResourceInventoryReport) {
+    println!("Resource inventory for job {}", job_id);
+    println!("Pages audited: {}", report.pages_audited);
+    println!("Resources loaded: {}", report.total_resources);
+    println!();
+    
+    println!("Third-party hosts:");
+    for host in &report.third_party_hosts {
+        println!("  - {} on {} page(s), {} resource(s) [{}]",
+            host.host, host.pages, host.resources, host.resource_types.join(", "));
+    }
+    
+    if !report.mixed_content_pages.is_empty() {
+        println!();
+        println!("Pages with mixed content:");
+        for page in &report.mixed_content_pages {
+            println!("  - {}", page.url);
+            for resource in &page.resources {
+                println!("      {}", resource);
+            }
+        }
+    }
+}
+
 /// Export data from a completed job
-pub async fn export(job_id: String, format: String, output: Option<String>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub async fn export(
+    job_id: String,
+    format: String,
+    output: Option<String>,
+    resume: bool,
+    columns: Option<String>,
+    include_url: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    compress: Option<String>,
+    threads: usize,
+) -> Result<()> {
+    let formats = ExportFormat::parse_list(&format)?;
+    let filter = RowFilter::parse(include_url.as_deref(), since.as_deref(), until.as_deref())?;
+    let compression = compress.as_deref().map(ExportCompression::parse).transpose()?;
+    if compression.is_some() && formats.contains(&ExportFormat::Parquet) {
+        anyhow::bail!("Parquet exports are compressed internally, export them separately without --compress");
+    }
+    let data_columns = columns.map(|columns| {
+        columns.split(',')
+            .map(|column| column.trim().to_string())
+            .filter(|column| !column.is_empty())
+            .collect::<Vec<_>>()
+    });
+    if data_columns.is_some() && !formats.iter().any(|format| format.has_data_columns()) {
+        warn!("--columns only applies to CSV and Parquet exports");
+    }
+    
+    // Only the export itself may go to stdout, log lines are written to stderr
+    let to_stdout = output.as_deref() == Some("-");
+    if to_stdout && formats.len() > 1 {
+        anyhow::bail!("Only one format can be exported to stdout");
+    }
+    if to_stdout && resume {
+        anyhow::bail!("--resume doesn't apply to exports to stdout");
+    }
+    
     // Load the controller
     let controller = CrawlerController::connect().await?;
     
@@ -67,23 +944,499 @@ pub async fn export(job_id: String, format: String, output: Option<String>) -> R
         warn!("Job is still in progress, data may be incomplete");
     }
     
-    // Determine output path
-    let output_path = if let Some(path) = output {
-        PathBuf::from(path)
-    } else {
-        let extension = match format.as_str() {
-            "json" => "json",
-            "csv" => "csv",
-            "sql" => "sql",
-            _ => "data",
+    // A single JSON array has to be parsed in one piece by consumers
+    if formats.contains(&ExportFormat::Json) && status.pages_crawled > NDJSON_RECOMMENDED_PAGES {
+        warn!(
+            "Job has {} pages, consider `--format ndjson` for streaming one record per line",
+            status.pages_crawled
+        );
+    }
+    
+    // Determine output paths, with several formats the output path only gives the stem
+    let targets = ExportTarget::for_formats(&job_id, &formats, output.as_deref(), compression);
+    
+    // Export the data
+    if !filter.is_empty() {
+        info!("Exporting only pages where {}", filter);
+    }
+    let progress = Arc::new(RowProgressBar::new(&format!("Exporting job {}", job_id)));
+    let options = ExportOptions {
+        resume,
+        data_columns,
+        filter,
+        compression,
+        progress: Some(progress.clone()),
+        threads,
+    };
+    let counts = controller.export_job_data(&job_id, &targets, &options).await;
+    progress.finish();
+    let counts = counts?;
+    
+    for (target, records) in targets.iter().zip(counts) {
+        if target.to_stdout() {
+            info!("Exported {} records to stdout", records);
+        } else {
+            info!("Exported {} records to: {}", records, target.output_path.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Verify an exported file against its manifest
+pub async fn verify_export(file: String) -> Result<()> {
+    let path = PathBuf::from(&file);
+    let manifest = ExportManifest::load(&path)?;
+    
+    let problems = manifest.verify(&path)?;
+    if !problems.is_empty() {
+        println!("{}: FAILED", file);
+        for problem in &problems {
+            println!("  - {}", problem);
+        }
+        anyhow::bail!("Export does not match its manifest: {}", file);
+    }
+    
+    println!("{}: OK", file);
+    println!("Job: {} ({})", manifest.job.job_id, manifest.job.seed_url);
+    match manifest.compression {
+        Some(compression) => println!("Format: {} ({})", manifest.format.extension(), compression.extension()),
+        None => println!("Format: {}", manifest.format.extension()),
+    }
+    println!("Records: {}", manifest.records);
+    if let Some(filter) = &manifest.filter {
+        println!("Filter: {}", filter);
+    }
+    println!("SHA-256: {}", manifest.sha256);
+    println!("Exported: {}", manifest.exported_at);
+    
+    Ok(())
+}
+
+/// Sample a site and propose a profile with extraction selectors
+pub async fn suggest_profile(url: String, pages: usize, profile: String, save: Option<String>) -> Result<()> {
+    // Start from an existing profile so browser and storage settings carry over
+    let mut config = CrawlerConfig::load_profile(&profile).unwrap_or_else(|_| {
+        warn!("Profile '{}' not found, starting from the default configuration", profile);
+        CrawlerConfig::default()
+    });
+    
+    let sampled = sample_pages(&url, pages, &config).await?;
+    let suggestions = suggest_selectors(&sampled);
+    
+    println!("Sampled {} page(s) from {}", sampled.len(), url);
+    for page in &sampled {
+        println!("  - {}", page.url);
+    }
+    println!();
+    
+    if suggestions.is_empty() {
+        println!("No selectors found, the profile only restricts the crawl to the site.");
+    }
+    for suggestion in &suggestions {
+        println!(
+            "{:<10} {} (matched {}/{} pages)",
+            suggestion.field, suggestion.selector, suggestion.matched_pages, sampled.len()
+        );
+        println!("           e.g. {}", suggestion.example);
+    }
+    
+    // Restrict the crawl to the sampled site and extract the suggested fields
+    if let Some(host) = url::Url::parse(&url)?.host_str() {
+        config.crawler.allowed_domains = vec![host.to_string()];
+    }
+    config.extraction.selectors = suggestions.iter()
+        .map(|suggestion| (suggestion.field.clone(), suggestion.selector.clone()))
+        .collect();
+    
+    match save {
+        Some(name) => {
+            config.save_as_profile(&name).await?;
+            println!();
+            println!("Saved profile '{}', review the selectors before crawling with it", name);
+        },
+        None => {
+            println!();
+            println!("{}", serde_yaml::to_string(&config)
+                .context("Failed to serialize profile")?);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Extract the data of a job's pages again from their raw HTML into processed storage
+pub async fn reprocess(job_id: String, profile: Option<String>) -> Result<()> {
+    let controller = match profile {
+        Some(profile) => {
+            let config = CrawlerConfig::load_profile(&profile)
+                .context(format!("Failed to load profile: {}", profile))?;
+            CrawlerController::new(config).await?
+        },
+        None => CrawlerController::connect().await?,
+    };
+    
+    let stored = controller.reprocess_job(&job_id).await?;
+    println!("Stored the data of {} page(s) of job {}", stored, job_id);
+    
+    Ok(())
+}
+
+/// Start a job fetching the pages of a job again
+pub async fn recrawl(job_id: String, profile: Option<String>) -> Result<()> {
+    let controller = match profile {
+        Some(profile) => {
+            let config = CrawlerConfig::load_profile(&profile)
+                .context(format!("Failed to load profile: {}", profile))?;
+            CrawlerController::new(config).await?
+        },
+        None => CrawlerController::connect().await?,
+    };
+    
+    let recrawl_job_id = controller.start_recrawl_job(&job_id).await?;
+    info!("Recrawl of job {} started with ID: {}", job_id, recrawl_job_id);
+    info!("Use `crawler status {}` to check which pages changed", recrawl_job_id);
+    
+    Ok(())
+}
+
+/// Generate the Rust struct of the record model declared by a profile
+pub async fn codegen(profile: String, output: Option<String>) -> Result<()> {
+    let config = CrawlerConfig::load_profile(&profile)
+        .context(format!("Failed to load profile: {}", profile))?;
+    let settings = config.extraction.model
+        .context(format!("Profile {} declares no model under extraction.model", profile))?;
+    let source = RecordModel::from_settings(&settings)?.rust_source();
+    
+    match output {
+        Some(output) => {
+            std::fs::write(&output, source)
+                .context(format!("Failed to write model source: {}", output))?;
+            println!("Wrote model {} to {}", settings.name, output);
+        },
+        None => print!("{}", source),
+    }
+    
+    Ok(())
+}
+
+/// Remove expired quarantined pages and pruned page versions, once or periodically
+pub async fn gc(every: Option<u64>) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    
+    loop {
+        let quarantined = controller.purge_expired_quarantine().await?;
+        let versions = controller.prune_page_versions().await?;
+        let requeued = controller.requeue_stale_tasks().await?;
+        let stalled = controller.detect_stalled_jobs().await?;
+        println!(
+            "{}  removed {} quarantined page(s), {} page version(s), requeued {} stale task(s), {} job(s) stalled",
+            Utc::now().format("%Y-%m-%d %H:%M:%S"), quarantined, versions, requeued, stalled.len()
+        );
+        
+        let Some(every) = every else {
+            return Ok(());
         };
-        PathBuf::from(format!("{}.{}", job_id, extension))
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(every.max(1))) => {},
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Store a schedule starting jobs of a profile on its seed URLs
+pub async fn schedule_add(cron: String, profile: String, seed_urls: Vec<String>) -> Result<()> {
+    // Fail now rather than at the first run
+    CrawlerConfig::load_profile(&profile)
+        .context(format!("Failed to load profile: {}", profile))?;
+    let schedule = CrawlSchedule::new(&cron, profile, seed_urls)?;
+    
+    let controller = CrawlerController::connect().await?;
+    controller.add_schedule(&schedule).await?;
+    
+    println!("Schedule {} added", schedule.id);
+    match schedule.next_run() {
+        Some(next_run) => println!("Next run: {}", next_run.format("%Y-%m-%d %H:%M:%S UTC")),
+        None => println!("The schedule never runs"),
+    }
+    println!("Jobs are started by `crawler serve` while it runs");
+    
+    Ok(())
+}
+
+/// List the schedules with their next and last runs
+pub async fn schedule_list() -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    let schedules = controller.list_schedules().await?;
+    if schedules.is_empty() {
+        println!("No schedules");
+        return Ok(());
+    }
+    
+    let format_time = |time: Option<DateTime<Utc>>| {
+        time.map_or_else(|| "-".to_string(), |time| time.format("%Y-%m-%d %H:%M").to_string())
     };
+    println!("{:<36}  {:<18}  {:<12}  {:<16}  {:<16}  Seeds", "ID", "Cron", "Profile", "Next Run", "Last Run");
+    for schedule in &schedules {
+        println!(
+            "{:<36}  {:<18}  {:<12}  {:<16}  {:<16}  {}",
+            schedule.id,
+            schedule.cron,
+            schedule.profile,
+            format_time(schedule.next_run()),
+            format_time(schedule.last_run_at),
+            schedule.seed_urls.join(", "),
+        );
+    }
     
-    // Export the data
-    controller.export_job_data(&job_id, &format, &output_path).await?;
+    Ok(())
+}
+
+/// Remove a schedule
+pub async fn schedule_remove(id: String) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    if !controller.remove_schedule(&id).await? {
+        anyhow::bail!("Schedule not found: {}", id);
+    }
+    println!("Schedule {} removed", id);
+    
+    Ok(())
+}
+
+/// Generate and register a service running a crawler daemon, a systemd unit
+/// on Linux or a WinSW service wrapper on Windows
+#[allow(clippy::too_many_arguments)]
+pub async fn daemon_install(
+    service: String,
+    name: Option<String>,
+    host: String,
+    port: Option<u16>,
+    every: u64,
+    restart: String,
+    restart_delay: u64,
+    log_dir: Option<String>,
+    run_as: Option<String>,
+    user: bool,
+    wrapper: Option<String>,
+    service_dir: String,
+    print: bool,
+) -> Result<()> {
+    let service = DaemonService::parse(&service, host, port, every)?;
+    if cfg!(not(feature = "grpc")) && matches!(service, DaemonService::Grpc { .. }) {
+        anyhow::bail!("The grpc daemon needs a build with the grpc feature");
+    }
+    if user && run_as.is_some() {
+        anyhow::bail!("User units run as the user installing them, --run-as needs a system unit");
+    }
+    
+    // The daemon reads its profiles from the config directory under the current one
+    let working_dir = std::env::current_dir().context("Failed to read the current directory")?;
+    let spec = DaemonSpec {
+        name: name.unwrap_or_else(|| service.default_name().to_string()),
+        service,
+        program: std::env::current_exe().context("Failed to find the crawler executable")?,
+        working_dir: working_dir.clone(),
+        restart: RestartPolicy::parse(&restart)?,
+        restart_delay,
+        log_dir: log_dir.map(|dir| working_dir.join(dir)),
+        run_as,
+    };
+    spec.validate()?;
+    
+    let windows = cfg!(windows);
+    if print {
+        print!("{}", if windows { spec.winsw_config() } else { spec.systemd_unit(user) });
+        return Ok(());
+    }
+    
+    let path = if windows {
+        let wrapper = wrapper.context("Windows services run through WinSW, pass its executable with --wrapper")?;
+        daemon::install_windows(&spec, &working_dir.join(service_dir), &PathBuf::from(wrapper)).await?
+    } else {
+        daemon::install_systemd(&spec, user).await?
+    };
+    println!("Daemon {} installed and started: {}", spec.name, path.display());
+    
+    Ok(())
+}
+
+/// Stop a daemon and remove its service
+pub async fn daemon_uninstall(name: String, user: bool, service_dir: String) -> Result<()> {
+    let path = if cfg!(windows) {
+        daemon::uninstall_windows(&name, &PathBuf::from(service_dir)).await?
+    } else {
+        daemon::uninstall_systemd(&name, user).await?
+    };
+    println!("Daemon {} stopped and removed: {}", name, path.display());
+    
+    Ok(())
+}
+
+/// Serve the REST API until interrupted
+pub async fn serve(host: String, port: u16) -> Result<()> {
+    crate::api::serve(&host, port).await
+}
+
+/// Serve the gRPC job control API until interrupted
+#[cfg(feature = "grpc")]
+pub async fn serve_grpc(host: String, port: u16) -> Result<()> {
+    crate::grpc::serve(&host, port).await
+}
+
+/// Crawl the bundled test site through the configured queue, browser
+/// service and storage, failing unless every page was crawled and extracted
+pub async fn selftest(profile: String, bind: String, site_host: Option<String>, timeout: u64) -> Result<()> {
+    use crate::crawler::selftest::TestSite;
+    
+    let site = TestSite::serve(&bind, site_host.as_deref()).await?;
+    println!("Serving the test site at {}", site.url());
+    
+    let mut config = CrawlerConfig::load_profile(&profile)
+        .context(format!("Failed to load profile: {}", profile))?;
+    site.configure(&mut config);
+    config.mode = ExecutionMode::Standalone;
+    
+    let controller = CrawlerController::new(config).await
+        .context("Failed to connect to the queue and storage")?;
+    println!("ok    queue and storage reachable");
+    
+    let job_id = controller.start_job(vec![site.url().to_string()]).await?;
+    println!("      crawling as job {}", job_id);
+    
+    let started = Instant::now();
+    let status = loop {
+        let status = controller.get_job_status(&job_id).await?;
+        if matches!(status.state.as_str(), "completed" | "failed" | "cancelled") {
+            break status;
+        }
+        if started.elapsed() >= Duration::from_secs(timeout) {
+            controller.cancel_job(&job_id).await?;
+            anyhow::bail!(
+                "Self-test crawl didn't finish within {} seconds, {} page(s) crawled",
+                timeout, status.pages_crawled
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    };
+    
+    let mut failed = false;
+    let mut report = |ok: bool, check: &str, details: &[String]| {
+        println!("{}  {}", if ok { "ok  " } else { "FAIL" }, check);
+        for line in details {
+            println!("        {}", line);
+        }
+        failed |= !ok;
+    };
+    
+    let counts = controller.get_queue_counts(&job_id).await?;
+    let dead_letters = controller.list_dead_letters(&job_id).await?;
+    let errors: Vec<String> = dead_letters.iter()
+        .map(|dead_letter| format!("{}: {}", dead_letter.task.url, dead_letter.error))
+        .collect();
+    report(
+        status.state == "completed" && counts.pending == 0 && counts.processing == 0 && errors.is_empty(),
+        &format!("job {}, {} task(s) completed, {} failed", status.state, counts.completed, dead_letters.len()),
+        &errors,
+    );
+    
+    let results = controller.list_page_results(&job_id).await?;
+    let problems = site.check_results(&results);
+    report(
+        problems.is_empty(),
+        &format!("{} page(s) fetched, extracted and stored", results.len()),
+        &problems,
+    );
+    
+    if failed {
+        anyhow::bail!("Self-test failed, see job {} for details", job_id);
+    }
+    
+    println!("Self-test passed");
+    Ok(())
+}
+
+/// Print the result of a read-only query over a job's processed data
+pub async fn query(
+    job_id: String,
+    sql: String,
+    format: String,
+    limit: usize,
+    duckdb: bool,
+    database: Option<String>,
+) -> Result<()> {
+    let format = QueryFormat::parse(&format)?;
+    let controller = CrawlerController::connect().await?;
+    
+    // Fail on unknown jobs rather than querying empty tables
+    controller.get_job_status(&job_id).await?;
+    
+    let result = if duckdb {
+        query_duckdb(&controller, &job_id, &sql, limit, database).await?
+    } else {
+        controller.query_page_data(&job_id, &sql, limit).await?
+    };
+    
+    if result.truncated && format != QueryFormat::Table {
+        warn!("Only the first {} rows are printed, raise --limit for more", limit);
+    }
+    print!("{}", result.render(format));
+    
+    Ok(())
+}
+
+/// Load a job into DuckDB and run a query over it
+#[cfg(feature = "duckdb")]
+async fn query_duckdb(
+    controller: &CrawlerController,
+    job_id: &str,
+    sql: &str,
+    limit: usize,
+    database: Option<String>,
+) -> Result<QueryResult> {
+    use crate::storage::analytics::JobDatabase;
     
-    info!("Data exported to: {}", output_path.display());
+    let database = JobDatabase::open(database.as_deref().map(std::path::Path::new))?;
+    let progress = RowProgressBar::new(&format!("Loading job {} into DuckDB", job_id));
+    let (results, pages) = database.load(controller, job_id, &progress).await?;
+    info!("Loaded {} results and {} pages into DuckDB", results, pages);
+    
+    database.query(sql, limit)
+}
+
+#[cfg(not(feature = "duckdb"))]
+async fn query_duckdb(
+    _controller: &CrawlerController,
+    _job_id: &str,
+    _sql: &str,
+    _limit: usize,
+    _database: Option<String>,
+) -> Result<QueryResult> {
+    anyhow::bail!("--duckdb needs the crawler to be built with the duckdb feature")
+}
+
+/// Print the stored versions of a page across jobs
+pub async fn history(url: String, limit: usize, json: bool) -> Result<()> {
+    let controller = CrawlerController::connect().await?;
+    
+    let versions = controller.page_history(&url, limit).await?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&versions)
+            .context("Failed to serialize page history")?);
+        return Ok(());
+    }
+    
+    if versions.is_empty() {
+        println!("No stored versions of {}.", url);
+        return Ok(());
+    }
+    
+    println!("History of {}", url);
+    for version in &versions {
+        println!();
+        println!("{}  job {}", version.stored_at.format("%Y-%m-%d %H:%M:%S"), version.job_id);
+        println!("    {}", version.data);
+    }
     
     Ok(())
 }
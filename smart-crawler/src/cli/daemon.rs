@@ -0,0 +1,405 @@
+use anyhow::{Result, Context};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::info;
+
+/// Seconds a daemon gets to stop before it is killed, longer than the default
+/// shutdown grace so workers can finish or requeue their tasks
+const STOP_TIMEOUT: u64 = 60;
+
+/// Log size in kilobytes past which the Windows wrapper rolls to a new file
+const LOG_ROLL_SIZE: u64 = 10 * 1024;
+
+/// Rolled log files the Windows wrapper keeps
+const LOG_KEEP_FILES: u32 = 8;
+
+/// Long-running crawler command run as a service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DaemonService {
+    /// REST API, also starting the jobs of crawl schedules and running the
+    /// workers of the jobs it starts in standalone mode
+    Api { host: String, port: u16 },
+    /// gRPC job control API
+    Grpc { host: String, port: u16 },
+    /// Garbage collection, requeueing the stale tasks of dead workers
+    Gc { every: u64 },
+}
+
+impl DaemonService {
+    /// Service for a daemon name, with its listening address or interval
+    pub fn parse(service: &str, host: String, port: Option<u16>, every: u64) -> Result<Self> {
+        match service.to_lowercase().as_str() {
+            "api" => Ok(Self::Api { host, port: port.unwrap_or(8080) }),
+            "grpc" => Ok(Self::Grpc { host, port: port.unwrap_or(50051) }),
+            "gc" => Ok(Self::Gc { every: every.max(1) }),
+            _ => anyhow::bail!("Unsupported daemon: {} (expected api, grpc or gc)", service),
+        }
+    }
+    
+    /// Name the service is registered under unless another one is given
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            Self::Api { .. } => "crawler-api",
+            Self::Grpc { .. } => "crawler-grpc",
+            Self::Gc { .. } => "crawler-gc",
+        }
+    }
+    
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Api { .. } => "Smart Crawler REST API",
+            Self::Grpc { .. } => "Smart Crawler gRPC API",
+            Self::Gc { .. } => "Smart Crawler garbage collection",
+        }
+    }
+    
+    /// Arguments of the crawler command the service runs
+    fn args(&self) -> Vec<String> {
+        match self {
+            Self::Api { host, port } => vec!["serve".into(), "--host".into(), host.clone(), "--port".into(), port.to_string()],
+            Self::Grpc { host, port } => vec!["serve-grpc".into(), "--host".into(), host.clone(), "--port".into(), port.to_string()],
+            Self::Gc { every } => vec!["gc".into(), "--every".into(), every.to_string()],
+        }
+    }
+}
+
+/// When the service manager restarts a daemon that exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    Always,
+    OnFailure,
+    Never,
+}
+
+impl RestartPolicy {
+    pub fn parse(policy: &str) -> Result<Self> {
+        match policy.to_lowercase().as_str() {
+            "always" => Ok(Self::Always),
+            "on-failure" => Ok(Self::OnFailure),
+            "never" | "no" => Ok(Self::Never),
+            _ => anyhow::bail!("Unsupported restart policy: {} (expected always, on-failure or never)", policy),
+        }
+    }
+}
+
+/// A crawler daemon to register with the service manager of the host
+#[derive(Debug, Clone)]
+pub struct DaemonSpec {
+    pub name: String,
+    pub service: DaemonService,
+    /// Crawler executable
+    pub program: PathBuf,
+    /// Directory the daemon runs in, where its `config` directory is
+    pub working_dir: PathBuf,
+    pub restart: RestartPolicy,
+    /// Seconds to wait before restarting
+    pub restart_delay: u64,
+    /// Directory of the daemon's log file, the journal or the wrapper's directory when None
+    pub log_dir: Option<PathBuf>,
+    /// Account the daemon runs as, systemd only
+    pub run_as: Option<String>,
+}
+
+impl DaemonSpec {
+    /// Check the service name is usable as a unit or service name, and the
+    /// account and paths can't add lines to a unit file
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            anyhow::bail!("Daemon names may only contain letters, digits, '-' and '_': {}", self.name);
+        }
+        if let Some(user) = &self.run_as {
+            let valid = user.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && user.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+            if !valid {
+                anyhow::bail!("User names may only contain letters, digits, '-', '_' and '.', starting with a letter or '_': {:?}", user);
+            }
+        }
+        for path in std::iter::once(&self.working_dir).chain(&self.log_dir) {
+            if path.to_string_lossy().chars().any(char::is_control) {
+                anyhow::bail!("Daemon paths can't contain control characters: {:?}", path);
+            }
+        }
+        Ok(())
+    }
+    
+    /// systemd unit running the daemon
+    pub fn systemd_unit(&self, user_unit: bool) -> String {
+        let exec_start = std::iter::once(self.program.display().to_string())
+            .chain(self.service.args())
+            .map(|arg| systemd_quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let restart = match self.restart {
+            RestartPolicy::Always => "always",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::Never => "no",
+        };
+        
+        let mut unit = format!(
+            "[Unit]\nDescription={}\nAfter=network-online.target\nWants=network-online.target\n\n\
+             [Service]\nType=simple\nExecStart={}\nWorkingDirectory={}\nRestart={}\nRestartSec={}\n\
+             KillSignal=SIGTERM\nTimeoutStopSec={}\n",
+            self.service.description(),
+            exec_start,
+            self.working_dir.display().to_string().replace('%', "%%"),
+            restart,
+            self.restart_delay,
+            STOP_TIMEOUT,
+        );
+        if let Some(user) = &self.run_as {
+            unit.push_str(&format!("User={}\n", user));
+        }
+        if let Some(log_dir) = &self.log_dir {
+            let log_file = log_dir.join(format!("{}.log", self.name)).display().to_string();
+            unit.push_str(&format!("StandardOutput=append:{}\nStandardError=append:{}\n", log_file, log_file));
+        }
+        
+        let target = if user_unit { "default.target" } else { "multi-user.target" };
+        unit.push_str(&format!("\n[Install]\nWantedBy={}\n", target));
+        unit
+    }
+    
+    /// Configuration of the WinSW service wrapper running the daemon on Windows
+    pub fn winsw_config(&self) -> String {
+        let arguments = self.service.args()
+            .iter()
+            .map(|arg| windows_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        
+        let mut config = format!(
+            "<service>\n  <id>{}</id>\n  <name>{}</name>\n  <description>{}</description>\n  \
+             <executable>{}</executable>\n  <arguments>{}</arguments>\n  \
+             <workingdirectory>{}</workingdirectory>\n  <stoptimeout>{} sec</stoptimeout>\n",
+            xml_escape(&self.name),
+            xml_escape(&self.name),
+            xml_escape(self.service.description()),
+            xml_escape(&self.program.display().to_string()),
+            xml_escape(&arguments),
+            xml_escape(&self.working_dir.display().to_string()),
+            STOP_TIMEOUT,
+        );
+        // The wrapper only restarts daemons exiting with an error
+        if self.restart != RestartPolicy::Never {
+            config.push_str(&format!("  <onfailure action=\"restart\" delay=\"{} sec\"/>\n", self.restart_delay));
+        }
+        if let Some(log_dir) = &self.log_dir {
+            config.push_str(&format!("  <logpath>{}</logpath>\n", xml_escape(&log_dir.display().to_string())));
+        }
+        config.push_str(&format!(
+            "  <log mode=\"roll-by-size\">\n    <sizeThreshold>{}</sizeThreshold>\n    <keepFiles>{}</keepFiles>\n  </log>\n</service>\n",
+            LOG_ROLL_SIZE, LOG_KEEP_FILES,
+        ));
+        config
+    }
+}
+
+/// Directory systemd units are installed to
+fn systemd_unit_dir(user_unit: bool) -> Result<PathBuf> {
+    if !user_unit {
+        return Ok(PathBuf::from("/etc/systemd/system"));
+    }
+    let dirs = directories::BaseDirs::new().context("Failed to find the home directory for a user unit")?;
+    Ok(dirs.config_dir().join("systemd").join("user"))
+}
+
+/// Write the systemd unit of a daemon, then enable and start it
+pub async fn install_systemd(spec: &DaemonSpec, user_unit: bool) -> Result<PathBuf> {
+    spec.validate()?;
+    
+    let unit_dir = systemd_unit_dir(user_unit)?;
+    fs::create_dir_all(&unit_dir)
+        .context(format!("Failed to create unit directory: {}", unit_dir.display()))?;
+    if let Some(log_dir) = &spec.log_dir {
+        fs::create_dir_all(log_dir)
+            .context(format!("Failed to create log directory: {}", log_dir.display()))?;
+    }
+    
+    let unit_path = unit_dir.join(format!("{}.service", spec.name));
+    fs::write(&unit_path, spec.systemd_unit(user_unit))
+        .context(format!("Failed to write unit: {}", unit_path.display()))?;
+    info!("Wrote systemd unit: {}", unit_path.display());
+    
+    systemctl(user_unit, &["daemon-reload"]).await?;
+    systemctl(user_unit, &["enable", "--now", &spec.name]).await?;
+    
+    Ok(unit_path)
+}
+
+/// Stop and disable a daemon installed with systemd, removing its unit
+pub async fn uninstall_systemd(name: &str, user_unit: bool) -> Result<PathBuf> {
+    let unit_path = systemd_unit_dir(user_unit)?.join(format!("{}.service", name));
+    if !unit_path.exists() {
+        anyhow::bail!("No unit installed for daemon {}: {}", name, unit_path.display());
+    }
+    
+    systemctl(user_unit, &["disable", "--now", name]).await?;
+    fs::remove_file(&unit_path)
+        .context(format!("Failed to remove unit: {}", unit_path.display()))?;
+    systemctl(user_unit, &["daemon-reload"]).await?;
+    
+    Ok(unit_path)
+}
+
+async fn systemctl(user_unit: bool, args: &[&str]) -> Result<()> {
+    let mut command = Command::new("systemctl");
+    if user_unit {
+        command.arg("--user");
+    }
+    let output = command.args(args)
+        .output()
+        .await
+        .context("Failed to run systemctl")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("systemctl {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(())
+}
+
+/// Set up the WinSW wrapper of a daemon in `service_dir`, then register and start it
+///
+/// WinSW reads the configuration next to its executable with the same name,
+/// so the wrapper is copied there as `<name>.exe`.
+pub async fn install_windows(spec: &DaemonSpec, service_dir: &Path, wrapper: &Path) -> Result<PathBuf> {
+    spec.validate()?;
+    
+    fs::create_dir_all(service_dir)
+        .context(format!("Failed to create service directory: {}", service_dir.display()))?;
+    if let Some(log_dir) = &spec.log_dir {
+        fs::create_dir_all(log_dir)
+            .context(format!("Failed to create log directory: {}", log_dir.display()))?;
+    }
+    
+    let executable = service_dir.join(format!("{}.exe", spec.name));
+    fs::copy(wrapper, &executable)
+        .context(format!("Failed to copy service wrapper: {}", wrapper.display()))?;
+    let config_path = service_dir.join(format!("{}.xml", spec.name));
+    fs::write(&config_path, spec.winsw_config())
+        .context(format!("Failed to write service configuration: {}", config_path.display()))?;
+    info!("Wrote service wrapper configuration: {}", config_path.display());
+    
+    winsw(&executable, "install").await?;
+    winsw(&executable, "start").await?;
+    
+    Ok(config_path)
+}
+
+/// Stop and unregister a daemon installed with the WinSW wrapper, removing its files
+pub async fn uninstall_windows(name: &str, service_dir: &Path) -> Result<PathBuf> {
+    let executable = service_dir.join(format!("{}.exe", name));
+    if !executable.exists() {
+        anyhow::bail!("No service wrapper installed for daemon {}: {}", name, executable.display());
+    }
+    
+    winsw(&executable, "stop").await?;
+    winsw(&executable, "uninstall").await?;
+    
+    let config_path = service_dir.join(format!("{}.xml", name));
+    for path in [&executable, &config_path] {
+        fs::remove_file(path).context(format!("Failed to remove {}", path.display()))?;
+    }
+    
+    Ok(config_path)
+}
+
+async fn winsw(executable: &Path, action: &str) -> Result<()> {
+    let output = Command::new(executable)
+        .arg(action)
+        .output()
+        .await
+        .context(format!("Failed to run service wrapper: {}", executable.display()))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Service wrapper {} failed: {}", action, stderr.trim());
+    }
+    Ok(())
+}
+
+/// Quote a word of a systemd command line, escaping the specifiers systemd expands
+fn systemd_quote(word: &str) -> String {
+    let escaped = word.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty() && !escaped.chars().any(|c| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        return escaped;
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote an argument of a Windows command line when it has spaces
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    format!("\"{}\"", arg.replace('"', "\\\""))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spec(service: DaemonService) -> DaemonSpec {
+        DaemonSpec {
+            name: service.default_name().to_string(),
+            service,
+            program: PathBuf::from("/opt/smart crawler/crawler"),
+            working_dir: PathBuf::from("/srv/crawler"),
+            restart: RestartPolicy::OnFailure,
+            restart_delay: 5,
+            log_dir: Some(PathBuf::from("/var/log/crawler")),
+            run_as: Some("crawler".to_string()),
+        }
+    }
+    
+    #[test]
+    fn test_service_files() {
+        let api = spec(DaemonService::parse("api", "0.0.0.0".to_string(), None, 3600).unwrap());
+        assert_eq!(api.systemd_unit(false), "[Unit]
+Description=Smart Crawler REST API
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart=\"/opt/smart crawler/crawler\" serve --host 0.0.0.0 --port 8080
+WorkingDirectory=/srv/crawler
+Restart=on-failure
+RestartSec=5
+KillSignal=SIGTERM
+TimeoutStopSec=60
+User=crawler
+StandardOutput=append:/var/log/crawler/crawler-api.log
+StandardError=append:/var/log/crawler/crawler-api.log
+
+[Install]
+WantedBy=multi-user.target
+");
+        
+        let gc = DaemonSpec { restart: RestartPolicy::Never, log_dir: None, ..spec(DaemonService::Gc { every: 600 }) };
+        assert!(gc.systemd_unit(true).contains("gc --every 600\n"));
+        assert!(gc.systemd_unit(true).contains("Restart=no\n"));
+        assert!(gc.systemd_unit(true).ends_with("WantedBy=default.target\n"));
+        
+        let config = api.winsw_config();
+        assert!(config.contains("<id>crawler-api</id>"));
+        assert!(config.contains("<arguments>serve --host 0.0.0.0 --port 8080</arguments>"));
+        assert!(config.contains("<onfailure action=\"restart\" delay=\"5 sec\"/>"));
+        assert!(config.contains("<logpath>/var/log/crawler</logpath>"));
+        assert!(!gc.winsw_config().contains("onfailure"));
+        
+        assert!(DaemonService::parse("worker", String::new(), None, 60).is_err());
+        api.validate().unwrap();
+        assert!(DaemonSpec { run_as: Some("crawler\nExecStartPre=/bin/sh".to_string()), ..spec(DaemonService::Gc { every: 600 }) }.validate().is_err());
+        assert!(DaemonSpec { log_dir: Some(PathBuf::from("/var/log\nUser=root")), ..spec(DaemonService::Gc { every: 600 }) }.validate().is_err());
+        assert!(DaemonSpec { name: "crawler api".to_string(), ..api }.validate().is_err());
+    }
+}
\ No newline at end of file
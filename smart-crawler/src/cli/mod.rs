@@ -1,24 +1,42 @@
+#[cfg(feature = "cli")]
 pub mod commands;
 pub mod config;
+#[cfg(feature = "cli")]
+pub mod daemon;
+#[cfg(feature = "cli")]
+pub mod progress;
 
+#[cfg(feature = "cli")]
 use anyhow::Result;
+#[cfg(feature = "cli")]
 use clap::{Parser, Subcommand};
+#[cfg(feature = "cli")]
 use tracing::info;
 
+#[cfg(feature = "cli")]
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// Where workers run, standalone in this process or distributed in
+    /// worker processes, over the mode of the configuration
+    #[arg(long, global = true)]
+    mode: Option<String>,
 }
 
+#[cfg(feature = "cli")]
 #[derive(Subcommand)]
 enum Commands {
     /// Start a new crawling job
     Crawl {
-        /// Target URL to start crawling from
-        #[arg(required = true)]
-        url: String,
+        /// Target URLs to start crawling from, several entry points of a site in one job
+        #[arg(
+            required_unless_present_any = ["seeds_from_job", "seeds_file", "sitemap", "feed"],
+            conflicts_with_all = ["seeds_from_job", "seeds_file", "sitemap", "feed"],
+        )]
+        urls: Vec<String>,
         
         /// Site profile to use
         #[arg(short, long, default_value = "general")]
@@ -31,6 +49,34 @@ enum Commands {
         /// Maximum number of pages to crawl
         #[arg(short, long)]
         limit: Option<u32>,
+        
+        /// Priority class of the job: low, normal or high
+        #[arg(long)]
+        priority: Option<String>,
+        
+        /// Seed the job with URLs extracted by a previous job instead of a single URL
+        #[arg(long, requires = "seeds_field")]
+        seeds_from_job: Option<String>,
+        
+        /// Extracted field of the previous job holding the seed URLs, a list or a single URL
+        #[arg(long, requires = "seeds_from_job")]
+        seeds_field: Option<String>,
+        
+        /// Seed the job with the URLs of a file, one per line, `#` starting a comment
+        #[arg(long, conflicts_with = "seeds_from_job")]
+        seeds_file: Option<std::path::PathBuf>,
+        
+        /// Seed the job with the pages of a sitemap or sitemap index, can be repeated
+        #[arg(long, conflicts_with_all = ["seeds_from_job", "seeds_file"])]
+        sitemap: Vec<String>,
+        
+        /// Seed the job with the entries of an RSS or Atom feed, can be repeated
+        #[arg(long, conflicts_with_all = ["seeds_from_job", "seeds_file", "sitemap"])]
+        feed: Vec<String>,
+        
+        /// Keep reading the feeds every this many seconds until Ctrl-C, queueing new entries
+        #[arg(long)]
+        poll: Option<u64>,
     },
     
     /// Check status of a crawling job
@@ -38,6 +84,192 @@ enum Commands {
         /// Job ID to check status for
         #[arg(required = true)]
         job_id: String,
+        
+        /// Keep refreshing the status until the job finishes or Ctrl-C
+        #[arg(short, long)]
+        watch: bool,
+        
+        /// Seconds between refreshes with --watch
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        
+        /// List the URLs the job didn't crawl and why, needs `crawler.record_skips`
+        #[arg(long, conflicts_with = "watch")]
+        skips: bool,
+        
+        /// Show the fields of the last pages crawled, 5 unless a count is given
+        #[arg(long, num_args = 0..=1, default_missing_value = "5")]
+        preview: Option<usize>,
+    },
+    
+    /// Pause a running crawling job
+    Pause {
+        /// Job ID to pause
+        #[arg(required = true)]
+        job_id: String,
+    },
+    
+    /// Resume a paused or stalled crawling job
+    Resume {
+        /// Job ID to resume
+        #[arg(required = true)]
+        job_id: String,
+    },
+    
+    /// Run a pipeline, crawling with each stage the URLs extracted by the one before
+    Pipeline {
+        /// Pipeline name, defined in config/pipelines/<name>.yaml
+        #[arg(required = true)]
+        name: String,
+        
+        /// Seed URL of the first stage
+        #[arg(required = true)]
+        url: String,
+    },
+    
+    /// Restart workers for running jobs left without any, like after a crash
+    ResumeAll {
+        /// Profile the jobs were started with, jobs of other profiles are skipped
+        #[arg(short, long, default_value = "general")]
+        profile: String,
+    },
+    
+    /// Cancel a crawling job and clean up its queue
+    Cancel {
+        /// Job ID to cancel
+        #[arg(required = true)]
+        job_id: String,
+    },
+    
+    /// Requeue the failed URLs of a job
+    RetryFailed {
+        /// Job ID to retry failed URLs for
+        #[arg(required = true)]
+        job_id: String,
+    },
+    
+    /// List the tasks of a job that failed on every retry
+    Dlq {
+        /// Job ID to list dead-letter tasks for
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Put the tasks back into the queue and restart the job if it finished
+        #[arg(long)]
+        requeue: bool,
+        
+        /// Only the task of this URL
+        #[arg(long)]
+        url: Option<String>,
+        
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// List the failure traces captured for a job with `traces.enabled`
+    Traces {
+        /// Job ID to list failure traces for
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Only the traces of this URL
+        #[arg(long)]
+        url: Option<String>,
+        
+        /// Directory to write the screenshots and HTML snapshots of the traces to
+        #[arg(long)]
+        save: Option<String>,
+        
+        /// Print the traces as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// List all crawling jobs
+    Jobs {
+        #[command(subcommand)]
+        action: Option<JobsAction>,
+        
+        /// Only show jobs in this state (pending, running, stalled, completed, failed)
+        #[arg(short, long)]
+        state: Option<String>,
+        
+        /// Print the job list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Print a site-wide audit report for a job
+    Audit {
+        /// Job ID to report on
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Audit kind (accessibility, security, resources)
+        #[arg(short, long, default_value = "accessibility")]
+        kind: String,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Write the JSON report to a file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    
+    /// Group the pages of a job by DOM structure and report the main page templates
+    Templates {
+        /// Job ID to analyze
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Compare two crawls of the same site: pages gained and lost, status and extracted field changes
+    Compare {
+        /// Baseline job ID
+        #[arg(required = true)]
+        job_a: String,
+        
+        /// Job ID compared to the baseline
+        #[arg(required = true)]
+        job_b: String,
+        
+        /// List every change rather than a summary
+        #[arg(short, long)]
+        details: bool,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Write the detailed report to a file
+        #[arg(short, long)]
+        output: Option<String>,
+        
+        /// Format of the report file (json, csv)
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    
+    /// List the pages of a job whose extracted data failed validation
+    Quarantine {
+        /// Job ID to list quarantined pages for
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Remove all quarantined pages of the job
+        #[arg(long)]
+        purge: bool,
+        
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
     },
     
     /// Export data from a completed job
@@ -46,13 +278,209 @@ enum Commands {
         #[arg(required = true)]
         job_id: String,
         
-        /// Export format (csv, json, sql)
+        /// Export formats, comma-separated (csv, json, ndjson, sql, parquet, warc, graphml, dot); ndjson is recommended for large jobs
         #[arg(short, long, default_value = "json")]
         format: String,
         
-        /// Output file path
+        /// Output file path, `-` writes a single format to stdout
         #[arg(short, long)]
         output: Option<String>,
+        
+        /// Continue an interrupted export of the same job and format
+        #[arg(long)]
+        resume: bool,
+        
+        /// Extracted data columns for CSV and Parquet, comma-separated (defaults to every field found)
+        #[arg(long)]
+        columns: Option<String>,
+        
+        /// Only export pages whose URL matches this regular expression
+        #[arg(long)]
+        include_url: Option<String>,
+        
+        /// Only export pages updated at or after this time (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        
+        /// Only export pages updated before this time (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+        
+        /// Compress the output (gzip, zstd), not available for Parquet
+        #[arg(long)]
+        compress: Option<String>,
+        
+        /// Threads serializing rows, 0 uses one per CPU
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+    
+    /// Check an exported file against its manifest
+    VerifyExport {
+        /// Exported file to verify
+        #[arg(required = true)]
+        file: String,
+    },
+    
+    /// Sample a few pages of a site and suggest extraction selectors for a new profile
+    SuggestProfile {
+        /// Site URL to sample from
+        #[arg(required = true)]
+        url: String,
+        
+        /// Number of pages to sample
+        #[arg(short = 'n', long, default_value_t = 5)]
+        pages: usize,
+        
+        /// Profile to base the suggestion on
+        #[arg(short, long, default_value = "general")]
+        profile: String,
+        
+        /// Save the suggested profile under this name instead of printing it
+        #[arg(long)]
+        save: Option<String>,
+    },
+    
+    /// Extract the data of a job's pages again from their raw HTML into processed storage
+    Reprocess {
+        /// Job ID to reprocess
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Profile to extract with, the default configuration otherwise
+        #[arg(short, long)]
+        profile: Option<String>,
+    },
+    
+    /// Fetch the pages of a job again in a new job, extracting only the ones that changed
+    Recrawl {
+        /// Job ID whose pages are fetched again
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Profile to crawl and extract with, the default configuration otherwise
+        #[arg(short, long)]
+        profile: Option<String>,
+    },
+    
+    /// Generate the Rust struct of the record model a profile declares
+    Codegen {
+        /// Profile with the model under `extraction.model`
+        #[arg(short, long, default_value = "general")]
+        profile: String,
+        
+        /// Write the struct to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    
+    /// Serve a REST API to start jobs, check their status, read pages and export data
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        
+        /// Address to bind, use 0.0.0.0 to accept connections from other hosts
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    
+    /// Serve the gRPC job control API
+    #[cfg(feature = "grpc")]
+    ServeGrpc {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 50051)]
+        port: u16,
+        
+        /// Address to bind, use 0.0.0.0 to accept connections from other hosts
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    
+    /// Run a read-only SQL query over a job's processed data, which the
+    /// query reads as the `pages` table
+    Query {
+        /// Job ID to query
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// SELECT query to run, can also be given with --sql
+        #[arg(value_name = "SQL", required_unless_present = "sql", conflicts_with = "sql")]
+        query: Option<String>,
+        
+        /// SELECT query to run
+        #[arg(long)]
+        sql: Option<String>,
+        
+        /// Output format: table, csv or json
+        #[arg(short, long, default_value = "table")]
+        format: String,
+        
+        /// Most rows to print
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+        
+        /// Load the job into DuckDB and query it there, with its raw results as the `results` table
+        #[arg(long)]
+        duckdb: bool,
+        
+        /// Keep the tables loaded with --duckdb in this DuckDB file instead of in memory
+        #[arg(long, requires = "duckdb")]
+        database: Option<String>,
+    },
+    
+    /// Show the stored versions of a page across jobs, needs versioned processed storage
+    History {
+        /// URL of the page
+        #[arg(required = true)]
+        url: String,
+        
+        /// Most versions to show, newest first
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        
+        /// Print the versions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Remove expired quarantined pages and page versions past their retention, and requeue stale tasks
+    Gc {
+        /// Keep running, collecting again every this many seconds until Ctrl-C
+        #[arg(long)]
+        every: Option<u64>,
+    },
+    
+    /// Crawl a small test site served on this host, checking that queueing,
+    /// fetching, extraction and storage work with a profile
+    Selftest {
+        /// Profile whose queue, storage and browser service settings are tested
+        #[arg(short, long, default_value = "general")]
+        profile: String,
+        
+        /// Address to serve the test site on, port 0 picks a free one
+        #[arg(long, default_value = "127.0.0.1:0")]
+        bind: String,
+        
+        /// Host name the browser service reaches this machine under, when it runs elsewhere
+        #[arg(long)]
+        site_host: Option<String>,
+        
+        /// Seconds to wait for the crawl to finish
+        #[arg(long, default_value_t = 120)]
+        timeout: u64,
+    },
+    
+    /// Crawl sites again and again on cron schedules, started by `crawler serve`
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleAction,
+    },
+    
+    /// Run the API or garbage collection as a service restarted by the host
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
     },
     
     /// Manage configuration profiles
@@ -67,25 +495,316 @@ enum Commands {
     },
 }
 
+#[cfg(feature = "cli")]
+#[derive(Subcommand)]
+enum JobsAction {
+    /// Change the limits of a job while it runs
+    Update {
+        /// Job ID to update
+        #[arg(required = true)]
+        job_id: String,
+        
+        /// Maximum number of pages to crawl
+        #[arg(short, long)]
+        limit: Option<u32>,
+        
+        /// Maximum crawling depth
+        #[arg(short, long)]
+        depth: Option<u32>,
+        
+        /// Number of workers taking tasks
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+        
+        /// Share of the idle workers of other jobs this job gets, 0 for none
+        #[arg(short, long)]
+        weight: Option<u32>,
+        
+        /// Priority class of the job: low, normal or high
+        #[arg(long)]
+        priority: Option<String>,
+    },
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand)]
+enum ScheduleAction {
+    /// Add a schedule, like `crawler schedule add "0 3 * * *" --url https://example.com`
+    Add {
+        /// Cron expression in UTC: minute, hour, day of month, month and day
+        /// of week, optionally preceded by seconds
+        #[arg(required = true)]
+        cron: String,
+        
+        /// Site profile the jobs use
+        #[arg(short, long, default_value = "general")]
+        profile: String,
+        
+        /// Seed URL of the jobs, can be repeated
+        #[arg(short, long, required = true)]
+        url: Vec<String>,
+    },
+    
+    /// List the schedules with their next and last runs
+    List,
+    
+    /// Remove a schedule, leaving the jobs it started
+    Remove {
+        /// Schedule ID to remove
+        #[arg(required = true)]
+        id: String,
+    },
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Generate a systemd unit, or a WinSW service wrapper on Windows, for a
+    /// daemon and register it, running it from the current directory
+    Install {
+        /// Daemon to run: api, grpc or gc
+        #[arg(required = true)]
+        service: String,
+        
+        /// Service name, crawler-<daemon> by default
+        #[arg(long)]
+        name: Option<String>,
+        
+        /// Address the api and grpc daemons bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        
+        /// Port the api and grpc daemons listen on, 8080 and 50051 by default
+        #[arg(short, long)]
+        port: Option<u16>,
+        
+        /// Seconds between collections of the gc daemon
+        #[arg(long, default_value_t = 3600)]
+        every: u64,
+        
+        /// When to restart the daemon: always, on-failure or never
+        #[arg(long, default_value = "on-failure")]
+        restart: String,
+        
+        /// Seconds to wait before restarting the daemon
+        #[arg(long, default_value_t = 5)]
+        restart_delay: u64,
+        
+        /// Directory to write the daemon's log to, instead of the journal or the wrapper's directory
+        #[arg(long)]
+        log_dir: Option<String>,
+        
+        /// Account to run the daemon as (systemd system units only)
+        #[arg(long)]
+        run_as: Option<String>,
+        
+        /// Install a systemd user unit instead of a system one
+        #[arg(long)]
+        user: bool,
+        
+        /// WinSW executable wrapping the daemon as a Windows service
+        #[arg(long)]
+        wrapper: Option<String>,
+        
+        /// Directory the Windows service wrapper and its configuration are put in
+        #[arg(long, default_value = "services")]
+        service_dir: String,
+        
+        /// Print the unit or wrapper configuration instead of installing it
+        #[arg(long)]
+        print: bool,
+    },
+    
+    /// Stop a daemon and remove its service
+    Uninstall {
+        /// Service name the daemon was installed under
+        #[arg(required = true)]
+        name: String,
+        
+        /// Remove a systemd user unit instead of a system one
+        #[arg(long)]
+        user: bool,
+        
+        /// Directory the Windows service wrapper was put in
+        #[arg(long, default_value = "services")]
+        service_dir: String,
+    },
+}
+
 /// Parse command line arguments
+#[cfg(feature = "cli")]
 pub fn parse_args() -> Cli {
     Cli::parse()
 }
 
 /// Process the command
+#[cfg(feature = "cli")]
 pub async fn process_command(cli: Cli) -> Result<()> {
+    if let Some(mode) = &cli.mode {
+        config::CrawlerConfig::override_mode(config::ExecutionMode::parse(mode)?);
+    }
+    
     match cli.command {
-        Commands::Crawl { url, profile, depth, limit } => {
-            info!("Starting crawl on {} with profile {}", url, profile);
-            commands::crawl(url, profile, depth, limit).await
+        Commands::Crawl { urls, profile, depth, limit, priority, seeds_from_job, seeds_field, seeds_file, sitemap, feed, poll } => {
+            let seeds = match (urls, seeds_from_job, seeds_field) {
+                _ if seeds_file.is_some() => {
+                    let path = seeds_file.expect("checked by the guard");
+                    info!("Starting crawl on the URLs of {} with profile {}", path.display(), profile);
+                    commands::Seeds::File(path)
+                },
+                _ if !feed.is_empty() => {
+                    info!("Starting crawl on the entries of {} with profile {}", feed.join(", "), profile);
+                    commands::Seeds::Feeds(feed)
+                },
+                _ if !sitemap.is_empty() => {
+                    info!("Starting crawl on the pages of {} with profile {}", sitemap.join(", "), profile);
+                    commands::Seeds::Sitemaps(sitemap)
+                },
+                (_, Some(job_id), Some(field)) => {
+                    info!("Starting crawl on the {} URLs of job {} with profile {}", field, job_id, profile);
+                    commands::Seeds::FromJob { job_id, field }
+                },
+                (urls, _, _) if !urls.is_empty() => {
+                    info!("Starting crawl on {} with profile {}", urls.join(", "), profile);
+                    commands::Seeds::Urls(urls)
+                },
+                _ => anyhow::bail!("Give a URL to crawl, --seeds-file, --sitemap, --feed or --seeds-from-job with --seeds-field"),
+            };
+            commands::crawl(seeds, profile, depth, limit, priority, poll).await
         },
-        Commands::Status { job_id } => {
+        Commands::Status { job_id, watch, interval, skips, preview } => {
             info!("Checking status for job {}", job_id);
-            commands::status(job_id).await
+            commands::status(job_id, watch, interval, skips, preview).await
+        },
+        Commands::Pause { job_id } => {
+            info!("Pausing job {}", job_id);
+            commands::pause(job_id).await
+        },
+        Commands::Resume { job_id } => {
+            info!("Resuming job {}", job_id);
+            commands::resume(job_id).await
+        },
+        Commands::Pipeline { name, url } => {
+            info!("Running pipeline {} from {}", name, url);
+            commands::pipeline(name, url).await
+        },
+        Commands::ResumeAll { profile } => {
+            info!("Resuming interrupted jobs of profile: {}", profile);
+            commands::resume_all(profile).await
+        },
+        Commands::Cancel { job_id } => {
+            info!("Cancelling job {}", job_id);
+            commands::cancel(job_id).await
+        },
+        Commands::RetryFailed { job_id } => {
+            info!("Retrying failed URLs for job {}", job_id);
+            commands::retry_failed(job_id).await
+        },
+        Commands::Dlq { job_id, requeue, url, json } => {
+            info!("Checking dead-letter tasks for job {}", job_id);
+            commands::dlq(job_id, requeue, url, json).await
+        },
+        Commands::Traces { job_id, url, save, json } => {
+            info!("Checking failure traces for job {}", job_id);
+            commands::traces(job_id, url, save, json).await
+        },
+        Commands::Jobs { action: Some(JobsAction::Update { job_id, limit, depth, concurrency, weight, priority }), .. } => {
+            info!("Updating limits of job {}", job_id);
+            commands::update_job(job_id, limit, depth, concurrency, weight, priority).await
+        },
+        Commands::Jobs { action: None, state, json } => {
+            info!("Listing jobs");
+            commands::list_jobs(state, json).await
+        },
+        Commands::Audit { job_id, kind, json, output } => {
+            info!("Building {} audit report for job {}", kind, job_id);
+            commands::audit(job_id, kind, json, output).await
+        },
+        Commands::Templates { job_id, json } => {
+            info!("Finding page templates for job {}", job_id);
+            commands::templates(job_id, json).await
+        },
+        Commands::Compare { job_a, job_b, details, json, output, format } => {
+            info!("Comparing job {} to job {}", job_b, job_a);
+            commands::compare(job_a, job_b, details, json, output, format).await
+        },
+        Commands::Quarantine { job_id, purge, json } => {
+            info!("Checking quarantined pages for job {}", job_id);
+            commands::quarantine(job_id, purge, json).await
         },
-        Commands::Export { job_id, format, output } => {
+        Commands::Export { job_id, format, output, resume, columns, include_url, since, until, compress, threads } => {
             info!("Exporting job {} as {}", job_id, format);
-            commands::export(job_id, format, output).await
+            commands::export(job_id, format, output, resume, columns, include_url, since, until, compress, threads).await
+        },
+        Commands::VerifyExport { file } => {
+            info!("Verifying export {}", file);
+            commands::verify_export(file).await
+        },
+        Commands::SuggestProfile { url, pages, profile, save } => {
+            info!("Sampling {} page(s) from {} to suggest a profile", pages, url);
+            commands::suggest_profile(url, pages, profile, save).await
+        },
+        Commands::Reprocess { job_id, profile } => {
+            info!("Reprocessing job {}", job_id);
+            commands::reprocess(job_id, profile).await
+        },
+        Commands::Recrawl { job_id, profile } => {
+            info!("Recrawling job {}", job_id);
+            commands::recrawl(job_id, profile).await
+        },
+        Commands::Codegen { profile, output } => {
+            info!("Generating the record model of profile: {}", profile);
+            commands::codegen(profile, output).await
+        },
+        Commands::Serve { port, host } => {
+            info!("Starting API server on {}:{}", host, port);
+            commands::serve(host, port).await
+        },
+        #[cfg(feature = "grpc")]
+        Commands::ServeGrpc { port, host } => {
+            info!("Starting gRPC server on {}:{}", host, port);
+            commands::serve_grpc(host, port).await
+        },
+        Commands::Query { job_id, query, sql, format, limit, duckdb, database } => {
+            info!("Querying job {}", job_id);
+            let sql = query.or(sql).unwrap_or_default();
+            commands::query(job_id, sql, format, limit, duckdb, database).await
+        },
+        Commands::History { url, limit, json } => {
+            info!("Reading the history of {}", url);
+            commands::history(url, limit, json).await
+        },
+        Commands::Gc { every } => {
+            info!("Collecting garbage");
+            commands::gc(every).await
+        },
+        Commands::Selftest { profile, bind, site_host, timeout } => {
+            info!("Running self-test with profile: {}", profile);
+            commands::selftest(profile, bind, site_host, timeout).await
+        },
+        Commands::Schedule { action: ScheduleAction::Add { cron, profile, url } } => {
+            info!("Scheduling crawls of {} with profile {}", url.join(", "), profile);
+            commands::schedule_add(cron, profile, url).await
+        },
+        Commands::Schedule { action: ScheduleAction::List } => {
+            info!("Listing schedules");
+            commands::schedule_list().await
+        },
+        Commands::Schedule { action: ScheduleAction::Remove { id } } => {
+            info!("Removing schedule {}", id);
+            commands::schedule_remove(id).await
+        },
+        Commands::Daemon { action: DaemonAction::Install {
+            service, name, host, port, every, restart, restart_delay, log_dir, run_as, user, wrapper, service_dir, print,
+        } } => {
+            info!("Installing {} daemon", service);
+            commands::daemon_install(
+                service, name, host, port, every, restart, restart_delay, log_dir, run_as, user, wrapper, service_dir, print,
+            ).await
+        },
+        Commands::Daemon { action: DaemonAction::Uninstall { name, user, service_dir } } => {
+            info!("Uninstalling daemon {}", name);
+            commands::daemon_uninstall(name, user, service_dir).await
         },
         Commands::Config { profile, list } => {
             if list {
@@ -102,7 +821,7 @@ pub async fn process_command(cli: Cli) -> Result<()> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "cli"))]
 mod tests {
     use super::*;
     
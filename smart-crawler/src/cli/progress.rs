@@ -0,0 +1,162 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::storage::progress::RowProgress;
+
+/// Row count and throughput while the total isn't known
+const SPINNER_TEMPLATE: &str = "{spinner} {msg} {human_pos} rows ({per_sec})";
+
+/// Bar with throughput and estimated time left once the total is known
+const BAR_TEMPLATE: &str = "{msg} [{bar:30}] {human_pos}/{human_len} rows ({per_sec}, {eta} left)";
+
+/// Progress bar on stderr for the rows of a long operation, so it doesn't look
+/// hung. Nothing is drawn when stderr isn't a terminal.
+#[derive(Debug)]
+pub struct RowProgressBar {
+    bar: ProgressBar,
+}
+
+impl RowProgressBar {
+    pub fn new(message: &str) -> Self {
+        let bar = ProgressBar::new_spinner()
+            .with_style(ProgressStyle::with_template(SPINNER_TEMPLATE).expect("valid progress template"))
+            .with_message(message.to_string());
+        bar.enable_steady_tick(Duration::from_millis(200));
+        
+        Self { bar }
+    }
+}
+
+impl RowProgress for RowProgressBar {
+    fn set_total(&self, rows: u64) {
+        self.bar.set_length(rows);
+        self.bar.set_style(ProgressStyle::with_template(BAR_TEMPLATE)
+            .expect("valid progress template")
+            .progress_chars("=> "));
+    }
+    
+    fn advance(&self, rows: u64) {
+        self.bar.inc(rows);
+    }
+    
+    fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Crawl rate measured over the latest samples of the pages crawled
+///
+/// Only samples within the window count, so the rate follows slowdowns and
+/// speedups rather than averaging over the whole job.
+#[derive(Debug)]
+pub struct RateTracker {
+    samples: VecDeque<(Instant, usize)>,
+    window: Duration,
+}
+
+impl RateTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            window,
+        }
+    }
+    
+    /// Record the number of pages crawled at a point in time
+    pub fn record(&mut self, at: Instant, pages: usize) {
+        self.samples.push_back((at, pages));
+        
+        // Keep one sample at or before the start of the window to measure from
+        while self.samples.len() > 2 && at.duration_since(self.samples[1].0) >= self.window {
+            self.samples.pop_front();
+        }
+    }
+    
+    /// Pages crawled per second, None until two samples are apart in time
+    pub fn rate(&self) -> Option<f64> {
+        let (first_at, first_pages) = self.samples.front()?;
+        let (last_at, last_pages) = self.samples.back()?;
+        
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        
+        Some(last_pages.saturating_sub(*first_pages) as f64 / elapsed)
+    }
+    
+    /// Time left to crawl `remaining` pages at the current rate
+    pub fn eta(&self, remaining: usize) -> Option<Duration> {
+        if remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+        
+        let rate = self.rate()?;
+        if rate <= 0.0 {
+            return None;
+        }
+        
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+/// Short duration for progress displays, like `1h 05m`, `2m 10s` or `45s`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Text progress bar of `width` characters
+pub fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = if total == 0 { 0 } else { (done.min(total) * width) / total };
+    format!("[{}{}]", "#".repeat(filled), ".".repeat(width - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rate_tracker() {
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut tracker = RateTracker::new(Duration::from_secs(10));
+        
+        tracker.record(at(0), 0);
+        assert_eq!(tracker.rate(), None);
+        
+        tracker.record(at(5), 50);
+        assert_eq!(tracker.rate(), Some(10.0));
+        assert_eq!(tracker.eta(100), Some(Duration::from_secs(10)));
+        
+        // Older samples leave the window, the rate follows the slowdown
+        tracker.record(at(10), 60);
+        tracker.record(at(20), 70);
+        assert_eq!(tracker.rate(), Some(1.0));
+        
+        // A stalled crawl has no estimate
+        tracker.record(at(35), 70);
+        tracker.record(at(45), 70);
+        assert_eq!(tracker.rate(), Some(0.0));
+        assert_eq!(tracker.eta(10), None);
+        assert_eq!(tracker.eta(0), Some(Duration::ZERO));
+    }
+    
+    #[test]
+    fn test_format_progress() {
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(130)), "2m 10s");
+        assert_eq!(format_duration(Duration::from_secs(3900)), "1h 05m");
+        
+        assert_eq!(progress_bar(1, 4, 8), "[##......]");
+        assert_eq!(progress_bar(9, 4, 4), "[####]");
+        assert_eq!(progress_bar(0, 0, 4), "[....]");
+    }
+}
\ No newline at end of file
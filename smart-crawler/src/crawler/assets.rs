@@ -0,0 +1,302 @@
+use anyhow::{Result, Context};
+use chrono::Utc;
+use regex::Regex;
+use reqwest::Client;
+use scraper::{Html, Selector};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::browser::remote::BrandAssetLinks;
+use crate::cli::config::AssetSettings;
+use crate::storage::raw::{RawStorageBackend, StoredAsset};
+
+/// Most assets linked from a single page that are downloaded
+const MAX_DOWNLOADS_PER_PAGE: usize = 50;
+
+/// Reference to a captured asset, stored in the page's extracted data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetRef {
+    /// Asset kind ("favicon", "og_image", "payload", "image", "document")
+    pub kind: String,
+    
+    /// URL the asset was downloaded from
+    pub url: String,
+    
+    /// SHA-256 of the content, key of the asset in raw storage
+    pub hash: String,
+    
+    /// Content type reported by the server
+    pub content_type: String,
+    
+    /// Size in bytes
+    pub size: usize,
+}
+
+/// Downloads favicons, og:images and the assets pages link to and stores
+/// them in raw storage
+pub struct AssetCapture {
+    client: Client,
+    settings: AssetSettings,
+    
+    /// URL patterns of the linked images and documents to download
+    downloads: Vec<Regex>,
+    
+    /// Results per (job, asset URL), None for assets that could not be captured.
+    /// Favicons are shared by every page of a domain, so each is fetched once.
+    captured: Mutex<HashMap<(String, String), Option<AssetRef>>>,
+}
+
+impl AssetCapture {
+    /// Create a new asset capture
+    pub fn new(settings: AssetSettings) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+        let downloads = settings.downloads.iter()
+            .map(|pattern| Regex::new(pattern).context(format!("Invalid asset download pattern: {}", pattern)))
+            .collect::<Result<_>>()?;
+        
+        Ok(Self {
+            client,
+            settings,
+            downloads,
+            captured: Mutex::new(HashMap::new()),
+        })
+    }
+    
+    /// Create an asset capture if it is enabled in the settings
+    pub fn from_settings(settings: &AssetSettings) -> Result<Option<Self>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+        
+        Ok(Some(Self::new(settings.clone())?))
+    }
+    
+    /// Capture the assets declared by a page, returning references to the stored copies
+    pub async fn capture(
+        &self,
+        job_id: &str,
+        page_url: &str,
+        links: &BrandAssetLinks,
+        raw_storage: &Arc<dyn RawStorageBackend>,
+    ) -> Vec<AssetRef> {
+        let mut candidates = Vec::new();
+        if self.settings.favicons {
+            candidates.extend(links.favicons.iter().map(|url| ("favicon", url.as_str())));
+        }
+        if self.settings.og_images {
+            candidates.extend(links.og_image.iter().map(|url| ("og_image", url.as_str())));
+        }
+        
+        let mut assets = Vec::new();
+        for (kind, url) in candidates {
+            // Resolve relative URLs against the page
+            let url = match Url::parse(page_url).and_then(|base| base.join(url)) {
+                Ok(url) => url.to_string(),
+                Err(_) => continue,
+            };
+            
+            if let Some(asset) = self.capture_one(job_id, kind, &url, raw_storage).await {
+                assets.push(asset);
+            }
+        }
+        
+        assets
+    }
+    
+    /// Download the images and documents a page links to whose URL matches
+    /// a download pattern, returning references to the stored copies
+    pub async fn capture_linked(
+        &self,
+        job_id: &str,
+        page_url: &str,
+        html: &str,
+        raw_storage: &Arc<dyn RawStorageBackend>,
+    ) -> Vec<AssetRef> {
+        if self.downloads.is_empty() {
+            return Vec::new();
+        }
+        
+        let mut assets = Vec::new();
+        for (kind, url) in linked_assets(page_url, html, &self.downloads) {
+            if let Some(asset) = self.capture_one(job_id, kind, &url, raw_storage).await {
+                assets.push(asset);
+            }
+        }
+        
+        assets
+    }
+    
+    /// Capture the body of a binary page, when payloads are captured
+    pub async fn capture_payload(
+        &self,
+        job_id: &str,
+        url: &str,
+        raw_storage: &Arc<dyn RawStorageBackend>,
+    ) -> Option<AssetRef> {
+        if !self.settings.payloads {
+            return None;
+        }
+        
+        self.capture_one(job_id, "payload", url, raw_storage).await
+    }
+    
+    /// Capture a single asset, reusing an earlier capture of the same URL
+    async fn capture_one(
+        &self,
+        job_id: &str,
+        kind: &str,
+        url: &str,
+        raw_storage: &Arc<dyn RawStorageBackend>,
+    ) -> Option<AssetRef> {
+        let key = (job_id.to_string(), url.to_string());
+        if let Some(captured) = self.captured.lock().await.get(&key) {
+            return captured.clone().map(|asset| AssetRef { kind: kind.to_string(), ..asset });
+        }
+        
+        let asset = match self.download(job_id, url).await {
+            Ok(stored) => match raw_storage.store_asset(&stored).await {
+                Ok(()) => {
+                    debug!("Captured {} for job {}: {}", kind, job_id, url);
+                    Some(AssetRef {
+                        kind: kind.to_string(),
+                        url: url.to_string(),
+                        hash: stored.hash,
+                        content_type: stored.content_type,
+                        size: stored.content.len(),
+                    })
+                },
+                Err(e) => {
+                    warn!("Failed to store {} {}: {}", kind, url, e);
+                    None
+                }
+            },
+            Err(e) => {
+                debug!("Skipping {} {}: {}", kind, url, e);
+                None
+            }
+        };
+        
+        self.captured.lock().await.insert(key, asset.clone());
+        
+        asset
+    }
+    
+    /// Download an asset, enforcing the size limit
+    async fn download(&self, job_id: &str, url: &str) -> Result<StoredAsset> {
+        let response = self.client.get(url)
+            .send()
+            .await
+            .context("Failed to download asset")?
+            .error_for_status()
+            .context("Asset request failed")?;
+        
+        if response.content_length().map_or(false, |len| len as usize > self.settings.max_size) {
+            anyhow::bail!("Asset exceeds {} bytes", self.settings.max_size);
+        }
+        
+        let content_type = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        
+        // Missing assets are often answered with an HTML error page
+        if content_type.starts_with("text/html") {
+            anyhow::bail!("Unexpected content type: {}", content_type);
+        }
+        
+        let content = response.bytes()
+            .await
+            .context("Failed to read asset")?
+            .to_vec();
+        
+        if content.len() > self.settings.max_size {
+            anyhow::bail!("Asset exceeds {} bytes", self.settings.max_size);
+        }
+        
+        Ok(StoredAsset {
+            job_id: job_id.to_string(),
+            hash: format!("{:x}", Sha256::digest(&content)),
+            url: url.to_string(),
+            content_type,
+            fetched_at: Utc::now(),
+            content,
+        })
+    }
+}
+
+/// Images and linked documents of a page whose absolute URL matches one of
+/// the patterns, by kind and URL, without duplicates
+fn linked_assets(page_url: &str, html: &str, patterns: &[Regex]) -> Vec<(&'static str, String)> {
+    let Ok(base) = Url::parse(page_url) else {
+        return Vec::new();
+    };
+    let document = Html::parse_document(html);
+    let sources = [
+        ("image", "img[src]", "src"),
+        ("image", "source[src]", "src"),
+        ("document", "a[href]", "href"),
+        ("document", "embed[src]", "src"),
+        ("document", "object[data]", "data"),
+    ];
+    
+    let mut seen = HashSet::new();
+    let mut assets = Vec::new();
+    for (kind, selector, attribute) in sources {
+        let selector = Selector::parse(selector).expect("valid asset selector");
+        for element in document.select(&selector) {
+            let Some(url) = element.value().attr(attribute).and_then(|value| base.join(value.trim()).ok()) else {
+                continue;
+            };
+            if !matches!(url.scheme(), "http" | "https") {
+                continue;
+            }
+            
+            let url = url.to_string();
+            if patterns.iter().any(|pattern| pattern.is_match(&url)) && seen.insert(url.clone()) {
+                assets.push((kind, url));
+            }
+        }
+    }
+    
+    if assets.len() > MAX_DOWNLOADS_PER_PAGE {
+        warn!("{} links to {} assets to download, keeping the first {}", page_url, assets.len(), MAX_DOWNLOADS_PER_PAGE);
+        assets.truncate(MAX_DOWNLOADS_PER_PAGE);
+    }
+    assets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_linked_assets() {
+        let html = r#"<html><body>
+            <img src="/images/kettle.jpg"><img src="data:image/png;base64,AAAA">
+            <a href="manuals/kettle.pdf">Manual</a>
+            <a href="https://cdn.example.com/prices.csv?v=2">Prices</a>
+            <a href="/kettles/red">Red kettle</a>
+            <object data="/images/kettle.jpg"></object>
+        </body></html>"#;
+        let patterns = vec![
+            Regex::new(r"\.(jpg|png)$").unwrap(),
+            Regex::new(r"\.(pdf|csv)(\?|$)").unwrap(),
+        ];
+        
+        assert_eq!(linked_assets("https://shop.example.com/kettles/", html, &patterns), vec![
+            ("image", "https://shop.example.com/images/kettle.jpg".to_string()),
+            ("document", "https://shop.example.com/kettles/manuals/kettle.pdf".to_string()),
+            ("document", "https://cdn.example.com/prices.csv?v=2".to_string()),
+        ]);
+    }
+}
\ No newline at end of file
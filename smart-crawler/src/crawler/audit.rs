@@ -0,0 +1,470 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+use crate::browser::remote::LoadedResource;
+use crate::crawler::task::TaskResult;
+
+/// Summary of a single accessibility rule across a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSummary {
+    /// axe-core rule identifier
+    pub id: String,
+    
+    /// Impact level ("critical", "serious", "moderate", "minor")
+    pub impact: String,
+    
+    /// Rule description
+    pub description: String,
+    
+    /// Link to the rule documentation
+    pub help_url: String,
+    
+    /// Number of pages violating the rule
+    pub pages: usize,
+    
+    /// Number of offending elements across all pages
+    pub nodes: usize,
+    
+    /// A few URLs where the rule is violated
+    pub example_urls: Vec<String>,
+}
+
+/// Site-wide accessibility report built from per-page axe-core results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessibilityReport {
+    /// Pages that were audited
+    pub pages_audited: usize,
+    
+    /// Pages with at least one violation
+    pub pages_with_violations: usize,
+    
+    /// Violated rules, most severe first
+    pub rules: Vec<RuleSummary>,
+}
+
+/// Maximum number of example URLs kept per rule
+const MAX_EXAMPLE_URLS: usize = 5;
+
+impl AccessibilityReport {
+    /// Build a report from the page results of a job
+    pub fn from_results(results: &[TaskResult]) -> Self {
+        let mut rules: HashMap<String, RuleSummary> = HashMap::new();
+        let mut pages_audited = 0;
+        let mut pages_with_violations = 0;
+        
+        for result in results {
+            let violations = match result.extracted_data["accessibility"]["violations"].as_array() {
+                Some(violations) => violations,
+                None => continue,
+            };
+            
+            pages_audited += 1;
+            if !violations.is_empty() {
+                pages_with_violations += 1;
+            }
+            
+            for violation in violations {
+                let id = match violation["id"].as_str() {
+                    Some(id) => id,
+                    None => continue,
+                };
+                
+                let summary = rules.entry(id.to_string()).or_insert_with(|| RuleSummary {
+                    id: id.to_string(),
+                    impact: violation["impact"].as_str().unwrap_or("unknown").to_string(),
+                    description: violation["description"].as_str().unwrap_or_default().to_string(),
+                    help_url: violation["help_url"].as_str().unwrap_or_default().to_string(),
+                    pages: 0,
+                    nodes: 0,
+                    example_urls: Vec::new(),
+                });
+                
+                summary.pages += 1;
+                summary.nodes += violation["nodes"].as_array().map_or(0, |nodes| nodes.len());
+                if summary.example_urls.len() < MAX_EXAMPLE_URLS {
+                    summary.example_urls.push(result.url.clone());
+                }
+            }
+        }
+        
+        let mut rules: Vec<RuleSummary> = rules.into_values().collect();
+        rules.sort_by(|a, b| {
+            impact_rank(&a.impact).cmp(&impact_rank(&b.impact))
+                .then(b.pages.cmp(&a.pages))
+                .then(a.id.cmp(&b.id))
+        });
+        
+        Self {
+            pages_audited,
+            pages_with_violations,
+            rules,
+        }
+    }
+}
+
+/// Presence of one security header across a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderCoverage {
+    /// Lowercase header name
+    pub header: String,
+    
+    /// Number of pages sending the header
+    pub pages_present: usize,
+    
+    /// Number of pages missing the header
+    pub pages_missing: usize,
+    
+    /// A few URLs missing the header
+    pub example_missing_urls: Vec<String>,
+}
+
+/// Cookie set without the recommended security flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CookieIssue {
+    /// Cookie name
+    pub name: String,
+    
+    /// Cookie was seen without the Secure flag
+    pub missing_secure: bool,
+    
+    /// Cookie was seen without the HttpOnly flag
+    pub missing_http_only: bool,
+    
+    /// Number of pages where the cookie was seen with a missing flag
+    pub pages: usize,
+}
+
+/// Site-wide security header report built from per-page header captures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityHeaderReport {
+    /// Pages with security information recorded
+    pub pages_audited: usize,
+    
+    /// Pages where the browser could not expose response headers
+    pub pages_without_headers: usize,
+    
+    /// Coverage for each audited header
+    pub headers: Vec<HeaderCoverage>,
+    
+    /// Cookies missing Secure or HttpOnly
+    pub cookies: Vec<CookieIssue>,
+}
+
+impl SecurityHeaderReport {
+    /// Build a report from the page results of a job
+    pub fn from_results(results: &[TaskResult]) -> Self {
+        let mut headers: Vec<HeaderCoverage> = Vec::new();
+        let mut cookies: HashMap<String, CookieIssue> = HashMap::new();
+        let mut pages_audited = 0;
+        let mut pages_without_headers = 0;
+        
+        for result in results {
+            let security = &result.extracted_data["security"];
+            if security.is_null() {
+                continue;
+            }
+            
+            pages_audited += 1;
+            
+            if security["headers_available"].as_bool() == Some(true) {
+                if let Some(page_headers) = security["headers"].as_object() {
+                    for (name, value) in page_headers {
+                        let position = match headers.iter().position(|h| h.header == *name) {
+                            Some(position) => position,
+                            None => {
+                                headers.push(HeaderCoverage {
+                                    header: name.clone(),
+                                    pages_present: 0,
+                                    pages_missing: 0,
+                                    example_missing_urls: Vec::new(),
+                                });
+                                headers.len() - 1
+                            }
+                        };
+                        let coverage = &mut headers[position];
+                        
+                        if value.is_null() {
+                            coverage.pages_missing += 1;
+                            if coverage.example_missing_urls.len() < MAX_EXAMPLE_URLS {
+                                coverage.example_missing_urls.push(result.url.clone());
+                            }
+                        } else {
+                            coverage.pages_present += 1;
+                        }
+                    }
+                }
+            } else {
+                pages_without_headers += 1;
+            }
+            
+            for cookie in security["cookies"].as_array().into_iter().flatten() {
+                let missing_secure = cookie["secure"].as_bool() != Some(true);
+                let missing_http_only = cookie["http_only"].as_bool() != Some(true);
+                if !missing_secure && !missing_http_only {
+                    continue;
+                }
+                
+                let name = cookie["name"].as_str().unwrap_or_default().to_string();
+                let issue = cookies.entry(name.clone()).or_insert_with(|| CookieIssue {
+                    name,
+                    missing_secure: false,
+                    missing_http_only: false,
+                    pages: 0,
+                });
+                issue.missing_secure |= missing_secure;
+                issue.missing_http_only |= missing_http_only;
+                issue.pages += 1;
+            }
+        }
+        
+        let mut cookies: Vec<CookieIssue> = cookies.into_values().collect();
+        cookies.sort_by(|a, b| b.pages.cmp(&a.pages).then(a.name.cmp(&b.name)));
+        
+        Self {
+            pages_audited,
+            pages_without_headers,
+            headers,
+            cookies,
+        }
+    }
+}
+
+/// Sub-resource of a page classified for the resource inventory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifiedResource {
+    /// Resource URL
+    pub url: String,
+    
+    /// Initiator type reported by the browser ("script", "img", "css", ...)
+    pub resource_type: String,
+    
+    /// Resource host
+    pub host: String,
+    
+    /// Resource is served from a different site than the page
+    pub third_party: bool,
+    
+    /// Resource is loaded over HTTP by an HTTPS page
+    pub mixed_content: bool,
+}
+
+/// Classify the resources loaded by a page
+pub fn classify_resources(page_url: &str, resources: &[LoadedResource]) -> Vec<ClassifiedResource> {
+    let page = Url::parse(page_url).ok();
+    let page_secure = page.as_ref().map_or(false, |u| u.scheme() == "https");
+    let page_site = page.as_ref()
+        .and_then(|u| u.host_str())
+        .map(site_of)
+        .unwrap_or_default();
+    
+    resources.iter()
+        .filter_map(|resource| {
+            let url = Url::parse(&resource.url).ok()?;
+            let host = url.host_str()?.to_lowercase();
+            
+            Some(ClassifiedResource {
+                url: resource.url.clone(),
+                resource_type: resource.resource_type.clone(),
+                third_party: site_of(&host) != page_site,
+                mixed_content: page_secure && url.scheme() == "http",
+                host,
+            })
+        })
+        .collect()
+}
+
+/// Approximate the registrable domain of a host from its last labels
+fn site_of(host: &str) -> String {
+    let host = host.to_lowercase();
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() <= 2 || host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+    
+    // Keep three labels for country second-level suffixes like co.uk or com.au
+    let second_level = labels[labels.len() - 2];
+    let country_suffix = labels[labels.len() - 1].len() == 2
+        && matches!(second_level, "co" | "com" | "net" | "org" | "gov" | "edu" | "ac" | "ne" | "or");
+    let keep = if country_suffix { 3 } else { 2 };
+    labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+/// Third-party host seen across a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThirdPartyHost {
+    /// Host name
+    pub host: String,
+    
+    /// Number of pages loading from the host
+    pub pages: usize,
+    
+    /// Number of resources loaded from the host
+    pub resources: usize,
+    
+    /// Resource types loaded from the host
+    pub resource_types: Vec<String>,
+}
+
+/// Page loading insecure resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixedContentPage {
+    /// Page URL
+    pub url: String,
+    
+    /// HTTP resources loaded by the page
+    pub resources: Vec<String>,
+}
+
+/// Site-wide inventory of third-party and mixed-content resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceInventoryReport {
+    /// Pages with a resource inventory
+    pub pages_audited: usize,
+    
+    /// Total resources loaded across all pages
+    pub total_resources: usize,
+    
+    /// Third-party hosts, most widely used first
+    pub third_party_hosts: Vec<ThirdPartyHost>,
+    
+    /// Pages with mixed content
+    pub mixed_content_pages: Vec<MixedContentPage>,
+}
+
+impl ResourceInventoryReport {
+    /// Build a report from the page results of a job
+    pub fn from_results(results: &[TaskResult]) -> Self {
+        let mut hosts: HashMap<String, (ThirdPartyHost, HashSet<String>)> = HashMap::new();
+        let mut mixed_content_pages = Vec::new();
+        let mut pages_audited = 0;
+        let mut total_resources = 0;
+        
+        for result in results {
+            let resources: Vec<ClassifiedResource> = match serde_json::from_value(result.extracted_data["resources"].clone()) {
+                Ok(resources) => resources,
+                Err(_) => continue,
+            };
+            
+            pages_audited += 1;
+            total_resources += resources.len();
+            
+            let mut page_hosts = HashSet::new();
+            let mut mixed = Vec::new();
+            
+            for resource in &resources {
+                if resource.mixed_content {
+                    mixed.push(resource.url.clone());
+                }
+                if !resource.third_party {
+                    continue;
+                }
+                
+                let (entry, types) = hosts.entry(resource.host.clone()).or_insert_with(|| {
+                    (ThirdPartyHost {
+                        host: resource.host.clone(),
+                        pages: 0,
+                        resources: 0,
+                        resource_types: Vec::new(),
+                    }, HashSet::new())
+                });
+                entry.resources += 1;
+                types.insert(resource.resource_type.clone());
+                if page_hosts.insert(resource.host.clone()) {
+                    entry.pages += 1;
+                }
+            }
+            
+            if !mixed.is_empty() {
+                mixed_content_pages.push(MixedContentPage {
+                    url: result.url.clone(),
+                    resources: mixed,
+                });
+            }
+        }
+        
+        let mut third_party_hosts: Vec<ThirdPartyHost> = hosts.into_values()
+            .map(|(mut host, types)| {
+                host.resource_types = types.into_iter().collect();
+                host.resource_types.sort();
+                host
+            })
+            .collect();
+        third_party_hosts.sort_by(|a, b| b.pages.cmp(&a.pages).then(a.host.cmp(&b.host)));
+        mixed_content_pages.sort_by(|a, b| a.url.cmp(&b.url));
+        
+        Self {
+            pages_audited,
+            total_resources,
+            third_party_hosts,
+            mixed_content_pages,
+        }
+    }
+}
+
+/// Sort key for axe-core impact levels, most severe first
+fn impact_rank(impact: &str) -> u8 {
+    match impact {
+        "critical" => 0,
+        "serious" => 1,
+        "moderate" => 2,
+        "minor" => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn page(url: &str, violations: serde_json::Value) -> TaskResult {
+        TaskResult {
+            extracted_data: serde_json::json!({ "accessibility": { "violations": violations } }),
+            ..TaskResult::test_page(url)
+        }
+    }
+    
+    #[test]
+    fn test_classify_resources() {
+        let resources = vec![
+            LoadedResource { url: "https://cdn.example.com/app.js".to_string(), resource_type: "script".to_string() },
+            LoadedResource { url: "http://example.com/logo.png".to_string(), resource_type: "img".to_string() },
+            LoadedResource { url: "https://www.google-analytics.com/ga.js".to_string(), resource_type: "script".to_string() },
+            LoadedResource { url: "https://static.example.co.uk/a.css".to_string(), resource_type: "css".to_string() },
+        ];
+        
+        let classified = classify_resources("https://www.example.com/page", &resources);
+        
+        assert!(!classified[0].third_party && !classified[0].mixed_content);
+        assert!(!classified[1].third_party && classified[1].mixed_content);
+        assert!(classified[2].third_party);
+        assert!(classified[3].third_party);
+        assert_eq!(site_of("static.example.co.uk"), "example.co.uk");
+    }
+    
+    #[test]
+    fn test_accessibility_report() {
+        let results = vec![
+            page("https://example.com/a", serde_json::json!([
+                { "id": "image-alt", "impact": "critical", "nodes": ["img"] },
+                { "id": "color-contrast", "impact": "serious", "nodes": ["p", "span"] },
+            ])),
+            page("https://example.com/b", serde_json::json!([
+                { "id": "color-contrast", "impact": "serious", "nodes": ["h1"] },
+            ])),
+            page("https://example.com/c", serde_json::json!([])),
+        ];
+        
+        let report = AccessibilityReport::from_results(&results);
+        
+        assert_eq!(report.pages_audited, 3);
+        assert_eq!(report.pages_with_violations, 2);
+        assert_eq!(report.rules.len(), 2);
+        
+        // Critical rules come first regardless of page count
+        assert_eq!(report.rules[0].id, "image-alt");
+        assert_eq!(report.rules[1].id, "color-contrast");
+        assert_eq!(report.rules[1].pages, 2);
+        assert_eq!(report.rules[1].nodes, 3);
+    }
+}
\ No newline at end of file
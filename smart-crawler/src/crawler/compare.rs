@@ -0,0 +1,218 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::crawler::task::TaskResult;
+use crate::storage::export::csv_field;
+
+/// Page whose HTTP status differs between the two jobs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub url: String,
+    pub before: u16,
+    pub after: u16,
+}
+
+/// Extracted field whose value differs between the two jobs, None where the
+/// page didn't have the field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub url: String,
+    pub field: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Differences between two crawls of the same site
+///
+/// Pages are matched by URL. The first job is the baseline, so gained pages
+/// are only in the second job and lost pages only in the first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobComparison {
+    pub job_a: String,
+    pub job_b: String,
+    pub pages_a: usize,
+    pub pages_b: usize,
+    
+    /// Pages crawled by both jobs with the same status and extracted data
+    pub pages_unchanged: usize,
+    
+    pub pages_gained: Vec<String>,
+    pub pages_lost: Vec<String>,
+    pub status_changes: Vec<StatusChange>,
+    pub field_changes: Vec<FieldChange>,
+}
+
+impl JobComparison {
+    /// Compare the results of two jobs, all lists are sorted by URL
+    pub fn from_results(job_a: &str, results_a: &[TaskResult], job_b: &str, results_b: &[TaskResult]) -> Self {
+        let pages_a = pages_by_url(results_a);
+        let pages_b = pages_by_url(results_b);
+        
+        let mut comparison = Self {
+            job_a: job_a.to_string(),
+            job_b: job_b.to_string(),
+            pages_a: pages_a.len(),
+            pages_b: pages_b.len(),
+            pages_unchanged: 0,
+            pages_gained: pages_b.keys().filter(|url| !pages_a.contains_key(*url)).map(|url| url.to_string()).collect(),
+            pages_lost: pages_a.keys().filter(|url| !pages_b.contains_key(*url)).map(|url| url.to_string()).collect(),
+            status_changes: Vec::new(),
+            field_changes: Vec::new(),
+        };
+        
+        for (url, before) in &pages_a {
+            let Some(after) = pages_b.get(url) else {
+                continue;
+            };
+            
+            let mut changed = false;
+            if before.status_code != after.status_code {
+                comparison.status_changes.push(StatusChange {
+                    url: url.to_string(),
+                    before: before.status_code,
+                    after: after.status_code,
+                });
+                changed = true;
+            }
+            
+            let fields_before = fields(&before.extracted_data);
+            let fields_after = fields(&after.extracted_data);
+            let names: BTreeSet<&String> = fields_before.keys().chain(fields_after.keys()).collect();
+            for name in names {
+                let value_before = fields_before.get(name).copied();
+                let value_after = fields_after.get(name).copied();
+                if value_before != value_after {
+                    comparison.field_changes.push(FieldChange {
+                        url: url.to_string(),
+                        field: name.clone(),
+                        before: value_before.cloned(),
+                        after: value_after.cloned(),
+                    });
+                    changed = true;
+                }
+            }
+            
+            if !changed {
+                comparison.pages_unchanged += 1;
+            }
+        }
+        
+        comparison
+    }
+    
+    /// Number of pages crawled by both jobs whose extracted data changed
+    pub fn pages_with_field_changes(&self) -> usize {
+        self.field_changes.iter()
+            .map(|change| &change.url)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+    
+    /// One CSV row per difference, as `change,url,field,before,after`
+    pub fn to_csv(&self) -> String {
+        let mut output = String::from("change,url,field,before,after\n");
+        let mut row = |change: &str, url: &str, field: &str, before: &str, after: &str| {
+            let fields = [change, url, field, before, after].map(csv_field);
+            output.push_str(&fields.join(","));
+            output.push('\n');
+        };
+        
+        for url in &self.pages_gained {
+            row("gained", url, "", "", "");
+        }
+        for url in &self.pages_lost {
+            row("lost", url, "", "", "");
+        }
+        for change in &self.status_changes {
+            row("status", &change.url, "", &change.before.to_string(), &change.after.to_string());
+        }
+        for change in &self.field_changes {
+            let text = |value: &Option<Value>| value.as_ref().map(Value::to_string).unwrap_or_default();
+            row("field", &change.url, &change.field, &text(&change.before), &text(&change.after));
+        }
+        
+        output
+    }
+}
+
+/// Results keyed by URL, the latest crawl of a URL wins
+fn pages_by_url(results: &[TaskResult]) -> BTreeMap<&str, &TaskResult> {
+    let mut pages: BTreeMap<&str, &TaskResult> = BTreeMap::new();
+    for result in results {
+        match pages.get(result.url.as_str()) {
+            Some(page) if page.crawled_at > result.crawled_at => {},
+            _ => {
+                pages.insert(&result.url, result);
+            },
+        }
+    }
+    pages
+}
+
+/// Top-level fields of extracted data, data that isn't an object is a single `value` field
+fn fields(data: &Value) -> BTreeMap<String, &Value> {
+    match data {
+        Value::Object(map) => map.iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(name, value)| (name.clone(), value))
+            .collect(),
+        Value::Null => BTreeMap::new(),
+        other => BTreeMap::from([("value".to_string(), other)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn result(job_id: &str, url: &str, status_code: u16, extracted_data: Value) -> TaskResult {
+        TaskResult {
+            job_id: job_id.to_string(),
+            status_code,
+            extracted_data,
+            ..TaskResult::test_page(url)
+        }
+    }
+    
+    #[test]
+    fn test_compare_jobs() {
+        let before = vec![
+            result("a", "https://example.com/", 200, json!({"title": "Home"})),
+            result("a", "https://example.com/old", 200, json!({})),
+            result("a", "https://example.com/item", 200, json!({"price": "10", "stock": 3})),
+            result("a", "https://example.com/gone", 200, json!({"title": "Gone"})),
+        ];
+        let after = vec![
+            result("b", "https://example.com/", 200, json!({"title": "Home"})),
+            result("b", "https://example.com/item", 200, json!({"price": "12", "sale": true})),
+            result("b", "https://example.com/gone", 404, json!({"title": "Gone"})),
+            result("b", "https://example.com/new", 200, json!({})),
+        ];
+        
+        let comparison = JobComparison::from_results("a", &before, "b", &after);
+        assert_eq!(comparison.pages_unchanged, 1);
+        assert_eq!(comparison.pages_gained, vec!["https://example.com/new"]);
+        assert_eq!(comparison.pages_lost, vec!["https://example.com/old"]);
+        assert_eq!(comparison.status_changes, vec![StatusChange {
+            url: "https://example.com/gone".to_string(),
+            before: 200,
+            after: 404,
+        }]);
+        
+        let fields: Vec<(&str, Option<Value>, Option<Value>)> = comparison.field_changes.iter()
+            .map(|change| (change.field.as_str(), change.before.clone(), change.after.clone()))
+            .collect();
+        assert_eq!(fields, vec![
+            ("price", Some(json!("10")), Some(json!("12"))),
+            ("sale", None, Some(json!(true))),
+            ("stock", Some(json!(3)), None),
+        ]);
+        assert_eq!(comparison.pages_with_field_changes(), 1);
+        
+        let csv = comparison.to_csv();
+        assert!(csv.contains("status,https://example.com/gone,,200,404\n"));
+        assert!(csv.contains("field,https://example.com/item,price,\"\"\"10\"\"\",\"\"\"12\"\"\"\n"));
+    }
+}
\ No newline at end of file
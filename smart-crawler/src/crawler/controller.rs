@@ -1,37 +1,97 @@
 use anyhow::{Result, Context};
 use chrono::Utc;
+#[cfg(feature = "duckdb")]
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use rand::{thread_rng, Rng};
+use serde::de::DeserializeOwned;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, instrument, warn};
 use url::Url;
 use uuid::Uuid;
 
-use crate::browser::fingerprint::FingerprintManager;
-use crate::browser::remote::RemoteBrowserService;
-use crate::cli::config::CrawlerConfig;
+use crate::browser::captcha::CaptchaSolver;
+use crate::browser::fingerprint::{CompleteFingerprint, FingerprintManager};
+use crate::browser::remote::{BrowserServiceError, BrowserServiceResponse, CrawlOptions, RemoteBrowserService};
+use crate::cli::config::{CrawlerConfig, ExecutionMode, JobPriority};
+use crate::crawler::assets::AssetCapture;
+use crate::crawler::audit::classify_resources;
+use crate::crawler::error_policy::{ErrorAction, ErrorWindow};
+use crate::crawler::pacing::Pacing;
+use crate::crawler::plugins::Plugins;
+use crate::crawler::task::TaskTimeout;
+use crate::crawler::extract;
+use crate::crawler::extractors;
+use crate::crawler::feed::{self, FeedEntry};
+use crate::crawler::quarantine;
+use crate::crawler::recrawl::{PageChange, RecrawlSummary};
+use crate::crawler::robots::PageRobots;
+use crate::crawler::schedule::{self, CrawlSchedule};
 use crate::crawler::scheduler::Scheduler;
-use crate::crawler::task::{CrawlTask, TaskResult};
-use crate::storage::queue::QueueManager;
-use crate::storage::raw::{RawStorage, RawStorageBackend, JobStatus};
-use crate::storage::processed::{ProcessedStorage, ProcessedStorageFactory};
+use crate::crawler::scripts::Scripts;
+use crate::crawler::sitemap;
+use crate::crawler::simhash::{self, NearDuplicateAction, NearDuplicatePolicy};
+use crate::crawler::task::{ContentKind, CrawlTask, DeadLetter, QuarantinedPage, RecordPreview, TaskResult, TaskTrace};
+use crate::storage::export::{
+    export_job, save_skipped_urls, ExportFormat, ExportManifest, ExportOptions, ExportSummary, ExportTarget, SchemaFile,
+};
+use crate::storage::queue::{QueueCounts, QueueManager};
+use crate::storage::models::{RecordModel, TypedRecord};
+use crate::storage::retry::task_retry_delay;
+use crate::storage::raw::{RawStorage, RawStorageBackend, JobLimits, JobStatus, SeedProgress};
+use crate::storage::processed::{PageVersion, ProcessedStorage, ProcessedStorageFactory, RowFilter};
+use crate::storage::query::QueryResult;
+use crate::storage::scheduler_state::{DomainStats, RedisSchedulerState};
+#[cfg(feature = "duckdb")]
+use crate::storage::processed::PageData;
+use crate::storage::graph::export_graph;
+use crate::storage::warc::export_warc;
+use crate::utils::shutdown;
+
+/// Extracted data of a page that comes from the browser service or the crawl
+/// rather than its HTML, kept as it is when the page is reprocessed
+const CRAWL_FIELDS: &[&str] = &["accessibility", "security", "resources", "assets", "near_duplicate_of", "feed", "recrawl"];
+
+/// Least time between the attempts of an idle worker to take tasks of other jobs
+const STEAL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Time between the checks of a job's concurrency limit for workers to start
+const WORKER_SCALE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
 
 pub struct CrawlerController {
     config: CrawlerConfig,
     queue: Arc<QueueManager>,
-    scheduler: Arc<Mutex<Scheduler>>,
+    scheduler: Arc<Scheduler>,
     raw_storage: Arc<dyn RawStorageBackend>,
     processed_storage: Arc<dyn ProcessedStorage>,
     browser_service: Arc<RemoteBrowserService>,
+    captcha_solver: Option<Arc<CaptchaSolver>>,
+    asset_capture: Option<Arc<AssetCapture>>,
+    plugins: Arc<Plugins>,
+    scripts: Arc<Scripts>,
 }
 
 impl CrawlerController {
     /// Create a new crawler controller with the given configuration
     pub async fn new(config: CrawlerConfig) -> Result<Self> {
+        // Catch broken selectors, models, scripts, unknown extractors and plugins before any page is fetched
+        extract::validate_selectors(&config.extraction.selectors)?;
+        extractors::validate_extractors(&config.extraction.extractors)?;
+        if let Some(model) = &config.extraction.model {
+            RecordModel::from_settings(model)?;
+        }
+        let plugins = Arc::new(Plugins::load(&config.plugins, &config.extraction.extractors)?);
+        let scripts = Arc::new(Scripts::compile(&config.scripts)?);
+        
         // Initialize queue manager
         let queue = Arc::new(QueueManager::new(&config.storage.queue).await?);
         
-        // Initialize scheduler
-        let scheduler = Arc::new(Mutex::new(Scheduler::new(config.crawler.clone())));
+        // Initialize scheduler, its state is shared with other crawlers through Redis
+        let scheduler_state = Arc::new(RedisSchedulerState::new(&config.storage.queue).await?);
+        let scheduler = Arc::new(Scheduler::new(config.crawler.clone(), scheduler_state).with_plugins(plugins.clone()));
         
         // Initialize storage
         let raw_storage = RawStorage::create(&config.storage.raw_data).await?;
@@ -39,6 +99,12 @@ impl CrawlerController {
         // Initialize browser service
         let browser_service = Arc::new(RemoteBrowserService::new());
         
+        // Initialize captcha solver if enabled
+        let captcha_solver = CaptchaSolver::from_settings(&config.captcha)?.map(Arc::new);
+        
+        // Initialize asset capture if enabled
+        let asset_capture = AssetCapture::from_settings(&config.assets)?.map(Arc::new);
+        
         Ok(Self {
             config,
             queue,
@@ -46,6 +112,10 @@ impl CrawlerController {
             raw_storage,
             processed_storage,
             browser_service,
+            captcha_solver,
+            asset_capture,
+            plugins,
+            scripts,
         })
     }
     
@@ -53,18 +123,27 @@ impl CrawlerController {
     pub async fn connect() -> Result<Self> {
         // Load the default configuration
         let config = CrawlerConfig::load_default()?;
+        let plugins = Arc::new(Plugins::load(&config.plugins, &config.extraction.extractors)?);
+        let scripts = Arc::new(Scripts::compile(&config.scripts)?);
         
         // Connect to existing components rather than creating new ones
         let queue = Arc::new(QueueManager::connect(&config.storage.queue).await?);
         let raw_storage = RawStorage::connect(&config.storage.raw_data).await?;
         let processed_storage = ProcessedStorageFactory::connect(&config.storage.processed_data).await?;
         
-        // Create a new scheduler (stateless component)
-        let scheduler = Arc::new(Mutex::new(Scheduler::new(config.crawler.clone())));
+        // Create a new scheduler over the shared scheduler state
+        let scheduler_state = Arc::new(RedisSchedulerState::new(&config.storage.queue).await?);
+        let scheduler = Arc::new(Scheduler::new(config.crawler.clone(), scheduler_state).with_plugins(plugins.clone()));
         
         // Initialize browser service
         let browser_service = Arc::new(RemoteBrowserService::new());
         
+        // Initialize captcha solver if enabled
+        let captcha_solver = CaptchaSolver::from_settings(&config.captcha)?.map(Arc::new);
+        
+        // Initialize asset capture if enabled
+        let asset_capture = AssetCapture::from_settings(&config.assets)?.map(Arc::new);
+        
         Ok(Self {
             config,
             queue,
@@ -72,44 +151,213 @@ impl CrawlerController {
             raw_storage,
             processed_storage,
             browser_service,
+            captcha_solver,
+            asset_capture,
+            plugins,
+            scripts,
         })
     }
     
-    /// Start a new crawling job
-    pub async fn start_job(&self, seed_url: String) -> Result<String> {
+    /// Start a new crawling job from one or more entry points of a site, the
+    /// progress of each kept in the job status
+    #[instrument(skip_all, fields(seed_url = seed_urls.first().map(String::as_str).unwrap_or_default()))]
+    pub async fn start_job(&self, seed_urls: Vec<String>) -> Result<String> {
+        self.start_seeded_job(seed_urls, None, Vec::new()).await
+    }
+    
+    /// Start a crawling job from several seed URLs, like the URLs a previous
+    /// pipeline stage extracted, all crawled at depth 0
+    ///
+    /// The pages listed in the profile's sitemaps and feeds are seeds too,
+    /// the sitemap pages that changed recently first. They go through the
+    /// domain and URL filters, sitemaps up to the job's page limit.
+    pub async fn start_seeded_job(
+        &self,
+        seed_urls: Vec<String>,
+        parent_job_id: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<String> {
+        let sitemaps = &self.config.crawler.sitemaps;
+        let feeds = &self.config.crawler.feeds;
+        let Some(seed_url) = seed_urls.first().or(sitemaps.first()).or(feeds.first()).cloned() else {
+            anyhow::bail!("A job needs at least one seed URL, sitemap or feed");
+        };
+        
+        // Workers parse these settings for every page, fail before queueing anything
+        NearDuplicatePolicy::from_settings(&self.config.near_duplicates)?;
+        
+        let sitemap_entries = if sitemaps.is_empty() {
+            Vec::new()
+        } else {
+            let limit = self.config.crawler.max_pages as usize;
+            sitemap::read_sitemaps(sitemaps, &self.config.crawler.user_agent, limit).await?
+        };
+        let feed_entries = if feeds.is_empty() {
+            Vec::new()
+        } else {
+            feed::read_feeds(feeds, &self.config.crawler.user_agent).await?
+        };
+        
         // Generate a unique job ID
         let job_id = Uuid::new_v4().to_string();
         
+        // Create the initial tasks, links back to a seed aren't crawled again
+        let mut tasks = Vec::with_capacity(seed_urls.len());
+        let mut seeds: Vec<SeedProgress> = Vec::with_capacity(seed_urls.len());
+        for url in seed_urls {
+            if seeds.iter().any(|seed| seed.url == url) {
+                continue;
+            }
+            self.scheduler.mark_seen(&job_id, &url).await?;
+            tasks.push(CrawlTask {
+                job_id: job_id.clone(),
+                url: url.clone(),
+                depth: 0,
+                parent_url: None,
+                priority: 0,
+                attempt: 0,
+                feed_entry: None,
+                seed: Some(seeds.len()),
+                recrawl_of: None,
+            });
+            seeds.push(SeedProgress { url, pages_crawled: 0, pages_total: 1 });
+        }
+        let now = Utc::now();
+        for entry in sitemap_entries {
+            if self.scheduler.should_crawl(&job_id, &entry.url).await? {
+                tasks.push(CrawlTask {
+                    job_id: job_id.clone(),
+                    priority: entry.priority(now),
+                    url: entry.url,
+                    depth: 0,
+                    parent_url: None,
+                    attempt: 0,
+                    feed_entry: None,
+                    seed: None,
+                    recrawl_of: None,
+                });
+            }
+        }
+        for entry in feed_entries {
+            if self.scheduler.should_crawl(&job_id, &entry.url).await? {
+                tasks.push(Self::feed_task(&job_id, entry));
+            }
+        }
+        if tasks.is_empty() {
+            anyhow::bail!("The sitemaps and feeds list no URL the profile crawls");
+        }
+        
         // Create the initial job status
         let status = JobStatus {
             job_id: job_id.clone(),
-            seed_url: seed_url.clone(),
+            seed_url,
             state: "pending".to_string(),
             pages_crawled: 0,
-            pages_total: 1,  // Start with the seed URL
+            pages_total: tasks.len(),  // Start with the seed URLs
             started_at: Utc::now(),
             updated_at: Utc::now(),
             errors: Vec::new(),
+            captcha_spend: 0.0,
+            limits: Some(JobLimits::from_settings(&self.config.crawler)),
+            stop_reason: None,
+            pages_per_minute: 0.0,
+            avg_page_ms: 0,
+            eta_seconds: None,
+            config_hash: Some(self.config.fingerprint()?),
+            parent_job_id,
+            tags,
+            seeds,
+            recrawl: None,
         };
         
-        // Store the job status
-        self.raw_storage.store_job_status(&status).await?;
+        self.launch_job(status, tasks).await
+    }
+    
+    /// Start a job fetching the pages a previous job crawled again, without
+    /// following their links
+    ///
+    /// Pages whose content is the same as in the previous job keep the data
+    /// extracted from them then, only changed and new pages are extracted
+    /// again. Each page's data says how it changed under `recrawl`, and the
+    /// job status counts them.
+    pub async fn start_recrawl_job(&self, previous_job_id: &str) -> Result<String> {
+        let previous = self.raw_storage.get_job_status(previous_job_id).await?;
         
-        // Create the initial task
-        let task = CrawlTask {
+        let job_id = Uuid::new_v4().to_string();
+        let mut tasks = Vec::new();
+        let mut urls = std::collections::HashSet::new();
+        for result in self.raw_storage.list_page_results(previous_job_id).await? {
+            if !urls.insert(result.url.clone()) {
+                continue;
+            }
+            self.scheduler.mark_seen(&job_id, &result.url).await?;
+            tasks.push(CrawlTask {
+                job_id: job_id.clone(),
+                url: result.url,
+                depth: 0,
+                parent_url: None,
+                priority: 0,
+                attempt: 0,
+                feed_entry: None,
+                seed: None,
+                recrawl_of: Some(previous_job_id.to_string()),
+            });
+        }
+        if tasks.is_empty() {
+            anyhow::bail!("Job {} crawled no pages to recrawl", previous_job_id);
+        }
+        
+        // Every page of the previous job, and none of the pages they link to
+        let mut limits = JobLimits::from_settings(&self.config.crawler);
+        limits.max_pages = u32::try_from(tasks.len()).unwrap_or(u32::MAX);
+        limits.max_depth = 0;
+        
+        let status = JobStatus {
             job_id: job_id.clone(),
-            url: seed_url,
-            depth: 0,
-            parent_url: None,
-            priority: 0,
+            seed_url: previous.seed_url,
+            state: "pending".to_string(),
+            pages_crawled: 0,
+            pages_total: tasks.len(),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            errors: Vec::new(),
+            captcha_spend: 0.0,
+            limits: Some(limits),
+            stop_reason: None,
+            pages_per_minute: 0.0,
+            avg_page_ms: 0,
+            eta_seconds: None,
+            config_hash: Some(self.config.fingerprint()?),
+            parent_job_id: None,
+            tags: previous.tags,
+            seeds: Vec::new(),
+            recrawl: Some(RecrawlSummary::new(previous_job_id)),
         };
         
-        // Add the task to the queue
-        self.queue.push_task(&task).await?;
+        self.launch_job(status, tasks).await
+    }
+    
+    /// Store the status of a new job, queue its first tasks and start its
+    /// workers in standalone mode
+    async fn launch_job(&self, status: JobStatus, tasks: Vec<CrawlTask>) -> Result<String> {
+        let job_id = status.job_id.clone();
+        
+        // Store the job status
+        self.raw_storage.store_job_status(&status).await?;
+        
+        // Housekeeping, drop quarantined pages past their retention
+        if let Err(e) = self.purge_expired_quarantine().await {
+            warn!("Failed to purge expired quarantined pages: {}", e);
+        }
+        
+        for task in &tasks {
+            self.queue.push_task(task).await?;
+        }
         
         // Start worker threads if in standalone mode
-        #[cfg(feature = "standalone")]
-        self.start_workers(job_id.clone()).await?;
+        if self.config.mode == ExecutionMode::Standalone {
+            self.start_workers(job_id.clone()).await?;
+        }
         
         // Update job status to running
         let mut updated_status = status;
@@ -119,57 +367,881 @@ impl CrawlerController {
         Ok(job_id)
     }
     
+    /// Read the profile's feeds again, queueing the entries a job hasn't seen
+    /// and restarting the job if it had completed, returning how many were
+    /// queued. None once the job is cancelled or failed.
+    pub async fn poll_feeds(&self, job_id: &str) -> Result<Option<usize>> {
+        let mut status = self.raw_storage.get_job_status(job_id).await?;
+        if status.state == "cancelled" || status.state == "failed" {
+            return Ok(None);
+        }
+        
+        let mut queued = 0;
+        for entry in feed::read_feeds(&self.config.crawler.feeds, &self.config.crawler.user_agent).await? {
+            if self.scheduler.should_crawl(job_id, &entry.url).await? {
+                self.queue.push_task(&Self::feed_task(job_id, entry)).await?;
+                queued += 1;
+            }
+        }
+        if queued == 0 {
+            return Ok(Some(0));
+        }
+        
+        let restart = status.state == "completed";
+        if restart {
+            status.state = "running".to_string();
+            status.stop_reason = None;
+        }
+        status.pages_total += queued;
+        status.updated_at = Utc::now();
+        self.raw_storage.store_job_status(&status).await?;
+        
+        if restart && self.config.mode == ExecutionMode::Standalone {
+            self.start_workers(job_id.to_string()).await?;
+        }
+        
+        Ok(Some(queued))
+    }
+    
+    /// Depth 0 task crawling the page of a feed entry
+    fn feed_task(job_id: &str, entry: FeedEntry) -> CrawlTask {
+        CrawlTask {
+            job_id: job_id.to_string(),
+            url: entry.url.clone(),
+            depth: 0,
+            parent_url: Some(entry.feed_url.clone()),
+            priority: 0,
+            attempt: 0,
+            feed_entry: Some(entry),
+            seed: None,
+            recrawl_of: None,
+        }
+    }
+    
     /// Get the status of a job
     pub async fn get_job_status(&self, job_id: &str) -> Result<JobStatus> {
         self.raw_storage.get_job_status(job_id).await
     }
     
-    /// Export job data
-    pub async fn export_job_data(&self, job_id: &str, format: &str, output_path: &std::path::Path) -> Result<()> {
-        match format {
-            "json" => {
-                self.processed_storage.export_as_json(job_id, output_path).await?;
-            },
-            "csv" => {
-                self.processed_storage.export_as_csv(job_id, output_path).await?;
-            },
-            "sql" => {
-                self.processed_storage.export_as_sql(job_id, output_path).await?;
-            },
-            _ => {
-                anyhow::bail!("Unsupported export format: {}", format);
+    /// Wait until a job completes, fails or is cancelled, checking its status
+    /// every `poll`
+    pub async fn wait_for_job(&self, job_id: &str, poll: Duration) -> Result<JobStatus> {
+        loop {
+            let status = self.raw_storage.get_job_status(job_id).await?;
+            if matches!(status.state.as_str(), "completed" | "failed" | "cancelled") {
+                return Ok(status);
             }
+            tokio::time::sleep(poll).await;
+        }
+    }
+    
+    /// Get the task counters of a job's queue
+    pub async fn get_queue_counts(&self, job_id: &str) -> Result<QueueCounts> {
+        self.queue.get_counts(job_id).await
+    }
+    
+    /// Get the pages crawled per domain of a job, most crawled first
+    pub async fn get_domain_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>> {
+        self.scheduler.domain_counts(job_id).await
+    }
+    
+    /// Pause a running job, leaving its queued tasks in place
+    pub async fn pause_job(&self, job_id: &str) -> Result<()> {
+        let mut status = self.raw_storage.get_job_status(job_id).await?;
+        if status.state != "running" && status.state != "pending" {
+            anyhow::bail!("Cannot pause job {} in state '{}'", job_id, status.state);
         }
         
+        self.queue.set_paused(job_id, true).await?;
+        
+        status.state = "paused".to_string();
+        status.updated_at = Utc::now();
+        self.raw_storage.store_job_status(&status).await?;
+        
         Ok(())
     }
     
-    /// Process a crawl task
+    /// Resume a paused or stalled job
+    ///
+    /// The tasks stalled jobs left processing go back to the queue first, and
+    /// in standalone mode their workers are restarted.
+    pub async fn resume_job(&self, job_id: &str) -> Result<()> {
+        let mut status = self.raw_storage.get_job_status(job_id).await?;
+        if status.state != "paused" && status.state != "stalled" {
+            anyhow::bail!("Cannot resume job {} in state '{}'", job_id, status.state);
+        }
+        let stalled = status.state == "stalled";
+        if stalled {
+            self.reclaim_orphaned_tasks(job_id).await?;
+        }
+        
+        status.state = "running".to_string();
+        status.stop_reason = None;
+        status.updated_at = Utc::now();
+        self.raw_storage.store_job_status(&status).await?;
+        
+        self.queue.set_paused(job_id, false).await?;
+        
+        if stalled && self.config.mode == ExecutionMode::Standalone {
+            self.start_workers(job_id.to_string()).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Cancel a job, stop its workers and release its queue resources
+    pub async fn cancel_job(&self, job_id: &str) -> Result<()> {
+        let mut status = self.raw_storage.get_job_status(job_id).await?;
+        if status.state == "completed" || status.state == "failed" || status.state == "cancelled" {
+            anyhow::bail!("Cannot cancel job {} in state '{}'", job_id, status.state);
+        }
+        
+        // Signal workers first so nothing new gets pushed while we clean up
+        self.queue.set_cancelled(job_id).await?;
+        
+        status.state = "cancelled".to_string();
+        status.updated_at = Utc::now();
+        self.raw_storage.store_job_status(&status).await?;
+        
+        self.queue.clear_job(job_id).await?;
+        self.scheduler.clear(job_id).await?;
+        
+        Ok(())
+    }
+    
+    /// Change the limits of an unfinished job, values left out are kept
+    ///
+    /// Workers read the limits from the job status before each task, so the
+    /// change applies without restarting the job. In standalone mode, workers
+    /// are started or stopped within a second to match a new concurrency.
+    pub async fn update_job_limits(
+        &self,
+        job_id: &str,
+        max_pages: Option<u32>,
+        max_depth: Option<u32>,
+        concurrency: Option<usize>,
+        weight: Option<u32>,
+        priority: Option<JobPriority>,
+    ) -> Result<JobLimits> {
+        let mut status = self.raw_storage.get_job_status(job_id).await?;
+        if !matches!(status.state.as_str(), "pending" | "running" | "paused") {
+            anyhow::bail!("Cannot update limits of job {} in state '{}'", job_id, status.state);
+        }
+        if let Some(concurrency) = concurrency {
+            if !(1..=JobLimits::MAX_CONCURRENCY).contains(&concurrency) {
+                anyhow::bail!("Concurrency must be between 1 and {}", JobLimits::MAX_CONCURRENCY);
+            }
+        }
+        
+        let mut limits = status.limits.unwrap_or_else(|| JobLimits::from_settings(&self.config.crawler));
+        if let Some(max_pages) = max_pages {
+            if (max_pages as usize) <= status.pages_crawled {
+                warn!("Job {} already crawled {} pages, it will stop", job_id, status.pages_crawled);
+            }
+            limits.max_pages = max_pages;
+        }
+        if let Some(max_depth) = max_depth {
+            limits.max_depth = max_depth;
+        }
+        if let Some(concurrency) = concurrency {
+            limits.concurrency = concurrency;
+        }
+        if let Some(weight) = weight {
+            limits.weight = weight;
+        }
+        if let Some(priority) = priority {
+            limits.priority = priority;
+        }
+        
+        status.limits = Some(limits);
+        status.updated_at = Utc::now();
+        self.raw_storage.store_job_status(&status).await?;
+        
+        Ok(limits)
+    }
+    
+    /// Move failed URLs back into the queue, all of them or only `url`, and restart the job
+    pub async fn retry_failed(&self, job_id: &str, url: Option<&str>) -> Result<usize> {
+        let mut status = self.raw_storage.get_job_status(job_id).await?;
+        if status.state == "cancelled" {
+            anyhow::bail!("Cannot retry failed URLs of cancelled job {}", job_id);
+        }
+        
+        let requeued = self.queue.requeue_failed(job_id, url).await?;
+        if requeued == 0 {
+            return Ok(0);
+        }
+        
+        // Finished jobs need to run again to pick up the requeued tasks
+        let restart = status.state == "completed" || status.state == "failed";
+        if restart {
+            status.state = "running".to_string();
+            status.stop_reason = None;
+        }
+        status.updated_at = Utc::now();
+        self.raw_storage.store_job_status(&status).await?;
+        
+        if restart && self.config.mode == ExecutionMode::Standalone {
+            self.start_workers(job_id.to_string()).await?;
+        }
+        
+        Ok(requeued)
+    }
+    
+    /// Fields of the pages a job crawled last, newest first, up to `count`
+    pub async fn recent_records(&self, job_id: &str, count: usize) -> Result<Vec<RecordPreview>> {
+        self.queue.recent_previews(job_id, count).await
+    }
+    
+    /// Store a new crawl schedule
+    pub async fn add_schedule(&self, schedule: &CrawlSchedule) -> Result<()> {
+        self.queue.add_schedule(schedule).await
+    }
+    
+    /// Every crawl schedule, oldest first
+    pub async fn list_schedules(&self) -> Result<Vec<CrawlSchedule>> {
+        self.queue.list_schedules().await
+    }
+    
+    /// Remove a crawl schedule, returning whether it existed
+    pub async fn remove_schedule(&self, id: &str) -> Result<bool> {
+        self.queue.remove_schedule(id).await
+    }
+    
+    /// Start the jobs of due schedules until shutdown is requested
+    pub async fn run_schedules(&self) {
+        schedule::run_schedules(&self.queue).await
+    }
+    
+    /// Get the tasks of a job that failed on every retry, oldest first
+    pub async fn list_dead_letters(&self, job_id: &str) -> Result<Vec<DeadLetter>> {
+        self.queue.list_dead_letters(job_id).await
+    }
+    
+    /// Get the failure traces of a job, oldest first
+    pub async fn list_task_traces(&self, job_id: &str) -> Result<Vec<TaskTrace>> {
+        let mut traces = self.raw_storage.list_task_traces(job_id).await?;
+        traces.sort_by(|a, b| a.captured_at.cmp(&b.captured_at));
+        
+        Ok(traces)
+    }
+    
+    /// Renew the lease of a task with heartbeats for as long as it is processed
+    async fn renew_lease(queue: &QueueManager, worker_id: &str, job_id: &str, url: &str) {
+        loop {
+            tokio::time::sleep(queue.heartbeat_interval()).await;
+            if let Err(e) = queue.heartbeat(worker_id, job_id, Some(url)).await {
+                error!("Failed to renew lease of task {}: {}", url, e);
+            }
+        }
+    }
+    
+    /// Wait until a job is flagged as cancelled
+    async fn wait_for_cancel(queue: &QueueManager, job_id: &str) {
+        loop {
+            if queue.is_cancelled(job_id).await.unwrap_or(false) {
+                return;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        }
+    }
+    
+    /// Restart workers for running and stalled jobs that were left without
+    /// any, like after the host crawling them crashed, returning the resumed
+    /// job IDs
+    ///
+    /// Only jobs started with this controller's configuration are resumed.
+    /// Their processing sets are reconciled first, the tasks held by dead
+    /// workers go back to the queue.
+    pub async fn resume_interrupted(&self) -> Result<Vec<String>> {
+        let config_hash = self.config.fingerprint()?;
+        
+        let mut resumed = Vec::new();
+        for mut job in self.raw_storage.list_jobs().await? {
+            if job.state != "running" && job.state != "stalled" {
+                continue;
+            }
+            if job.config_hash.as_deref() != Some(config_hash.as_str()) {
+                info!("Skipping job {}, started with another configuration", job.job_id);
+                continue;
+            }
+            
+            let workers = self.queue.get_worker_count(&job.job_id).await?;
+            if workers > 0 {
+                info!("Skipping job {}, {} worker(s) still running it", job.job_id, workers);
+                continue;
+            }
+            
+            self.reclaim_orphaned_tasks(&job.job_id).await?;
+            
+            if self.queue.get_pending_count(&job.job_id).await? == 0 {
+                info!("Skipping job {}, no tasks left in its queue", job.job_id);
+                continue;
+            }
+            
+            if job.state == "stalled" {
+                job.state = "running".to_string();
+                job.stop_reason = None;
+                job.updated_at = Utc::now();
+                self.raw_storage.store_job_status(&job).await?;
+            }
+            
+            self.start_workers(job.job_id.clone()).await?;
+            resumed.push(job.job_id);
+        }
+        
+        Ok(resumed)
+    }
+    
+    /// Mark running jobs whose workers sent no heartbeat for the stall timeout
+    /// of the profile as stalled, returning their IDs
+    ///
+    /// Each stalled job's status is posted to the alert URL of the profile.
+    /// With auto-resume, jobs started with this controller's configuration
+    /// get their orphaned tasks back in the queue and run again right away.
+    /// Stalled jobs whose workers come back are marked running again.
+    pub async fn detect_stalled_jobs(&self) -> Result<Vec<String>> {
+        let settings = &self.config.crawler.stall;
+        if !settings.enabled {
+            return Ok(Vec::new());
+        }
+        let config_hash = self.config.fingerprint()?;
+        
+        let mut stalled = Vec::new();
+        for mut job in self.raw_storage.list_jobs().await? {
+            if job.state != "running" && job.state != "stalled" {
+                continue;
+            }
+            let heartbeat = self.queue.last_heartbeat(&job.job_id).await?;
+            
+            if job.state == "stalled" {
+                if heartbeat.is_some_and(|at| at > job.updated_at) {
+                    info!("Workers of stalled job {} are back, marking it running", job.job_id);
+                    job.state = "running".to_string();
+                    job.stop_reason = None;
+                    job.updated_at = Utc::now();
+                    self.raw_storage.store_job_status(&job).await?;
+                }
+                continue;
+            }
+            
+            // Status updates of the workers also show they are alive
+            let last_seen = heartbeat.map_or(job.updated_at, |at| at.max(job.updated_at));
+            let silent = (Utc::now() - last_seen).num_seconds();
+            if silent < settings.after as i64 {
+                continue;
+            }
+            
+            job.state = "stalled".to_string();
+            job.stop_reason = Some(format!("no worker heartbeat for {} minutes", silent / 60));
+            job.updated_at = Utc::now();
+            self.raw_storage.store_job_status(&job).await?;
+            
+            error!("Job {} stalled, its workers sent no heartbeat for {} seconds", job.job_id, silent);
+            if let Some(url) = &settings.alert_url {
+                Self::send_stall_alert(url, &job).await;
+            }
+            
+            if settings.auto_resume && job.config_hash.as_deref() == Some(config_hash.as_str()) {
+                info!("Resuming stalled job: {}", job.job_id);
+                self.resume_job(&job.job_id).await?;
+            } else {
+                error!("Use `crawler resume {}` to continue crawling", job.job_id);
+            }
+            stalled.push(job.job_id);
+        }
+        
+        Ok(stalled)
+    }
+    
+    /// Post the status of a stalled job to an alert URL
+    async fn send_stall_alert(url: &str, status: &JobStatus) {
+        let sent = reqwest::Client::new().post(url).json(status).send().await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            warn!("Failed to send stall alert of job {} to {}: {}", status.job_id, url, e);
+        }
+    }
+    
+    /// Return the tasks of a job's dead workers to the queue, with no live
+    /// workers left every processing task is orphaned
+    async fn reclaim_orphaned_tasks(&self, job_id: &str) -> Result<usize> {
+        let reclaimed = self.queue.reclaim_expired_tasks(job_id).await?
+            + self.queue.requeue_unleased_tasks(job_id).await?;
+        if reclaimed > 0 {
+            warn!("Returned {} interrupted tasks to the queue for job: {}", reclaimed, job_id);
+        }
+        
+        Ok(reclaimed)
+    }
+    
+    /// Statistics of the domains requested recently across jobs, most requested first
+    pub async fn domain_stats(&self) -> Result<Vec<DomainStats>> {
+        self.scheduler.domain_stats().await
+    }
+    
+    /// Number of URLs a job skipped for each reason, most common first
+    pub async fn get_skip_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>> {
+        self.scheduler.skip_counts(job_id).await
+    }
+    
+    /// URLs a job skipped and why
+    pub async fn list_skipped_urls(&self, job_id: &str) -> Result<Vec<(String, String)>> {
+        self.scheduler.skipped_urls(job_id).await
+    }
+    
+    /// Get all raw page results for a job
+    pub async fn list_page_results(&self, job_id: &str) -> Result<Vec<TaskResult>> {
+        self.raw_storage.list_page_results(job_id).await
+    }
+    
+    /// Extract the data of a job's pages again from their raw HTML with this
+    /// controller's profile and store it in processed storage, returning the
+    /// number of pages stored
+    ///
+    /// Pages that fail the profile's validation are skipped, like error pages
+    /// and other documents than HTML. Audits, assets
+    /// and near-duplicate flags can't be rebuilt from the HTML, they are kept
+    /// from the first crawl.
+    pub async fn reprocess_job(&self, job_id: &str) -> Result<usize> {
+        // Fail on unknown jobs rather than storing nothing
+        self.raw_storage.get_job_status(job_id).await?;
+        
+        let mut stored = 0;
+        for mut result in self.raw_storage.list_page_results(job_id).await? {
+            if !result.is_extractable() {
+                continue;
+            }
+            
+            let mut data = extract::extract_page(&result.raw_content, &result.url, &self.config.extraction)?;
+            for field in CRAWL_FIELDS {
+                if let Some(value) = result.extracted_data.get(*field) {
+                    data.insert(field.to_string(), value.clone());
+                }
+            }
+            result.extracted_data = serde_json::Value::Object(data);
+            extractors::run_extractors(&mut result, &self.config.extraction.extractors);
+            self.plugins.extract(&mut result);
+            self.scripts.extract(&mut result);
+            
+            if let Some(page) = quarantine::validate(&result, &self.config.quarantine) {
+                debug!("Not storing {}, missing fields: {}", result.url, page.missing_fields.join(", "));
+                continue;
+            }
+            self.processed_storage.store_page_data(job_id, &result.url, result.extracted_data).await?;
+            stored += 1;
+        }
+        self.processed_storage.flush().await?;
+        
+        info!("Reprocessed {} page(s) of job {}", stored, job_id);
+        Ok(stored)
+    }
+    
+    /// Run a read-only SQL query over the processed rows of a job
+    pub async fn query_page_data(&self, job_id: &str, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        self.processed_storage.query(job_id, sql, max_rows).await
+    }
+    
+    /// Stored versions of a URL across all jobs, newest first, in versioned storage mode
+    pub async fn page_history(&self, url: &str, limit: usize) -> Result<Vec<PageVersion>> {
+        self.processed_storage.page_history(url, limit).await
+    }
+    
+    /// Count the processed rows of a job matching `filter`, when the storage can count them
+    pub async fn count_page_data(&self, job_id: &str, filter: &RowFilter) -> Result<Option<usize>> {
+        self.processed_storage.count_rows(job_id, filter).await
+    }
+    
+    /// Read the processed rows of a job matching `filter` as typed records,
+    /// ordered by URL
+    ///
+    /// With a model in the profile, the extracted data is converted to it
+    /// first, so `T` can be the struct `crawler codegen` generates. Without
+    /// one, `T` deserializes from the extracted data as it is. Rows that
+    /// don't convert fail the read, with their URL.
+    pub async fn typed_records<T: DeserializeOwned>(&self, job_id: &str, filter: &RowFilter) -> Result<Vec<TypedRecord<T>>> {
+        let model = self.config.extraction.model.as_ref()
+            .map(RecordModel::from_settings)
+            .transpose()?;
+        
+        let mut records = Vec::new();
+        let mut rows = self.processed_storage.stream_rows(job_id, None, filter);
+        while let Some(row) = rows.try_next().await? {
+            let data = match &model {
+                Some(model) => model.convert(&row.data).context(format!("Failed to convert the data of {}", row.url))?,
+                None => row.data,
+            };
+            let data = serde_json::from_value(data)
+                .context(format!("Failed to read the data of {} as a record", row.url))?;
+            
+            records.push(TypedRecord { url: row.url, updated_at: row.updated_at, data });
+        }
+        
+        Ok(records)
+    }
+    
+    /// Stream the processed rows of a job matching `filter`, ordered by URL
+    #[cfg(feature = "duckdb")]
+    pub fn stream_page_data<'a>(&'a self, job_id: &'a str, filter: &'a RowFilter) -> BoxStream<'a, Result<PageData>> {
+        self.processed_storage.stream_rows(job_id, None, filter)
+    }
+    
+    /// List all jobs known to raw storage
+    pub async fn list_jobs(&self) -> Result<Vec<JobStatus>> {
+        self.raw_storage.list_jobs().await
+    }
+    
+    /// Get the quarantined pages of a job, oldest first
+    pub async fn list_quarantined_pages(&self, job_id: &str) -> Result<Vec<QuarantinedPage>> {
+        let mut pages = self.raw_storage.list_quarantined_pages(job_id).await?;
+        pages.sort_by(|a, b| a.quarantined_at.cmp(&b.quarantined_at));
+        
+        Ok(pages)
+    }
+    
+    /// Remove the quarantined pages of a job, only the expired ones unless `all` is set
+    pub async fn purge_quarantine(&self, job_id: &str, all: bool) -> Result<usize> {
+        let before = if all { None } else { Some(Utc::now()) };
+        
+        self.raw_storage.purge_quarantine(job_id, before).await
+    }
+    
+    /// Remove expired quarantined pages across all jobs
+    pub async fn purge_expired_quarantine(&self) -> Result<usize> {
+        let mut purged = 0;
+        for job in self.raw_storage.list_jobs().await? {
+            purged += self.purge_quarantine(&job.job_id, false).await?;
+        }
+        
+        if purged > 0 {
+            info!("Purged {} expired quarantined page(s)", purged);
+        }
+        
+        Ok(purged)
+    }
+    
+    /// Return tasks of dead workers and tasks stuck processing to the queue,
+    /// across all running jobs
+    pub async fn requeue_stale_tasks(&self) -> Result<usize> {
+        let mut requeued = 0;
+        for job in self.raw_storage.list_jobs().await? {
+            if job.state != "running" {
+                continue;
+            }
+            requeued += self.queue.reclaim_expired_tasks(&job.job_id).await?;
+            requeued += self.queue.requeue_stale_tasks(&job.job_id).await?;
+        }
+        
+        if requeued > 0 {
+            info!("Requeued {} stale task(s)", requeued);
+        }
+        
+        Ok(requeued)
+    }
+    
+    /// Remove the page versions the retention policy of the profile doesn't keep
+    pub async fn prune_page_versions(&self) -> Result<usize> {
+        let pruned = self.processed_storage
+            .prune_versions(&self.config.storage.processed_data.version_retention)
+            .await?;
+        
+        if pruned > 0 {
+            info!("Pruned {} page version(s)", pruned);
+        }
+        
+        Ok(pruned)
+    }
+    
+    /// Directory the API writes exports to
+    pub fn export_dir(&self) -> &std::path::Path {
+        std::path::Path::new(&self.config.storage.export_dir)
+    }
+    
+    /// Export job data, with a manifest next to each file. Processed rows are
+    /// read once for all row formats, WARC files and link graphs are built
+    /// from raw storage.
+    /// Exports to stdout get no manifest. Returns the number of records
+    /// written to each target.
+    #[instrument(skip_all, fields(job_id = %job_id, targets = targets.len()))]
+    pub async fn export_job_data(
+        &self,
+        job_id: &str,
+        targets: &[ExportTarget],
+        options: &ExportOptions,
+    ) -> Result<Vec<usize>> {
+        let status = self.raw_storage.get_job_status(job_id).await?;
+        
+        let row_targets: Vec<ExportTarget> = targets.iter()
+            .filter(|target| !target.format.from_raw_storage())
+            .cloned()
+            .collect();
+        let summary = if row_targets.is_empty() {
+            ExportSummary::default()
+        } else {
+            export_job(self.processed_storage.as_ref(), job_id, &row_targets, options).await?
+        };
+        
+        // Skipped URLs are only there when the job's profile records them
+        let skip_counts = self.scheduler.skip_counts(job_id).await?;
+        let skipped = if skip_counts.is_empty() {
+            Vec::new()
+        } else {
+            self.scheduler.skipped_urls(job_id).await?
+        };
+        
+        let mut counts = Vec::with_capacity(targets.len());
+        for target in targets {
+            let records = match target.format {
+                ExportFormat::Warc => export_warc(
+                    self.raw_storage.as_ref(),
+                    &status,
+                    target,
+                    &options.filter,
+                    options.compression,
+                ).await?,
+                ExportFormat::Graphml | ExportFormat::Dot => export_graph(
+                    self.raw_storage.as_ref(),
+                    &status,
+                    target,
+                    &options.filter,
+                    options.compression,
+                ).await?,
+                _ => summary.records,
+            };
+            
+            counts.push(records);
+            if target.to_stdout() {
+                continue;
+            }
+            
+            let mut manifest = ExportManifest::create(
+                &target.output_path,
+                target.format,
+                records,
+                &summary.data_columns,
+                &options.filter,
+                options.compression,
+                status.clone(),
+            )?;
+            manifest.skips = skip_counts.iter().cloned().collect();
+            let manifest_path = manifest.save(&target.output_path)?;
+            
+            // Typed columns are also described on their own for loading into other tools
+            if target.format.has_data_columns() {
+                SchemaFile::save(&target.output_path, target.format, &summary.data_columns)?;
+            }
+            
+            if !skipped.is_empty() {
+                save_skipped_urls(&target.output_path, &skipped)?;
+            }
+            
+            debug!("Wrote export manifest: {}", manifest_path.display());
+        }
+        
+        Ok(counts)
+    }
+    
+    /// Fetch a page through the browser service, solving captchas if needed
+    #[allow(clippy::too_many_arguments)]
+    async fn fetch_page(
+        task: &CrawlTask,
+        config: &CrawlerConfig,
+        fingerprint: &CompleteFingerprint,
+        scheduler: &Scheduler,
+        queue: &QueueManager,
+        raw_storage: &Arc<dyn RawStorageBackend>,
+        browser_service: &RemoteBrowserService,
+        captcha_solver: Option<Arc<CaptchaSolver>>,
+    ) -> Result<BrowserServiceResponse> {
+        let mut options = CrawlOptions {
+            capture_trace: config.traces.enabled,
+            accessibility_audit: config.audits.accessibility,
+            security_audit: config.audits.security_headers,
+            resource_inventory: config.audits.resources,
+            brand_assets: config.assets.enabled,
+            ..Default::default()
+        };
+        
+        // Crawl the URL using the remote browser service
+        let mut response = browser_service.crawl_url(
+            &task.url,
+            &config.browser.browser_type,
+            fingerprint,
+            &config.browser.behavior,
+            &options,
+        ).await?;
+        
+        // Solve any captcha the browser service ran into and retry with the solution
+        if let Some(challenge) = response.captcha.clone() {
+            if let Err(e) = scheduler.record_block(&task.url, "captcha").await {
+                warn!("Failed to record captcha on {}: {}", task.url, e);
+            }
+            
+            let solver = captcha_solver
+                .context(format!("Captcha challenge detected on {}", task.url))?;
+            
+            // Reserve the solve's cost first, so workers solving at once can't
+            // go over the limit together
+            let spent = raw_storage.get_job_status(&task.job_id).await?.captcha_spend;
+            let reserved = queue
+                .reserve_captcha_spend(&task.job_id, solver.cost_per_solve(), solver.max_spend_per_job(), spent)
+                .await?
+                .context(format!("Captcha spend limit reached for job {}", task.job_id))?;
+            
+            let token = match solver.solve(&challenge).await {
+                Ok(token) => token,
+                Err(e) => {
+                    if let Err(e) = queue.release_captcha_spend(&task.job_id, solver.cost_per_solve()).await {
+                        warn!("Failed to release captcha spend of job {}: {}", task.job_id, e);
+                    }
+                    return Err(e);
+                }
+            };
+            
+            // Record the spend before retrying, the solve is billed either way. The
+            // status is read again since other workers updated it during the solve.
+            let mut status = raw_storage.get_job_status(&task.job_id).await?;
+            status.captcha_spend = status.captcha_spend.max(reserved);
+            status.updated_at = Utc::now();
+            raw_storage.store_job_status(&status).await?;
+            
+            options.captcha_token = Some(token);
+            
+            response = browser_service.crawl_url(
+                &task.url,
+                &config.browser.browser_type,
+                fingerprint,
+                &config.browser.behavior,
+                &options,
+            ).await?;
+            
+            if response.captcha.is_some() {
+                anyhow::bail!("Captcha challenge persisted after solving on {}", task.url);
+            }
+        }
+        
+        Ok(response)
+    }
+    
+    /// Store a debug trace for a page that failed to load
+    async fn store_failure_trace(
+        task: &CrawlTask,
+        error: &anyhow::Error,
+        config: &CrawlerConfig,
+        fingerprint: &CompleteFingerprint,
+        raw_storage: &Arc<dyn RawStorageBackend>,
+    ) {
+        let browser_trace = error.downcast_ref::<BrowserServiceError>()
+            .and_then(|e| e.trace.clone());
+        
+        let mut trace = TaskTrace {
+            job_id: task.job_id.clone(),
+            url: task.url.clone(),
+            error: error.to_string(),
+            screenshot: None,
+            html: None,
+            console_logs: Vec::new(),
+            request_headers: fingerprint.headers.clone(),
+            proxy: None,
+            captured_at: Utc::now(),
+        };
+        
+        if let Some(browser_trace) = browser_trace {
+            if config.traces.include_screenshot {
+                trace.screenshot = browser_trace.screenshot;
+            }
+            if config.traces.include_html {
+                trace.html = browser_trace.html;
+            }
+            trace.console_logs = browser_trace.console_logs;
+            trace.proxy = browser_trace.proxy;
+        }
+        
+        if let Err(e) = raw_storage.store_task_trace(&trace).await {
+            warn!("Failed to store failure trace for {}: {}", task.url, e);
+        }
+    }
+    
+    /// Process a crawl task, each one is the root of its own trace
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(name = "crawl_task", skip_all, fields(job_id = %task.job_id, url = %task.url, depth = task.depth))]
     async fn process_task(
         task: CrawlTask,
         config: &CrawlerConfig,
-        scheduler: Arc<Mutex<Scheduler>>,
+        scheduler: Arc<Scheduler>,
         raw_storage: Arc<dyn RawStorageBackend>,
+        processed_storage: Arc<dyn ProcessedStorage>,
         queue: Arc<QueueManager>,
         browser_service: Arc<RemoteBrowserService>,
+        captcha_solver: Option<Arc<CaptchaSolver>>,
+        asset_capture: Option<Arc<AssetCapture>>,
+        plugins: Arc<Plugins>,
+        scripts: Arc<Scripts>,
     ) -> Result<()> {
+        let started = Instant::now();
+        
         // Get fingerprint
         let fingerprint_manager = FingerprintManager::new(config.browser.fingerprints.clone());
         let fingerprint = fingerprint_manager.random_fingerprint()?;
         
-        // Crawl the URL using the remote browser service
-        let response = browser_service.crawl_url(
-            &task.url,
-            &config.browser.browser_type,
+        // Keep to the request rate of the domain across all workers
+        scheduler.wait_rate_limit(&task.job_id, &task.url).await?;
+        
+        let fetch_started = Instant::now();
+        let fetched = Self::fetch_page(
+            &task,
+            config,
             &fingerprint,
-            &config.browser.behavior
-        ).await?;
+            &scheduler,
+            &queue,
+            &raw_storage,
+            &browser_service,
+            captcha_solver,
+        ).await;
+        // Server errors and rate limiting slow down the domain like failed loads
+        let success = fetched.as_ref()
+            .is_ok_and(|response| !response.status_code.is_some_and(|code| code >= 500 || code == 429));
+        if let Err(e) = scheduler.record_request(&task.job_id, &task.url, fetch_started.elapsed(), success).await {
+            warn!("Failed to record request to {}: {}", task.url, e);
+        }
         
-        // Parse the URL to get absolute links
-        let base_url = Url::parse(&task.url)?;
+        let response = match fetched {
+            Ok(response) => response,
+            Err(e) => {
+                if config.traces.enabled {
+                    Self::store_failure_trace(&task, &e, config, &fingerprint, &raw_storage).await;
+                }
+                return Err(e);
+            }
+        };
+        
+        // Relative links resolve against the URL the page ended on
+        let final_url = response.final_url.clone().filter(|url| *url != task.url);
+        let base_url = Url::parse(final_url.as_deref().unwrap_or(&task.url))?;
+        
+        // Only HTML pages have links and data to extract, other documents
+        // keep their body as raw content when it is text
+        let content_type = response.mime_type.clone()
+            .or_else(|| response.headers.as_ref().and_then(|headers| headers.get("content-type")).cloned())
+            .unwrap_or_else(|| "text/html".to_string());
+        let content_kind = ContentKind::from_mime(&content_type);
+        if content_kind != ContentKind::Html {
+            debug!("{} is a {} document ({})", task.url, content_kind.as_str(), content_type);
+        }
+        let raw_content = if content_kind.is_text() { response.content } else { String::new() };
+        
+        // Robots directives of the page, none when the profile ignores them
+        let robots = if config.crawler.respect_robots_directives {
+            let x_robots_tag = response.headers.as_ref().and_then(|headers| headers.get("x-robots-tag"));
+            let html = if content_kind == ContentKind::Html { raw_content.as_str() } else { "" };
+            PageRobots::read(html, &base_url, x_robots_tag.map(String::as_str))
+        } else {
+            PageRobots::default()
+        };
         
         // Process links to get absolute URLs
         let links: Vec<String> = response.links.iter()
+            .filter(|_| content_kind == ContentKind::Html)
             .filter_map(|link| {
                 match Url::parse(link) {
                     Ok(absolute_url) => Some(absolute_url.to_string()),
@@ -179,47 +1251,169 @@ impl CrawlerController {
                     }
                 }
             })
+            .filter(|link| robots.follows(link))
             .collect();
         
+        // Pages of a recrawl whose content didn't change keep the data
+        // extracted from them before
+        let previous = match &task.recrawl_of {
+            Some(previous_job_id) => raw_storage.get_page_result(previous_job_id, &task.url).await?,
+            None => None,
+        };
+        let change = task.recrawl_of.as_ref().map(|_| PageChange::detect(previous.as_ref(), &raw_content));
+        let unchanged = change == Some(PageChange::Unchanged);
+        
+        // Extract the profile's fields, then add audit results
+        let mut extracted_data = match previous.filter(|_| unchanged) {
+            Some(previous) => previous.extracted_data,
+            None if content_kind == ContentKind::Html => {
+                serde_json::Value::Object(extract::extract_page(&raw_content, &task.url, &config.extraction)?)
+            },
+            None => serde_json::json!({}),
+        };
+        if let Some(accessibility) = response.accessibility {
+            extracted_data["accessibility"] = accessibility;
+        }
+        if let Some(security) = response.security {
+            extracted_data["security"] = security;
+        }
+        if let Some(resources) = &response.resources {
+            extracted_data["resources"] = serde_json::to_value(classify_resources(&task.url, resources))?;
+        }
+        
+        // Capture favicons, og:images and the assets the page links to,
+        // keeping references to the stored copies
+        if let Some(asset_capture) = &asset_capture {
+            let mut assets = match &response.brand_assets {
+                Some(links) => asset_capture.capture(&task.job_id, &task.url, links, &raw_storage).await,
+                None => Vec::new(),
+            };
+            if content_kind == ContentKind::Html {
+                assets.extend(asset_capture.capture_linked(&task.job_id, base_url.as_str(), &raw_content, &raw_storage).await);
+            }
+            if !assets.is_empty() {
+                extracted_data["assets"] = serde_json::to_value(assets)?;
+            }
+        }
+        
+        // Keep the published date and author of pages listed in a feed
+        if let Some(entry) = &task.feed_entry {
+            extracted_data["feed"] = serde_json::to_value(entry)?;
+        }
+        
+        // Flag pages nearly identical to one already crawled, like the same
+        // listing under another sort order
+        let mut dropped = false;
+        if let Some(policy) = NearDuplicatePolicy::from_settings(&config.near_duplicates)? {
+            if let Some(fingerprint) = simhash::fingerprint(&raw_content) {
+                if let Some(original) = scheduler.near_duplicate_of(&task.job_id, &task.url, fingerprint, policy.max_distance).await? {
+                    debug!("{} is a near-duplicate of {}", task.url, original);
+                    extracted_data["near_duplicate_of"] = serde_json::Value::String(original);
+                    dropped = policy.action == NearDuplicateAction::Drop;
+                }
+            }
+        }
+        
+        if robots.noindex {
+            debug!("Not keeping {}, it is marked noindex", task.url);
+            dropped = true;
+        }
+        
+        // Drop pages redirected in a loop, off the profile's filters or to a
+        // page the job already has, keeping the redirect target as seen
+        if let Some(reason) = scheduler.check_redirect(&task.job_id, &response.redirects, base_url.as_str()).await? {
+            debug!("Not keeping {}, redirected to {} ({})", task.url, base_url, reason.as_str());
+            dropped = true;
+        }
+        
         // Create a task result
-        let result = TaskResult {
+        let mut result = TaskResult {
             job_id: task.job_id.clone(),
             url: task.url.clone(),
             depth: task.depth,
-            status_code: 200, // We assume success since the service returned success
-            content_type: "text/html".to_string(),
+            // Older browser services only report pages that loaded
+            status_code: response.status_code.unwrap_or(200),
+            content_type,
             title: response.title,
             links,
-            raw_content: response.content,
-            extracted_data: serde_json::json!({}),
+            raw_content,
+            extracted_data,
             crawled_at: Utc::now(),
+            redirects: response.redirects,
+            final_url,
+            headers: response.headers.unwrap_or_default(),
+            content_kind,
+            payload: None,
         };
         
-        // Store the result
-        raw_storage.store_page_result(&result).await?;
+        // Keep a copy of the body of binary documents, like PDFs and images
+        if let Some(asset_capture) = asset_capture.as_ref().filter(|_| !content_kind.is_text()) {
+            result.payload = asset_capture.capture_payload(&task.job_id, result.resolved_url(), &raw_storage).await;
+        }
+        if !unchanged {
+            extractors::run_extractors(&mut result, &config.extraction.extractors);
+            plugins.extract(&mut result);
+            scripts.extract(&mut result);
+        }
+        if let Some(change) = change {
+            result.extracted_data["recrawl"] = serde_json::Value::String(change.as_str().to_string());
+        }
         
-        // Update the job status
+        // Store the result, dropped pages only have their links followed
+        if !dropped {
+            raw_storage.store_page_result(&result).await?;
+            
+            let preview = RecordPreview::new(&result, &config.extraction.selectors);
+            if let Err(e) = queue.push_preview(&task.job_id, &preview).await {
+                warn!("Failed to store preview of {}: {}", result.url, e);
+            }
+            
+            // Keep the body of pages that fail validation so they can be
+            // reprocessed, only valid pages make it to processed storage.
+            // Error pages and other documents than HTML stay in raw storage.
+            match quarantine::validate(&result, &config.quarantine) {
+                _ if !result.is_extractable() => debug!("Not processing {} ({}, status {})", result.url, result.content_kind.as_str(), result.status_code),
+                Some(page) => {
+                    warn!("Quarantining {}, missing fields: {}", result.url, page.missing_fields.join(", "));
+                    if let Err(e) = raw_storage.store_quarantined_page(&page).await {
+                        warn!("Failed to quarantine {}: {}", result.url, e);
+                    }
+                },
+                None => processed_storage.store_page_data(&result.job_id, &result.url, result.extracted_data.clone()).await?,
+            }
+        }
+        
+        // Update the job status and its crawl rate
+        let pending = queue.get_pending_count(&task.job_id).await?;
         let mut status = raw_storage.get_job_status(&task.job_id).await?;
-        status.pages_crawled += 1;
-        status.updated_at = Utc::now();
+        status.record_page(task.seed, started.elapsed(), pending, Utc::now());
+        if let (Some(change), Some(recrawl)) = (change, status.recrawl.as_mut()) {
+            recrawl.record(change);
+        }
         raw_storage.store_job_status(&status).await?;
         
         // Schedule new tasks for discovered links if needed
-        if task.depth < config.crawler.max_depth {
-            let mut scheduler_lock = scheduler.lock().await;
-            
+        let max_depth = status.limits.map_or(config.crawler.max_depth, |limits| limits.max_depth);
+        if task.depth < max_depth {
             for link in &result.links {
-                if scheduler_lock.should_crawl(link) {
+                if scheduler.should_crawl(&task.job_id, link).await? {
                     let new_task = CrawlTask {
                         job_id: task.job_id.clone(),
                         url: link.clone(),
                         depth: task.depth + 1,
                         parent_url: Some(task.url.clone()),
-                        priority: 0,
+                        priority: scripts.score(link, task.depth + 1, &result),
+                        attempt: 0,
+                        feed_entry: None,
+                        seed: task.seed,
+                        recrawl_of: None,
                     };
                     
                     // Update total pages count
                     status.pages_total += 1;
+                    if let Some(seed) = task.seed.and_then(|seed| status.seeds.get_mut(seed)) {
+                        seed.pages_total += 1;
+                    }
                     
                     // Add task to queue
                     queue.push_task(&new_task).await?;
@@ -228,22 +1422,159 @@ impl CrawlerController {
             
             // Update job status again with new total
             raw_storage.store_job_status(&status).await?;
+        } else {
+            for link in &result.links {
+                scheduler.skip_too_deep(&task.job_id, link).await?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Pause or fail a running job whose error rate went over the policy threshold
+    async fn stop_on_errors(
+        queue: &QueueManager,
+        raw_storage: &dyn RawStorageBackend,
+        job_id: &str,
+        action: ErrorAction,
+        error_rate: f64,
+        window: usize,
+    ) -> Result<()> {
+        let mut status = raw_storage.get_job_status(job_id).await?;
+        if status.state != "running" {
+            return Ok(());
         }
         
+        match action {
+            ErrorAction::Pause => {
+                queue.set_paused(job_id, true).await?;
+                status.state = "paused".to_string();
+            },
+            ErrorAction::Fail => status.state = "failed".to_string(),
+        }
+        
+        let reason = format!("{:.0}% of the last {} tasks failed", error_rate * 100.0, window);
+        error!("Job {} {} by the error policy, {}", job_id, status.state, reason);
+        if action == ErrorAction::Pause {
+            error!("Check the site, then use `crawler resume {}` to continue crawling", job_id);
+        }
+        
+        status.stop_reason = Some(format!("error policy, {}", reason));
+        status.updated_at = Utc::now();
+        raw_storage.store_job_status(&status).await?;
+        
         Ok(())
     }
     
+    /// Pop a task of another running job with a backlog, so a worker idle on
+    /// its own job helps the others, only of jobs in a priority class above
+    /// `above` when given
+    ///
+    /// Higher priority classes go first, within a class jobs are drawn at
+    /// random in proportion to their weight. Only jobs started with the same
+    /// configuration are helped, so their pages are crawled and extracted as
+    /// their own workers would.
+    async fn steal_task(
+        queue: &QueueManager,
+        raw_storage: &dyn RawStorageBackend,
+        own_job_id: &str,
+        worker_id: &str,
+        config: &CrawlerConfig,
+        config_hash: &str,
+        above: Option<JobPriority>,
+    ) -> Result<Option<CrawlTask>> {
+        let mut candidates = Vec::new();
+        for job in raw_storage.list_jobs().await? {
+            if job.job_id == own_job_id || job.state != "running" || job.config_hash.as_deref() != Some(config_hash) {
+                continue;
+            }
+            
+            let limits = job.limits.unwrap_or_else(|| JobLimits::from_settings(&config.crawler));
+            if limits.weight == 0 || job.pages_crawled >= limits.max_pages as usize {
+                continue;
+            }
+            if above.is_some_and(|above| limits.priority <= above) {
+                continue;
+            }
+            if queue.is_paused(&job.job_id).await? || queue.is_cancelled(&job.job_id).await? {
+                continue;
+            }
+            
+            if queue.get_pending_count(&job.job_id).await? > 0 {
+                candidates.push((job.job_id, limits.priority, limits.weight));
+            }
+        }
+        
+        // A job without a task ready, its domains cooling down, gives way to the next draw
+        while let Some(top) = candidates.iter().map(|(_, priority, _)| *priority).max() {
+            let total: u64 = candidates.iter()
+                .filter(|(_, priority, _)| *priority == top)
+                .map(|(_, _, weight)| *weight as u64)
+                .sum();
+            let mut draw = thread_rng().gen_range(0..total);
+            let index = candidates.iter()
+                .position(|(_, priority, weight)| {
+                    if *priority != top {
+                        return false;
+                    }
+                    if draw < *weight as u64 {
+                        return true;
+                    }
+                    draw -= *weight as u64;
+                    false
+                })
+                .unwrap_or(0);
+            
+            let (job_id, _, _) = candidates.swap_remove(index);
+            if let Some(task) = queue.pop_task(&job_id, worker_id, config.crawler.politeness_delay).await? {
+                debug!("Helping job {} with task: {}", job_id, task.url);
+                return Ok(Some(task));
+            }
+        }
+        
+        Ok(None)
+    }
+    
+    /// Run a task, failing it with a timeout error once it has run for
+    /// `timeout` seconds. The task is dropped, cancelling whatever it waits on.
+    async fn with_timeout(process: impl Future<Output = Result<()>>, timeout: u64) -> Result<()> {
+        if timeout == 0 {
+            return process.await;
+        }
+        
+        tokio::time::timeout(std::time::Duration::from_secs(timeout), process)
+            .await
+            .unwrap_or_else(|_| Err(TaskTimeout { seconds: timeout }.into()))
+    }
+    
+    /// Kind of a task error, kept with dead-lettered tasks
+    fn error_type(error: &anyhow::Error) -> &'static str {
+        if error.is::<TaskTimeout>() {
+            "timeout"
+        } else if error.is::<BrowserServiceError>() {
+            "browser"
+        } else {
+            "crawl"
+        }
+    }
+    
     // Start worker threads in standalone mode
-    #[cfg(feature = "standalone")]
     async fn start_workers(&self, job_id: String) -> Result<()> {
         use tokio::task;
-        use num_cpus;
         
-        let worker_count = num_cpus::get().min(4); // Use at most 4 cores
-        info!("Starting {} worker threads for job: {}", worker_count, job_id);
+        // Error rate of the job's latest tasks, shared by all its workers
+        let error_window = ErrorWindow::from_settings(&self.config.error_policy)?
+            .map(|window| Arc::new(Mutex::new(window)));
+        
+        // Share of the workers crawling at each time of day
+        let pacing = Pacing::from_settings(&self.config.crawler.schedule)?;
         
-        for i in 0..worker_count {
-            // Clone the necessary components for the worker
+        // Idle workers only help jobs started with the same configuration
+        let config_hash = self.config.fingerprint()?;
+        
+        // Workers are started up to the job's concurrency limit, and started or
+        // stopped when the limit changes
+        let spawn_worker = {
             let queue = self.queue.clone();
             let scheduler = self.scheduler.clone();
             let raw_storage = self.raw_storage.clone();
@@ -251,88 +1582,329 @@ impl CrawlerController {
             let config = self.config.clone();
             let job_id = job_id.clone();
             let browser_service = self.browser_service.clone();
+            let captcha_solver = self.captcha_solver.clone();
+            let asset_capture = self.asset_capture.clone();
+            let plugins = self.plugins.clone();
+            let scripts = self.scripts.clone();
             
-            // Spawn a worker task
-            task::spawn(async move {
-                info!("Worker {} started for job: {}", i, job_id);
+            move |i: usize| {
+                // Each worker gets its own handles on the shared components
+                let queue = queue.clone();
+                let scheduler = scheduler.clone();
+                let raw_storage = raw_storage.clone();
+                let processed_storage = processed_storage.clone();
+                let config = config.clone();
+                let job_id = job_id.clone();
+                let browser_service = browser_service.clone();
+                let captcha_solver = captcha_solver.clone();
+                let asset_capture = asset_capture.clone();
+                let plugins = plugins.clone();
+                let scripts = scripts.clone();
+                let error_window = error_window.clone();
+                let config_hash = config_hash.clone();
+                let pacing = pacing.clone();
                 
-                loop {
-                    // Try to get a task from the queue
-                    match queue.pop_task(&job_id).await {
-                        Ok(Some(task)) => {
-                            debug!("Worker {} processing task: {}", i, task.url);
-                            
-                            // Process the task
-                            let result = Self::process_task(
-                                task.clone(),
-                                &config,
-                                scheduler.clone(),
-                                raw_storage.clone(),
-                                queue.clone(),
-                                browser_service.clone(),
-                            ).await;
+                // Counted before it is spawned, so the process waits for the worker
+                // even when shut down right away
+                let running = shutdown::worker_running();
+                
+                // Spawn a worker task
+                task::spawn(async move {
+                    let _running = running;
+                    let worker_id = format!("{}-{}", Uuid::new_v4(), i);
+                    info!("Worker {} started for job: {}", i, job_id);
+                    
+                    let mut last_heartbeat: Option<Instant> = None;
+                    let mut last_steal: Option<Instant> = None;
+                    loop {
+                        // Keep the worker registered while it idles between tasks
+                        if last_heartbeat.map_or(true, |at| at.elapsed() >= queue.heartbeat_interval()) {
+                            if let Err(e) = queue.heartbeat(&worker_id, &job_id, None).await {
+                                error!("Worker {} failed to send heartbeat: {}", i, e);
+                            }
+                            last_heartbeat = Some(Instant::now());
+                        }
+                        
+                        
+                        // Take no new tasks once the process is shutting down
+                        if shutdown::is_requested() {
+                            info!("Worker {} stopping, shutting down: {}", i, job_id);
+                            break;
+                        }
+                        
+                        // Stop working on cancelled jobs
+                        if queue.is_cancelled(&job_id).await.unwrap_or(false) {
+                            info!("Worker {} stopping, job cancelled: {}", i, job_id);
+                            break;
+                        }
+                        
+                        // Leave the queue untouched while the job is paused
+                        if queue.is_paused(&job_id).await.unwrap_or(false) {
+                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                            continue;
+                        }
+                        
+                        // Limits can change while the job runs, read them before each task
+                        let mut status = match raw_storage.get_job_status(&job_id).await {
+                            Ok(status) => status,
+                            Err(e) => {
+                                error!("Worker {} failed to read job status: {}", i, e);
+                                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                                continue;
+                            }
+                        };
+                        let limits = status.limits.unwrap_or_else(|| JobLimits::from_settings(&config.crawler));
+                        
+                        if status.state == "failed" || status.state == "completed" {
+                            info!("Worker {} stopping, job {}: {}", i, status.state, job_id);
+                            break;
+                        }
+                        
+                        if status.pages_crawled >= limits.max_pages as usize {
+                            if status.state != "completed" {
+                                status.state = "completed".to_string();
+                                status.updated_at = Utc::now();
+                                if let Err(e) = raw_storage.store_job_status(&status).await {
+                                    error!("Failed to update job status: {}", e);
+                                }
+                            }
                             
-                            // Handle the result
-                            match result {
-                                Ok(_) => {
-                                    // Mark the task as complete
-                                    if let Err(e) = queue.complete_task(&job_id, &task.url).await {
-                                        error!("Failed to mark task as complete: {}", e);
-                                    }
+                            info!("Worker {} stopping, page limit reached for job: {}", i, job_id);
+                            break;
+                        }
+                        
+                        if i >= limits.concurrency {
+                            info!("Worker {} stopping, concurrency of job {} lowered to {}", i, job_id, limits.concurrency);
+                            break;
+                        }
+                        
+                        // Outside the crawl windows of the profile the job's tasks wait in the queue
+                        if pacing.as_ref().is_some_and(|pacing| i >= pacing.workers(limits.concurrency, Utc::now())) {
+                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                            continue;
+                        }
+                        
+                        // Take a task of a job in a higher priority class first, then
+                        // of this job, then of another job while this one has none ready
+                        let steal_due = last_steal.map_or(true, |at| at.elapsed() >= STEAL_INTERVAL);
+                        if steal_due {
+                            last_steal = Some(Instant::now());
+                        }
+                        let mut popped = Ok(None);
+                        if steal_due && limits.priority < JobPriority::High {
+                            popped = Self::steal_task(&queue, raw_storage.as_ref(), &job_id, &worker_id, &config, &config_hash, Some(limits.priority)).await;
+                        }
+                        if matches!(popped, Ok(None)) {
+                            popped = match queue.pop_task(&job_id, &worker_id, config.crawler.politeness_delay).await {
+                                Ok(None) if steal_due => {
+                                    Self::steal_task(&queue, raw_storage.as_ref(), &job_id, &worker_id, &config, &config_hash, None).await
                                 },
-                                Err(e) => {
-                                    error!("Worker {} task processing error: {}", i, e);
-                                    
-                                    // Mark the task as failed
-                                    if let Err(e) = queue.fail_task(&job_id, &task.url, &e.to_string()).await {
-                                        error!("Failed to mark task as failed: {}", e);
+                                popped => popped,
+                            };
+                        }
+                        match popped {
+                            Ok(Some(task)) => {
+                                debug!("Worker {} processing task of job {}: {}", i, task.job_id, task.url);
+                                
+                                // Process the task, abandoning it if it runs too long, the job gets
+                                // cancelled or the shutdown grace period is over
+                                let process = Self::process_task(
+                                    task.clone(),
+                                    &config,
+                                    scheduler.clone(),
+                                    raw_storage.clone(),
+                                    processed_storage.clone(),
+                                    queue.clone(),
+                                    browser_service.clone(),
+                                    captcha_solver.clone(),
+                                    asset_capture.clone(),
+                                    plugins.clone(),
+                                    scripts.clone(),
+                                );
+                                let result = tokio::select! {
+                                    result = Self::with_timeout(process, config.crawler.task_timeout) => result,
+                                    _ = Self::wait_for_cancel(&queue, &task.job_id) => {
+                                        info!("Worker {} abandoned task for cancelled job: {}", i, task.url);
+                                        if task.job_id == job_id {
+                                            break;
+                                        }
+                                        continue;
                                     }
-                                    
-                                    // Update job status with error
-                                    if let Ok(mut status) = raw_storage.get_job_status(&job_id).await {
-                                        status.errors.push(e.to_string());
-                                        status.updated_at = Utc::now();
-                                        if let Err(e) = raw_storage.store_job_status(&status).await {
-                                            error!("Failed to update job status: {}", e);
+                                    _ = shutdown::grace_over(std::time::Duration::from_secs(config.crawler.shutdown_grace)) => {
+                                        warn!("Worker {} requeued task interrupted by shutdown: {}", i, task.url);
+                                        if let Err(e) = queue.requeue_task(&task).await {
+                                            error!("Failed to requeue interrupted task: {}", e);
+                                        }
+                                        break;
+                                    }
+                                    _ = Self::renew_lease(&queue, &worker_id, &task.job_id, &task.url) => {
+                                        unreachable!("leases are renewed until the task ends")
+                                    }
+                                };
+                                last_heartbeat = Some(Instant::now());
+                                
+                                // Handle the result
+                                let failed = result.is_err();
+                                match result {
+                                    Ok(_) => {
+                                        // Mark the task as complete
+                                        if let Err(e) = queue.complete_task(&task.job_id, &task.url).await {
+                                            error!("Failed to mark task as complete: {}", e);
+                                        }
+                                    },
+                                    Err(e) => {
+                                        error!("Worker {} task processing error: {}", i, e);
+                                        
+                                        // Mark the task as failed, retrying it later while it has attempts left
+                                        let retry_in = task_retry_delay(&config.crawler.retry, task.attempt, thread_rng().gen());
+                                        if let Err(e) = queue.fail_task(&task, &e.to_string(), Self::error_type(&e), retry_in).await {
+                                            error!("Failed to mark task as failed: {}", e);
+                                        }
+                                        
+                                        // Update job status with error
+                                        if let Ok(mut status) = raw_storage.get_job_status(&task.job_id).await {
+                                            status.errors.push(e.to_string());
+                                            status.updated_at = Utc::now();
+                                            if let Err(e) = raw_storage.store_job_status(&status).await {
+                                                error!("Failed to update job status: {}", e);
+                                            }
                                         }
                                     }
                                 }
-                            }
-                        },
-                        Ok(None) => {
-                            // No tasks available, check if we're done
-                            let pending = queue.get_pending_count(&job_id).await.unwrap_or(0);
-                            let processing = queue.get_processing_count(&job_id).await.unwrap_or(0);
-                            
-                            if pending == 0 && processing == 0 {
-                                // All tasks are done, update job status
-                                if let Ok(mut status) = raw_storage.get_job_status(&job_id).await {
-                                    if status.state != "completed" {
-                                        status.state = "completed".to_string();
-                                        status.updated_at = Utc::now();
-                                        if let Err(e) = raw_storage.store_job_status(&status).await {
-                                            error!("Failed to update job status: {}", e);
+                                
+                                // Stop the job when too many of its latest tasks failed
+                                if let Some(window) = error_window.as_ref().filter(|_| task.job_id == job_id) {
+                                    let mut window = window.lock().await;
+                                    if let Some(error_rate) = window.record(failed) {
+                                        if let Err(e) = Self::stop_on_errors(
+                                            &queue,
+                                            raw_storage.as_ref(),
+                                            &job_id,
+                                            window.action(),
+                                            error_rate,
+                                            window.size(),
+                                        ).await {
+                                            error!("Failed to apply error policy: {}", e);
                                         }
                                     }
-                                    
-                                    info!("Worker {} completed job: {}", i, job_id);
-                                    break;
                                 }
+                            },
+                            Ok(None) => {
+                                // Tasks of workers that died would otherwise stay processing forever
+                                match queue.reclaim_expired_tasks(&job_id).await {
+                                    Ok(0) => {},
+                                    Ok(reclaimed) => {
+                                        warn!("Returned {} tasks of dead workers to the queue for job: {}", reclaimed, job_id);
+                                        continue;
+                                    },
+                                    Err(e) => error!("Worker {} failed to reclaim expired tasks: {}", i, e),
+                                }
+                                match queue.requeue_stale_tasks(&job_id).await {
+                                    Ok(0) => {},
+                                    Ok(requeued) => {
+                                        warn!("Requeued {} stale processing tasks for job: {}", requeued, job_id);
+                                        continue;
+                                    },
+                                    Err(e) => error!("Worker {} failed to requeue stale tasks: {}", i, e),
+                                }
+                                
+                                // No tasks available, check if we're done
+                                let pending = queue.get_pending_count(&job_id).await.unwrap_or(0);
+                                let processing = queue.get_processing_count(&job_id).await.unwrap_or(0);
+                                
+                                if pending == 0 && processing == 0 {
+                                    // All tasks are done, update job status
+                                    if let Ok(mut status) = raw_storage.get_job_status(&job_id).await {
+                                        if status.state != "completed" {
+                                            status.state = "completed".to_string();
+                                            status.updated_at = Utc::now();
+                                            if let Err(e) = raw_storage.store_job_status(&status).await {
+                                                error!("Failed to update job status: {}", e);
+                                            }
+                                        }
+                                        
+                                        info!("Worker {} completed job: {}", i, job_id);
+                                        break;
+                                    }
+                                }
+                                
+                                // Wait before checking again
+                                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                            },
+                            Err(e) => {
+                                error!("Worker {} failed to get task: {}", i, e);
+                                
+                                // Wait before retrying
+                                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                            }
+                        }
+                    }
+                    
+                    if let Err(e) = queue.deregister_worker(&worker_id, &job_id).await {
+                        error!("Worker {} failed to deregister: {}", i, e);
+                    }
+                    
+                    // Write out pages a batching backend still holds
+                    if let Err(e) = processed_storage.flush().await {
+                        error!("Worker {} failed to flush processed data: {}", i, e);
+                    }
+                    
+                    // Store the job status as of the shutdown, the job stays running
+                    // for workers of other processes
+                    if shutdown::is_requested() {
+                        if let Ok(mut status) = raw_storage.get_job_status(&job_id).await {
+                            status.updated_at = Utc::now();
+                            if let Err(e) = raw_storage.store_job_status(&status).await {
+                                error!("Failed to update job status: {}", e);
                             }
-                            
-                            // Wait before checking again
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        },
-                        Err(e) => {
-                            error!("Worker {} failed to get task: {}", i, e);
-                            
-                            // Wait before retrying
-                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                         }
                     }
+                });
+            }
+        };
+        
+        let default_limits = JobLimits::from_settings(&self.config.crawler);
+        let concurrency = self.raw_storage.get_job_status(&job_id).await?.limits
+            .unwrap_or(default_limits)
+            .concurrency;
+        info!("Starting {} worker threads for job: {}", concurrency, job_id);
+        let mut workers: Vec<_> = (0..concurrency).map(&spawn_worker).collect();
+        
+        // Follow the concurrency limit while the job runs, with one read of its
+        // status per second rather than one per idle worker
+        let queue = self.queue.clone();
+        let raw_storage = self.raw_storage.clone();
+        task::spawn(async move {
+            loop {
+                tokio::time::sleep(WORKER_SCALE_INTERVAL).await;
+                if shutdown::is_requested() || queue.is_cancelled(&job_id).await.unwrap_or(false) {
+                    break;
                 }
-            });
-        }
+                
+                let status = match raw_storage.get_job_status(&job_id).await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        error!("Failed to read limits of job {}: {}", job_id, e);
+                        continue;
+                    }
+                };
+                if !matches!(status.state.as_str(), "pending" | "running" | "paused") {
+                    break;
+                }
+                
+                // Workers above a lowered limit stop on their own before their next task,
+                // the missing ones below a raised limit are started again
+                let concurrency = status.limits.unwrap_or(default_limits).concurrency;
+                for (i, worker) in workers.iter_mut().enumerate().take(concurrency) {
+                    if worker.is_finished() {
+                        *worker = spawn_worker(i);
+                    }
+                }
+                for i in workers.len()..concurrency {
+                    workers.push(spawn_worker(i));
+                }
+            }
+        });
         
         Ok(())
     }
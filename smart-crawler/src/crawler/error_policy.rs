@@ -0,0 +1,140 @@
+use anyhow::Result;
+use std::collections::VecDeque;
+
+use crate::cli::config::ErrorPolicySettings;
+
+/// What happens to a job once too many of its tasks fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Pause the job, it can be resumed once the site is reachable again
+    Pause,
+    /// Mark the job as failed and stop its workers
+    Fail,
+}
+
+impl ErrorAction {
+    pub fn parse(action: &str) -> Result<Self> {
+        match action.to_lowercase().as_str() {
+            "pause" => Ok(Self::Pause),
+            "fail" => Ok(Self::Fail),
+            _ => anyhow::bail!("Unsupported error policy action: {}", action),
+        }
+    }
+}
+
+/// Outcomes of the latest tasks of a job, shared by its workers
+///
+/// The error rate is only measured once the window is full, so a few early
+/// failures don't stop a job. The window starts over after tripping, letting
+/// a resumed job run a full window before being judged again.
+#[derive(Debug)]
+pub struct ErrorWindow {
+    outcomes: VecDeque<bool>,
+    size: usize,
+    max_error_rate: f64,
+    action: ErrorAction,
+}
+
+impl ErrorWindow {
+    /// Create the window from settings, None when the policy is disabled
+    pub fn from_settings(settings: &ErrorPolicySettings) -> Result<Option<Self>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+        if settings.window == 0 {
+            anyhow::bail!("Error policy window must hold at least one task");
+        }
+        if !(0.0..1.0).contains(&settings.max_error_rate) {
+            anyhow::bail!("Error policy max_error_rate must be at least 0 and below 1");
+        }
+        
+        Ok(Some(Self {
+            outcomes: VecDeque::with_capacity(settings.window),
+            size: settings.window,
+            max_error_rate: settings.max_error_rate,
+            action: ErrorAction::parse(&settings.action)?,
+        }))
+    }
+    
+    /// Number of tasks the error rate is measured over
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    
+    /// Action to take when the policy trips
+    pub fn action(&self) -> ErrorAction {
+        self.action
+    }
+    
+    /// Record the outcome of a task, returning the error rate when it goes
+    /// over the threshold
+    pub fn record(&mut self, failed: bool) -> Option<f64> {
+        if self.outcomes.len() == self.size {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(failed);
+        
+        if self.outcomes.len() < self.size {
+            return None;
+        }
+        
+        let failures = self.outcomes.iter().filter(|failed| **failed).count();
+        let error_rate = failures as f64 / self.size as f64;
+        if error_rate <= self.max_error_rate {
+            return None;
+        }
+        
+        self.outcomes.clear();
+        Some(error_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn settings(window: usize, max_error_rate: f64) -> ErrorPolicySettings {
+        ErrorPolicySettings {
+            enabled: true,
+            window,
+            max_error_rate,
+            action: "pause".to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_error_window() {
+        let mut window = ErrorWindow::from_settings(&settings(4, 0.5)).unwrap().unwrap();
+        assert_eq!(window.action(), ErrorAction::Pause);
+        
+        // Nothing is measured until the window is full
+        assert_eq!(window.record(true), None);
+        assert_eq!(window.record(true), None);
+        assert_eq!(window.record(false), None);
+        
+        // Half the tasks failing is still within the threshold
+        assert_eq!(window.record(false), None);
+        
+        // Old outcomes slide out of the window
+        assert_eq!(window.record(true), None);
+        assert_eq!(window.record(true), None);
+        assert_eq!(window.record(true), Some(0.75));
+        
+        // The window starts over after tripping
+        assert_eq!(window.record(true), None);
+    }
+    
+    #[test]
+    fn test_error_window_settings() {
+        let mut disabled = settings(4, 0.5);
+        disabled.enabled = false;
+        assert!(ErrorWindow::from_settings(&disabled).unwrap().is_none());
+        
+        assert!(ErrorWindow::from_settings(&settings(0, 0.5)).is_err());
+        assert!(ErrorWindow::from_settings(&settings(4, 1.0)).is_err());
+        
+        let mut unknown = settings(4, 0.5);
+        unknown.action = "retry".to_string();
+        assert!(ErrorWindow::from_settings(&unknown).is_err());
+    }
+}
\ No newline at end of file
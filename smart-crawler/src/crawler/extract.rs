@@ -0,0 +1,151 @@
+use anyhow::Result;
+use scraper::{Html, Selector};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::cli::config::ExtractionSettings;
+use crate::crawler::{markdown, meta, readability, structured};
+
+/// Selector for a single field value
+///
+/// Written as a CSS selector, optionally followed by `@attribute` to read an
+/// attribute instead of the element text (`a[rel=next]@href`). Ending it with
+/// `[]` reads every match into a list (`a.product@href[]`).
+pub struct FieldSelector {
+    css: Selector,
+    attribute: Option<String>,
+    all: bool,
+}
+
+impl FieldSelector {
+    /// Parse a field selector
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (selector, all) = match spec.trim_end().strip_suffix("[]") {
+            Some(selector) => (selector, true),
+            None => (spec, false),
+        };
+        
+        let (css, attribute) = match selector.rsplit_once('@') {
+            Some((css, attribute)) if is_attribute_name(attribute) => (css, Some(attribute.to_string())),
+            _ => (selector, None),
+        };
+        
+        let css = Selector::parse(css.trim())
+            .map_err(|e| anyhow::anyhow!("Invalid selector '{}': {}", spec, e))?;
+        
+        Ok(Self { css, attribute, all })
+    }
+    
+    /// Non-empty values the selector matches in a document, in document order
+    fn values<'a>(&'a self, document: &'a Html) -> impl Iterator<Item = String> + 'a {
+        document.select(&self.css).filter_map(|element| {
+            let value = match &self.attribute {
+                Some(attribute) => element.value().attr(attribute)?.trim().to_string(),
+                None => element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" "),
+            };
+            
+            (!value.is_empty()).then_some(value)
+        })
+    }
+    
+    /// First non-empty value the selector matches in a document
+    pub fn select(&self, document: &Html) -> Option<String> {
+        self.values(document).next()
+    }
+    
+    /// Value of the field in a document, a list of every match for list selectors
+    pub fn extract(&self, document: &Html) -> Option<Value> {
+        if !self.all {
+            return self.select(document).map(Value::String);
+        }
+        
+        let values: Vec<Value> = self.values(document).map(Value::String).collect();
+        (!values.is_empty()).then_some(Value::Array(values))
+    }
+}
+
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+}
+
+/// Check that every selector of a profile parses
+pub fn validate_selectors(selectors: &BTreeMap<String, String>) -> Result<()> {
+    for spec in selectors.values() {
+        FieldSelector::parse(spec)?;
+    }
+    
+    Ok(())
+}
+
+/// Extract the configured fields from a page, fields without a match are left out
+pub fn extract_fields(html: &str, selectors: &BTreeMap<String, String>) -> Result<Map<String, Value>> {
+    let document = Html::parse_document(html);
+    
+    let mut fields = Map::new();
+    for (name, spec) in selectors {
+        if let Some(value) = FieldSelector::parse(spec)?.extract(&document) {
+            fields.insert(name.clone(), value);
+        }
+    }
+    
+    Ok(fields)
+}
+
+/// Everything the profile extracts from the HTML of a page: its fields, and
+/// the meta tags, structured data, Markdown and main content when enabled
+pub fn extract_page(html: &str, page_url: &str, settings: &ExtractionSettings) -> Result<Map<String, Value>> {
+    let mut data = extract_fields(html, &settings.selectors)?;
+    if settings.meta_tags {
+        if let Some(meta) = meta::extract_meta(html, page_url) {
+            data.insert("meta".to_string(), meta);
+        }
+    }
+    if settings.structured_data {
+        if let Some(structured) = structured::extract_structured(html, page_url) {
+            data.insert("structured".to_string(), structured);
+        }
+    }
+    if settings.markdown {
+        data.insert("markdown".to_string(), Value::String(markdown::to_markdown(html, page_url)));
+    }
+    if settings.main_content {
+        if let Some(main_content) = readability::extract_main_content(html) {
+            data.insert("main_content".to_string(), main_content);
+        }
+    }
+    
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_extract_fields() {
+        let html = r#"<html><head><meta property="og:title" content="Shared title"></head>
+            <body><h1>  Product
+                name </h1><a rel="next" href="/page/2">Next</a>
+                <a class="tag" href="/tag/a">A</a><a class="tag" href="/tag/b">B</a></body></html>"#;
+        
+        let selectors: BTreeMap<String, String> = [
+            ("title", "h1"),
+            ("og_title", "meta[property='og:title']@content"),
+            ("next_page", "a[rel=next]@href"),
+            ("price", ".price"),
+            ("tags", "a.tag@href[]"),
+            ("missing_tags", "span.tag[]"),
+        ]
+        .iter()
+        .map(|(name, spec)| (name.to_string(), spec.to_string()))
+        .collect();
+        
+        let fields = extract_fields(html, &selectors).unwrap();
+        assert_eq!(fields["title"], "Product name");
+        assert_eq!(fields["og_title"], "Shared title");
+        assert_eq!(fields["next_page"], "/page/2");
+        assert!(!fields.contains_key("price"));
+        assert_eq!(fields["tags"], serde_json::json!(["/tag/a", "/tag/b"]));
+        assert!(!fields.contains_key("missing_tags"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::warn;
+
+use crate::crawler::task::TaskResult;
+
+/// Keys of the extracted data the crawler fills itself, which extractors can't be named after
+pub(crate) const RESERVED_NAMES: &[&str] = &[
+    "meta", "structured", "markdown", "main_content", "accessibility", "security", "resources", "assets",
+    "near_duplicate_of", "feed", "recrawl",
+];
+
+/// Parser compiled into the crawler, run on the pages of the profiles naming
+/// it under `extraction.extractors`
+///
+/// Extractors see the whole page result, with its raw HTML and the data the
+/// profile's selectors extracted, and their output is stored under their
+/// name in the page's extracted data.
+pub trait PageExtractor: Send + Sync {
+    /// Data of a page, None when the page has nothing for this extractor
+    fn extract(&self, page: &TaskResult) -> Result<Option<Value>>;
+}
+
+impl<F> PageExtractor for F
+where
+    F: Fn(&TaskResult) -> Result<Option<Value>> + Send + Sync,
+{
+    fn extract(&self, page: &TaskResult) -> Result<Option<Value>> {
+        self(page)
+    }
+}
+
+fn registry() -> &'static RwLock<BTreeMap<String, Arc<dyn PageExtractor>>> {
+    static REGISTRY: OnceLock<RwLock<BTreeMap<String, Arc<dyn PageExtractor>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+/// Make an extractor available to profiles under a name, before any
+/// controller using it is created
+pub fn register_extractor(name: &str, extractor: impl PageExtractor + 'static) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        anyhow::bail!("Extractor names may only contain letters, digits, '-' and '_': {}", name);
+    }
+    if RESERVED_NAMES.contains(&name) {
+        anyhow::bail!("Extractor name {} is used by the crawler's own extracted data", name);
+    }
+    
+    let mut extractors = registry().write().expect("extractor registry poisoned");
+    if extractors.contains_key(name) {
+        anyhow::bail!("An extractor is already registered as {}", name);
+    }
+    extractors.insert(name.to_string(), Arc::new(extractor));
+    
+    Ok(())
+}
+
+/// Names of the registered extractors, in name order
+pub fn registered_extractors() -> Vec<String> {
+    registry().read().expect("extractor registry poisoned").keys().cloned().collect()
+}
+
+/// Check every extractor a profile names is registered
+pub fn validate_extractors(names: &[String]) -> Result<()> {
+    let extractors = registry().read().expect("extractor registry poisoned");
+    for name in names {
+        if !extractors.contains_key(name) {
+            let registered: Vec<&str> = extractors.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Unknown extractor: {} (registered: {})",
+                name,
+                if registered.is_empty() { "none".to_string() } else { registered.join(", ") }
+            );
+        }
+    }
+    
+    Ok(())
+}
+
+/// Run the named extractors on a page, adding their output to its extracted
+/// data. An extractor failing on a page is logged and leaves nothing.
+pub fn run_extractors(page: &mut TaskResult, names: &[String]) {
+    let extractors: Vec<(String, Arc<dyn PageExtractor>)> = {
+        let registry = registry().read().expect("extractor registry poisoned");
+        names.iter()
+            .filter_map(|name| registry.get(name).map(|extractor| (name.clone(), extractor.clone())))
+            .collect()
+    };
+    
+    for (name, extractor) in extractors {
+        match extractor.extract(page) {
+            Ok(Some(value)) => page.extracted_data[name.as_str()] = value,
+            Ok(None) => {},
+            Err(e) => warn!("Extractor {} failed on {}: {}", name, page.url, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_run_extractors() {
+        register_extractor("test_sku", |page: &TaskResult| -> Result<Option<Value>> {
+            Ok(page.raw_content.split_once("SKU-").map(|(_, rest)| json!(rest.chars().take(4).collect::<String>())))
+        }).unwrap();
+        register_extractor("test_failing", |_: &TaskResult| -> Result<Option<Value>> {
+            anyhow::bail!("parser broke")
+        }).unwrap();
+        
+        assert!(register_extractor("test_sku", |_: &TaskResult| -> Result<Option<Value>> { Ok(None) }).is_err());
+        assert!(register_extractor("meta", |_: &TaskResult| -> Result<Option<Value>> { Ok(None) }).is_err());
+        assert!(register_extractor("test sku", |_: &TaskResult| -> Result<Option<Value>> { Ok(None) }).is_err());
+        
+        let names = vec!["test_sku".to_string(), "test_failing".to_string()];
+        validate_extractors(&names).unwrap();
+        assert!(validate_extractors(&["test_missing".to_string()]).is_err());
+        
+        let mut page = TaskResult {
+            title: "Blue kettle".to_string(),
+            raw_content: "<p>Blue kettle, SKU-4242</p>".to_string(),
+            extracted_data: json!({"name": "Blue kettle"}),
+            ..TaskResult::test_page("https://shop.example.com/kettles/blue")
+        };
+        run_extractors(&mut page, &names);
+        assert_eq!(page.extracted_data, json!({"name": "Blue kettle", "test_sku": "4242"}));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,139 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Serialize, Deserialize};
+use tracing::{debug, info};
+#[cfg(feature = "feeds")]
+use url::Url;
+
+/// Entry of an RSS or Atom feed, kept with the task crawling its page and
+/// stored under `feed` in the page's extracted data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedEntry {
+    /// URL of the entry's page
+    pub url: String,
+    
+    /// URL of the feed listing the entry
+    pub feed_url: String,
+    
+    pub title: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    
+    /// Name of the entry's first author
+    pub author: Option<String>,
+}
+
+/// Entries of an RSS, Atom or JSON feed that link to a page, with relative
+/// links resolved against the feed's URL
+#[cfg(feature = "feeds")]
+pub fn parse_feed(feed_url: &str, content: &[u8]) -> Result<Vec<FeedEntry>> {
+    let feed = feed_rs::parser::parse(content).context(format!("Failed to parse feed {}", feed_url))?;
+    let base = Url::parse(feed_url).ok();
+    
+    let entries = feed.entries.into_iter()
+        .filter_map(|entry| {
+            // The entry's own page, rather than its comments or enclosures
+            let link = entry.links.iter()
+                .find(|link| link.rel.as_deref().map_or(true, |rel| rel == "alternate"))
+                .or(entry.links.first())?;
+            let url = match &base {
+                Some(base) => base.join(link.href.trim()).ok()?.to_string(),
+                None => link.href.trim().to_string(),
+            };
+            
+            Some(FeedEntry {
+                url,
+                feed_url: feed_url.to_string(),
+                title: entry.title.map(|title| title.content.trim().to_string()).filter(|title| !title.is_empty()),
+                published: entry.published.or(entry.updated),
+                author: entry.authors.into_iter().map(|author| author.name).find(|name| !name.trim().is_empty()),
+            })
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[cfg(not(feature = "feeds"))]
+pub fn parse_feed(feed_url: &str, _content: &[u8]) -> Result<Vec<FeedEntry>> {
+    anyhow::bail!("Feed {} can't be read, feeds need a build with the feeds feature", feed_url)
+}
+
+/// Entries of every feed, failing on a feed that can't be read
+pub async fn read_feeds(feed_urls: &[String], user_agent: &str) -> Result<Vec<FeedEntry>> {
+    let client = Client::builder()
+        .user_agent(user_agent)
+        .timeout(std::time::Duration::from_secs(60))
+        .build()
+        .context("Failed to create HTTP client")?;
+    
+    let mut entries = Vec::new();
+    for feed_url in feed_urls {
+        let body = client.get(feed_url).send().await
+            .and_then(|response| response.error_for_status())
+            .context(format!("Failed to fetch feed {}", feed_url))?
+            .bytes().await
+            .context(format!("Failed to read feed {}", feed_url))?;
+        
+        let feed_entries = parse_feed(feed_url, &body)?;
+        debug!("Feed {} lists {} entries", feed_url, feed_entries.len());
+        entries.extend(feed_entries);
+    }
+    
+    info!("Read {} entries from {} feed(s)", entries.len(), feed_urls.len());
+    Ok(entries)
+}
+
+#[cfg(all(test, feature = "feeds"))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    #[test]
+    fn test_parse_feed() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+                <channel>
+                    <title>Kettle news</title>
+                    <link>https://news.example.com/</link>
+                    <item>
+                        <title> Blue kettle boils faster </title>
+                        <link>/2024/07/blue-kettle</link>
+                        <pubDate>Mon, 01 Jul 2024 10:00:00 +0200</pubDate>
+                        <author>ana@example.com (Ana)</author>
+                    </item>
+                    <item><title>No link</title></item>
+                </channel>
+            </rss>"#;
+        let entries = parse_feed("https://news.example.com/feed.xml", rss.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url, "https://news.example.com/2024/07/blue-kettle");
+        assert_eq!(entries[0].title.as_deref(), Some("Blue kettle boils faster"));
+        assert_eq!(entries[0].published, Some(Utc.with_ymd_and_hms(2024, 7, 1, 8, 0, 0).unwrap()));
+        assert!(entries[0].author.is_some());
+        
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+                <title>Kettle news</title>
+                <id>urn:kettles</id>
+                <updated>2024-07-02T09:00:00Z</updated>
+                <entry>
+                    <title>Kettle recall</title>
+                    <id>urn:kettles:recall</id>
+                    <link rel="replies" href="https://news.example.com/recall#comments"/>
+                    <link rel="alternate" href="https://news.example.com/recall"/>
+                    <updated>2024-07-02T09:00:00Z</updated>
+                    <author><name>Luis</name></author>
+                </entry>
+            </feed>"#;
+        let entries = parse_feed("https://news.example.com/atom.xml", atom.as_bytes()).unwrap();
+        assert_eq!(entries, vec![FeedEntry {
+            url: "https://news.example.com/recall".to_string(),
+            feed_url: "https://news.example.com/atom.xml".to_string(),
+            title: Some("Kettle recall".to_string()),
+            published: Some(Utc.with_ymd_and_hms(2024, 7, 2, 9, 0, 0).unwrap()),
+            author: Some("Luis".to_string()),
+        }]);
+        
+        assert!(parse_feed("https://news.example.com/feed.xml", b"<html><body>Not a feed</body></html>").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,300 @@
+use anyhow::{Result, Context};
+use async_stream::try_stream;
+use futures::stream::BoxStream;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::cli::config::{CrawlerConfig, JobPriority};
+use crate::crawler::controller::CrawlerController;
+use crate::storage::export::{ExportFormat, ExportOptions, ExportTarget};
+use crate::storage::models::TypedRecord;
+use crate::storage::processed::RowFilter;
+use crate::storage::raw::JobStatus;
+
+/// Interval between checks of a job's status while waiting on it
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configures a crawling job for programs embedding the crawler
+///
+/// Starts from a profile or a configuration, validates everything once
+/// `start` is called and returns a handle to follow the job.
+pub struct JobBuilder {
+    config: CrawlerConfig,
+    seed_urls: Vec<String>,
+    max_pages: Option<u32>,
+    max_depth: Option<u32>,
+    priority: Option<JobPriority>,
+    tags: Vec<String>,
+    notify: Vec<String>,
+}
+
+impl JobBuilder {
+    /// Builder for a job crawling with a configuration
+    pub fn new(config: CrawlerConfig) -> Self {
+        Self {
+            config,
+            seed_urls: Vec::new(),
+            max_pages: None,
+            max_depth: None,
+            priority: None,
+            tags: Vec::new(),
+            notify: Vec::new(),
+        }
+    }
+    
+    /// Builder for a job crawling with a site profile
+    pub fn from_profile(profile: &str) -> Result<Self> {
+        let config = CrawlerConfig::load_profile(profile)
+            .context(format!("Failed to load profile: {}", profile))?;
+        Ok(Self::new(config))
+    }
+    
+    /// Add a seed URL, crawled at depth 0
+    pub fn seed(mut self, url: impl Into<String>) -> Self {
+        self.seed_urls.push(url.into());
+        self
+    }
+    
+    /// Add several seed URLs
+    pub fn seeds<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.seed_urls.extend(urls.into_iter().map(Into::into));
+        self
+    }
+    
+    /// Override settings of the profile, like its selectors or retry policy
+    pub fn configure(mut self, configure: impl FnOnce(&mut CrawlerConfig)) -> Self {
+        configure(&mut self.config);
+        self
+    }
+    
+    /// Maximum number of pages to crawl
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+    
+    /// Maximum crawling depth
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+    
+    /// Priority class of the job
+    pub fn priority(mut self, priority: JobPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+    
+    /// Add a label stored with the job, shown by `crawler status`
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+    
+    /// Add a URL the final status of the job is posted to as JSON
+    ///
+    /// Notifications are sent by the process that started the job, which has
+    /// to keep running until the job is done.
+    pub fn notify(mut self, url: impl Into<String>) -> Self {
+        self.notify.push(url.into());
+        self
+    }
+    
+    /// Check the job can be started, returning its configuration with the
+    /// overrides applied
+    fn validate(&self) -> Result<CrawlerConfig> {
+        if self.seed_urls.is_empty() {
+            anyhow::bail!("A job needs at least one seed URL");
+        }
+        for url in self.seed_urls.iter().chain(&self.notify) {
+            let parsed = Url::parse(url).context(format!("Invalid URL: {}", url))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                anyhow::bail!("Only http and https URLs are supported: {}", url);
+            }
+        }
+        
+        for (index, tag) in self.tags.iter().enumerate() {
+            if tag.trim().is_empty() {
+                anyhow::bail!("Job tags can't be empty");
+            }
+            if self.tags[..index].contains(tag) {
+                anyhow::bail!("Duplicate job tag: {}", tag);
+            }
+        }
+        
+        let mut config = self.config.clone();
+        if let Some(max_pages) = self.max_pages {
+            if max_pages == 0 {
+                anyhow::bail!("A job needs a page limit above 0");
+            }
+            config.crawler.max_pages = max_pages;
+        }
+        if let Some(max_depth) = self.max_depth {
+            config.crawler.max_depth = max_depth;
+        }
+        if let Some(priority) = self.priority {
+            config.crawler.priority = priority;
+        }
+        
+        Ok(config)
+    }
+    
+    /// Validate the job and start it, connecting to the profile's queue and storage
+    pub async fn start(self) -> Result<JobHandle> {
+        let config = self.validate()?;
+        let controller = Arc::new(CrawlerController::new(config).await?);
+        
+        let job_id = controller.start_seeded_job(self.seed_urls, None, self.tags).await?;
+        info!("Crawling job started with ID: {}", job_id);
+        
+        let handle = JobHandle { job_id, controller };
+        if !self.notify.is_empty() {
+            handle.spawn_notifications(self.notify);
+        }
+        
+        Ok(handle)
+    }
+}
+
+/// Change in a job followed through `JobHandle::events`
+#[derive(Debug, Clone)]
+pub enum JobEvent {
+    /// The job moved to another state, like from running to paused
+    State(String),
+    
+    /// More pages were crawled or found
+    Progress { pages_crawled: usize, pages_total: usize },
+    
+    /// The job completed, failed or was cancelled, always the last event
+    Finished(JobStatus),
+}
+
+/// A job started with `JobBuilder`
+#[derive(Clone)]
+pub struct JobHandle {
+    job_id: String,
+    controller: Arc<CrawlerController>,
+}
+
+impl JobHandle {
+    /// ID of the job, as used by the CLI and the API
+    pub fn id(&self) -> &str {
+        &self.job_id
+    }
+    
+    /// Current status of the job
+    pub async fn status(&self) -> Result<JobStatus> {
+        self.controller.get_job_status(&self.job_id).await
+    }
+    
+    /// Wait until the job completes, fails or is cancelled
+    pub async fn wait(&self) -> Result<JobStatus> {
+        self.controller.wait_for_job(&self.job_id, POLL_INTERVAL).await
+    }
+    
+    /// Changes of the job's state and progress, ending once it is finished
+    pub fn events(&self) -> BoxStream<'_, Result<JobEvent>> {
+        Box::pin(try_stream! {
+            let mut last: Option<JobStatus> = None;
+            loop {
+                let status = self.status().await?;
+                if matches!(status.state.as_str(), "completed" | "failed" | "cancelled") {
+                    yield JobEvent::Finished(status);
+                    break;
+                }
+                
+                if last.as_ref().map_or(true, |last| last.state != status.state) {
+                    yield JobEvent::State(status.state.clone());
+                }
+                if last.as_ref().map_or(true, |last| {
+                    last.pages_crawled != status.pages_crawled || last.pages_total != status.pages_total
+                }) {
+                    yield JobEvent::Progress {
+                        pages_crawled: status.pages_crawled,
+                        pages_total: status.pages_total,
+                    };
+                }
+                
+                last = Some(status);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        })
+    }
+    
+    /// Cancel the job and clean up its queue
+    pub async fn cancel(&self) -> Result<()> {
+        self.controller.cancel_job(&self.job_id).await
+    }
+    
+    /// Extracted data of the job's pages as records of type `T`, converted to
+    /// the model of the profile when it has one
+    pub async fn records<T: DeserializeOwned>(&self) -> Result<Vec<TypedRecord<T>>> {
+        self.controller.typed_records(&self.job_id, &RowFilter::default()).await
+    }
+    
+    /// Export the job's data to a file, returning the number of records written
+    pub async fn export(&self, format: ExportFormat, output: &str) -> Result<usize> {
+        let targets = ExportTarget::for_formats(&self.job_id, &[format], Some(output), None);
+        let counts = self.controller.export_job_data(&self.job_id, &targets, &ExportOptions::default()).await?;
+        Ok(counts.into_iter().sum())
+    }
+    
+    /// Post the final status of the job to each URL once it is finished
+    fn spawn_notifications(&self, urls: Vec<String>) {
+        let handle = self.clone();
+        tokio::spawn(async move {
+            let status = match handle.wait().await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Failed to wait for job {} to notify: {}", handle.job_id, e);
+                    return;
+                }
+            };
+            
+            let client = reqwest::Client::new();
+            for url in urls {
+                let sent = client.post(&url).json(&status).send().await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = sent {
+                    warn!("Failed to notify {} of job {}: {}", url, handle.job_id, e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_validate() {
+        let builder = || JobBuilder::new(CrawlerConfig::default());
+        
+        let config = builder()
+            .seed("https://example.com")
+            .max_pages(10)
+            .max_depth(1)
+            .priority(JobPriority::High)
+            .tag("nightly")
+            .validate()
+            .unwrap();
+        assert_eq!(config.crawler.max_pages, 10);
+        assert_eq!(config.crawler.max_depth, 1);
+        assert_eq!(config.crawler.priority, JobPriority::High);
+        
+        assert!(builder().validate().is_err());
+        assert!(builder().seed("example.com").validate().is_err());
+        assert!(builder().seed("ftp://example.com").validate().is_err());
+        assert!(builder().seed("https://example.com").max_pages(0).validate().is_err());
+        assert!(builder().seed("https://example.com").tag("a").tag("a").validate().is_err());
+        assert!(builder().seed("https://example.com").notify("not a url").validate().is_err());
+    }
+}
\ No newline at end of file
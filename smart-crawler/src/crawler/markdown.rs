@@ -0,0 +1,308 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use url::Url;
+
+/// Elements left out of the Markdown, with everything in them
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "iframe", "form", "button", "select", "textarea", "input",
+];
+
+/// Elements starting a block of their own
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "dd", "details", "div", "dl", "dt", "figcaption", "figure",
+    "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "html", "li", "main", "nav", "ol", "p", "pre",
+    "section", "summary", "table", "ul",
+];
+
+/// Markdown of the content of a page, for consumers that don't want to
+/// clean up HTML themselves
+///
+/// Headings, paragraphs, lists, quotes, code, tables, links and images are
+/// kept, with link and image URLs resolved against the page's URL. Scripts,
+/// styles, forms and embedded frames are left out.
+pub fn to_markdown(html: &str, page_url: &str) -> String {
+    let document = Html::parse_document(html);
+    let base = Url::parse(page_url).ok();
+    
+    Converter { base: base.as_ref() }.blocks(document.root_element()).join("\n\n")
+}
+
+struct Converter<'a> {
+    base: Option<&'a Url>,
+}
+
+impl Converter<'_> {
+    /// Markdown blocks of the content of an element, runs of inline content
+    /// between block elements making a paragraph each
+    fn blocks(&self, element: ElementRef) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+        
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(&collapse(text)),
+                Node::Element(node) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    if SKIPPED_ELEMENTS.contains(&node.name()) {
+                        continue;
+                    }
+                    
+                    if BLOCK_ELEMENTS.contains(&node.name()) {
+                        push_paragraph(&mut inline, &mut blocks);
+                        blocks.extend(self.block(child));
+                    } else {
+                        inline.push_str(&self.inline(child));
+                    }
+                },
+                _ => {},
+            }
+        }
+        push_paragraph(&mut inline, &mut blocks);
+        
+        blocks
+    }
+    
+    fn block(&self, element: ElementRef) -> Vec<String> {
+        let name = element.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = tidy(&self.inline_content(element)).replace('\n', " ");
+                let level = name[1..].parse().unwrap_or(1);
+                if text.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![format!("{} {}", "#".repeat(level), text)]
+                }
+            },
+            "hr" => vec!["---".to_string()],
+            "pre" => {
+                let code: String = element.text().collect();
+                let code = code.trim_matches('\n');
+                if code.trim().is_empty() {
+                    Vec::new()
+                } else {
+                    vec![format!("```\n{}\n```", code)]
+                }
+            },
+            "ul" | "ol" => self.list(element, name == "ol"),
+            "blockquote" => {
+                let quoted = self.blocks(element).join("\n\n");
+                if quoted.is_empty() {
+                    return Vec::new();
+                }
+                let lines: Vec<String> = quoted.lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                    .collect();
+                vec![lines.join("\n")]
+            },
+            "table" => self.table(element),
+            _ => self.blocks(element),
+        }
+    }
+    
+    /// A list, with the blocks of each item indented under its marker
+    fn list(&self, element: ElementRef, ordered: bool) -> Vec<String> {
+        let mut number: u32 = element.value().attr("start").and_then(|start| start.parse().ok()).unwrap_or(1);
+        
+        let mut items = Vec::new();
+        for item in element.children().filter_map(ElementRef::wrap) {
+            if item.value().name() != "li" {
+                continue;
+            }
+            let marker = if ordered { format!("{}. ", number) } else { "- ".to_string() };
+            number += 1;
+            
+            let content = self.blocks(item).join("\n");
+            if content.is_empty() {
+                continue;
+            }
+            
+            let indent = " ".repeat(marker.len());
+            let lines: Vec<String> = content.lines()
+                .enumerate()
+                .map(|(index, line)| match index {
+                    0 => format!("{}{}", marker, line),
+                    _ if line.is_empty() => String::new(),
+                    _ => format!("{}{}", indent, line),
+                })
+                .collect();
+            items.push(lines.join("\n"));
+        }
+        
+        if items.is_empty() {
+            Vec::new()
+        } else {
+            vec![items.join("\n")]
+        }
+    }
+    
+    /// A table, its first row taken as the header
+    fn table(&self, element: ElementRef) -> Vec<String> {
+        let row_selector = Selector::parse("tr").expect("valid row selector");
+        let rows: Vec<Vec<String>> = element.select(&row_selector)
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .map(|cell| tidy(&self.inline_content(cell)).replace('\n', " ").replace('|', "\\|"))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect();
+        
+        let Some(width) = rows.iter().map(Vec::len).max() else {
+            return Vec::new();
+        };
+        
+        let mut lines = Vec::with_capacity(rows.len() + 1);
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = (0..width).map(|column| row.get(column).map_or("", String::as_str)).collect();
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}|", vec![" --- "; width].join("|")));
+            }
+        }
+        
+        vec![lines.join("\n")]
+    }
+    
+    /// Inline Markdown of the content of an element
+    fn inline_content(&self, element: ElementRef) -> String {
+        let mut text = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(node) => text.push_str(&collapse(node)),
+                Node::Element(node) if !SKIPPED_ELEMENTS.contains(&node.name()) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        text.push_str(&self.inline(child));
+                    }
+                },
+                _ => {},
+            }
+        }
+        text
+    }
+    
+    fn inline(&self, element: ElementRef) -> String {
+        match element.value().name() {
+            "br" => "\n".to_string(),
+            "strong" | "b" => emphasize(&self.inline_content(element), "**"),
+            "em" | "i" => emphasize(&self.inline_content(element), "*"),
+            "code" => emphasize(&element.text().collect::<String>(), "`"),
+            "a" => {
+                let text = self.inline_content(element);
+                match element.value().attr("href").map(str::trim) {
+                    Some(href) if !text.trim().is_empty() && !href.starts_with('#') && !href.starts_with("javascript:") => {
+                        format!("[{}]({})", text.trim(), self.resolve(href))
+                    },
+                    _ => text,
+                }
+            },
+            "img" => match element.value().attr("src") {
+                Some(src) => format!("![{}]({})", element.value().attr("alt").unwrap_or_default().trim(), self.resolve(src)),
+                None => String::new(),
+            },
+            _ => self.inline_content(element),
+        }
+    }
+    
+    fn resolve(&self, url: &str) -> String {
+        self.base
+            .and_then(|base| base.join(url.trim()).ok())
+            .map(|url| url.to_string())
+            .unwrap_or_else(|| url.trim().to_string())
+    }
+}
+
+/// Wrap text in an emphasis or code marker, keeping the markers tight
+fn emphasize(text: &str, marker: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}{}", marker, text, marker)
+    }
+}
+
+/// Text with every run of whitespace collapsed to a single space
+fn collapse(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_whitespace() {
+            collapsed.push(c);
+        } else if !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+    }
+    collapsed
+}
+
+/// Inline Markdown with the spaces around line breaks and at both ends removed
+fn tidy(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+fn push_paragraph(inline: &mut String, blocks: &mut Vec<String>) {
+    let paragraph = tidy(inline);
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+    inline.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_to_markdown() {
+        let html = r#"<html><head><title>Ignored</title><style>p { color: red }</style></head><body>
+            <h1>Blue   kettle</h1>
+            <p>Boils <strong>1.7 l</strong> in <em>three</em> minutes, see the
+               <a href="/manual.pdf">manual</a>.<br>Ships in 2 days.</p>
+            <script>track()</script>
+            <ul>
+                <li>Steel body</li>
+                <li>Comes with:<ol start="3"><li>Base</li><li>Filter</li></ol></li>
+            </ul>
+            <blockquote><p>Fast and quiet.</p></blockquote>
+            <pre><code>model: K-42
+voltage: 230</code></pre>
+            <table>
+                <tr><th>Size</th><th>Price</th></tr>
+                <tr><td>1.7 l</td><td>24 | 90 €</td></tr>
+            </table>
+            <img src="img/kettle.jpg" alt="Kettle">
+            <form><input name="q"><button>Search</button></form>
+        </body></html>"#;
+        
+        assert_eq!(to_markdown(html, "https://shop.example.com/kettles/blue"), "# Blue kettle
+
+Boils **1.7 l** in *three* minutes, see the [manual](https://shop.example.com/manual.pdf).
+Ships in 2 days.
+
+- Steel body
+- Comes with:
+  3. Base
+  4. Filter
+
+> Fast and quiet.
+
+```
+model: K-42
+voltage: 230
+```
+
+| Size | Price |
+| --- | --- |
+| 1.7 l | 24 \\| 90 € |
+
+![Kettle](https://shop.example.com/kettles/img/kettle.jpg)");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,116 @@
+use scraper::{Html, Selector};
+use serde_json::{Map, Value};
+use url::Url;
+
+/// Prefixes of the meta properties grouped by the platform they are for
+const GROUPS: &[(&str, &str)] = &[("og:", "og"), ("twitter:", "twitter")];
+
+/// Description, keywords, canonical URL, robots directives and the Open Graph
+/// and Twitter card tags of a page, None when it has none of them
+///
+/// Open Graph and Twitter tags are grouped under `og` and `twitter` without
+/// their prefix (`og:image:width` is `og.image:width`), and tags repeated on a
+/// page, like several `og:image`, read as a list.
+pub fn extract_meta(html: &str, page_url: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let meta_selector = Selector::parse("meta[content]").expect("valid meta selector");
+    let canonical_selector = Selector::parse(r#"link[rel~="canonical"][href]"#).expect("valid canonical selector");
+    
+    let mut meta = Map::new();
+    let mut groups: Map<String, Value> = Map::new();
+    for element in document.select(&meta_selector) {
+        let node = element.value();
+        let Some(key) = node.attr("property").or_else(|| node.attr("name")) else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let content = node.attr("content").unwrap_or_default().trim();
+        if content.is_empty() {
+            continue;
+        }
+        
+        match key.as_str() {
+            "description" | "robots" => {
+                meta.entry(key).or_insert_with(|| Value::String(content.to_string()));
+            },
+            "keywords" => {
+                let keywords: Vec<Value> = content.split(',')
+                    .map(str::trim)
+                    .filter(|keyword| !keyword.is_empty())
+                    .map(|keyword| Value::String(keyword.to_string()))
+                    .collect();
+                meta.entry(key).or_insert_with(|| Value::Array(keywords));
+            },
+            _ => {
+                let Some((prefix, group)) = GROUPS.iter().find(|(prefix, _)| key.starts_with(prefix)) else {
+                    continue;
+                };
+                let Value::Object(tags) = groups.entry(group.to_string()).or_insert_with(|| Value::Object(Map::new())) else {
+                    continue;
+                };
+                add_tag(tags, &key[prefix.len()..], content);
+            },
+        }
+    }
+    
+    if let Some(href) = document.select(&canonical_selector).find_map(|element| element.value().attr("href")) {
+        let canonical = Url::parse(page_url)
+            .and_then(|base| base.join(href.trim()))
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| href.trim().to_string());
+        meta.insert("canonical".to_string(), Value::String(canonical));
+    }
+    
+    meta.extend(groups);
+    (!meta.is_empty()).then_some(Value::Object(meta))
+}
+
+/// Add a tag to its group, turning it into a list when it is repeated
+fn add_tag(tags: &mut Map<String, Value>, name: &str, content: &str) {
+    let content = Value::String(content.to_string());
+    match tags.get_mut(name) {
+        None => {
+            tags.insert(name.to_string(), content);
+        },
+        Some(Value::Array(values)) => values.push(content),
+        Some(value) => *value = Value::Array(vec![value.take(), content]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_extract_meta() {
+        let html = r#"<html><head>
+            <meta name="description" content=" A kettle that boils fast ">
+            <meta name="Keywords" content="kettle, kitchen,, appliances">
+            <meta name="robots" content="noindex, follow">
+            <link rel="canonical" href="/kettles/blue">
+            <meta property="og:title" content="Blue kettle">
+            <meta property="og:image" content="https://cdn.example.com/1.jpg">
+            <meta property="og:image:width" content="800">
+            <meta property="og:image" content="https://cdn.example.com/2.jpg">
+            <meta name="twitter:card" content="summary_large_image">
+            <meta name="viewport" content="width=device-width">
+            <meta property="og:description" content="">
+            </head><body></body></html>"#;
+        
+        assert_eq!(extract_meta(html, "https://shop.example.com/kettles?sort=price").unwrap(), json!({
+            "description": "A kettle that boils fast",
+            "keywords": ["kettle", "kitchen", "appliances"],
+            "robots": "noindex, follow",
+            "canonical": "https://shop.example.com/kettles/blue",
+            "og": {
+                "title": "Blue kettle",
+                "image": ["https://cdn.example.com/1.jpg", "https://cdn.example.com/2.jpg"],
+                "image:width": "800",
+            },
+            "twitter": {"card": "summary_large_image"},
+        }));
+        
+        assert_eq!(extract_meta(r#"<meta name="viewport" content="width=device-width">"#, "https://example.com/"), None);
+    }
+}
\ No newline at end of file
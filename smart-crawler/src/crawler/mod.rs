@@ -1,8 +1,39 @@
+pub mod assets;
+pub mod audit;
+pub mod compare;
+#[cfg(feature = "redis")]
 pub mod controller;
+pub mod error_policy;
+pub mod extract;
+pub mod extractors;
+pub mod feed;
+#[cfg(feature = "redis")]
+pub mod job;
+pub mod markdown;
+pub mod meta;
+pub mod pacing;
+#[cfg(feature = "redis")]
+pub mod pipeline;
+pub mod plugins;
+pub mod quarantine;
+pub mod readability;
+pub mod recrawl;
+pub mod robots;
+pub mod schedule;
 pub mod scheduler;
+pub mod scripts;
+#[cfg(feature = "api")]
+pub mod selftest;
+pub mod simhash;
+pub mod sitemap;
+pub mod structured;
+pub mod suggest;
 pub mod task;
+pub mod templates;
 
 // Re-export common types
+#[cfg(feature = "redis")]
 pub use controller::CrawlerController;
-pub use task::{CrawlTask, TaskResult, TaskError};
+pub use extractors::{PageExtractor, register_extractor};
+pub use task::{CrawlTask, TaskResult, TaskError, TaskTrace};
 pub use scheduler::Scheduler;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::cli::config::ScheduleSettings;
+
+/// Crawl windows of a profile, telling how many of a job's workers crawl
+/// at a given time
+#[derive(Debug, Clone)]
+pub struct Pacing {
+    timezone: Tz,
+    windows: Vec<(NaiveTime, NaiveTime, f64)>,
+}
+
+impl Pacing {
+    /// Create the pacing from settings, None when the job crawls at any time
+    pub fn from_settings(settings: &ScheduleSettings) -> Result<Option<Self>> {
+        if settings.windows.is_empty() {
+            return Ok(None);
+        }
+        
+        let timezone: Tz = settings.timezone.parse()
+            .map_err(|e| anyhow::anyhow!("Unknown crawl schedule time zone {}: {}", settings.timezone, e))?;
+        
+        let mut windows = Vec::with_capacity(settings.windows.len());
+        for window in &settings.windows {
+            let time = |text: &str| NaiveTime::parse_from_str(text, "%H:%M")
+                .context(format!("Invalid crawl window time, expected HH:MM: {}", text));
+            if !(0.0..=1.0).contains(&window.pace) {
+                anyhow::bail!("Crawl window pace must be between 0 and 1: {}", window.pace);
+            }
+            windows.push((time(&window.start)?, time(&window.end)?, window.pace));
+        }
+        
+        Ok(Some(Self { timezone, windows }))
+    }
+    
+    /// Share of the workers crawling at a time, the highest of the windows
+    /// it falls in and 0 outside them
+    pub fn pace_at(&self, now: DateTime<Utc>) -> f64 {
+        let time = now.with_timezone(&self.timezone).time();
+        self.windows.iter()
+            .filter(|(start, end, _)| {
+                if start <= end {
+                    *start <= time && time < *end
+                } else {
+                    *start <= time || time < *end
+                }
+            })
+            .map(|(_, _, pace)| *pace)
+            .fold(0.0, f64::max)
+    }
+    
+    /// Number of a job's workers crawling at a time, at least one in any
+    /// window with a pace
+    pub fn workers(&self, concurrency: usize, now: DateTime<Utc>) -> usize {
+        let pace = self.pace_at(now);
+        if pace <= 0.0 {
+            return 0;
+        }
+        
+        ((concurrency as f64 * pace).round() as usize).clamp(1, concurrency.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::cli::config::CrawlWindow;
+    
+    fn window(start: &str, end: &str, pace: f64) -> CrawlWindow {
+        CrawlWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            pace,
+        }
+    }
+    
+    #[test]
+    fn test_pacing() {
+        let settings = ScheduleSettings {
+            timezone: "Europe/Madrid".to_string(),
+            windows: vec![
+                window("22:00", "01:00", 0.5),
+                window("01:00", "06:00", 1.0),
+            ],
+        };
+        let pacing = Pacing::from_settings(&settings).unwrap().unwrap();
+        
+        // Madrid is UTC+2 in summer
+        let at = |hour: u32, minute: u32| Utc.with_ymd_and_hms(2024, 7, 1, hour, minute, 0).unwrap();
+        assert_eq!(pacing.pace_at(at(20, 30)), 0.5);
+        assert_eq!(pacing.pace_at(at(23, 0)), 1.0);
+        assert_eq!(pacing.pace_at(at(4, 0)), 0.0);
+        assert_eq!(pacing.pace_at(at(10, 0)), 0.0);
+        
+        assert_eq!(pacing.workers(4, at(20, 30)), 2);
+        assert_eq!(pacing.workers(1, at(20, 30)), 1);
+        assert_eq!(pacing.workers(4, at(10, 0)), 0);
+    }
+    
+    #[test]
+    fn test_pacing_settings() {
+        assert!(Pacing::from_settings(&ScheduleSettings::default()).unwrap().is_none());
+        
+        let invalid = |timezone: &str, window: CrawlWindow| Pacing::from_settings(&ScheduleSettings {
+            timezone: timezone.to_string(),
+            windows: vec![window],
+        });
+        assert!(invalid("Mars/Olympus", window("01:00", "06:00", 1.0)).is_err());
+        assert!(invalid("UTC", window("1am", "06:00", 1.0)).is_err());
+        assert!(invalid("UTC", window("01:00", "06:00", 1.5)).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,125 @@
+use anyhow::{Result, Context};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::info;
+use url::Url;
+
+use crate::cli::config::{CrawlerConfig, PipelineConfig};
+use crate::crawler::controller::CrawlerController;
+use crate::crawler::task::TaskResult;
+
+/// Interval between checks of whether a stage's job is done
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// URLs a stage hands to the next one, read from the `field` extracted from
+/// each page and resolved against the page's URL, in crawl order without
+/// duplicates
+pub fn handoff_urls(results: &[TaskResult], field: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+    
+    for result in results {
+        let Ok(base) = Url::parse(&result.url) else {
+            continue;
+        };
+        
+        let values = match result.extracted_data.get(field) {
+            Some(Value::String(value)) => vec![value.as_str()],
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+            _ => continue,
+        };
+        
+        for value in values {
+            let Ok(url) = base.join(value.trim()) else {
+                continue;
+            };
+            if !matches!(url.scheme(), "http" | "https") {
+                continue;
+            }
+            
+            let url = url.to_string();
+            if seen.insert(url.clone()) {
+                urls.push(url);
+            }
+        }
+    }
+    
+    urls
+}
+
+/// Run the stages of a pipeline in order, the first seeded with `seed_url`
+/// and each of the others with the URLs the one before extracted. Returns
+/// the job IDs of the stages.
+pub async fn run_pipeline(pipeline: &PipelineConfig, seed_url: String) -> Result<Vec<String>> {
+    pipeline.validate()?;
+    
+    let mut seeds = vec![seed_url];
+    let mut parent_job_id = None;
+    let mut job_ids = Vec::new();
+    
+    for (index, stage) in pipeline.stages.iter().enumerate() {
+        let config = CrawlerConfig::load_profile(&stage.profile)
+            .context(format!("Failed to load profile: {}", stage.profile))?;
+        let controller = CrawlerController::new(config).await?;
+        
+        let job_id = controller.start_seeded_job(seeds, parent_job_id.take(), Vec::new()).await?;
+        info!("Pipeline stage {} ({}) started as job {}", index + 1, stage.profile, job_id);
+        job_ids.push(job_id.clone());
+        
+        let status = controller.wait_for_job(&job_id, POLL_INTERVAL).await?;
+        if status.state != "completed" {
+            anyhow::bail!("Pipeline stage {} job {} ended {}", index + 1, job_id, status.state);
+        }
+        info!("Pipeline stage {} job {} completed, {} page(s) crawled", index + 1, job_id, status.pages_crawled);
+        
+        let Some(field) = &stage.urls_field else {
+            break;
+        };
+        
+        let results = controller.list_page_results(&job_id).await?;
+        seeds = handoff_urls(&results, field);
+        if seeds.is_empty() {
+            anyhow::bail!("Pipeline stage {} job {} extracted no URLs in field '{}'", index + 1, job_id, field);
+        }
+        info!("Pipeline stage {} hands {} URL(s) to the next stage", index + 1, seeds.len());
+        
+        parent_job_id = Some(job_id);
+    }
+    
+    Ok(job_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn result(url: &str, extracted_data: Value) -> TaskResult {
+        TaskResult {
+            extracted_data,
+            ..TaskResult::test_page(url)
+        }
+    }
+    
+    #[test]
+    fn test_handoff_urls() {
+        let results = vec![
+            result("https://example.com/list?page=1", serde_json::json!({
+                "detail_url": ["/item/1", "https://example.com/item/2", "mailto:shop@example.com"],
+            })),
+            result("https://example.com/list/page/2", serde_json::json!({
+                "detail_url": ["../../item/2", "item/3"],
+            })),
+            result("https://example.com/featured", serde_json::json!({"detail_url": "/item/4"})),
+            result("https://example.com/empty", serde_json::json!({"title": "No items"})),
+        ];
+        
+        assert_eq!(handoff_urls(&results, "detail_url"), vec![
+            "https://example.com/item/1",
+            "https://example.com/item/2",
+            "https://example.com/list/page/item/3",
+            "https://example.com/item/4",
+        ]);
+        assert!(handoff_urls(&results, "missing").is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,292 @@
+use anyhow::Result;
+#[cfg(feature = "wasm")]
+use anyhow::Context;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use crate::cli::config::PluginSettings;
+use crate::crawler::extractors::RESERVED_NAMES;
+use crate::crawler::task::TaskResult;
+
+/// WASM modules of a profile, shipping site-specific extraction and URL
+/// filtering without rebuilding the crawler
+///
+/// A plugin exports its `memory` and `alloc(len: i32) -> i32`, handing out
+/// room for the crawler's input, and at least one of:
+///
+/// - `extract(ptr: i32, len: i32) -> i64`, given the page result as JSON and
+///   returning the pointer of its JSON output in the high 32 bits and its
+///   length in the low ones, or 0 when it has nothing for the page
+/// - `filter_url(ptr: i32, len: i32) -> i32`, given a URL and returning 0
+///   to skip it
+///
+/// Every call runs in a fresh instance, with no imports and a limited amount
+/// of fuel, so plugins can't keep state between pages or hang a worker.
+#[derive(Default)]
+pub struct Plugins {
+    plugins: Vec<WasmPlugin>,
+}
+
+impl Plugins {
+    /// Load the plugins of a profile, `extractors` being the names of the
+    /// compiled-in extractors it runs, which plugins can't share
+    pub fn load(settings: &[PluginSettings], extractors: &[String]) -> Result<Self> {
+        let mut plugins: Vec<WasmPlugin> = Vec::with_capacity(settings.len());
+        for plugin in settings {
+            if RESERVED_NAMES.contains(&plugin.name.as_str()) || extractors.contains(&plugin.name) {
+                anyhow::bail!("Plugin name {} is already used in the extracted data", plugin.name);
+            }
+            if plugins.iter().any(|other| other.name() == plugin.name) {
+                anyhow::bail!("Two plugins are named {}", plugin.name);
+            }
+            
+            let plugin = WasmPlugin::load(plugin)?;
+            info!("Loaded plugin {} ({})", plugin.name(), plugin.describe());
+            plugins.push(plugin);
+        }
+        
+        Ok(Self { plugins })
+    }
+    
+    /// Run the extracting plugins on a page, adding their output under their
+    /// name to its extracted data. A plugin failing on a page is logged and
+    /// leaves nothing.
+    pub fn extract(&self, page: &mut TaskResult) {
+        let extracting: Vec<&WasmPlugin> = self.plugins.iter().filter(|plugin| plugin.extracts()).collect();
+        if extracting.is_empty() {
+            return;
+        }
+        
+        let input = match serde_json::to_vec(&*page) {
+            Ok(input) => input,
+            Err(e) => {
+                warn!("Failed to serialize {} for plugins: {}", page.url, e);
+                return;
+            }
+        };
+        for plugin in extracting {
+            match plugin.extract(&input) {
+                Ok(Some(value)) => page.extracted_data[plugin.name()] = value,
+                Ok(None) => {},
+                Err(e) => warn!("Plugin {} failed on {}: {:#}", plugin.name(), page.url, e),
+            }
+        }
+    }
+    
+    /// Whether every filtering plugin lets a URL be crawled. A plugin failing
+    /// on a URL is logged and doesn't skip it.
+    pub fn allows(&self, url: &str) -> bool {
+        for plugin in self.plugins.iter().filter(|plugin| plugin.filters()) {
+            match plugin.allows(url) {
+                Ok(true) => {},
+                Ok(false) => {
+                    debug!("Plugin {} skipped {}", plugin.name(), url);
+                    return false;
+                },
+                Err(e) => warn!("Plugin {} failed on {}: {:#}", plugin.name(), url, e),
+            }
+        }
+        true
+    }
+}
+
+#[cfg(feature = "wasm")]
+struct WasmPlugin {
+    name: String,
+    fuel: u64,
+    engine: wasmtime::Engine,
+    instance: wasmtime::InstancePre<()>,
+    extracts: bool,
+    filters: bool,
+}
+
+#[cfg(feature = "wasm")]
+impl WasmPlugin {
+    fn load(settings: &PluginSettings) -> Result<Self> {
+        use wasmtime::{Config, Engine, Linker, Module};
+        
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        
+        let module = Module::from_file(&engine, &settings.path)
+            .context(format!("Failed to load plugin {} from {}", settings.name, settings.path))?;
+        let exports: Vec<&str> = module.exports().map(|export| export.name()).collect();
+        for required in ["memory", "alloc"] {
+            if !exports.contains(&required) {
+                anyhow::bail!("Plugin {} doesn't export {}", settings.name, required);
+            }
+        }
+        let extracts = exports.contains(&"extract");
+        let filters = exports.contains(&"filter_url");
+        if !extracts && !filters {
+            anyhow::bail!("Plugin {} exports neither extract nor filter_url", settings.name);
+        }
+        
+        let instance = Linker::new(&engine).instantiate_pre(&module)
+            .context(format!("Plugin {} imports functions the crawler doesn't provide", settings.name))?;
+        
+        Ok(Self {
+            name: settings.name.clone(),
+            fuel: settings.fuel,
+            engine,
+            instance,
+            extracts,
+            filters,
+        })
+    }
+    
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn extracts(&self) -> bool {
+        self.extracts
+    }
+    
+    fn filters(&self) -> bool {
+        self.filters
+    }
+    
+    fn describe(&self) -> &'static str {
+        match (self.extracts, self.filters) {
+            (true, true) => "extraction and URL filter",
+            (true, false) => "extraction",
+            _ => "URL filter",
+        }
+    }
+    
+    fn extract(&self, input: &[u8]) -> Result<Option<Value>> {
+        let (mut store, instance, memory) = self.instantiate()?;
+        let (ptr, len) = Self::write_input(&mut store, &instance, &memory, input)?;
+        
+        let extract = instance.get_typed_func::<(i32, i32), i64>(&mut store, "extract")?;
+        let output = extract.call(&mut store, (ptr, len))?;
+        if output == 0 {
+            return Ok(None);
+        }
+        
+        let start = (output >> 32) as u32 as usize;
+        let end = start + (output as u32 as usize);
+        let output = memory.data(&store).get(start..end).context("Plugin output is outside its memory")?;
+        Ok(Some(serde_json::from_slice(output).context("Plugin output is not JSON")?))
+    }
+    
+    fn allows(&self, url: &str) -> Result<bool> {
+        let (mut store, instance, memory) = self.instantiate()?;
+        let (ptr, len) = Self::write_input(&mut store, &instance, &memory, url.as_bytes())?;
+        
+        let filter = instance.get_typed_func::<(i32, i32), i32>(&mut store, "filter_url")?;
+        Ok(filter.call(&mut store, (ptr, len))? != 0)
+    }
+    
+    /// Fresh instance of the module, with a full tank of fuel
+    fn instantiate(&self) -> Result<(wasmtime::Store<()>, wasmtime::Instance, wasmtime::Memory)> {
+        let mut store = wasmtime::Store::new(&self.engine, ());
+        store.set_fuel(self.fuel)?;
+        let instance = self.instance.instantiate(&mut store)?;
+        let memory = instance.get_memory(&mut store, "memory").context("Plugin exports no memory")?;
+        
+        Ok((store, instance, memory))
+    }
+    
+    /// Copy the input into room the plugin allocates for it
+    fn write_input(
+        store: &mut wasmtime::Store<()>,
+        instance: &wasmtime::Instance,
+        memory: &wasmtime::Memory,
+        input: &[u8],
+    ) -> Result<(i32, i32)> {
+        let len = i32::try_from(input.len()).context("Plugin input is too large")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+        let ptr = alloc.call(&mut *store, len)?;
+        memory.write(&mut *store, ptr as u32 as usize, input).context("Plugin allocated its input outside its memory")?;
+        
+        Ok((ptr, len))
+    }
+}
+
+/// Without the wasm feature no plugin loads, profiles declaring any fail
+#[cfg(not(feature = "wasm"))]
+enum WasmPlugin {}
+
+#[cfg(not(feature = "wasm"))]
+impl WasmPlugin {
+    fn load(_settings: &PluginSettings) -> Result<Self> {
+        anyhow::bail!("WASM plugins need the crawler to be built with the wasm feature")
+    }
+    
+    fn name(&self) -> &str {
+        match *self {}
+    }
+    
+    fn extracts(&self) -> bool {
+        match *self {}
+    }
+    
+    fn filters(&self) -> bool {
+        match *self {}
+    }
+    
+    fn describe(&self) -> &'static str {
+        match *self {}
+    }
+    
+    fn extract(&self, _input: &[u8]) -> Result<Option<Value>> {
+        match *self {}
+    }
+    
+    fn allows(&self, _url: &str) -> Result<bool> {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "wasm"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    /// Extracts `{"plugin":true}` from every page and skips URLs ending in `f`
+    const PLUGIN: &str = r#"(module
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 1024))
+        (data (i32.const 16) "{\"plugin\":true}")
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+        (func (export "extract") (param i32 i32) (result i64)
+            (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 15)))
+        (func (export "filter_url") (param $ptr i32) (param $len i32) (result i32)
+            (i32.ne
+                (i32.load8_u (i32.sub (i32.add (local.get $ptr) (local.get $len)) (i32.const 1)))
+                (i32.const 102))))"#;
+    
+    #[test]
+    fn test_plugins() {
+        let path = std::env::temp_dir().join(format!("plugin-{}.wat", uuid::Uuid::new_v4()));
+        std::fs::write(&path, PLUGIN).unwrap();
+        let settings = vec![PluginSettings {
+            name: "shop".to_string(),
+            path: path.to_string_lossy().to_string(),
+            fuel: 10_000,
+        }];
+        
+        assert!(Plugins::load(&settings, &["shop".to_string()]).is_err());
+        let plugins = Plugins::load(&settings, &[]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        
+        assert!(plugins.allows("https://shop.example.com/kettles/blue"));
+        assert!(!plugins.allows("https://shop.example.com/kettles/blue.pdf"));
+        
+        let mut page = TaskResult {
+            title: "Blue kettle".to_string(),
+            raw_content: "<p>Blue kettle</p>".to_string(),
+            extracted_data: json!({"name": "Blue kettle"}),
+            ..TaskResult::test_page("https://shop.example.com/kettles/blue")
+        };
+        plugins.extract(&mut page);
+        assert_eq!(page.extracted_data, json!({"name": "Blue kettle", "shop": {"plugin": true}}));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,71 @@
+use chrono::{Duration, Utc};
+use serde_json::Value;
+
+use crate::cli::config::QuarantineSettings;
+use crate::crawler::task::{QuarantinedPage, TaskResult};
+
+/// Required fields missing from extracted data
+///
+/// Fields are dot-separated paths into the data. A field counts as missing
+/// when it is absent, null, or an empty string, array or object.
+pub fn missing_fields(data: &Value, required: &[String]) -> Vec<String> {
+    required.iter()
+        .filter(|field| {
+            let value = field.split('.').try_fold(data, |value, key| value.get(key));
+            match value {
+                None | Some(Value::Null) => true,
+                Some(Value::String(text)) => text.trim().is_empty(),
+                Some(Value::Array(items)) => items.is_empty(),
+                Some(Value::Object(object)) => object.is_empty(),
+                Some(_) => false,
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// Quarantine a page result if its extracted data fails validation
+pub fn validate(result: &TaskResult, settings: &QuarantineSettings) -> Option<QuarantinedPage> {
+    if !settings.enabled {
+        return None;
+    }
+    
+    let missing = missing_fields(&result.extracted_data, &settings.required_fields);
+    if missing.is_empty() {
+        return None;
+    }
+    
+    let quarantined_at = Utc::now();
+    
+    Some(QuarantinedPage {
+        result: result.clone(),
+        missing_fields: missing,
+        quarantined_at,
+        expires_at: quarantined_at + Duration::hours(settings.retention_hours as i64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_missing_fields() {
+        let data = json!({
+            "title": "Product",
+            "description": "  ",
+            "price": { "amount": 10, "currency": null },
+            "images": [],
+        });
+        let required: Vec<String> = ["title", "description", "price.amount", "price.currency", "images", "sku"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        
+        assert_eq!(
+            missing_fields(&data, &required),
+            vec!["description", "price.currency", "images", "sku"]
+        );
+    }
+}
\ No newline at end of file
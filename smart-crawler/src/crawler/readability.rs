@@ -0,0 +1,260 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Elements that are never part of the main content
+const BOILERPLATE_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "iframe", "form", "button", "select", "textarea", "input",
+    "nav", "header", "footer", "aside", "menu", "dialog",
+];
+
+/// Elements starting a paragraph of their own in the extracted text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5",
+    "h6", "li", "main", "ol", "p", "pre", "section", "table", "tr", "ul",
+];
+
+/// Words in the class or id of an element marking it as navigation, ads and the like
+const NEGATIVE_HINTS: &[&str] = &[
+    "ad", "ads", "advert", "banner", "breadcrumb", "comment", "cookie", "footer", "menu", "nav", "newsletter",
+    "popup", "promo", "related", "share", "sidebar", "social", "sponsor", "subscribe", "widget",
+];
+
+/// Words in the class or id of an element marking it as the content of the page
+const POSITIVE_HINTS: &[&str] = &["article", "body", "content", "entry", "main", "post", "story", "text"];
+
+/// Shortest paragraph, in characters, counted as content when scoring
+const MIN_PARAGRAPH_LEN: usize = 25;
+
+/// Share of link text past which a block is taken for navigation
+const MAX_LINK_DENSITY: f64 = 0.5;
+
+/// Title and text of the main content of a page, with navigation, ads,
+/// footers and other boilerplate left out, None when no content is found
+///
+/// Like Readability, paragraphs score their parent and grandparent by their
+/// length and commas, the highest scoring element being the content. Class
+/// and id names like `sidebar` or `article` weigh in, and blocks that are
+/// mostly links are dropped. The title is the content's first heading, or the
+/// document title without the site name.
+pub fn extract_main_content(html: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let content = best_candidate(&document)?;
+    
+    let mut blocks = Vec::new();
+    let mut inline = String::new();
+    collect_text(content, &mut inline, &mut blocks);
+    push_paragraph(&mut inline, &mut blocks);
+    if blocks.is_empty() {
+        return None;
+    }
+    
+    let heading_selector = Selector::parse("h1, h2").expect("valid heading selector");
+    let title = content.select(&heading_selector)
+        .map(|heading| collapse(&heading.text().collect::<String>()))
+        .find(|heading| !heading.is_empty())
+        .or_else(|| document_title(&document));
+    
+    Some(json!({
+        "title": title,
+        "text": blocks.join("\n\n"),
+    }))
+}
+
+/// Element holding the main content, the highest scoring parent of paragraphs
+/// or the body when no paragraph scores
+fn best_candidate(document: &Html) -> Option<ElementRef<'_>> {
+    let paragraph_selector = Selector::parse("p, pre, td, blockquote").expect("valid paragraph selector");
+    
+    let mut scores: HashMap<_, (ElementRef, f64)> = HashMap::new();
+    for paragraph in document.select(&paragraph_selector) {
+        if is_boilerplate(paragraph) || paragraph.ancestors().filter_map(ElementRef::wrap).any(is_boilerplate) {
+            continue;
+        }
+        let text = collapse(&paragraph.text().collect::<String>());
+        if text.chars().count() < MIN_PARAGRAPH_LEN {
+            continue;
+        }
+        
+        let score = 1.0 + text.matches(',').count() as f64 + (text.chars().count() / 100).min(3) as f64;
+        let ancestors = paragraph.ancestors().filter_map(ElementRef::wrap).take(2);
+        for (level, ancestor) in ancestors.enumerate() {
+            let entry = scores.entry(ancestor.id()).or_insert_with(|| (ancestor, class_weight(ancestor)));
+            entry.1 += if level == 0 { score } else { score / 2.0 };
+        }
+    }
+    
+    let best = scores.into_values()
+        .map(|(element, score)| (element, score * (1.0 - link_density(element))))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| element);
+    
+    best.or_else(|| {
+        let body_selector = Selector::parse("body").expect("valid body selector");
+        document.select(&body_selector).next()
+    })
+}
+
+/// Add the paragraphs of the text under an element, leaving out boilerplate
+/// and blocks that are mostly links
+fn collect_text(element: ElementRef, inline: &mut String, blocks: &mut Vec<String>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => inline.push_str(text),
+            Node::Element(node) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if is_boilerplate(child) {
+                    continue;
+                }
+                
+                if node.name() == "br" {
+                    inline.push('\n');
+                } else if BLOCK_ELEMENTS.contains(&node.name()) {
+                    push_paragraph(inline, blocks);
+                    if link_density(child) <= MAX_LINK_DENSITY {
+                        collect_text(child, inline, blocks);
+                        push_paragraph(inline, blocks);
+                    }
+                } else {
+                    collect_text(child, inline, blocks);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Whether an element is boilerplate by its name, role or a negative class or
+/// id without a positive one
+fn is_boilerplate(element: ElementRef) -> bool {
+    let node = element.value();
+    if BOILERPLATE_ELEMENTS.contains(&node.name()) {
+        return true;
+    }
+    if matches!(node.attr("role"), Some("navigation" | "banner" | "contentinfo" | "complementary")) {
+        return true;
+    }
+    
+    let hints = hint_words(element);
+    hints.iter().any(|word| NEGATIVE_HINTS.contains(&word.as_str()))
+        && !hints.iter().any(|word| POSITIVE_HINTS.contains(&word.as_str()))
+}
+
+/// Starting score of a candidate from the hints in its class and id
+fn class_weight(element: ElementRef) -> f64 {
+    let hints = hint_words(element);
+    let mut weight = 0.0;
+    if hints.iter().any(|word| POSITIVE_HINTS.contains(&word.as_str())) {
+        weight += 25.0;
+    }
+    if hints.iter().any(|word| NEGATIVE_HINTS.contains(&word.as_str())) {
+        weight -= 25.0;
+    }
+    if matches!(element.value().name(), "article" | "main") {
+        weight += 10.0;
+    }
+    weight
+}
+
+/// Lowercase words of the class and id of an element, split on dashes and underscores
+fn hint_words(element: ElementRef) -> Vec<String> {
+    let node = element.value();
+    [node.attr("class"), node.attr("id")]
+        .into_iter()
+        .flatten()
+        .flat_map(|names| names.split(|c: char| c.is_whitespace() || c == '-' || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Share of the text of an element inside links
+fn link_density(element: ElementRef) -> f64 {
+    let link_selector = Selector::parse("a").expect("valid link selector");
+    let length = element.text().map(|text| text.trim().len()).sum::<usize>();
+    if length == 0 {
+        return 0.0;
+    }
+    let link_length = element.select(&link_selector)
+        .flat_map(|link| link.text())
+        .map(|text| text.trim().len())
+        .sum::<usize>();
+    link_length as f64 / length as f64
+}
+
+/// Document title without the site name after a separator, when what is left
+/// is more than a couple of words
+fn document_title(document: &Html) -> Option<String> {
+    let title_selector = Selector::parse("title").expect("valid title selector");
+    let title = collapse(&document.select(&title_selector).next()?.text().collect::<String>());
+    if title.is_empty() {
+        return None;
+    }
+    
+    for separator in [" | ", " - ", " – ", " — ", " :: "] {
+        if let Some((page, _)) = title.rsplit_once(separator) {
+            if page.split_whitespace().count() >= 3 {
+                return Some(page.to_string());
+            }
+        }
+    }
+    Some(title)
+}
+
+/// Text with every run of whitespace collapsed to a single space and trimmed
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn push_paragraph(inline: &mut String, blocks: &mut Vec<String>) {
+    let paragraph = inline.split('\n')
+        .map(collapse)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+    inline.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_extract_main_content() {
+        let html = r#"<html><head><title>Kettles that boil fast | Example Shop</title></head><body>
+            <header><a href="/">Example Shop</a><nav><a href="/kettles">Kettles</a> <a href="/toasters">Toasters</a></nav></header>
+            <div class="sidebar"><p>Subscribe to our newsletter, get offers, news and discounts every week.</p></div>
+            <div id="main-content">
+                <article>
+                    <h1>Blue   kettle review</h1>
+                    <p>The blue kettle boils 1.7 litres in three minutes, quieter than most, and keeps water warm.</p>
+                    <div class="ad-banner">Buy now, 20% off, limited time, while stocks last!</div>
+                    <p>Its steel body stays cool to the touch,<br>even right after boiling.</p>
+                    <ul class="share"><li><a href="/share/x">Share on X</a></li></ul>
+                    <ul><li><a href="/a">Toaster review</a></li><li><a href="/b">Mixer review</a></li></ul>
+                </article>
+            </div>
+            <footer><p>Copyright Example Shop, all rights reserved, since 1999, in every country.</p></footer>
+        </body></html>"#;
+        
+        let content = extract_main_content(html).unwrap();
+        assert_eq!(content["title"], "Blue kettle review");
+        assert_eq!(content["text"], "Blue kettle review
+
+The blue kettle boils 1.7 litres in three minutes, quieter than most, and keeps water warm.
+
+Its steel body stays cool to the touch,
+even right after boiling.");
+        
+        let html = "<html><head><title>Kettles that boil fast | Example Shop</title></head>
+            <body><div><p>Kettles boil water, some faster than others, and some quieter.</p></div></body></html>";
+        assert_eq!(extract_main_content(html).unwrap()["title"], "Kettles that boil fast");
+        
+        assert_eq!(extract_main_content("<html><body><nav><a href=\"/\">Home</a></nav></body></html>"), None);
+    }
+}
\ No newline at end of file
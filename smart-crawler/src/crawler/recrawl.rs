@@ -0,0 +1,93 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+use crate::crawler::task::TaskResult;
+
+/// How the content of a recrawled page compares to the previous crawl
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageChange {
+    /// The previous job has no result for the page
+    New,
+    Changed,
+    Unchanged,
+}
+
+impl PageChange {
+    /// Compare a page just fetched to its result in the previous job
+    pub fn detect(previous: Option<&TaskResult>, content: &str) -> Self {
+        match previous {
+            None => Self::New,
+            Some(previous) if content_hash(&previous.raw_content) == content_hash(content) => Self::Unchanged,
+            Some(_) => Self::Changed,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// SHA-256 of the content of a page, hex encoded
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Pages of a recrawl job by how they changed since the job it recrawls
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecrawlSummary {
+    pub previous_job_id: String,
+    pub pages_changed: usize,
+    pub pages_unchanged: usize,
+    pub pages_new: usize,
+}
+
+impl RecrawlSummary {
+    pub fn new(previous_job_id: &str) -> Self {
+        Self { previous_job_id: previous_job_id.to_string(), ..Self::default() }
+    }
+    
+    /// Count a recrawled page
+    pub fn record(&mut self, change: PageChange) {
+        match change {
+            PageChange::New => self.pages_new += 1,
+            PageChange::Changed => self.pages_changed += 1,
+            PageChange::Unchanged => self.pages_unchanged += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_detect_changes() {
+        let previous = TaskResult {
+            title: "Blue kettle".to_string(),
+            raw_content: "<p>Blue kettle, 25 EUR</p>".to_string(),
+            extracted_data: json!({"price": "25 EUR"}),
+            ..TaskResult::test_page("https://shop.example.com/kettles/blue")
+        };
+        
+        let mut summary = RecrawlSummary::new("job");
+        for (previous, content) in [
+            (Some(&previous), "<p>Blue kettle, 25 EUR</p>"),
+            (Some(&previous), "<p>Blue kettle, 19 EUR</p>"),
+            (None, "<p>Red kettle, 25 EUR</p>"),
+            (Some(&previous), "<p>Blue kettle, 25 EUR</p>"),
+        ] {
+            summary.record(PageChange::detect(previous, content));
+        }
+        assert_eq!(summary, RecrawlSummary {
+            previous_job_id: "job".to_string(),
+            pages_changed: 1,
+            pages_unchanged: 2,
+            pages_new: 1,
+        });
+    }
+}
\ No newline at end of file
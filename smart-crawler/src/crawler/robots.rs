@@ -0,0 +1,109 @@
+use scraper::{Html, Selector};
+use std::collections::HashSet;
+use url::Url;
+
+/// Robots directives of a page, from its robots meta tags, its X-Robots-Tag
+/// header and the rel attributes of its links
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PageRobots {
+    /// The page asks not to be indexed, so it isn't stored
+    pub noindex: bool,
+    
+    /// The page asks for none of its links to be followed
+    pub nofollow: bool,
+    
+    /// Absolute URLs of the links marked rel=nofollow
+    pub nofollow_links: HashSet<String>,
+}
+
+impl PageRobots {
+    /// Read the directives of a page, ignoring the ones aimed at a single
+    /// crawler like `googlebot: noindex`
+    pub fn read(html: &str, page_url: &Url, x_robots_tag: Option<&str>) -> Self {
+        let mut robots = Self::default();
+        
+        // Repeated headers are joined with newlines
+        for line in x_robots_tag.unwrap_or_default().lines() {
+            robots.add_directives(line);
+        }
+        
+        let document = Html::parse_document(html);
+        let meta_selector = Selector::parse("meta[name][content]").expect("valid meta selector");
+        for element in document.select(&meta_selector) {
+            let node = element.value();
+            if node.attr("name").is_some_and(|name| name.trim().eq_ignore_ascii_case("robots")) {
+                robots.add_directives(node.attr("content").unwrap_or_default());
+            }
+        }
+        
+        let link_selector = Selector::parse("a[href][rel], area[href][rel]").expect("valid link selector");
+        robots.nofollow_links = document.select(&link_selector)
+            .filter(|element| {
+                element.value().attr("rel").unwrap_or_default()
+                    .split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("nofollow"))
+            })
+            .filter_map(|element| page_url.join(element.value().attr("href")?.trim()).ok())
+            .map(|url| url.to_string())
+            .collect();
+        
+        robots
+    }
+    
+    /// Add the directives of a robots meta tag or X-Robots-Tag line
+    fn add_directives(&mut self, directives: &str) {
+        // A leading "name:" aims the line at that crawler only
+        if directives.split(',').next().is_some_and(|first| first.contains(':')) {
+            return;
+        }
+        
+        for directive in directives.split(',') {
+            match directive.trim().to_ascii_lowercase().as_str() {
+                "noindex" => self.noindex = true,
+                "nofollow" => self.nofollow = true,
+                "none" => {
+                    self.noindex = true;
+                    self.nofollow = true;
+                },
+                _ => {},
+            }
+        }
+    }
+    
+    /// Whether a link of the page may be followed
+    pub fn follows(&self, link: &str) -> bool {
+        !self.nofollow && !self.nofollow_links.contains(link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_read_robots() {
+        let page_url = Url::parse("https://shop.example.com/kettles/").unwrap();
+        let html = r#"<html><head>
+            <meta name="Robots" content="index, nofollow">
+            <meta name="googlebot" content="noindex">
+            </head><body>
+            <a href="/login" rel="NoFollow noopener">Log in</a>
+            <a href="blue">Blue kettle</a>
+            <a href="red" rel="sponsored">Red kettle</a>
+            </body></html>"#;
+        
+        let robots = PageRobots::read(html, &page_url, None);
+        assert!(!robots.noindex);
+        assert!(robots.nofollow);
+        assert_eq!(robots.nofollow_links, HashSet::from(["https://shop.example.com/login".to_string()]));
+        assert!(!robots.follows("https://shop.example.com/kettles/blue"));
+        
+        let robots = PageRobots::read("<a href='/login' rel='nofollow'>Log in</a>", &page_url, Some("googlebot: none\nnoarchive, noindex"));
+        assert!(robots.noindex);
+        assert!(!robots.nofollow);
+        assert!(robots.follows("https://shop.example.com/kettles/blue"));
+        assert!(!robots.follows("https://shop.example.com/login"));
+        
+        assert_eq!(PageRobots::read("", &page_url, Some("unavailable_after: 25 Jun 2010 15:00:00 PST")), PageRobots::default());
+    }
+}
\ No newline at end of file
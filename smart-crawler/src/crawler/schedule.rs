@@ -0,0 +1,181 @@
+use anyhow::Result;
+#[cfg(any(feature = "api", feature = "redis"))]
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "api")]
+use std::str::FromStr;
+#[cfg(feature = "redis")]
+use std::time::Duration;
+#[cfg(feature = "redis")]
+use tracing::{info, warn};
+
+#[cfg(feature = "redis")]
+use crate::cli::config::CrawlerConfig;
+#[cfg(feature = "redis")]
+use crate::crawler::controller::CrawlerController;
+#[cfg(feature = "redis")]
+use crate::storage::queue::QueueManager;
+
+/// Longest wait between checks for due schedules
+#[cfg(feature = "redis")]
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Crawl started again on a cron schedule by the processes serving the API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlSchedule {
+    pub id: String,
+    
+    /// Cron expression in UTC, with 5 fields from minutes to weekdays or 6
+    /// starting with seconds
+    pub cron: String,
+    
+    pub profile: String,
+    pub seed_urls: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    
+    /// When the schedule last started a job, and which
+    #[serde(default)]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_job_id: Option<String>,
+}
+
+impl CrawlSchedule {
+    /// New schedule, failing on an invalid cron expression or without seeds
+    pub fn new(cron: &str, profile: String, seed_urls: Vec<String>) -> Result<Self> {
+        next_fire(cron, Utc::now())?;
+        if seed_urls.is_empty() {
+            anyhow::bail!("A schedule needs at least one seed URL");
+        }
+        
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            cron: cron.trim().to_string(),
+            profile,
+            seed_urls,
+            created_at: Utc::now(),
+            last_run_at: None,
+            last_job_id: None,
+        })
+    }
+    
+    /// First time the schedule fires after `after`, None if it never does again
+    pub fn next_run_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        next_fire(&self.cron, after).ok()?
+    }
+    
+    /// Next time the schedule fires after its last run, or its creation.
+    /// Runs missed while no server was up are caught up once, not each.
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.next_run_after(self.last_run_at.unwrap_or(self.created_at))
+    }
+}
+
+/// First time a cron expression fires after `after`, failing on an invalid
+/// expression. 5-field expressions get a seconds field of 0.
+#[cfg(feature = "api")]
+fn next_fire(expression: &str, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+    let expression = expression.trim();
+    let with_seconds = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    
+    let schedule = cron::Schedule::from_str(&with_seconds)
+        .context(format!("Invalid cron expression: {}", expression))?;
+    Ok(schedule.after(&after).next())
+}
+
+#[cfg(not(feature = "api"))]
+fn next_fire(expression: &str, _after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+    anyhow::bail!("Cron schedule {} needs a build with the api feature", expression.trim())
+}
+
+/// Start a job for every schedule that is due, returning the IDs of the jobs
+/// started
+///
+/// Each run is claimed in the queue first, so several servers sharing it
+/// start a job only once. A schedule failing to start is logged and tried
+/// again at its next run.
+#[cfg(feature = "redis")]
+pub async fn run_due_schedules(queue: &QueueManager, now: DateTime<Utc>) -> Result<Vec<String>> {
+    let mut job_ids = Vec::new();
+    for mut schedule in queue.list_schedules().await? {
+        let Some(due) = schedule.next_run().filter(|due| *due <= now) else {
+            continue;
+        };
+        if !queue.claim_schedule_run(&schedule.id, due).await? {
+            continue;
+        }
+        
+        match start_scheduled_job(&schedule).await {
+            Ok(job_id) => {
+                info!("Schedule {} started job {}", schedule.id, job_id);
+                schedule.last_job_id = Some(job_id.clone());
+                job_ids.push(job_id);
+            },
+            Err(e) => warn!("Schedule {} failed to start a job: {:#}", schedule.id, e),
+        }
+        schedule.last_run_at = Some(now);
+        queue.update_schedule(&schedule).await?;
+    }
+    
+    Ok(job_ids)
+}
+
+#[cfg(feature = "redis")]
+async fn start_scheduled_job(schedule: &CrawlSchedule) -> Result<String> {
+    let config = CrawlerConfig::load_profile(&schedule.profile)
+        .context(format!("Failed to load profile: {}", schedule.profile))?;
+    let controller = CrawlerController::new(config).await?;
+    
+    controller.start_job(schedule.seed_urls.clone()).await
+}
+
+/// Start the jobs of due schedules until shutdown is requested
+#[cfg(feature = "redis")]
+pub async fn run_schedules(queue: &QueueManager) {
+    loop {
+        if let Err(e) = run_due_schedules(queue, Utc::now()).await {
+            warn!("Failed to run schedules: {:#}", e);
+        }
+        
+        // Wake up for the next run rather than a check interval later
+        let next_run = queue.list_schedules().await.unwrap_or_default().iter()
+            .filter_map(CrawlSchedule::next_run)
+            .min();
+        let wait = next_run
+            .and_then(|next_run| (next_run - Utc::now()).to_std().ok())
+            .map_or(CHECK_INTERVAL, |wait| wait.min(CHECK_INTERVAL));
+        
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {},
+            _ = crate::utils::shutdown::requested() => return,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "api"))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    #[test]
+    fn test_next_run() {
+        let mut schedule = CrawlSchedule::new("30 2 * * Mon", "news".to_string(), vec!["https://news.example.com".to_string()]).unwrap();
+        schedule.created_at = Utc.with_ymd_and_hms(2024, 7, 2, 12, 0, 0).unwrap();
+        assert_eq!(schedule.next_run(), Some(Utc.with_ymd_and_hms(2024, 7, 8, 2, 30, 0).unwrap()));
+        
+        // The next run follows the last one, however late it was
+        schedule.last_run_at = Some(Utc.with_ymd_and_hms(2024, 7, 29, 9, 0, 0).unwrap());
+        assert_eq!(schedule.next_run(), Some(Utc.with_ymd_and_hms(2024, 8, 5, 2, 30, 0).unwrap()));
+        
+        let every_ten_seconds = CrawlSchedule { cron: "*/10 * * * * *".to_string(), ..schedule.clone() };
+        assert_eq!(every_ten_seconds.next_run(), Some(Utc.with_ymd_and_hms(2024, 7, 29, 9, 0, 10).unwrap()));
+        
+        assert!(CrawlSchedule::new("every monday", "news".to_string(), vec!["https://news.example.com".to_string()]).is_err());
+        assert!(CrawlSchedule::new("0 * * * *", "news".to_string(), Vec::new()).is_err());
+    }
+}
\ No newline at end of file
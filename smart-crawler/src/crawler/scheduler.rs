@@ -1,17 +1,63 @@
+use anyhow::Result;
 use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 use regex::Regex;
 use url::Url;
 use tracing::{debug, warn};
 
-use crate::cli::config::CrawlerSettings;
+use crate::browser::remote::RedirectHop;
+use crate::cli::config::{CrawlerSettings, SubdomainPolicy};
+use crate::crawler::plugins::Plugins;
+use crate::storage::scheduler_state::{DomainStats, SchedulerState};
 
-/// Scheduler for determining which URLs should be crawled
+/// Longest delay between requests a domain backs off to, in milliseconds
+const MAX_DOMAIN_DELAY: u64 = 60_000;
+
+/// Why the scheduler didn't crawl a URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Invalid,
+    Scheme,
+    Port,
+    Domain,
+    Excluded,
+    NotIncluded,
+    Plugin,
+    Seen,
+    Depth,
+    RedirectLoop,
+}
+
+impl SkipReason {
+    /// Name of the reason, as recorded
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Invalid => "invalid",
+            Self::Scheme => "scheme",
+            Self::Port => "port",
+            Self::Domain => "domain",
+            Self::Excluded => "excluded",
+            Self::NotIncluded => "not_included",
+            Self::Plugin => "plugin",
+            Self::Seen => "seen",
+            Self::Depth => "depth",
+            Self::RedirectLoop => "redirect_loop",
+        }
+    }
+}
+
+/// Scheduler for determining which URLs should be crawled and when
+///
+/// Decisions depend on the shared scheduler state rather than on anything
+/// held by the scheduler, so all workers of a job agree and a restarted
+/// crawler picks up where it stopped.
 pub struct Scheduler {
     /// Configuration for the crawler
     config: CrawlerSettings,
     
-    /// Set of already seen URLs to avoid duplicates
-    seen_urls: HashSet<String>,
+    /// URLs seen, pages per domain and domain delays of every job
+    state: Arc<dyn SchedulerState>,
     
     /// Compiled regex patterns for URL inclusion
     include_patterns: Vec<Regex>,
@@ -21,11 +67,17 @@ pub struct Scheduler {
     
     /// Allowed domains for crawling (if empty, any domain is allowed)
     allowed_domains: HashSet<String>,
+    
+    /// Names of the query parameters dropped from URLs, whole and ignoring case
+    strip_query_params: Vec<Regex>,
+    
+    /// WASM plugins of the profile, filtering URLs past the patterns
+    plugins: Arc<Plugins>,
 }
 
 impl Scheduler {
     /// Create a new scheduler with the given crawler settings
-    pub fn new(config: CrawlerSettings) -> Self {
+    pub fn new(config: CrawlerSettings, state: Arc<dyn SchedulerState>) -> Self {
         // Compile regex patterns for inclusion
         let include_patterns = config.url_patterns.include.iter()
             .filter_map(|pattern| {
@@ -52,6 +104,19 @@ impl Scheduler {
             })
             .collect();
         
+        // Compile the names of the query parameters to drop
+        let strip_query_params = config.strip_query_params.iter()
+            .filter_map(|pattern| {
+                match Regex::new(&format!("(?i)^(?:{})$", pattern)) {
+                    Ok(regex) => Some(regex),
+                    Err(e) => {
+                        warn!("Invalid query parameter pattern '{}': {}", pattern, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        
         // Create a set of allowed domains
         let allowed_domains = config.allowed_domains.iter()
             .map(|domain| domain.to_lowercase())
@@ -59,52 +124,165 @@ impl Scheduler {
         
         Self {
             config,
-            seen_urls: HashSet::new(),
+            state,
             include_patterns,
             exclude_patterns,
             allowed_domains,
+            strip_query_params,
+            plugins: Arc::new(Plugins::default()),
         }
     }
     
-    /// Determine if a URL should be crawled
-    pub fn should_crawl(&mut self, url: &str) -> bool {
+    /// Also skip the URLs the URL filters of these plugins reject
+    pub fn with_plugins(mut self, plugins: Arc<Plugins>) -> Self {
+        self.plugins = plugins;
+        self
+    }
+    
+    /// Determine if a URL should be crawled for a job, recording it as seen when it should
+    pub async fn should_crawl(&self, job_id: &str, url: &str) -> Result<bool> {
         // Normalize the URL
         let normalized_url = self.normalize_url(url);
         
+        if let Some(reason) = self.skip_reason(&normalized_url) {
+            self.record_skip(job_id, &normalized_url, reason).await?;
+            return Ok(false);
+        }
+        
         // Check if we've already seen this URL
-        if self.seen_urls.contains(&normalized_url) {
+        if !self.state.mark_seen(job_id, &normalized_url).await? {
             debug!("Skipping already seen URL: {}", normalized_url);
-            return false;
+            self.record_skip(job_id, &normalized_url, SkipReason::Seen).await?;
+            return Ok(false);
+        }
+        
+        Ok(true)
+    }
+    
+    /// Record a link found past the depth limit, unless a filter skips it anyway
+    pub async fn skip_too_deep(&self, job_id: &str, url: &str) -> Result<()> {
+        if !self.config.record_skips {
+            return Ok(());
+        }
+        
+        let normalized_url = self.normalize_url(url);
+        let reason = self.skip_reason(&normalized_url).unwrap_or(SkipReason::Depth);
+        
+        self.record_skip(job_id, &normalized_url, reason).await
+    }
+    
+    /// Record why a URL was skipped, when the profile asks for it
+    async fn record_skip(&self, job_id: &str, normalized_url: &str, reason: SkipReason) -> Result<()> {
+        if !self.config.record_skips {
+            return Ok(());
+        }
+        
+        self.state.record_skip(job_id, normalized_url, reason.as_str()).await
+    }
+    
+    /// Number of URLs skipped for each reason, most common first
+    pub async fn skip_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>> {
+        self.state.skip_counts(job_id).await
+    }
+    
+    /// URLs skipped with the reason each was first skipped for
+    pub async fn skipped_urls(&self, job_id: &str) -> Result<Vec<(String, String)>> {
+        self.state.skipped_urls(job_id).await
+    }
+    
+    /// URL of an earlier page of the job whose text is within `max_distance`
+    /// bits of a page's SimHash fingerprint, None when the page is new
+    pub async fn near_duplicate_of(&self, job_id: &str, url: &str, fingerprint: u64, max_distance: u32) -> Result<Option<String>> {
+        self.state.near_duplicate(job_id, &self.normalize_url(url), fingerprint, max_distance).await
+    }
+    
+    /// Record a URL crawled without going through `should_crawl`, like the seed
+    pub async fn mark_seen(&self, job_id: &str, url: &str) -> Result<()> {
+        self.state.mark_seen(job_id, &self.normalize_url(url)).await?;
+        
+        Ok(())
+    }
+    
+    /// Check where a page was redirected to, recording its target as seen so
+    /// it isn't crawled again under its own URL
+    ///
+    /// Returns why the page shouldn't be kept: its redirects went through a
+    /// URL twice, the filters skip its target or the job already has it.
+    /// Targets that are one of the redirecting URLs, like a login redirect
+    /// back to the page, are kept.
+    pub async fn check_redirect(&self, job_id: &str, redirects: &[RedirectHop], final_url: &str) -> Result<Option<SkipReason>> {
+        if redirects.is_empty() {
+            return Ok(None);
+        }
+        
+        let normalized_url = self.normalize_url(final_url);
+        let mut hops = HashSet::new();
+        if redirects.iter().any(|hop| !hops.insert(self.normalize_url(&hop.url))) {
+            warn!("Redirect loop through {}", redirects.iter().map(|hop| hop.url.as_str()).collect::<Vec<_>>().join(" -> "));
+            self.record_skip(job_id, &normalized_url, SkipReason::RedirectLoop).await?;
+            return Ok(Some(SkipReason::RedirectLoop));
+        }
+        if hops.contains(&normalized_url) {
+            return Ok(None);
         }
         
+        if let Some(reason) = self.skip_reason(&normalized_url) {
+            self.record_skip(job_id, &normalized_url, reason).await?;
+            return Ok(Some(reason));
+        }
+        if !self.state.mark_seen(job_id, &normalized_url).await? {
+            debug!("Redirected to already seen URL: {}", normalized_url);
+            self.record_skip(job_id, &normalized_url, SkipReason::Seen).await?;
+            return Ok(Some(SkipReason::Seen));
+        }
+        
+        Ok(None)
+    }
+    
+    /// Why the domain and pattern filters reject a normalized URL, None when it passes them
+    fn skip_reason(&self, normalized_url: &str) -> Option<SkipReason> {
         // Parse the URL
-        let parsed_url = match Url::parse(&normalized_url) {
+        let parsed_url = match Url::parse(normalized_url) {
             Ok(url) => url,
             Err(e) => {
                 debug!("Skipping invalid URL {}: {}", normalized_url, e);
-                return false;
+                return Some(SkipReason::Invalid);
             }
         };
         
+        // Check the scheme and port before anything else, so links like
+        // mailto: and javascript: never reach the browser service
+        if !self.config.allowed_schemes.iter().any(|scheme| scheme.eq_ignore_ascii_case(parsed_url.scheme())) {
+            debug!("Skipping URL with non-allowed scheme: {}", normalized_url);
+            return Some(SkipReason::Scheme);
+        }
+        if !self.config.allowed_ports.is_empty() {
+            let port = parsed_url.port_or_known_default();
+            if !port.is_some_and(|port| self.config.allowed_ports.contains(&port)) {
+                debug!("Skipping URL on non-allowed port: {}", normalized_url);
+                return Some(SkipReason::Port);
+            }
+        }
+        
         // Check if the URL is in an allowed domain
         if !self.allowed_domains.is_empty() {
             if let Some(host) = parsed_url.host_str() {
                 let host = host.to_lowercase();
-                if !self.allowed_domains.iter().any(|domain| host == *domain || host.ends_with(&format!(".{}", domain))) {
+                if !self.domain_allowed(&host) {
                     debug!("Skipping URL from non-allowed domain: {}", host);
-                    return false;
+                    return Some(SkipReason::Domain);
                 }
             } else {
                 debug!("Skipping URL without host: {}", normalized_url);
-                return false;
+                return Some(SkipReason::Invalid);
             }
         }
         
         // Check against exclusion patterns
         for pattern in &self.exclude_patterns {
-            if pattern.is_match(&normalized_url) {
+            if pattern.is_match(normalized_url) {
                 debug!("Skipping URL matching exclusion pattern: {}", normalized_url);
-                return false;
+                return Some(SkipReason::Excluded);
             }
         }
         
@@ -112,7 +290,7 @@ impl Scheduler {
         if !self.include_patterns.is_empty() {
             let mut included = false;
             for pattern in &self.include_patterns {
-                if pattern.is_match(&normalized_url) {
+                if pattern.is_match(normalized_url) {
                     included = true;
                     break;
                 }
@@ -120,14 +298,102 @@ impl Scheduler {
             
             if !included {
                 debug!("Skipping URL not matching any inclusion pattern: {}", normalized_url);
-                return false;
+                return Some(SkipReason::NotIncluded);
             }
         }
         
-        // Add the URL to the seen set
-        self.seen_urls.insert(normalized_url);
+        if !self.plugins.allows(normalized_url) {
+            return Some(SkipReason::Plugin);
+        }
+        
+        None
+    }
+    
+    /// Wait until a request to a URL fits in the rate limit of its domain,
+    /// counted over the requests of all workers
+    pub async fn wait_rate_limit(&self, job_id: &str, url: &str) -> Result<()> {
+        if !self.config.rate_limit.enabled {
+            return Ok(());
+        }
+        let Some(domain) = domain_of(url) else {
+            return Ok(());
+        };
+        
+        let limit = self.config.rate_limit.for_domain(&domain);
+        if limit.requests_per_second <= 0.0 {
+            return Ok(());
+        }
+        
+        let wait = self.state.take_token(job_id, &domain, limit.requests_per_second, limit.burst).await?;
+        if wait > 0 {
+            debug!("Waiting {} ms for the rate limit of {}", wait, domain);
+            tokio::time::sleep(Duration::from_millis(wait)).await;
+        }
         
-        true
+        Ok(())
+    }
+    
+    /// Record how a request to a URL went, counting the page, adapting the
+    /// delay of its domain and adding it to the domain's statistics
+    pub async fn record_request(&self, job_id: &str, url: &str, latency: Duration, success: bool) -> Result<()> {
+        let Some(domain) = domain_of(url) else {
+            return Ok(());
+        };
+        
+        let base = self.config.politeness_delay;
+        let current = self.state.domain_delay(job_id, &domain).await?.unwrap_or(base);
+        let delay = adapt_delay(current, base, success);
+        if delay != current {
+            debug!("Delay of {} is now {} ms", domain, delay);
+            self.state.set_domain_delay(job_id, &domain, delay).await?;
+        }
+        
+        if success {
+            self.state.count_page(job_id, &domain).await?;
+        }
+        
+        self.state.record_domain_request(&domain, latency.as_millis() as u64, success, delay).await
+    }
+    
+    /// Record that the domain of a URL blocked a request, like with a captcha
+    pub async fn record_block(&self, url: &str, reason: &str) -> Result<()> {
+        let Some(domain) = domain_of(url) else {
+            return Ok(());
+        };
+        
+        self.state.record_domain_block(&domain, reason).await
+    }
+    
+    /// Statistics of the domains requested recently across jobs, most requested first
+    pub async fn domain_stats(&self) -> Result<Vec<DomainStats>> {
+        self.state.domain_stats().await
+    }
+    
+    /// Pages crawled per domain of a job, most crawled first
+    pub async fn domain_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>> {
+        self.state.domain_counts(job_id).await
+    }
+    
+    /// Whether a lowercase host is one the subdomain policy allows for the
+    /// allowed domains
+    fn domain_allowed(&self, host: &str) -> bool {
+        match self.config.subdomains {
+            SubdomainPolicy::SameHost => self.allowed_domains.contains(host),
+            SubdomainPolicy::SameRegistrableDomain => {
+                // IP addresses have no registrable domain
+                let registrable = |host: &str| match host.parse::<std::net::IpAddr>() {
+                    Ok(_) => Some(host.to_string()),
+                    Err(_) => psl::domain_str(host).map(str::to_string),
+                };
+                self.allowed_domains.contains(host) || registrable(host).is_some_and(|domain| {
+                    self.allowed_domains.iter().any(|allowed| registrable(allowed).as_ref() == Some(&domain))
+                })
+            },
+            SubdomainPolicy::ExplicitList => self.allowed_domains.iter().any(|allowed| match allowed.strip_prefix("*.") {
+                Some(parent) => host.ends_with(&format!(".{}", parent)),
+                None => host == allowed,
+            }),
+        }
     }
     
     /// Normalize a URL to avoid duplicates due to minor differences
@@ -167,7 +433,8 @@ impl Scheduler {
             }
         }
         
-        // Sort query parameters if present
+        // Sort query parameters if present, dropping the ones that don't
+        // tell pages apart
         if let Some(query) = normalized.query() {
             if !query.is_empty() {
                 let mut params: Vec<(String, String)> = Vec::new();
@@ -175,6 +442,9 @@ impl Scheduler {
                     let mut kv = pair.split('=');
                     let k = kv.next().unwrap_or("").to_string();
                     let v = kv.next().unwrap_or("").to_string();
+                    if self.strip_query_params.iter().any(|pattern| pattern.is_match(&k)) {
+                        continue;
+                    }
                     params.push((k, v));
                 }
                 
@@ -187,7 +457,7 @@ impl Scheduler {
                     .collect::<Vec<String>>()
                     .join("&");
                 
-                normalized.set_query(Some(&sorted_query));
+                normalized.set_query((!sorted_query.is_empty()).then_some(sorted_query.as_str()));
             }
         }
         
@@ -197,21 +467,154 @@ impl Scheduler {
         normalized.to_string()
     }
     
-    /// Get the current count of seen URLs
-    pub fn seen_count(&self) -> usize {
-        self.seen_urls.len()
+    /// Get the current count of seen URLs of a job
+    pub async fn seen_count(&self, job_id: &str) -> Result<usize> {
+        self.state.seen_count(job_id).await
     }
     
-    /// Clear the seen URLs cache
-    pub fn clear_seen(&mut self) {
-        self.seen_urls.clear();
+    /// Forget the seen URLs, counters and delays of a job
+    pub async fn clear(&self, job_id: &str) -> Result<()> {
+        self.state.clear(job_id).await
+    }
+}
+
+/// Lowercase host of a URL, requests are spaced per host
+fn domain_of(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(str::to_lowercase)
+}
+
+/// Delay of a domain after a request, doubled after a failure and eased a
+/// quarter of the way back to the configured delay after a success
+pub fn adapt_delay(current: u64, base: u64, success: bool) -> u64 {
+    if success {
+        current - current.saturating_sub(base).div_ceil(4)
+    } else {
+        current.max(base).max(250).saturating_mul(2).min(MAX_DOMAIN_DELAY.max(base))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::config::{CrawlerSettings, UrlPatterns};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use crate::cli::config::{CrawlerSettings, DomainRateLimit, RateLimitSettings, UrlPatterns};
+    use crate::crawler::simhash;
+    
+    /// Scheduler state of a single job held in memory
+    #[derive(Default)]
+    struct MemoryState {
+        seen: Mutex<HashSet<String>>,
+        domain_pages: Mutex<HashMap<String, u64>>,
+        domain_delays: Mutex<HashMap<String, u64>>,
+        buckets: Mutex<HashMap<String, f64>>,
+        domain_stats: Mutex<HashMap<String, HashMap<String, String>>>,
+        skipped: Mutex<HashMap<String, String>>,
+        fingerprints: Mutex<Vec<(u64, String)>>,
+    }
+    
+    #[async_trait]
+    impl SchedulerState for MemoryState {
+        async fn mark_seen(&self, _job_id: &str, url: &str) -> Result<bool> {
+            Ok(self.seen.lock().unwrap().insert(url.to_string()))
+        }
+        
+        async fn seen_count(&self, _job_id: &str) -> Result<usize> {
+            Ok(self.seen.lock().unwrap().len())
+        }
+        
+        async fn count_page(&self, _job_id: &str, domain: &str) -> Result<()> {
+            *self.domain_pages.lock().unwrap().entry(domain.to_string()).or_default() += 1;
+            Ok(())
+        }
+        
+        async fn domain_counts(&self, _job_id: &str) -> Result<Vec<(String, u64)>> {
+            Ok(self.domain_pages.lock().unwrap().iter().map(|(domain, pages)| (domain.clone(), *pages)).collect())
+        }
+        
+        async fn domain_delay(&self, _job_id: &str, domain: &str) -> Result<Option<u64>> {
+            Ok(self.domain_delays.lock().unwrap().get(domain).copied())
+        }
+        
+        async fn set_domain_delay(&self, _job_id: &str, domain: &str, delay_ms: u64) -> Result<()> {
+            self.domain_delays.lock().unwrap().insert(domain.to_string(), delay_ms);
+            Ok(())
+        }
+        
+        async fn take_token(&self, _job_id: &str, domain: &str, rate: f64, burst: u32) -> Result<u64> {
+            // No refill, the test takes its tokens at once
+            let mut buckets = self.buckets.lock().unwrap();
+            let tokens = buckets.entry(domain.to_string()).or_insert(burst as f64);
+            *tokens -= 1.0;
+            Ok(if *tokens >= 0.0 { 0 } else { (-*tokens * 1000.0 / rate).ceil() as u64 })
+        }
+        
+        async fn record_domain_request(&self, domain: &str, latency_ms: u64, success: bool, delay_ms: u64) -> Result<()> {
+            let mut stats = self.domain_stats.lock().unwrap();
+            let fields = stats.entry(domain.to_string()).or_default();
+            for (field, increment) in [("requests", 1), ("errors", u64::from(!success)), ("latency_ms", latency_ms)] {
+                let value = fields.get(field).map_or(0, |value| value.parse::<u64>().unwrap());
+                fields.insert(field.to_string(), (value + increment).to_string());
+            }
+            fields.insert("delay_ms".to_string(), delay_ms.to_string());
+            Ok(())
+        }
+        
+        async fn record_domain_block(&self, domain: &str, reason: &str) -> Result<()> {
+            let mut stats = self.domain_stats.lock().unwrap();
+            let fields = stats.entry(domain.to_string()).or_default();
+            fields.insert("last_block_at".to_string(), "1700000000".to_string());
+            fields.insert("last_block_reason".to_string(), reason.to_string());
+            Ok(())
+        }
+        
+        async fn domain_stats(&self) -> Result<Vec<DomainStats>> {
+            Ok(self.domain_stats.lock().unwrap().iter().map(|(domain, fields)| DomainStats::from_fields(domain, fields)).collect())
+        }
+        
+        async fn record_skip(&self, _job_id: &str, url: &str, reason: &str) -> Result<()> {
+            self.skipped.lock().unwrap().entry(url.to_string()).or_insert_with(|| reason.to_string());
+            Ok(())
+        }
+        
+        async fn skip_counts(&self, _job_id: &str) -> Result<Vec<(String, u64)>> {
+            let mut counts: HashMap<String, u64> = HashMap::new();
+            for reason in self.skipped.lock().unwrap().values() {
+                *counts.entry(reason.clone()).or_default() += 1;
+            }
+            let mut counts: Vec<(String, u64)> = counts.into_iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            Ok(counts)
+        }
+        
+        async fn skipped_urls(&self, _job_id: &str) -> Result<Vec<(String, String)>> {
+            let mut skipped: Vec<(String, String)> = self.skipped.lock().unwrap().clone().into_iter().collect();
+            skipped.sort();
+            Ok(skipped)
+        }
+        
+        async fn near_duplicate(&self, _job_id: &str, url: &str, fingerprint: u64, max_distance: u32) -> Result<Option<String>> {
+            let mut fingerprints = self.fingerprints.lock().unwrap();
+            let duplicate = fingerprints.iter()
+                .find(|(other, other_url)| other_url != url && simhash::hamming_distance(fingerprint, *other) <= max_distance)
+                .map(|(_, other_url)| other_url.clone());
+            if duplicate.is_none() {
+                fingerprints.push((fingerprint, url.to_string()));
+            }
+            Ok(duplicate)
+        }
+        
+        async fn clear(&self, _job_id: &str) -> Result<()> {
+            self.seen.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+    
+    fn create_test_scheduler() -> (Scheduler, Arc<MemoryState>) {
+        let state = Arc::new(MemoryState::default());
+        (Scheduler::new(create_test_config(), state.clone()), state)
+    }
     
     fn create_test_config() -> CrawlerSettings {
         CrawlerSettings {
@@ -219,40 +622,194 @@ mod tests {
             max_pages: 100,
             politeness_delay: 1000,
             respect_robots_txt: true,
+            respect_robots_directives: false,
             allowed_domains: vec!["example.com".to_string()],
+            subdomains: SubdomainPolicy::default(),
+            allowed_schemes: vec!["http".to_string(), "https".to_string()],
+            allowed_ports: vec![],
             url_patterns: UrlPatterns {
                 include: vec![r"^https?://example\.com/.*$".to_string()],
                 exclude: vec![r"^.*\.(jpg|jpeg|png|gif|css|js)$".to_string()],
             },
             user_agent: "TestBot/1.0".to_string(),
+            priority: Default::default(),
+            concurrency: 1,
+            task_timeout: 0,
+            shutdown_grace: 0,
+            record_skips: true,
+            rate_limit: RateLimitSettings::default(),
+            retry: Default::default(),
+            schedule: Default::default(),
+            stall: Default::default(),
+            sitemaps: vec![],
+            feeds: vec![],
+            feed_poll_interval: 0,
+            strip_query_params: vec!["utm_.*".to_string(), "phpsessid".to_string()],
         }
     }
     
-    #[test]
-    fn test_should_crawl() {
-        let config = create_test_config();
-        let mut scheduler = Scheduler::new(config);
+    #[tokio::test]
+    async fn test_should_crawl() {
+        let (scheduler, _) = create_test_scheduler();
+        let should_crawl = |url: &'static str| scheduler.should_crawl("job", url);
         
         // Should crawl valid URL in allowed domain
-        assert!(scheduler.should_crawl("https://example.com/page1"));
+        assert!(should_crawl("https://example.com/page1").await.unwrap());
         
         // Should not crawl the same URL twice
-        assert!(!scheduler.should_crawl("https://example.com/page1"));
+        assert!(!should_crawl("https://example.com/page1").await.unwrap());
         
         // Should not crawl URLs in non-allowed domains
-        assert!(!scheduler.should_crawl("https://other-site.com/page"));
+        assert!(!should_crawl("https://other-site.com/page").await.unwrap());
         
         // Should not crawl excluded file types
-        assert!(!scheduler.should_crawl("https://example.com/image.jpg"));
+        assert!(!should_crawl("https://example.com/image.jpg").await.unwrap());
         
         // Should crawl other valid URLs
-        assert!(scheduler.should_crawl("https://example.com/page2"));
+        assert!(should_crawl("https://example.com/page2").await.unwrap());
+        
+        // Each skipped URL is recorded once, with the first reason it was skipped for
+        scheduler.skip_too_deep("job", "https://example.com/deep").await.unwrap();
+        scheduler.skip_too_deep("job", "https://other-site.com/page").await.unwrap();
+        assert_eq!(scheduler.skip_counts("job").await.unwrap(), vec![
+            ("depth".to_string(), 1),
+            ("domain".to_string(), 1),
+            ("excluded".to_string(), 1),
+            ("seen".to_string(), 1),
+        ]);
+        assert_eq!(scheduler.skipped_urls("job").await.unwrap()[0], ("https://example.com/deep".to_string(), "depth".to_string()));
+        
+        // Another scheduler over the same state agrees, like another worker would
+        let (_, state) = create_test_scheduler();
+        state.mark_seen("job", "https://example.com/page3").await.unwrap();
+        let other = Scheduler::new(create_test_config(), state);
+        assert!(!other.should_crawl("job", "https://example.com/page3").await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_scheme_and_port() {
+        let mut config = create_test_config();
+        config.allowed_schemes = vec!["https".to_string()];
+        config.allowed_ports = vec![443, 8443];
+        config.url_patterns.include = vec![];
+        let scheduler = Scheduler::new(config, Arc::new(MemoryState::default()));
+        
+        assert!(scheduler.should_crawl("job", "https://example.com/a").await.unwrap());
+        assert!(scheduler.should_crawl("job", "https://example.com:8443/b").await.unwrap());
+        assert_eq!(scheduler.skip_reason("http://example.com/c"), Some(SkipReason::Scheme));
+        assert_eq!(scheduler.skip_reason("mailto:shop@example.com"), Some(SkipReason::Scheme));
+        assert_eq!(scheduler.skip_reason("javascript:void(0)"), Some(SkipReason::Scheme));
+        assert_eq!(scheduler.skip_reason("https://example.com:8080/d"), Some(SkipReason::Port));
+    }
+    
+    #[test]
+    fn test_subdomain_policy() {
+        let scheduler = |subdomains: SubdomainPolicy, allowed: &[&str]| {
+            let mut config = create_test_config();
+            config.subdomains = subdomains;
+            config.allowed_domains = allowed.iter().map(|domain| domain.to_string()).collect();
+            Scheduler::new(config, Arc::new(MemoryState::default()))
+        };
+        
+        let same_host = scheduler(SubdomainPolicy::SameHost, &["www.example.co.uk"]);
+        assert!(same_host.domain_allowed("www.example.co.uk"));
+        assert!(!same_host.domain_allowed("shop.example.co.uk"));
+        
+        let registrable = scheduler(SubdomainPolicy::SameRegistrableDomain, &["www.example.co.uk", "10.0.0.1"]);
+        assert!(registrable.domain_allowed("shop.example.co.uk"));
+        assert!(registrable.domain_allowed("example.co.uk"));
+        assert!(!registrable.domain_allowed("other.co.uk"));
+        assert!(!registrable.domain_allowed("notexample.co.uk"));
+        assert!(registrable.domain_allowed("10.0.0.1"));
+        assert!(!registrable.domain_allowed("20.0.0.1"));
+        
+        let explicit = scheduler(SubdomainPolicy::ExplicitList, &["*.example.com", "example.org"]);
+        assert!(explicit.domain_allowed("shop.example.com"));
+        assert!(!explicit.domain_allowed("example.com"));
+        assert!(explicit.domain_allowed("example.org"));
+        assert!(!explicit.domain_allowed("www.example.org"));
+    }
+    
+    #[tokio::test]
+    async fn test_check_redirect() {
+        let (scheduler, _) = create_test_scheduler();
+        let hop = |url: &str, status: u16| RedirectHop { url: url.to_string(), status };
+        scheduler.mark_seen("job", "https://example.com/old").await.unwrap();
+        
+        // The target is kept and won't be crawled again under its own URL
+        let redirects = vec![hop("https://example.com/old", 301)];
+        assert_eq!(scheduler.check_redirect("job", &redirects, "https://example.com/new").await.unwrap(), None);
+        assert!(!scheduler.should_crawl("job", "https://example.com/new").await.unwrap());
+        
+        // Another page redirecting to it is a duplicate
+        let redirects = vec![hop("https://example.com/older", 302)];
+        assert_eq!(scheduler.check_redirect("job", &redirects, "https://example.com/new").await.unwrap(), Some(SkipReason::Seen));
+        
+        let redirects = vec![hop("https://example.com/a", 302), hop("https://other-site.com/login", 302)];
+        assert_eq!(scheduler.check_redirect("job", &redirects, "https://example.com/a").await.unwrap(), None);
+        assert_eq!(
+            scheduler.check_redirect("job", &redirects[..1], "https://other-site.com/login").await.unwrap(),
+            Some(SkipReason::Domain),
+        );
+        
+        let redirects = vec![hop("https://example.com/a", 302), hop("https://example.com/b", 302), hop("https://example.com/a", 302)];
+        assert_eq!(scheduler.check_redirect("job", &redirects, "https://example.com/c").await.unwrap(), Some(SkipReason::RedirectLoop));
+        assert_eq!(scheduler.check_redirect("job", &[], "https://example.com/d").await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_adaptive_delay() {
+        assert_eq!(adapt_delay(1000, 1000, true), 1000);
+        assert_eq!(adapt_delay(1000, 1000, false), 2000);
+        assert_eq!(adapt_delay(0, 0, false), 500);
+        assert_eq!(adapt_delay(50_000, 1000, false), MAX_DOMAIN_DELAY);
+        assert_eq!(adapt_delay(9000, 1000, true), 7000);
+        
+        let (scheduler, state) = create_test_scheduler();
+        scheduler.record_request("job", "https://example.com/a", Duration::from_millis(300), false).await.unwrap();
+        scheduler.record_request("job", "https://example.com/b", Duration::from_millis(100), true).await.unwrap();
+        assert_eq!(state.domain_delay("job", "example.com").await.unwrap(), Some(1750));
+        assert_eq!(scheduler.domain_counts("job").await.unwrap(), vec![("example.com".to_string(), 1)]);
+        
+        // Statistics follow the domain, with the delay it was last adapted to
+        scheduler.record_block("https://example.com/c", "captcha").await.unwrap();
+        let stats = scheduler.domain_stats().await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].requests, stats[0].error_rate, stats[0].avg_latency_ms), (2, 0.5, 200));
+        assert_eq!(stats[0].delay_ms, 1750);
+        assert_eq!(stats[0].last_block_reason.as_deref(), Some("captcha"));
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let limit = |requests_per_second: f64, burst: u32| DomainRateLimit { requests_per_second, burst };
+        let mut config = create_test_config();
+        config.rate_limit = RateLimitSettings {
+            enabled: true,
+            requests_per_second: 2.0,
+            burst: 2,
+            domains: HashMap::from([
+                ("example.com".to_string(), limit(1.0, 1)),
+                ("api.example.com".to_string(), limit(10.0, 5)),
+            ]),
+        };
+        
+        // The most specific override wins, other domains get the defaults
+        assert_eq!(config.rate_limit.for_domain("example.com"), limit(1.0, 1));
+        assert_eq!(config.rate_limit.for_domain("v2.api.example.com"), limit(10.0, 5));
+        assert_eq!(config.rate_limit.for_domain("notexample.com"), limit(2.0, 2));
+        
+        let state = Arc::new(MemoryState::default());
+        let scheduler = Scheduler::new(config, state.clone());
+        scheduler.wait_rate_limit("job", "https://api.example.com/a").await.unwrap();
+        assert_eq!(state.take_token("job", "api.example.com", 10.0, 5).await.unwrap(), 0);
+        assert_eq!(state.take_token("job", "other.com", 2.0, 1).await.unwrap(), 0);
+        assert_eq!(state.take_token("job", "other.com", 2.0, 1).await.unwrap(), 500);
     }
     
     #[test]
     fn test_normalize_url() {
-        let config = create_test_config();
-        let scheduler = Scheduler::new(config);
+        let (scheduler, _) = create_test_scheduler();
         
         // Test case insensitivity in host
         assert_eq!(
@@ -278,6 +835,16 @@ mod tests {
             "https://example.com/search?a=1&b=2"
         );
         
+        // Test removal of tracking and session parameters
+        assert_eq!(
+            scheduler.normalize_url("https://example.com/search?q=kettle&utm_source=news&PHPSESSID=abc"),
+            "https://example.com/search?q=kettle"
+        );
+        assert_eq!(
+            scheduler.normalize_url("https://example.com/page?utm_campaign=summer"),
+            "https://example.com/page"
+        );
+        
         // Test fragment removal
         assert_eq!(
             scheduler.normalize_url("https://example.com/page#section"),
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::cli::config::ScriptSettings;
+use crate::crawler::extractors::RESERVED_NAMES;
+use crate::crawler::task::TaskResult;
+
+/// Rhai scripts of a profile, for extraction and link priorities beyond what
+/// selectors and patterns express, without a plugin toolchain
+///
+/// The extraction script sees `url`, `title`, `html` and `data`, the data
+/// extracted so far, and returns a map of fields added to it, or `()` for
+/// none. The scoring script sees the `url` and `depth` of a link and the
+/// `parent_url` and `parent_title` of the page it was found on, and returns
+/// the link's crawl priority, higher crawling sooner. Runs taking more than
+/// `max_operations` are aborted, a failing script is logged and leaves the
+/// page or link as it was.
+#[derive(Default)]
+pub struct Scripts {
+    compiled: Option<CompiledScripts>,
+}
+
+impl Scripts {
+    /// Compile the scripts of a profile
+    pub fn compile(settings: &ScriptSettings) -> Result<Self> {
+        if settings.extract.is_none() && settings.score.is_none() {
+            return Ok(Self::default());
+        }
+        
+        Ok(Self { compiled: Some(CompiledScripts::compile(settings)?) })
+    }
+    
+    /// Run the extraction script on a page, adding the fields it returns to
+    /// its extracted data
+    pub fn extract(&self, page: &mut TaskResult) {
+        let Some(compiled) = &self.compiled else {
+            return;
+        };
+        
+        let fields = match compiled.extract(page) {
+            Ok(Some(fields)) => fields,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Extraction script failed on {}: {}", page.url, e);
+                return;
+            }
+        };
+        for (name, value) in fields {
+            if RESERVED_NAMES.contains(&name.as_str()) {
+                warn!("Extraction script can't set {}, it is the crawler's own", name);
+                continue;
+            }
+            page.extracted_data[name.as_str()] = value;
+        }
+    }
+    
+    /// Crawl priority of a link found on a page, 0 without a scoring script
+    pub fn score(&self, url: &str, depth: u32, parent: &TaskResult) -> i32 {
+        let Some(compiled) = &self.compiled else {
+            return 0;
+        };
+        
+        compiled.score(url, depth, parent).unwrap_or_else(|e| {
+            warn!("Scoring script failed on {}: {}", url, e);
+            0
+        })
+    }
+}
+
+#[cfg(feature = "scripting")]
+struct CompiledScripts {
+    engine: rhai::Engine,
+    extract: Option<rhai::AST>,
+    score: Option<rhai::AST>,
+}
+
+#[cfg(feature = "scripting")]
+impl CompiledScripts {
+    fn compile(settings: &ScriptSettings) -> Result<Self> {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(settings.max_operations);
+        
+        let compile = |name: &str, source: &Option<String>| -> Result<Option<rhai::AST>> {
+            source.as_deref()
+                .map(|source| engine.compile(source).map_err(|e| anyhow::anyhow!("Invalid {} script: {}", name, e)))
+                .transpose()
+        };
+        let extract = compile("extraction", &settings.extract)?;
+        let score = compile("scoring", &settings.score)?;
+        
+        Ok(Self { engine, extract, score })
+    }
+    
+    fn extract(&self, page: &TaskResult) -> Result<Option<serde_json::Map<String, Value>>> {
+        let Some(script) = &self.extract else {
+            return Ok(None);
+        };
+        
+        let mut scope = rhai::Scope::new();
+        scope.push("url", page.url.clone());
+        scope.push("title", page.title.clone());
+        scope.push("html", page.raw_content.clone());
+        scope.push("data", rhai::serde::to_dynamic(&page.extracted_data).map_err(|e| anyhow::anyhow!("{}", e))?);
+        
+        let output: rhai::Dynamic = self.engine.eval_ast_with_scope(&mut scope, script)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if output.is_unit() {
+            return Ok(None);
+        }
+        match rhai::serde::from_dynamic::<Value>(&output).map_err(|e| anyhow::anyhow!("{}", e))? {
+            Value::Object(fields) => Ok(Some(fields)),
+            other => anyhow::bail!("returned {} instead of a map", other),
+        }
+    }
+    
+    fn score(&self, url: &str, depth: u32, parent: &TaskResult) -> Result<i32> {
+        let Some(script) = &self.score else {
+            return Ok(0);
+        };
+        
+        let mut scope = rhai::Scope::new();
+        scope.push("url", url.to_string());
+        scope.push("depth", depth as i64);
+        scope.push("parent_url", parent.url.clone());
+        scope.push("parent_title", parent.title.clone());
+        
+        let output: rhai::Dynamic = self.engine.eval_ast_with_scope(&mut scope, script)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let score = if let Ok(score) = output.as_int() {
+            score as f64
+        } else if let Ok(score) = output.as_float() {
+            score
+        } else {
+            anyhow::bail!("returned a {} instead of a number", output.type_name());
+        };
+        
+        Ok(score.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32)
+    }
+}
+
+/// Without the scripting feature no script compiles, profiles declaring any fail
+#[cfg(not(feature = "scripting"))]
+enum CompiledScripts {}
+
+#[cfg(not(feature = "scripting"))]
+impl CompiledScripts {
+    fn compile(_settings: &ScriptSettings) -> Result<Self> {
+        anyhow::bail!("Scripts need the crawler to be built with the scripting feature")
+    }
+    
+    fn extract(&self, _page: &TaskResult) -> Result<Option<serde_json::Map<String, Value>>> {
+        match *self {}
+    }
+    
+    fn score(&self, _url: &str, _depth: u32, _parent: &TaskResult) -> Result<i32> {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "scripting"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_scripts() {
+        let settings = ScriptSettings {
+            extract: Some(r#"
+                let start = html.index_of("SKU-");
+                if start < 0 { return; }
+                #{ sku: html.sub_string(start + 4, 4), name: data.name.to_upper(), meta: "overwritten" }
+            "#.to_string()),
+            score: Some(r#"if url.contains("/kettles/") { 10 - depth } else { -1.4 }"#.to_string()),
+            max_operations: 10_000,
+        };
+        let scripts = Scripts::compile(&settings).unwrap();
+        
+        let mut page = TaskResult {
+            title: "Kettles".to_string(),
+            raw_content: "<p>Blue kettle, SKU-4242</p>".to_string(),
+            extracted_data: json!({"name": "Blue kettle"}),
+            ..TaskResult::test_page("https://shop.example.com/kettles")
+        };
+        scripts.extract(&mut page);
+        assert_eq!(page.extracted_data, json!({"name": "BLUE KETTLE", "sku": "4242"}));
+        
+        assert_eq!(scripts.score("https://shop.example.com/kettles/blue", 1, &page), 9);
+        assert_eq!(scripts.score("https://shop.example.com/about", 1, &page), -1);
+        
+        let looping = ScriptSettings { extract: Some("loop {}".to_string()), ..settings.clone() };
+        let scripts = Scripts::compile(&looping).unwrap();
+        let before = page.extracted_data.clone();
+        scripts.extract(&mut page);
+        assert_eq!(page.extracted_data, before);
+        
+        assert!(Scripts::compile(&ScriptSettings { score: Some("if {".to_string()), ..settings }).is_err());
+    }
+}
\ No newline at end of file
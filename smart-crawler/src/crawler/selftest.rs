@@ -0,0 +1,201 @@
+use anyhow::{Result, Context};
+use axum::extract::OriginalUri;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use axum::Router;
+use std::collections::BTreeMap;
+use tokio::task::JoinHandle;
+use tracing::error;
+use url::Url;
+
+use crate::cli::config::{CrawlerConfig, UrlPatterns};
+use crate::crawler::task::TaskResult;
+
+/// Page of the bundled test site, with the fields the crawl must extract from it
+struct Page {
+    path: &'static str,
+    title: &'static str,
+    price: Option<&'static str>,
+    links: &'static [&'static str],
+}
+
+/// The test site, every page reachable from the index within two links
+const PAGES: &[Page] = &[
+    Page { path: "/", title: "Self-test shop", price: None, links: &["/products/1", "/products/2"] },
+    Page { path: "/products/1", title: "Blue mug", price: Some("12.50"), links: &["/", "/about"] },
+    Page { path: "/products/2", title: "Red teapot", price: Some("34.00"), links: &["/", "/products/1"] },
+    Page { path: "/about", title: "About the shop", price: None, links: &["/"] },
+];
+
+/// Selectors of the fields extracted from the test site
+const SELECTORS: &[(&str, &str)] = &[("title", "h1"), ("price", ".price")];
+
+fn page_html(page: &Page) -> String {
+    let price = page.price
+        .map(|price| format!("<p>Price: <span class=\"price\">{}</span> EUR</p>", price))
+        .unwrap_or_default();
+    let links: String = page.links.iter()
+        .map(|link| format!("<li><a href=\"{}\">{}</a></li>", link, link))
+        .collect();
+    
+    format!(
+        "<!DOCTYPE html><html><head><title>{title}</title></head>\
+         <body><h1>{title}</h1>{price}<ul>{links}</ul></body></html>",
+        title = page.title,
+        price = price,
+        links = links,
+    )
+}
+
+async fn serve_page(OriginalUri(uri): OriginalUri) -> Response {
+    match PAGES.iter().find(|page| page.path == uri.path()) {
+        Some(page) => Html(page_html(page)).into_response(),
+        None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+}
+
+/// The bundled test site, served until dropped
+pub struct TestSite {
+    url: Url,
+    server: JoinHandle<()>,
+}
+
+impl TestSite {
+    /// Serve the site on `bind`, an address like 127.0.0.1:0. The browser
+    /// service reaches it under `host` when given, the bound address otherwise.
+    pub async fn serve(bind: &str, host: Option<&str>) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind(bind).await
+            .context(format!("Failed to serve the test site on {}", bind))?;
+        let address = listener.local_addr()?;
+        
+        let url = match host {
+            Some(host) => format!("http://{}:{}/", host, address.port()),
+            None => format!("http://{}/", address),
+        };
+        let url = Url::parse(&url).context(format!("Invalid test site URL: {}", url))?;
+        
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, Router::new().fallback(serve_page)).await {
+                error!("Test site failed: {}", e);
+            }
+        });
+        
+        Ok(Self { url, server })
+    }
+    
+    /// URL of the site's index, the seed of the crawl
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+    
+    /// Point a profile at the site, keeping its storage, queue and browser
+    /// service settings so the crawl goes through the configured stack
+    pub fn configure(&self, config: &mut CrawlerConfig) {
+        let root = self.url.as_str().trim_end_matches('/');
+        
+        config.crawler.allowed_domains = self.url.host_str().map(str::to_lowercase).into_iter().collect();
+        config.crawler.url_patterns = UrlPatterns {
+            include: vec![format!("^{}(/|$)", regex::escape(root))],
+            exclude: vec![],
+        };
+        config.crawler.max_depth = 2;
+        config.crawler.max_pages = 100;
+        config.crawler.politeness_delay = 0;
+        config.crawler.rate_limit.enabled = false;
+        config.crawler.schedule = Default::default();
+        
+        // A failure should show up in the report, not be retried away
+        config.crawler.retry.max_attempts = 1;
+        config.error_policy.enabled = false;
+        config.quarantine.enabled = false;
+        
+        config.extraction.selectors = SELECTORS.iter()
+            .map(|(name, selector)| (name.to_string(), selector.to_string()))
+            .collect();
+    }
+    
+    /// Problems with the stored results of a crawl of the site, empty when
+    /// every page was crawled and its fields extracted
+    pub fn check_results(&self, results: &[TaskResult]) -> Vec<String> {
+        let by_path: BTreeMap<String, &TaskResult> = results.iter()
+            .filter_map(|result| Url::parse(&result.url).ok().map(|url| (url.path().to_string(), result)))
+            .collect();
+        
+        let mut problems = Vec::new();
+        for page in PAGES {
+            let Some(result) = by_path.get(page.path) else {
+                problems.push(format!("{} was not crawled", page.path));
+                continue;
+            };
+            
+            let expected = [("title", Some(page.title)), ("price", page.price)];
+            for (field, value) in expected {
+                let extracted = result.extracted_data.get(field).and_then(|value| value.as_str());
+                if extracted != value {
+                    problems.push(format!(
+                        "{} extracted {} {:?}, expected {:?}",
+                        page.path, field, extracted, value
+                    ));
+                }
+            }
+        }
+        
+        for path in by_path.keys() {
+            if !PAGES.iter().any(|page| page.path == path) {
+                problems.push(format!("{} is not a page of the test site", path));
+            }
+        }
+        
+        problems
+    }
+}
+
+impl Drop for TestSite {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::extract;
+    
+    fn crawl(site: &TestSite, pages: &[&Page]) -> Vec<TaskResult> {
+        let selectors: BTreeMap<String, String> = SELECTORS.iter()
+            .map(|(name, selector)| (name.to_string(), selector.to_string()))
+            .collect();
+        
+        pages.iter()
+            .map(|page| {
+                let html = page_html(page);
+                TaskResult {
+                    job_id: "selftest".to_string(),
+                    title: page.title.to_string(),
+                    extracted_data: serde_json::Value::Object(extract::extract_fields(&html, &selectors).unwrap()),
+                    raw_content: html,
+                    ..TaskResult::test_page(site.url().join(page.path).unwrap().as_str())
+                }
+            })
+            .collect()
+    }
+    
+    #[tokio::test]
+    async fn test_check_results() {
+        let site = TestSite::serve("127.0.0.1:0", None).await.unwrap();
+        
+        let all: Vec<&Page> = PAGES.iter().collect();
+        assert!(site.check_results(&crawl(&site, &all)).is_empty());
+        
+        let problems = site.check_results(&crawl(&site, &all[..2]));
+        assert_eq!(problems, vec![
+            "/products/2 was not crawled".to_string(),
+            "/about was not crawled".to_string(),
+        ]);
+        
+        let mut results = crawl(&site, &all);
+        results[1].extracted_data = serde_json::json!({"title": "Blue mug"});
+        let problems = site.check_results(&results);
+        assert_eq!(problems, vec![r#"/products/1 extracted price None, expected Some("12.50")"#.to_string()]);
+    }
+}
\ No newline at end of file
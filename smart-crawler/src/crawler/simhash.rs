@@ -0,0 +1,197 @@
+use anyhow::Result;
+use scraper::{ElementRef, Html, Node};
+
+use crate::cli::config::NearDuplicateSettings;
+
+/// Words per shingle, the features a fingerprint is built from
+const SHINGLE_WORDS: usize = 3;
+
+/// Elements whose text isn't part of the page content
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template"];
+
+/// Largest Hamming distance allowed, past it bands get so narrow that each
+/// page shares one with most others and is compared with all of them
+const MAX_DISTANCE: u32 = 16;
+
+/// What happens to a page whose text is nearly the same as an earlier one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NearDuplicateAction {
+    /// Store it with the URL of the earlier page under `near_duplicate_of`
+    Flag,
+    /// Don't store it, its links are still followed
+    Drop,
+}
+
+impl NearDuplicateAction {
+    pub fn parse(action: &str) -> Result<Self> {
+        match action.to_lowercase().as_str() {
+            "flag" => Ok(Self::Flag),
+            "drop" => Ok(Self::Drop),
+            _ => anyhow::bail!("Unsupported near-duplicate action: {}", action),
+        }
+    }
+}
+
+/// Near-duplicate detection of a profile
+#[derive(Debug, Clone, Copy)]
+pub struct NearDuplicatePolicy {
+    pub max_distance: u32,
+    pub action: NearDuplicateAction,
+}
+
+impl NearDuplicatePolicy {
+    /// Create the policy from settings, None when detection is disabled
+    pub fn from_settings(settings: &NearDuplicateSettings) -> Result<Option<Self>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+        
+        if settings.max_distance > MAX_DISTANCE {
+            anyhow::bail!("Near-duplicate max_distance must be at most {}: {}", MAX_DISTANCE, settings.max_distance);
+        }
+        
+        Ok(Some(Self {
+            max_distance: settings.max_distance,
+            action: NearDuplicateAction::parse(&settings.action)?,
+        }))
+    }
+}
+
+/// SimHash fingerprint of the visible text of a page, None when it has no text
+///
+/// Pages with similar text get fingerprints a small Hamming distance apart,
+/// so pages differing only in a date, a counter or a few words of
+/// boilerplate are recognized as near-duplicates.
+pub fn fingerprint(html: &str) -> Option<u64> {
+    let document = Html::parse_document(html);
+    let mut words = Vec::new();
+    collect_words(document.root_element(), &mut words);
+    
+    simhash(&words)
+}
+
+/// SimHash of the shingles of a list of words
+fn simhash(words: &[String]) -> Option<u64> {
+    if words.is_empty() {
+        return None;
+    }
+    
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let hash = fnv1a(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    
+    Some(weights.iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit))
+}
+
+/// Lowercase words of the text under an element, skipping scripts and styles
+fn collect_words(element: ElementRef, words: &mut Vec<String>) {
+    if SKIPPED_ELEMENTS.contains(&element.value().name()) {
+        return;
+    }
+    
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => words.extend(
+                text.split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_lowercase)
+            ),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_words(child, words);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash of a shingle, stable across builds so workers on
+/// different hosts fingerprint pages the same way
+fn fnv1a(shingle: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (index, word) in shingle.iter().enumerate() {
+        if index > 0 {
+            hash = (hash ^ b' ' as u64).wrapping_mul(0x100000001b3);
+        }
+        for byte in word.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Number of bits two fingerprints differ in
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Bands of a fingerprint, as (index, bits) pairs
+///
+/// The fingerprint is split into `max_distance + 1` bands, so two
+/// fingerprints at most `max_distance` bits apart have at least one band in
+/// common and only pages sharing a band need to be compared.
+pub fn bands(fingerprint: u64, max_distance: u32) -> Vec<(usize, u64)> {
+    let count = max_distance.min(MAX_DISTANCE) as usize + 1;
+    (0..count)
+        .map(|index| {
+            let start = index * 64 / count;
+            let end = (index + 1) * 64 / count;
+            let mask = if end - start == 64 { u64::MAX } else { (1 << (end - start)) - 1 };
+            (index, fingerprint >> start & mask)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn page(body: &str) -> String {
+        format!("<html><head><style>body {{ color: red }}</style></head><body>{}</body></html>", body)
+    }
+    
+    #[test]
+    fn test_fingerprint() {
+        let article = "The quick brown fox jumps over the lazy dog while the farmer watches \
+            from the porch, sipping coffee and reading yesterday's newspaper about the harvest \
+            festival that the whole village has been preparing for since early spring";
+        
+        let original = fingerprint(&page(&format!("<p>{}</p><footer>Updated 2024-01-01</footer>", article))).unwrap();
+        let updated = fingerprint(&page(&format!("<p>{}</p><footer>Updated 2024-02-15</footer>", article))).unwrap();
+        let scripted = fingerprint(&page(&format!("<p>{}</p><script>var x = 1;</script><footer>Updated 2024-01-01</footer>", article))).unwrap();
+        let other = fingerprint(&page("<p>Completely different text about stock markets, interest \
+            rates and the central bank meeting scheduled for next Thursday afternoon</p>")).unwrap();
+        
+        assert_eq!(original, scripted);
+        assert!(hamming_distance(original, updated) <= 8);
+        assert!(hamming_distance(original, other) > 8);
+        assert_eq!(fingerprint(&page("<script>only()</script>")), None);
+    }
+    
+    #[test]
+    fn test_bands() {
+        let fingerprint = 0x0123_4567_89ab_cdef;
+        assert_eq!(bands(fingerprint, 0), vec![(0, fingerprint)]);
+        assert_eq!(bands(fingerprint, 3), vec![(0, 0xcdef), (1, 0x89ab), (2, 0x4567), (3, 0x0123)]);
+        
+        // Within the distance, some band is equal
+        let near = fingerprint ^ (1 << 3) ^ (1 << 20) ^ (1 << 40);
+        let shared = bands(fingerprint, 3).into_iter()
+            .zip(bands(near, 3))
+            .filter(|(a, b)| a == b)
+            .count();
+        assert_eq!(shared, 1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,240 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use reqwest::Client;
+use scraper::{Html, Selector};
+use std::collections::{HashSet, VecDeque};
+use std::io::Read;
+use tracing::{debug, info, warn};
+
+/// Most sitemaps read for a job, counting the ones sitemap indexes list
+const MAX_SITEMAPS: usize = 1000;
+
+/// Page listed in a sitemap
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    pub url: String,
+    
+    /// When the page last changed, as the sitemap says
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+impl SitemapEntry {
+    /// Crawl priority of the page by how recently it changed: 3 within a
+    /// day, 2 within a week, 1 within a month and 0 for older or undated pages
+    pub fn priority(&self, now: DateTime<Utc>) -> i32 {
+        let Some(lastmod) = self.lastmod else {
+            return 0;
+        };
+        
+        let age = now - lastmod;
+        if age <= Duration::days(1) {
+            3
+        } else if age <= Duration::days(7) {
+            2
+        } else if age <= Duration::days(30) {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// Content of a sitemap file
+#[derive(Debug, PartialEq)]
+pub enum Sitemap {
+    /// A `urlset` of pages, or a text sitemap with one URL per line
+    Urls(Vec<SitemapEntry>),
+    
+    /// A `sitemapindex` listing other sitemaps
+    Index(Vec<String>),
+}
+
+/// Parse a sitemap or sitemap index
+pub fn parse_sitemap(content: &str) -> Sitemap {
+    let content = content.trim_start_matches('\u{feff}').trim();
+    if !content.starts_with('<') {
+        return Sitemap::Urls(content.lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("http://") || line.starts_with("https://"))
+            .map(|line| SitemapEntry { url: line.to_string(), lastmod: None })
+            .collect());
+    }
+    
+    // Sitemaps are simple enough for the HTML parser, which keeps unknown
+    // elements as they are and decodes the entities of their text
+    let document = Html::parse_document(content);
+    let index_selector = Selector::parse("sitemapindex > sitemap > loc").expect("valid sitemap index selector");
+    let sitemaps: Vec<String> = document.select(&index_selector)
+        .map(|loc| loc.text().collect::<String>().trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    if !sitemaps.is_empty() {
+        return Sitemap::Index(sitemaps);
+    }
+    
+    let url_selector = Selector::parse("urlset > url").expect("valid sitemap URL selector");
+    let loc_selector = Selector::parse("loc").expect("valid loc selector");
+    let lastmod_selector = Selector::parse("lastmod").expect("valid lastmod selector");
+    let entries = document.select(&url_selector)
+        .filter_map(|url| {
+            // Only the page's own loc, not the ones of the images and videos in it
+            let loc = url.children()
+                .filter_map(scraper::ElementRef::wrap)
+                .find(|child| loc_selector.matches(child))?;
+            let lastmod = url.children()
+                .filter_map(scraper::ElementRef::wrap)
+                .find(|child| lastmod_selector.matches(child))
+                .and_then(|lastmod| parse_lastmod(&lastmod.text().collect::<String>()));
+            
+            let url = loc.text().collect::<String>().trim().to_string();
+            (!url.is_empty()).then_some(SitemapEntry { url, lastmod })
+        })
+        .collect();
+    Sitemap::Urls(entries)
+}
+
+/// Parse a W3C datetime, from a full timestamp down to a bare date
+fn parse_lastmod(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    // Timestamps without seconds, like 2024-07-01T10:00+02:00
+    if let Ok(date) = DateTime::parse_from_str(text, "%Y-%m-%dT%H:%M%:z") {
+        return Some(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
+
+/// Pages listed in sitemaps, following sitemap indexes, at most `limit`
+/// of them unless it is 0
+///
+/// A sitemap given here that can't be read fails, one listed by an index is
+/// logged and skipped. Gzipped sitemaps are decompressed.
+pub async fn read_sitemaps(sitemap_urls: &[String], user_agent: &str, limit: usize) -> Result<Vec<SitemapEntry>> {
+    let client = Client::builder()
+        .user_agent(user_agent)
+        .timeout(std::time::Duration::from_secs(60))
+        .build()
+        .context("Failed to create HTTP client")?;
+    
+    let mut pending: VecDeque<(String, bool)> = sitemap_urls.iter().map(|url| (url.clone(), true)).collect();
+    let mut read: HashSet<String> = HashSet::new();
+    let mut entries = Vec::new();
+    
+    while let Some((sitemap_url, given)) = pending.pop_front() {
+        if !read.insert(sitemap_url.clone()) {
+            continue;
+        }
+        if read.len() > MAX_SITEMAPS {
+            warn!("Read {} sitemaps, skipping the rest", MAX_SITEMAPS);
+            break;
+        }
+        
+        let sitemap = match fetch_sitemap(&client, &sitemap_url).await {
+            Ok(sitemap) => sitemap,
+            Err(e) if given => return Err(e),
+            Err(e) => {
+                warn!("Skipping sitemap {}: {:#}", sitemap_url, e);
+                continue;
+            }
+        };
+        
+        match sitemap {
+            Sitemap::Index(sitemaps) => {
+                debug!("Sitemap index {} lists {} sitemaps", sitemap_url, sitemaps.len());
+                pending.extend(sitemaps.into_iter().map(|url| (url, false)));
+            },
+            Sitemap::Urls(urls) => {
+                debug!("Sitemap {} lists {} URLs", sitemap_url, urls.len());
+                entries.extend(urls);
+                if limit > 0 && entries.len() >= limit {
+                    entries.truncate(limit);
+                    break;
+                }
+            },
+        }
+    }
+    
+    info!("Read {} URL(s) from {} sitemap(s)", entries.len(), read.len().min(MAX_SITEMAPS));
+    Ok(entries)
+}
+
+async fn fetch_sitemap(client: &Client, url: &str) -> Result<Sitemap> {
+    let response = client.get(url).send().await
+        .context(format!("Failed to fetch sitemap {}", url))?
+        .error_for_status()
+        .context(format!("Failed to fetch sitemap {}", url))?;
+    let body = response.bytes().await
+        .context(format!("Failed to read sitemap {}", url))?;
+    
+    let content = if body.starts_with(&[0x1f, 0x8b]) {
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut content)
+            .context(format!("Failed to decompress sitemap {}", url))?;
+        content
+    } else {
+        String::from_utf8_lossy(&body).into_owned()
+    };
+    
+    Ok(parse_sitemap(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    #[test]
+    fn test_parse_sitemap() {
+        let index = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap><loc>https://shop.example.com/sitemap-products.xml.gz</loc></sitemap>
+                <sitemap><loc> https://shop.example.com/sitemap-pages.xml </loc><lastmod>2024-07-01</lastmod></sitemap>
+            </sitemapindex>"#;
+        assert_eq!(parse_sitemap(index), Sitemap::Index(vec![
+            "https://shop.example.com/sitemap-products.xml.gz".to_string(),
+            "https://shop.example.com/sitemap-pages.xml".to_string(),
+        ]));
+        
+        let urlset = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+                <url>
+                    <loc>https://shop.example.com/kettles?color=blue&amp;size=2</loc>
+                    <lastmod>2024-07-01T10:00+02:00</lastmod>
+                    <image:image><image:loc>https://shop.example.com/kettle.jpg</image:loc></image:image>
+                </url>
+                <url><loc>https://shop.example.com/about</loc><lastmod>2024-06-01</lastmod></url>
+                <url><loc>https://shop.example.com/terms</loc></url>
+            </urlset>"#;
+        let Sitemap::Urls(entries) = parse_sitemap(urlset) else {
+            panic!("expected a urlset");
+        };
+        assert_eq!(entries, vec![
+            SitemapEntry {
+                url: "https://shop.example.com/kettles?color=blue&size=2".to_string(),
+                lastmod: Some(Utc.with_ymd_and_hms(2024, 7, 1, 8, 0, 0).unwrap()),
+            },
+            SitemapEntry {
+                url: "https://shop.example.com/about".to_string(),
+                lastmod: Some(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()),
+            },
+            SitemapEntry { url: "https://shop.example.com/terms".to_string(), lastmod: None },
+        ]);
+        
+        let now = Utc.with_ymd_and_hms(2024, 7, 2, 0, 0, 0).unwrap();
+        let priorities: Vec<i32> = entries.iter().map(|entry| entry.priority(now)).collect();
+        assert_eq!(priorities, vec![3, 0, 0]);
+        
+        assert_eq!(
+            parse_sitemap("https://shop.example.com/a\n\n# not a URL\nhttps://shop.example.com/b\n"),
+            Sitemap::Urls(vec![
+                SitemapEntry { url: "https://shop.example.com/a".to_string(), lastmod: None },
+                SitemapEntry { url: "https://shop.example.com/b".to_string(), lastmod: None },
+            ]),
+        );
+    }
+}
\ No newline at end of file
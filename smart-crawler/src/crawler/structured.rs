@@ -0,0 +1,303 @@
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use tracing::debug;
+use url::Url;
+
+/// Elements whose microdata value is the URL in one of their attributes
+const URL_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("area", "href"),
+    ("link", "href"),
+    ("audio", "src"),
+    ("embed", "src"),
+    ("iframe", "src"),
+    ("img", "src"),
+    ("source", "src"),
+    ("track", "src"),
+    ("video", "src"),
+    ("object", "data"),
+];
+
+/// Attribute markup describing items
+#[derive(Debug, Clone, Copy)]
+enum Syntax {
+    Microdata,
+    Rdfa,
+}
+
+impl Syntax {
+    /// Selector of the elements starting an item
+    fn item_selector(self) -> &'static str {
+        match self {
+            Self::Microdata => "[itemscope]",
+            Self::Rdfa => "[typeof]",
+        }
+    }
+    
+    /// Attribute holding the property names of an element
+    fn property_attribute(self) -> &'static str {
+        match self {
+            Self::Microdata => "itemprop",
+            Self::Rdfa => "property",
+        }
+    }
+    
+    fn is_item(self, element: ElementRef) -> bool {
+        match self {
+            Self::Microdata => element.value().attr("itemscope").is_some(),
+            Self::Rdfa => element.value().attr("typeof").is_some(),
+        }
+    }
+    
+    /// Types of an item, RDFa types relative to the vocabulary in scope are made absolute
+    fn types(self, element: ElementRef) -> Vec<String> {
+        match self {
+            Self::Microdata => element.value().attr("itemtype")
+                .map(|types| types.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            Self::Rdfa => {
+                let vocab = rdfa_vocab(element);
+                element.value().attr("typeof")
+                    .map(|types| types.split_whitespace().map(|name| match vocab {
+                        Some(vocab) if !name.contains(':') => format!("{}{}", vocab, name),
+                        _ => name.to_string(),
+                    }).collect())
+                    .unwrap_or_default()
+            },
+        }
+    }
+    
+    /// Global identifier of an item
+    fn id(self, element: ElementRef, base: Option<&Url>) -> Option<String> {
+        let id = match self {
+            Self::Microdata => element.value().attr("itemid"),
+            Self::Rdfa => element.value().attr("resource").or_else(|| element.value().attr("about")),
+        };
+        id.map(|id| resolve(base, id))
+    }
+    
+    /// Value of a property that isn't an item itself
+    fn value(self, element: ElementRef, base: Option<&Url>) -> String {
+        let node = element.value();
+        match self {
+            Self::Microdata => {
+                if let Some((_, attribute)) = URL_ATTRIBUTES.iter().find(|(name, _)| *name == node.name()) {
+                    return node.attr(attribute).map(|url| resolve(base, url)).unwrap_or_default();
+                }
+                let attribute = match node.name() {
+                    "meta" => Some("content"),
+                    "data" | "meter" => Some("value"),
+                    "time" => Some("datetime"),
+                    _ => None,
+                };
+                if let Some(value) = attribute.and_then(|attribute| node.attr(attribute)) {
+                    return value.trim().to_string();
+                }
+            },
+            Self::Rdfa => {
+                if let Some(content) = node.attr("content") {
+                    return content.trim().to_string();
+                }
+                if let Some(url) = ["resource", "href", "src"].iter().find_map(|attribute| node.attr(attribute)) {
+                    return resolve(base, url);
+                }
+                if let Some(datetime) = node.attr("datetime") {
+                    return datetime.trim().to_string();
+                }
+            },
+        }
+        
+        element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// schema.org and other structured data of a page, None when it has none
+///
+/// JSON-LD blocks are kept as they are written, with `@graph` lists expanded
+/// into their items. Microdata and RDFa items read as `{"type": [...],
+/// "id": ..., "properties": {"name": [values]}}`, nested items as values of
+/// the property they belong to. URLs are resolved against the page's URL.
+pub fn extract_structured(html: &str, page_url: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let base = Url::parse(page_url).ok();
+    
+    let mut structured = Map::new();
+    for (key, items) in [
+        ("json_ld", json_ld(&document)),
+        ("microdata", items(&document, Syntax::Microdata, base.as_ref())),
+        ("rdfa", items(&document, Syntax::Rdfa, base.as_ref())),
+    ] {
+        if !items.is_empty() {
+            structured.insert(key.to_string(), Value::Array(items));
+        }
+    }
+    
+    (!structured.is_empty()).then_some(Value::Object(structured))
+}
+
+/// Objects of the JSON-LD blocks of a document, blocks that don't parse are skipped
+fn json_ld(document: &Html) -> Vec<Value> {
+    let selector = Selector::parse(r#"script[type="application/ld+json"]"#).expect("valid JSON-LD selector");
+    
+    let mut objects = Vec::new();
+    for script in document.select(&selector) {
+        let text: String = script.text().collect();
+        let value = match serde_json::from_str::<Value>(&text) {
+            Ok(value) => value,
+            Err(e) => {
+                debug!("Skipping invalid JSON-LD block: {}", e);
+                continue;
+            },
+        };
+        
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            match value.get("@graph") {
+                Some(Value::Array(graph)) => objects.extend(graph.iter().cloned()),
+                _ => objects.push(value),
+            }
+        }
+    }
+    
+    objects
+}
+
+/// Top-level items of a document, those that aren't the property of another item
+fn items(document: &Html, syntax: Syntax, base: Option<&Url>) -> Vec<Value> {
+    let selector = Selector::parse(syntax.item_selector()).expect("valid item selector");
+    document.select(&selector)
+        .filter(|element| element.value().attr(syntax.property_attribute()).is_none())
+        .map(|element| item(element, syntax, base))
+        .collect()
+}
+
+fn item(element: ElementRef, syntax: Syntax, base: Option<&Url>) -> Value {
+    let mut item = Map::new();
+    
+    let types = syntax.types(element);
+    if !types.is_empty() {
+        item.insert("type".to_string(), Value::from(types));
+    }
+    if let Some(id) = syntax.id(element, base) {
+        item.insert("id".to_string(), Value::String(id));
+    }
+    
+    let mut properties = Map::new();
+    collect_properties(element, syntax, base, &mut properties);
+    item.insert("properties".to_string(), Value::Object(properties));
+    
+    Value::Object(item)
+}
+
+/// Add the properties under an element to those of the item it belongs to,
+/// stopping at nested items, which have properties of their own
+fn collect_properties(element: ElementRef, syntax: Syntax, base: Option<&Url>, properties: &mut Map<String, Value>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let names = child.value().attr(syntax.property_attribute());
+        let nested = syntax.is_item(child);
+        
+        if let Some(names) = names {
+            let value = if nested {
+                item(child, syntax, base)
+            } else {
+                Value::String(syntax.value(child, base))
+            };
+            for name in names.split_whitespace() {
+                if let Value::Array(values) = properties.entry(name.to_string()).or_insert_with(|| Value::Array(Vec::new())) {
+                    values.push(value.clone());
+                }
+            }
+        }
+        
+        // Items without a property are top-level items of their own
+        if !nested {
+            collect_properties(child, syntax, base, properties);
+        }
+    }
+}
+
+/// RDFa vocabulary in scope of an element
+fn rdfa_vocab<'a>(element: ElementRef<'a>) -> Option<&'a str> {
+    let mut current = Some(element);
+    while let Some(element) = current {
+        if let Some(vocab) = element.value().attr("vocab") {
+            return Some(vocab);
+        }
+        current = element.parent().and_then(ElementRef::wrap);
+    }
+    None
+}
+
+fn resolve(base: Option<&Url>, url: &str) -> String {
+    base.and_then(|base| base.join(url.trim()).ok())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| url.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_extract_structured() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">{"@context": "https://schema.org", "@type": "Article", "author": {"@type": "Person", "name": "Ana"}}</script>
+            <script type="application/ld+json">{"@graph": [{"@type": "WebSite"}, {"@type": "Organization"}]}</script>
+            <script type="application/ld+json">{not json</script>
+            </head><body>
+            <div itemscope itemtype="https://schema.org/Product" itemid="/p/42">
+                <h1 itemprop="name">Blue   kettle</h1>
+                <img itemprop="image" src="/img/kettle.jpg">
+                <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <meta itemprop="priceCurrency" content="EUR"><data itemprop="price" value="24.90">24,90 €</data>
+                </div>
+                <section itemscope itemtype="https://schema.org/Review"><span itemprop="author">Luis</span></section>
+            </div>
+            <div vocab="https://schema.org/" typeof="Person">
+                <span property="name">Marta</span>
+                <a property="url" href="/marta">Profile</a>
+                <div property="address" typeof="PostalAddress"><span property="addressLocality">Bilbao</span></div>
+            </div>
+            </body></html>"#;
+        
+        let structured = extract_structured(html, "https://shop.example.com/kettles").unwrap();
+        
+        assert_eq!(structured["json_ld"], json!([
+            {"@context": "https://schema.org", "@type": "Article", "author": {"@type": "Person", "name": "Ana"}},
+            {"@type": "WebSite"},
+            {"@type": "Organization"},
+        ]));
+        assert_eq!(structured["microdata"], json!([
+            {
+                "type": ["https://schema.org/Product"],
+                "id": "https://shop.example.com/p/42",
+                "properties": {
+                    "name": ["Blue kettle"],
+                    "image": ["https://shop.example.com/img/kettle.jpg"],
+                    "offers": [{
+                        "type": ["https://schema.org/Offer"],
+                        "properties": {"priceCurrency": ["EUR"], "price": ["24.90"]},
+                    }],
+                },
+            },
+            {"type": ["https://schema.org/Review"], "properties": {"author": ["Luis"]}},
+        ]));
+        assert_eq!(structured["rdfa"], json!([{
+            "type": ["https://schema.org/Person"],
+            "properties": {
+                "name": ["Marta"],
+                "url": ["https://shop.example.com/marta"],
+                "address": [{
+                    "type": ["https://schema.org/PostalAddress"],
+                    "properties": {"addressLocality": ["Bilbao"]},
+                }],
+            },
+        }]));
+        
+        assert_eq!(extract_structured("<p>No structured data</p>", "https://example.com/"), None);
+    }
+}
\ No newline at end of file
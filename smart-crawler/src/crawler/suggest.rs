@@ -0,0 +1,227 @@
+use anyhow::{Result, Context};
+use scraper::Html;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashSet, VecDeque};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::browser::fingerprint::FingerprintManager;
+use crate::browser::remote::{CrawlOptions, RemoteBrowserService};
+use crate::cli::config::CrawlerConfig;
+use crate::crawler::extract::FieldSelector;
+
+/// Page fetched to base selector suggestions on
+#[derive(Debug, Clone)]
+pub struct SampledPage {
+    pub url: String,
+    pub html: String,
+}
+
+/// Selector proposed for a field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorSuggestion {
+    /// Field name in the extracted data
+    pub field: String,
+    
+    /// Field selector, see `FieldSelector`
+    pub selector: String,
+    
+    /// Number of sampled pages the selector found a plausible value on
+    pub matched_pages: usize,
+    
+    /// Value found on the first matching page
+    pub example: String,
+}
+
+/// Selectors to try for a field, most specific first
+struct Candidate {
+    field: &'static str,
+    selectors: &'static [&'static str],
+    plausible: fn(&str) -> bool,
+}
+
+const CANDIDATES: &[Candidate] = &[
+    Candidate {
+        field: "title",
+        selectors: &[
+            "[itemprop=headline]",
+            "h1[itemprop=name]",
+            "article h1",
+            "main h1",
+            "h1",
+            "meta[property='og:title']@content",
+        ],
+        plausible: |value| (3..=300).contains(&value.len()),
+    },
+    Candidate {
+        field: "price",
+        selectors: &[
+            "[itemprop=price]@content",
+            "[itemprop=price]",
+            "meta[property='product:price:amount']@content",
+            "[data-price]@data-price",
+            ".price",
+            "[class*=price]",
+        ],
+        plausible: |value| value.len() <= 40 && value.chars().any(|c| c.is_ascii_digit()),
+    },
+    Candidate {
+        field: "body",
+        selectors: &[
+            "[itemprop=articleBody]",
+            ".entry-content",
+            ".post-content",
+            ".article-body",
+            "article",
+            "main",
+        ],
+        plausible: |value| value.len() >= 200,
+    },
+    Candidate {
+        field: "next_page",
+        selectors: &[
+            "link[rel=next]@href",
+            "a[rel=next]@href",
+            ".pagination a.next@href",
+            "a[aria-label*=Next]@href",
+            ".pagination li:last-child a@href",
+            "nav[aria-label*=agination] a:last-child@href",
+        ],
+        plausible: |value| !value.starts_with('#') && !value.starts_with("javascript:"),
+    },
+];
+
+/// Propose a selector for each field that can be found on the sampled pages
+///
+/// Every candidate selector is tried on every page, the one finding a
+/// plausible value on the most pages wins, earlier candidates break ties.
+pub fn suggest_selectors(pages: &[SampledPage]) -> Vec<SelectorSuggestion> {
+    let documents: Vec<Html> = pages.iter()
+        .map(|page| Html::parse_document(&page.html))
+        .collect();
+    
+    let mut suggestions = Vec::new();
+    for candidate in CANDIDATES {
+        let mut best: Option<SelectorSuggestion> = None;
+        
+        for spec in candidate.selectors {
+            let selector = match FieldSelector::parse(spec) {
+                Ok(selector) => selector,
+                Err(e) => {
+                    debug!("Skipping candidate selector: {}", e);
+                    continue;
+                }
+            };
+            
+            let values: Vec<String> = documents.iter()
+                .filter_map(|document| selector.select(document))
+                .filter(|value| (candidate.plausible)(value))
+                .collect();
+            
+            if values.is_empty() || best.as_ref().map_or(false, |best| best.matched_pages >= values.len()) {
+                continue;
+            }
+            
+            best = Some(SelectorSuggestion {
+                field: candidate.field.to_string(),
+                selector: spec.to_string(),
+                matched_pages: values.len(),
+                example: values[0].chars().take(80).collect(),
+            });
+        }
+        
+        suggestions.extend(best);
+    }
+    
+    suggestions
+}
+
+/// Fetch up to `count` pages of a site, starting at the seed and following
+/// links on the same host
+pub async fn sample_pages(seed_url: &str, count: usize, config: &CrawlerConfig) -> Result<Vec<SampledPage>> {
+    let seed = Url::parse(seed_url)
+        .context(format!("Invalid URL: {}", seed_url))?;
+    
+    let browser_service = RemoteBrowserService::new();
+    let fingerprint_manager = FingerprintManager::new(config.browser.fingerprints.clone());
+    
+    let mut queue = VecDeque::from([seed.to_string()]);
+    let mut seen: HashSet<String> = queue.iter().cloned().collect();
+    let mut pages = Vec::new();
+    
+    while let Some(url) = queue.pop_front() {
+        if pages.len() >= count {
+            break;
+        }
+        
+        let fingerprint = fingerprint_manager.random_fingerprint()?;
+        let response = match browser_service.crawl_url(
+            &url,
+            &config.browser.browser_type,
+            &fingerprint,
+            &config.browser.behavior,
+            &CrawlOptions::default(),
+        ).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Skipping sample page {}: {}", url, e);
+                continue;
+            }
+        };
+        
+        let base = Url::parse(&url)?;
+        for link in &response.links {
+            let mut link = match base.join(link) {
+                Ok(link) => link,
+                Err(_) => continue,
+            };
+            link.set_fragment(None);
+            
+            if link.host_str() == seed.host_str() && seen.insert(link.to_string()) {
+                queue.push_back(link.to_string());
+            }
+        }
+        
+        debug!("Sampled page: {}", url);
+        pages.push(SampledPage { url, html: response.content });
+    }
+    
+    if pages.is_empty() {
+        anyhow::bail!("Could not fetch any page from {}", seed_url);
+    }
+    
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn page(html: &str) -> SampledPage {
+        SampledPage {
+            url: "https://shop.example.com/item".to_string(),
+            html: html.to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_suggest_selectors() {
+        let pages = vec![
+            page(r#"<h1>Blue kettle</h1><span class="price">€24,99</span><a rel="next" href="?page=2">Next</a>"#),
+            page(r#"<h1>Red kettle</h1><span class="price">€19,99</span>"#),
+            page(r#"<h1>Green kettle</h1><div class="price-box"><span>Sold out</span></div>"#),
+        ];
+        
+        let suggestions = suggest_selectors(&pages);
+        let selector = |field: &str| {
+            suggestions.iter()
+                .find(|suggestion| suggestion.field == field)
+                .map(|suggestion| (suggestion.selector.as_str(), suggestion.matched_pages))
+        };
+        
+        assert_eq!(selector("title"), Some(("h1", 3)));
+        assert_eq!(selector("price"), Some((".price", 2)));
+        assert_eq!(selector("next_page"), Some(("a[rel=next]@href", 1)));
+        assert_eq!(selector("body"), None);
+    }
+}
\ No newline at end of file
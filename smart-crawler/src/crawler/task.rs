@@ -1,6 +1,12 @@
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+use crate::browser::remote::RedirectHop;
+use crate::crawler::assets::AssetRef;
+use crate::crawler::feed::FeedEntry;
 
 /// Represents a crawling task to be executed
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +25,23 @@ pub struct CrawlTask {
     
     /// Priority of this task (higher values = higher priority)
     pub priority: i32,
+    
+    /// Number of times this task has been retried
+    #[serde(default)]
+    pub attempt: u32,
+    
+    /// Feed entry listing the URL, for tasks seeded from a feed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_entry: Option<FeedEntry>,
+    
+    /// Index in the job's seeds of the seed URL this task was found from,
+    /// None for sitemap and feed pages and the links found on them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<usize>,
+    
+    /// Job whose result for the URL the page is compared to, for tasks of a recrawl
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recrawl_of: Option<String>,
 }
 
 /// Result of a completed crawl task
@@ -53,6 +76,124 @@ pub struct TaskResult {
     
     /// Timestamp when the page was crawled
     pub crawled_at: DateTime<Utc>,
+    
+    /// Redirects followed to load the page, from the task's URL on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<RedirectHop>,
+    
+    /// URL the page was loaded from once redirects were followed, None when
+    /// it wasn't redirected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    
+    /// Headers of the page's HTTP response, by lowercase name. Repeated
+    /// headers are joined with newlines.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    
+    /// Kind of document, from the MIME type it was served with
+    #[serde(default)]
+    pub content_kind: ContentKind,
+    
+    /// Stored copy of the body of a non-HTML page, with `assets.payloads`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<AssetRef>,
+}
+
+/// Kind of document a page is, only HTML pages have links and data extracted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    #[default]
+    Html,
+    Pdf,
+    Image,
+    Json,
+    
+    /// Other text, like plain text, CSS or XML
+    Text,
+    
+    /// Other binary content
+    Other,
+}
+
+impl ContentKind {
+    /// Kind of a document served with a MIME type, parameters included or not
+    pub fn from_mime(mime_type: &str) -> Self {
+        let mime_type = mime_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match mime_type.as_str() {
+            "" | "text/html" | "application/xhtml+xml" => Self::Html,
+            "application/pdf" => Self::Pdf,
+            "application/json" => Self::Json,
+            "application/xml" | "application/javascript" => Self::Text,
+            _ if mime_type.ends_with("+json") => Self::Json,
+            _ if mime_type.ends_with("+xml") || mime_type.starts_with("text/") => Self::Text,
+            _ if mime_type.starts_with("image/") => Self::Image,
+            _ => Self::Other,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+            Self::Image => "image",
+            Self::Json => "json",
+            Self::Text => "text",
+            Self::Other => "other",
+        }
+    }
+    
+    /// Whether the content is text that can be kept as the page's raw content
+    pub fn is_text(&self) -> bool {
+        matches!(self, Self::Html | Self::Json | Self::Text)
+    }
+}
+
+impl TaskResult {
+    /// URL the page was actually loaded from, which its relative links resolve against
+    pub fn resolved_url(&self) -> &str {
+        self.final_url.as_deref().unwrap_or(&self.url)
+    }
+    
+    /// Whether the page was served with a client or server error status
+    pub fn is_error(&self) -> bool {
+        self.status_code >= 400
+    }
+    
+    /// Whether the page has data to extract, an HTML page served without error
+    pub fn is_extractable(&self) -> bool {
+        self.content_kind == ContentKind::Html && !self.is_error()
+    }
+    
+    /// Value of a response header, whatever the case of its name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+impl TaskResult {
+    /// An empty HTML page of job "job", served with 200, for tests to fill in
+    pub(crate) fn test_page(url: &str) -> Self {
+        Self {
+            job_id: "job".to_string(),
+            url: url.to_string(),
+            depth: 0,
+            status_code: 200,
+            content_type: "text/html".to_string(),
+            title: String::new(),
+            links: Vec::new(),
+            raw_content: String::new(),
+            extracted_data: Value::Object(serde_json::Map::new()),
+            crawled_at: Utc::now(),
+            redirects: Vec::new(),
+            final_url: None,
+            headers: BTreeMap::new(),
+            content_kind: ContentKind::Html,
+            payload: None,
+        }
+    }
 }
 
 /// Error result from a crawl task
@@ -72,4 +213,174 @@ pub struct TaskError {
     
     /// Timestamp when the error occurred
     pub occurred_at: DateTime<Utc>,
+}
+
+/// Error of a task that ran past the task timeout and was abandoned
+#[derive(Debug, Error)]
+#[error("Task timed out after {seconds} seconds")]
+pub struct TaskTimeout {
+    pub seconds: u64,
+}
+
+/// Debug bundle captured when a task fails
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTrace {
+    /// Unique identifier for the job this trace belongs to
+    pub job_id: String,
+    
+    /// URL that was being crawled
+    pub url: String,
+    
+    /// Error message
+    pub error: String,
+    
+    /// Base64-encoded PNG screenshot of the page at failure time
+    pub screenshot: Option<String>,
+    
+    /// HTML snapshot of the page at failure time
+    pub html: Option<String>,
+    
+    /// Browser console log lines
+    pub console_logs: Vec<String>,
+    
+    /// Request headers sent by the browser
+    pub request_headers: HashMap<String, String>,
+    
+    /// Proxy used for the request (if any)
+    pub proxy: Option<String>,
+    
+    /// Timestamp when the trace was captured
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Task that failed more often than it may be retried, kept with its last error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub task: CrawlTask,
+    pub error: String,
+    #[serde(default)]
+    pub error_type: String, // Like "timeout" or "browser", empty for tasks dead-lettered before types were recorded
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Page whose extracted data failed validation, kept so it can be
+/// reprocessed without refetching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedPage {
+    /// Page result, including the raw body
+    pub result: TaskResult,
+    
+    /// Required fields missing from the extracted data
+    pub missing_fields: Vec<String>,
+    
+    /// Timestamp when the page was quarantined
+    pub quarantined_at: DateTime<Utc>,
+    
+    /// Timestamp after which the page is purged
+    pub expires_at: DateTime<Utc>,
+}
+
+impl QuarantinedPage {
+    /// Whether a purge of pages expiring before `before` removes this page,
+    /// purging without a cutoff removes everything
+    pub fn purged_by(&self, before: Option<DateTime<Utc>>) -> bool {
+        before.map_or(true, |before| self.expires_at <= before)
+    }
+}
+
+/// Characters of an extracted value kept in a record preview
+const PREVIEW_VALUE_LEN: usize = 80;
+
+/// Profile fields of a page just crawled, with their values truncated, so
+/// broken selectors show up while the job is still running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordPreview {
+    pub url: String,
+    pub title: String,
+    
+    /// Value of each selector of the profile, None when it matched nothing
+    pub fields: BTreeMap<String, Option<String>>,
+    
+    pub crawled_at: DateTime<Utc>,
+}
+
+impl RecordPreview {
+    /// Preview of the fields a page result has for the profile's selectors
+    pub fn new(result: &TaskResult, selectors: &BTreeMap<String, String>) -> Self {
+        let fields = selectors.keys()
+            .map(|name| {
+                let value = match result.extracted_data.get(name) {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(text)) => Some(text.clone()),
+                    Some(Value::Array(values)) => Some(values.iter()
+                        .map(|value| value.as_str().map_or_else(|| value.to_string(), str::to_string))
+                        .collect::<Vec<_>>()
+                        .join(", ")),
+                    Some(value) => Some(value.to_string()),
+                };
+                let value = value.map(|value| truncate(value.trim())).filter(|value| !value.is_empty());
+                (name.clone(), value)
+            })
+            .collect();
+        
+        Self {
+            url: result.url.clone(),
+            title: result.title.clone(),
+            fields,
+            crawled_at: result.crawled_at,
+        }
+    }
+}
+
+/// Text cut to the preview length, with an ellipsis when it is longer
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_VALUE_LEN) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_record_preview() {
+        let result = TaskResult {
+            depth: 1,
+            title: "Blue kettle".to_string(),
+            extracted_data: json!({
+                "name": "  Blue kettle ",
+                "tags": ["kitchen", "steel"],
+                "description": "a".repeat(100),
+                "price": "",
+            }),
+            ..TaskResult::test_page("https://shop.example.com/kettles/blue")
+        };
+        let selectors: BTreeMap<String, String> = ["name", "tags", "description", "price", "sku"]
+            .iter()
+            .map(|name| (name.to_string(), format!(".{}", name)))
+            .collect();
+        
+        let preview = RecordPreview::new(&result, &selectors);
+        assert_eq!(preview.fields["name"].as_deref(), Some("Blue kettle"));
+        assert_eq!(preview.fields["tags"].as_deref(), Some("kitchen, steel"));
+        assert_eq!(preview.fields["description"], Some(format!("{}…", "a".repeat(80))));
+        assert_eq!(preview.fields["price"], None);
+        assert_eq!(preview.fields["sku"], None);
+    }
+    
+    #[test]
+    fn test_content_kind() {
+        assert_eq!(ContentKind::from_mime("text/html; charset=utf-8"), ContentKind::Html);
+        assert_eq!(ContentKind::from_mime(""), ContentKind::Html);
+        assert_eq!(ContentKind::from_mime("application/pdf"), ContentKind::Pdf);
+        assert_eq!(ContentKind::from_mime("image/svg+xml"), ContentKind::Text);
+        assert_eq!(ContentKind::from_mime("image/PNG"), ContentKind::Image);
+        assert_eq!(ContentKind::from_mime("application/ld+json"), ContentKind::Json);
+        assert_eq!(ContentKind::from_mime("text/csv"), ContentKind::Text);
+        assert_eq!(ContentKind::from_mime("application/zip"), ContentKind::Other);
+        assert!(!ContentKind::Pdf.is_text());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,322 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+use crate::crawler::task::TaskResult;
+
+/// Minimum structural similarity for a page to join a template
+const TEMPLATE_SIMILARITY: f64 = 0.6;
+
+/// Number of ancestor levels in a structural path
+const PATH_DEPTH: usize = 4;
+
+/// Sibling blocks with links needed to consider a page a listing
+const MIN_LISTING_ITEMS: usize = 8;
+
+/// Paragraph text needed to consider a page an article
+const MIN_ARTICLE_TEXT: usize = 1500;
+
+/// Maximum number of example URLs kept per template
+const MAX_EXAMPLE_URLS: usize = 5;
+
+/// Kind of page a template is used for, guessed from its structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateKind {
+    Listing,
+    Detail,
+    Article,
+    Other,
+}
+
+impl TemplateKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Listing => "listing",
+            Self::Detail => "detail",
+            Self::Article => "article",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Group of pages sharing the same DOM structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageTemplate {
+    /// Kind of page most members look like
+    pub kind: TemplateKind,
+    
+    /// Number of pages using the template
+    pub pages: usize,
+    
+    /// URL path pattern covering the members, `*` stands for a varying segment
+    pub url_pattern: String,
+    
+    /// A few URLs using the template
+    pub example_urls: Vec<String>,
+}
+
+/// Report of the main page templates of a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateReport {
+    /// HTML pages that were compared
+    pub pages_analyzed: usize,
+    
+    /// Templates found, most used first
+    pub templates: Vec<PageTemplate>,
+}
+
+/// Structure of one page
+struct PageStructure {
+    url: String,
+    paths: HashSet<String>,
+    kind: TemplateKind,
+}
+
+/// Pages grouped under the first page that started the group
+struct Cluster {
+    paths: HashSet<String>,
+    urls: Vec<String>,
+    kinds: HashMap<TemplateKind, usize>,
+}
+
+impl TemplateReport {
+    /// Cluster the successful HTML pages of a job by structure
+    ///
+    /// Each page is reduced to the set of tag paths in its DOM, ignoring text.
+    /// Pages join the template whose founding page shares the most paths,
+    /// measured as Jaccard similarity, or start a new one when none is close.
+    pub fn from_results(results: &[TaskResult]) -> Self {
+        let mut pages: Vec<&TaskResult> = results.iter()
+            .filter(|result| (200..300).contains(&result.status_code))
+            .filter(|result| result.content_type.contains("html") && !result.raw_content.is_empty())
+            .collect();
+        pages.sort_by(|a, b| a.url.cmp(&b.url));
+        
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for result in &pages {
+            let page = page_structure(result);
+            
+            let best = clusters.iter_mut()
+                .map(|cluster| (similarity(&cluster.paths, &page.paths), cluster))
+                .filter(|(score, _)| *score >= TEMPLATE_SIMILARITY)
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            
+            match best {
+                Some((_, cluster)) => {
+                    cluster.urls.push(page.url);
+                    *cluster.kinds.entry(page.kind).or_default() += 1;
+                },
+                None => clusters.push(Cluster {
+                    paths: page.paths,
+                    urls: vec![page.url],
+                    kinds: HashMap::from([(page.kind, 1)]),
+                }),
+            }
+        }
+        
+        let mut templates: Vec<PageTemplate> = clusters.into_iter()
+            .map(|cluster| {
+                let kind = cluster.kinds.into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                    .map_or(TemplateKind::Other, |(kind, _)| kind);
+                
+                PageTemplate {
+                    kind,
+                    pages: cluster.urls.len(),
+                    url_pattern: url_pattern(&cluster.urls),
+                    example_urls: cluster.urls.into_iter().take(MAX_EXAMPLE_URLS).collect(),
+                }
+            })
+            .collect();
+        templates.sort_by(|a, b| b.pages.cmp(&a.pages).then(a.url_pattern.cmp(&b.url_pattern)));
+        
+        Self {
+            pages_analyzed: pages.len(),
+            templates,
+        }
+    }
+}
+
+/// Reduce a page to its structural paths and guess its kind
+fn page_structure(result: &TaskResult) -> PageStructure {
+    let document = Html::parse_document(&result.raw_content);
+    
+    let mut paths = HashSet::new();
+    let mut listing_items = 0;
+    collect_paths(document.root_element(), &mut Vec::new(), &mut paths, &mut listing_items);
+    
+    let kind = if listing_items >= MIN_LISTING_ITEMS {
+        TemplateKind::Listing
+    } else if article_text(&document) >= MIN_ARTICLE_TEXT {
+        TemplateKind::Article
+    } else if looks_like_detail(&document) {
+        TemplateKind::Detail
+    } else {
+        TemplateKind::Other
+    };
+    
+    PageStructure {
+        url: result.url.clone(),
+        paths,
+        kind,
+    }
+}
+
+/// Collect the tag paths below an element, tracking the largest group of
+/// sibling blocks that hold links
+fn collect_paths(element: ElementRef, ancestors: &mut Vec<String>, paths: &mut HashSet<String>, listing_items: &mut usize) {
+    let name = element.value().name();
+    if matches!(name, "script" | "style" | "noscript" | "svg" | "template") {
+        return;
+    }
+    
+    ancestors.push(node_label(element));
+    let start = ancestors.len().saturating_sub(PATH_DEPTH);
+    paths.insert(ancestors[start..].join(">"));
+    
+    let mut siblings: HashMap<String, usize> = HashMap::new();
+    for child in element.children().filter_map(ElementRef::wrap) {
+        if has_link(child) {
+            let count = siblings.entry(node_label(child)).or_default();
+            *count += 1;
+            *listing_items = (*listing_items).max(*count);
+        }
+        collect_paths(child, ancestors, paths, listing_items);
+    }
+    
+    ancestors.pop();
+}
+
+/// Tag name with its first class, generated class names are left out
+fn node_label(element: ElementRef) -> String {
+    let name = element.value().name();
+    match element.value().classes().next() {
+        Some(class) if class.len() <= 30 && class.chars().all(|c| c.is_ascii_alphabetic() || c == '-' || c == '_') => {
+            format!("{}.{}", name, class)
+        },
+        _ => name.to_string(),
+    }
+}
+
+/// Whether an element is or contains a link
+fn has_link(element: ElementRef) -> bool {
+    element.value().name() == "a"
+        || element.descendants().filter_map(ElementRef::wrap).any(|e| e.value().name() == "a")
+}
+
+/// Length of the paragraph text of a page
+fn article_text(document: &Html) -> usize {
+    let paragraphs = Selector::parse("p").expect("valid selector");
+    document.select(&paragraphs)
+        .map(|p| p.text().map(str::trim).map(str::len).sum::<usize>())
+        .sum()
+}
+
+/// Whether a page describes a single product or item
+fn looks_like_detail(document: &Html) -> bool {
+    let markers = Selector::parse(
+        "[itemtype*=Product], [itemprop=price], meta[property='og:type'][content=product], \
+        meta[property='product:price:amount'], form[action*=cart], button[name=add-to-cart]"
+    ).expect("valid selector");
+    document.select(&markers).next().is_some()
+}
+
+/// Jaccard similarity of two path sets
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Path pattern shared by a set of URLs
+///
+/// Segments that differ between URLs become `*`. When the URLs have different
+/// depths, the pattern ends with `*` after their common leading segments.
+fn url_pattern(urls: &[String]) -> String {
+    let paths: Vec<Vec<String>> = urls.iter()
+        .filter_map(|url| Url::parse(url).ok())
+        .map(|url| {
+            url.path_segments()
+                .map(|segments| segments.filter(|s| !s.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+    
+    let first = match paths.first() {
+        Some(first) => first,
+        None => return "/".to_string(),
+    };
+    let same_depth = paths.iter().all(|path| path.len() == first.len());
+    
+    let mut segments = Vec::new();
+    for (i, segment) in first.iter().enumerate() {
+        let shared = paths.iter().all(|path| path.get(i) == Some(segment));
+        if shared {
+            segments.push(segment.clone());
+        } else if same_depth {
+            segments.push("*".to_string());
+        } else {
+            break;
+        }
+    }
+    if !same_depth {
+        segments.push("*".to_string());
+    }
+    
+    format!("/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn page(url: &str, body: &str) -> TaskResult {
+        TaskResult {
+            raw_content: format!("<html><body><header><nav><a href='/'>Home</a></nav></header>{}</body></html>", body),
+            ..TaskResult::test_page(url)
+        }
+    }
+    
+    #[test]
+    fn test_template_report() {
+        let items: String = (0..10)
+            .map(|i| format!("<li class='item'><a href='/p/{}'>Item {}</a></li>", i, i))
+            .collect();
+        let listing = format!("<main><h2>Items</h2><ul class='items'>{}</ul></main>", items);
+        let text = "Lorem ipsum dolor sit amet. ".repeat(30);
+        let article = format!(
+            "<article><h1>Title</h1><div class='meta'><span>Author</span><time>Today</time></div><p>{}</p><p>{}</p></article>",
+            text, text
+        );
+        
+        let results = vec![
+            page("https://example.com/category/shoes", &listing),
+            page("https://example.com/category/hats", &listing),
+            page("https://example.com/blog/2024/first-post", &article),
+            page("https://example.com/blog/2024/second-post", &article),
+            page("https://example.com/blog/2023/old-post", &article),
+        ];
+        let report = TemplateReport::from_results(&results);
+        
+        assert_eq!(report.pages_analyzed, 5);
+        assert_eq!(report.templates.len(), 2);
+        assert_eq!(report.templates[0].kind, TemplateKind::Article);
+        assert_eq!(report.templates[0].pages, 3);
+        assert_eq!(report.templates[0].url_pattern, "/blog/*/*");
+        assert_eq!(report.templates[1].kind, TemplateKind::Listing);
+        assert_eq!(report.templates[1].url_pattern, "/category/*");
+    }
+    
+    #[test]
+    fn test_url_pattern() {
+        let urls = |urls: &[&str]| urls.iter().map(|url| url.to_string()).collect::<Vec<_>>();
+        
+        assert_eq!(url_pattern(&urls(&["https://a.com/p/1", "https://a.com/p/2"])), "/p/*");
+        assert_eq!(url_pattern(&urls(&["https://a.com/p/1", "https://a.com/p/1/reviews"])), "/p/1/*");
+        assert_eq!(url_pattern(&urls(&["https://a.com/"])), "/");
+    }
+}
\ No newline at end of file
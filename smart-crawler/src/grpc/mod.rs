@@ -0,0 +1,189 @@
+use anyhow::{Result, Context};
+use axum::http::StatusCode;
+use futures::stream::{self, Stream};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::api::handlers::{run_export, ExportRequest as ApiExportRequest};
+use crate::api::ApiError;
+use crate::cli::config::CrawlerConfig;
+use crate::crawler::controller::CrawlerController;
+use crate::crawler::task::TaskResult;
+use crate::storage::processed::RowFilter;
+use crate::storage::raw::JobStatus as StoredJobStatus;
+use crate::utils::shutdown;
+
+pub mod proto {
+    tonic::include_proto!("crawler.v1");
+}
+
+use proto::crawler_control_server::{CrawlerControl, CrawlerControlServer};
+use proto::{
+    ExportRequest, ExportResponse, ExportedFile, GetStatusRequest, JobStatus, PageResult, StartJobRequest,
+    StartJobResponse, StreamResultsRequest,
+};
+
+/// gRPC job control, backed by the same controller as the CLI and REST API
+pub struct CrawlerControlService {
+    controller: Arc<CrawlerController>,
+}
+
+/// Serve the gRPC API until interrupted
+pub async fn serve(host: &str, port: u16) -> Result<()> {
+    let address = format!("{}:{}", host, port).parse::<SocketAddr>()
+        .context(format!("Invalid listen address: {}:{}", host, port))?;
+    let service = CrawlerControlService {
+        controller: Arc::new(CrawlerController::connect().await?),
+    };
+    
+    info!("gRPC API listening on {}", address);
+    tonic::transport::Server::builder()
+        .add_service(CrawlerControlServer::new(service))
+        .serve_with_shutdown(address, async {
+            shutdown::requested().await;
+            info!("Shutting down gRPC server");
+        })
+        .await
+        .context("gRPC server failed")?;
+    
+    // Workers of jobs started through the gRPC finish or requeue their tasks
+    shutdown::wait_for_workers().await;
+    
+    Ok(())
+}
+
+/// Map REST API errors onto gRPC codes
+fn to_status(e: ApiError) -> Status {
+    match e.status() {
+        StatusCode::BAD_REQUEST => Status::invalid_argument(e.message()),
+        StatusCode::NOT_FOUND => Status::not_found(e.message()),
+        StatusCode::CONFLICT => Status::failed_precondition(e.message()),
+        _ => Status::internal(e.message()),
+    }
+}
+
+/// Map controller errors onto gRPC codes the same way as in the REST API
+fn controller_error(e: anyhow::Error) -> Status {
+    to_status(ApiError::from(e))
+}
+
+/// Empty proto3 strings stand for unset fields
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+impl From<StoredJobStatus> for JobStatus {
+    fn from(status: StoredJobStatus) -> Self {
+        Self {
+            job_id: status.job_id,
+            seed_url: status.seed_url,
+            state: status.state,
+            pages_crawled: status.pages_crawled as u64,
+            pages_total: status.pages_total as u64,
+            started_at: status.started_at.to_rfc3339(),
+            updated_at: status.updated_at.to_rfc3339(),
+            errors: status.errors,
+            stop_reason: status.stop_reason,
+        }
+    }
+}
+
+impl From<TaskResult> for PageResult {
+    fn from(result: TaskResult) -> Self {
+        Self {
+            url: result.url,
+            depth: result.depth,
+            status_code: result.status_code as u32,
+            content_type: result.content_type,
+            title: result.title,
+            links: result.links,
+            extracted_data: result.extracted_data.to_string(),
+            crawled_at: result.crawled_at.to_rfc3339(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CrawlerControl for CrawlerControlService {
+    async fn start_job(&self, request: Request<StartJobRequest>) -> Result<Response<StartJobResponse>, Status> {
+        let request = request.into_inner();
+        let profile = non_empty(request.profile).unwrap_or_else(|| "general".to_string());
+        
+        let mut config = CrawlerConfig::load_profile(&profile)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        if let Some(depth) = request.depth {
+            config.crawler.max_depth = depth;
+        }
+        if let Some(limit) = request.limit {
+            config.crawler.max_pages = limit;
+        }
+        
+        let controller = CrawlerController::new(config).await.map_err(controller_error)?;
+        let job_id = controller.start_job(vec![request.url.clone()]).await.map_err(controller_error)?;
+        info!("Started job {} on {} with profile {}", job_id, request.url, profile);
+        
+        Ok(Response::new(StartJobResponse { job_id }))
+    }
+    
+    async fn get_status(&self, request: Request<GetStatusRequest>) -> Result<Response<JobStatus>, Status> {
+        let status = self.controller.get_job_status(&request.into_inner().job_id).await.map_err(controller_error)?;
+        Ok(Response::new(status.into()))
+    }
+    
+    type StreamResultsStream = Pin<Box<dyn Stream<Item = Result<PageResult, Status>> + Send>>;
+    
+    async fn stream_results(
+        &self,
+        request: Request<StreamResultsRequest>,
+    ) -> Result<Response<Self::StreamResultsStream>, Status> {
+        let request = request.into_inner();
+        let filter = RowFilter::parse(
+            non_empty(request.include_url).as_deref(),
+            non_empty(request.since).as_deref(),
+            non_empty(request.until).as_deref(),
+        ).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        
+        // Fail on unknown jobs rather than returning an empty stream
+        self.controller.get_job_status(&request.job_id).await.map_err(controller_error)?;
+        
+        let mut results = self.controller.list_page_results(&request.job_id).await.map_err(controller_error)?;
+        results.retain(|result| filter.matches(&result.url, result.crawled_at));
+        results.sort_by(|a, b| a.url.cmp(&b.url));
+        
+        let results = stream::iter(results.into_iter().map(|result| Ok(PageResult::from(result))));
+        Ok(Response::new(Box::pin(results)))
+    }
+    
+    async fn export(&self, request: Request<ExportRequest>) -> Result<Response<ExportResponse>, Status> {
+        let request = request.into_inner();
+        let export = ApiExportRequest {
+            format: request.format,
+            output: non_empty(request.output),
+            resume: request.resume,
+            columns: if request.columns.is_empty() { None } else { Some(request.columns) },
+            include_url: non_empty(request.include_url),
+            since: non_empty(request.since),
+            until: non_empty(request.until),
+            compress: non_empty(request.compress),
+        };
+        
+        let files = run_export(&self.controller, &request.job_id, export).await
+            .map_err(to_status)?
+            .into_iter()
+            .map(|file| ExportedFile {
+                format: file.format,
+                path: file.path,
+                records: file.records as u64,
+            })
+            .collect();
+        
+        Ok(Response::new(ExportResponse { files }))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+//! Smart Crawler as a library, for programs starting and following crawls
+//! themselves. `JobBuilder` configures and starts a job, the returned
+//! `JobHandle` follows, cancels and exports it.
+
+#[cfg(feature = "api")]
+pub mod api;
+pub mod cli;
+pub mod crawler;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod browser;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+pub mod storage;
+pub mod utils;
+
+pub use cli::config::{CrawlerConfig, ExecutionMode, JobPriority};
+pub use crawler::extractors::{PageExtractor, register_extractor};
+#[cfg(feature = "redis")]
+pub use crawler::job::{JobBuilder, JobEvent, JobHandle};
+pub use storage::export::ExportFormat;
+pub use storage::models::TypedRecord;
+pub use storage::raw::JobStatus;
\ No newline at end of file
@@ -1,20 +1,12 @@
 use anyhow::Result;
 use tracing::{info, error};
-use tracing_subscriber::{fmt, EnvFilter};
 
-mod cli;
-mod crawler;
-mod browser;
-mod proxy;
-mod storage;
-mod utils;
+use smart_crawler::{cli, utils};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    // Initialize logging and span export, kept until the command is done
+    let _telemetry = utils::init_tracing()?;
 
     info!("Starting Smart Crawler v{}", env!("CARGO_PKG_VERSION"));
 
@@ -0,0 +1,156 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use duckdb::{params, Connection};
+use futures::StreamExt;
+use std::path::Path;
+use tracing::debug;
+
+use crate::crawler::controller::CrawlerController;
+use crate::storage::processed::RowFilter;
+use crate::storage::progress::RowProgress;
+use crate::storage::query::{check_query, QueryResult};
+
+// Rows are appended as text to staging tables, then cast to their real types
+const CREATE_RESULTS_STAGING: &str = "CREATE OR REPLACE TABLE results_load (
+    job_id VARCHAR, url VARCHAR, depth INTEGER, status_code INTEGER, content_type VARCHAR,
+    title VARCHAR, links INTEGER, extracted_data VARCHAR, headers VARCHAR, crawled_at VARCHAR
+)";
+
+const CREATE_RESULTS: &str = "CREATE OR REPLACE TABLE results AS
+    SELECT job_id, url, depth, status_code, content_type, title, links,
+        CAST(extracted_data AS JSON) AS extracted_data,
+        CAST(headers AS JSON) AS headers,
+        CAST(crawled_at AS TIMESTAMP) AS crawled_at
+    FROM results_load;
+    DROP TABLE results_load";
+
+const CREATE_PAGES_STAGING: &str = "CREATE OR REPLACE TABLE pages_load (
+    job_id VARCHAR, url VARCHAR, data VARCHAR, created_at VARCHAR, updated_at VARCHAR
+)";
+
+const CREATE_PAGES: &str = "CREATE OR REPLACE TABLE pages AS
+    SELECT job_id, url, CAST(data AS JSON) AS data,
+        CAST(created_at AS TIMESTAMP) AS created_at,
+        CAST(updated_at AS TIMESTAMP) AS updated_at
+    FROM pages_load;
+    DROP TABLE pages_load";
+
+/// Crawl results of a job loaded into DuckDB for analytical SQL
+///
+/// Raw results become the `results` table and processed pages the `pages`
+/// table, with the extracted data as JSON columns, so fields are read with
+/// `data->>'$.price'`. Timestamps are in UTC.
+pub struct JobDatabase {
+    connection: Connection,
+}
+
+/// Format a time for a DuckDB TIMESTAMP cast
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S%.6f").to_string()
+}
+
+impl JobDatabase {
+    /// Open a database, in memory unless a file is given to keep the tables
+    pub fn open(path: Option<&Path>) -> Result<Self> {
+        let connection = match path {
+            Some(path) => Connection::open(path)
+                .context(format!("Failed to open DuckDB database: {}", path.display()))?,
+            None => Connection::open_in_memory()
+                .context("Failed to open in-memory DuckDB database")?,
+        };
+        
+        Ok(Self { connection })
+    }
+    
+    /// Load a job into the `results` and `pages` tables, replacing what they
+    /// held. Returns the number of results and pages loaded.
+    pub async fn load(&self, controller: &CrawlerController, job_id: &str, progress: &dyn RowProgress) -> Result<(usize, usize)> {
+        let results = controller.list_page_results(job_id).await?;
+        let filter = RowFilter::default();
+        if let Some(pages) = controller.count_page_data(job_id, &filter).await? {
+            progress.set_total((results.len() + pages) as u64);
+        }
+        
+        self.connection.execute_batch(CREATE_RESULTS_STAGING)
+            .context("Failed to create DuckDB results table")?;
+        {
+            let mut appender = self.connection.appender("results_load")
+                .context("Failed to load results into DuckDB")?;
+            for result in &results {
+                appender.append_row(params![
+                    result.job_id,
+                    result.url,
+                    result.depth,
+                    result.status_code,
+                    result.content_type,
+                    result.title,
+                    result.links.len() as i64,
+                    result.extracted_data.to_string(),
+                    serde_json::json!(result.headers).to_string(),
+                    timestamp(result.crawled_at),
+                ]).context(format!("Failed to load result into DuckDB: {}", result.url))?;
+            }
+        }
+        progress.advance(results.len() as u64);
+        self.connection.execute_batch(CREATE_RESULTS)
+            .context("Failed to create DuckDB results table")?;
+        
+        // Processed pages are streamed rather than loaded in memory first
+        self.connection.execute_batch(CREATE_PAGES_STAGING)
+            .context("Failed to create DuckDB pages table")?;
+        let mut pages = 0;
+        {
+            let mut appender = self.connection.appender("pages_load")
+                .context("Failed to load pages into DuckDB")?;
+            let mut rows = controller.stream_page_data(job_id, &filter);
+            while let Some(page) = rows.next().await {
+                let page = page?;
+                appender.append_row(params![
+                    page.job_id,
+                    page.url,
+                    page.data.to_string(),
+                    timestamp(page.created_at),
+                    timestamp(page.updated_at),
+                ]).context(format!("Failed to load page into DuckDB: {}", page.url))?;
+                pages += 1;
+                progress.advance(1);
+            }
+        }
+        progress.finish();
+        self.connection.execute_batch(CREATE_PAGES)
+            .context("Failed to create DuckDB pages table")?;
+        
+        debug!("Loaded {} results and {} pages of job {} into DuckDB", results.len(), pages, job_id);
+        
+        Ok((results.len(), pages))
+    }
+    
+    /// Run a SELECT query returning at most `max_rows` rows, values are converted to text by DuckDB
+    pub fn query(&self, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        let sql = check_query(sql)?;
+        
+        // Describing the query only binds it, giving the column names the casts below lose
+        let mut describe = self.connection.prepare(&format!("DESCRIBE {}", sql))
+            .context("Failed to prepare query, only SELECT queries are supported")?;
+        let columns = describe.query_map([], |row| row.get::<_, String>(0))
+            .context("Failed to prepare query, only SELECT queries are supported")?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to read query columns")?;
+        
+        let mut statement = self.connection.prepare(&format!("SELECT COLUMNS(*)::VARCHAR FROM ({}) AS query LIMIT {}", sql, max_rows + 1))
+            .context("Failed to prepare query")?;
+        let mut rows = statement.query([])
+            .context("Query failed")?;
+        
+        let mut result = QueryResult { columns, ..Default::default() };
+        while let Some(row) = rows.next().context("Query failed")? {
+            let values = (0..result.columns.len())
+                .map(|index| row.get::<_, Option<String>>(index))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .context("Failed to read query row")?;
+            result.rows.push(values);
+        }
+        
+        Ok(result.limit(max_rows))
+    }
+}
\ No newline at end of file
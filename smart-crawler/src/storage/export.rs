@@ -0,0 +1,1844 @@
+use anyhow::{Result, Context};
+#[cfg(feature = "parquet")]
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, TimestampMicrosecondArray};
+#[cfg(feature = "parquet")]
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+#[cfg(feature = "parquet")]
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use futures::TryStreamExt;
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::basic::Compression;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+#[cfg(feature = "parquet")]
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use serde::{Serialize, Deserialize};
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use crate::storage::processed::{PageData, ProcessedStorage, RowFilter};
+use crate::storage::progress::RowProgress;
+use crate::storage::raw::JobStatus;
+use crate::storage::graph::count_nodes;
+use crate::storage::warc::count_responses;
+
+/// Number of rows fetched and written between progress checkpoints
+pub const EXPORT_CHUNK_SIZE: usize = 1000;
+
+/// Jobs larger than this should be exported as NDJSON rather than one JSON array
+pub const NDJSON_RECOMMENDED_PAGES: usize = 100_000;
+
+/// Rows buffered per Parquet row group
+#[cfg(feature = "parquet")]
+const PARQUET_ROW_GROUP_SIZE: usize = 64 * EXPORT_CHUNK_SIZE;
+
+/// Compression applied to text and WARC exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportCompression {
+    Gzip,
+    Zstd,
+}
+
+impl ExportCompression {
+    /// Parse a compression name
+    pub fn parse(compression: &str) -> Result<Self> {
+        match compression {
+            "gzip" | "gz" => Ok(Self::Gzip),
+            "zstd" | "zst" => Ok(Self::Zstd),
+            _ => anyhow::bail!("Unsupported export compression: {}", compression),
+        }
+    }
+    
+    /// Extension appended to the format extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+}
+
+/// Open an export for reading, decompressing it if needed
+pub(crate) fn open_export(path: &Path, compression: Option<ExportCompression>) -> Result<Box<dyn Read>> {
+    let file = File::open(path)
+        .context(format!("Failed to open export: {}", path.display()))?;
+    
+    // Exports are written in several members, both decoders read through all of them
+    Ok(match compression {
+        None => Box::new(file),
+        Some(ExportCompression::Gzip) => Box::new(MultiGzDecoder::new(file)),
+        Some(ExportCompression::Zstd) => Box::new(
+            zstd::Decoder::new(file).context("Failed to create zstd decoder")?
+        ),
+    })
+}
+
+/// Where an export is written, a file or stdout for `--output -`
+pub(crate) enum Destination {
+    File(File),
+    Stdout(io::Stdout),
+}
+
+impl Destination {
+    /// File behind the destination, exports to stdout have none
+    pub(crate) fn file(&mut self) -> Option<&mut File> {
+        match self {
+            Self::File(file) => Some(file),
+            Self::Stdout(_) => None,
+        }
+    }
+}
+
+impl Write for Destination {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.write(buf),
+            Self::Stdout(out) => out.write(buf),
+        }
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::File(file) => file.flush(),
+            Self::Stdout(out) => out.flush(),
+        }
+    }
+}
+
+impl Destination {
+    /// Open the destination of a target written in one pass, files are
+    /// written to `<output>.partial` until published
+    pub(crate) fn create(target: &ExportTarget) -> Result<Self> {
+        if target.to_stdout() {
+            return Ok(Self::Stdout(io::stdout()));
+        }
+        
+        let partial_path = sibling_path(&target.output_path, "partial");
+        let file = File::create(&partial_path)
+            .context(format!("Failed to create output file: {}", partial_path.display()))?;
+        
+        Ok(Self::File(file))
+    }
+    
+    /// Flush a finished export and move it into place
+    pub(crate) fn publish(self, target: &ExportTarget) -> Result<()> {
+        let file = match self {
+            Self::File(file) => file,
+            Self::Stdout(mut out) => {
+                return out.flush().context("Failed to flush export");
+            },
+        };
+        file.sync_all()
+            .context("Failed to sync export")?;
+        drop(file);
+        
+        let partial_path = sibling_path(&target.output_path, "partial");
+        fs::rename(&partial_path, &target.output_path)
+            .context(format!("Failed to move export into place: {}", target.output_path.display()))
+    }
+}
+
+/// Encoder behind an export destination
+enum Encoder {
+    Plain(Destination),
+    Gzip(GzEncoder<Destination>),
+    Zstd(zstd::Encoder<'static, Destination>),
+}
+
+impl Encoder {
+    fn new(destination: Destination, compression: Option<ExportCompression>) -> Result<Self> {
+        Ok(match compression {
+            None => Self::Plain(destination),
+            Some(ExportCompression::Gzip) => Self::Gzip(GzEncoder::new(destination, flate2::Compression::default())),
+            Some(ExportCompression::Zstd) => Self::Zstd(
+                zstd::Encoder::new(destination, 0).context("Failed to create zstd encoder")?
+            ),
+        })
+    }
+    
+    fn destination(&mut self) -> &mut Destination {
+        match self {
+            Self::Plain(destination) => destination,
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Zstd(encoder) => encoder.get_mut(),
+        }
+    }
+    
+    fn finish(self) -> Result<Destination> {
+        match self {
+            Self::Plain(destination) => Ok(destination),
+            Self::Gzip(encoder) => encoder.finish().context("Failed to finish gzip stream"),
+            Self::Zstd(encoder) => encoder.finish().context("Failed to finish zstd stream"),
+        }
+    }
+}
+
+/// Export writer, optionally compressed
+///
+/// Compressed output is written as a sequence of independent gzip members or
+/// zstd frames. Ending a member leaves a complete file that decompresses to
+/// everything written so far, which is what checkpoints and resuming rely on.
+/// Encoders write nothing until data arrives, so right after a member ends
+/// the file position is the end of that member.
+pub(crate) struct ExportWriter {
+    encoder: Option<Encoder>,
+    compression: Option<ExportCompression>,
+    
+    /// Whether data was written since the current member started
+    pending: bool,
+}
+
+impl ExportWriter {
+    /// Wrap a destination positioned where output should continue
+    pub(crate) fn new(destination: Destination, compression: Option<ExportCompression>) -> Result<Self> {
+        Ok(Self {
+            encoder: Some(Encoder::new(destination, compression)?),
+            compression,
+            pending: false,
+        })
+    }
+    
+    /// Underlying file, an error for exports to stdout
+    pub(crate) fn file(&mut self) -> Result<&mut File> {
+        self.encoder.as_mut()
+            .context("Export writer is closed")?
+            .destination()
+            .file()
+            .context("Export to stdout has no file")
+    }
+    
+    /// Complete the current member and start a new one, nothing is written
+    /// when the member is empty
+    pub(crate) fn end_member(&mut self) -> Result<()> {
+        if !self.pending {
+            return Ok(());
+        }
+        
+        let destination = self.encoder.take()
+            .context("Export writer is closed")?
+            .finish()?;
+        self.encoder = Some(Encoder::new(destination, self.compression)?);
+        self.pending = false;
+        
+        Ok(())
+    }
+    
+    /// Append a member that was already compressed, or plain output when
+    /// the export isn't compressed
+    pub(crate) fn write_member(&mut self, member: &[u8]) -> Result<()> {
+        self.end_member()?;
+        self.encoder.as_mut()
+            .context("Export writer is closed")?
+            .destination()
+            .write_all(member)
+            .context("Failed to write export")
+    }
+    
+    /// Complete the output and get the destination back
+    pub(crate) fn finish(mut self) -> Result<Destination> {
+        self.encoder.take()
+            .context("Export writer is closed")?
+            .finish()
+    }
+}
+
+impl Write for ExportWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            self.pending = true;
+        }
+        
+        match &mut self.encoder {
+            Some(Encoder::Plain(destination)) => destination.write(buf),
+            Some(Encoder::Gzip(encoder)) => encoder.write(buf),
+            Some(Encoder::Zstd(encoder)) => encoder.write(buf),
+            None => Err(io::Error::other("Export writer is closed")),
+        }
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.encoder {
+            Some(Encoder::Plain(destination)) => destination.flush(),
+            Some(Encoder::Gzip(encoder)) => encoder.flush(),
+            Some(Encoder::Zstd(encoder)) => encoder.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Ndjson,
+    Csv,
+    Sql,
+    Parquet,
+    Warc,
+    Graphml,
+    Dot,
+}
+
+impl ExportFormat {
+    /// Parse a format name
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "json" => Ok(Self::Json),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            "sql" => Ok(Self::Sql),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(Self::Parquet),
+            #[cfg(not(feature = "parquet"))]
+            "parquet" => anyhow::bail!(PARQUET_DISABLED),
+            "warc" => Ok(Self::Warc),
+            "graphml" => Ok(Self::Graphml),
+            "dot" | "gv" => Ok(Self::Dot),
+            _ => anyhow::bail!("Unsupported export format: {}", format),
+        }
+    }
+    
+    /// Parse a comma-separated list of format names, ignoring repeats
+    pub fn parse_list(formats: &str) -> Result<Vec<Self>> {
+        let mut parsed = Vec::new();
+        for format in formats.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let format = Self::parse(format)?;
+            if !parsed.contains(&format) {
+                parsed.push(format);
+            }
+        }
+        
+        if parsed.is_empty() {
+            anyhow::bail!("No export format given");
+        }
+        
+        Ok(parsed)
+    }
+    
+    /// File extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+            Self::Sql => "sql",
+            Self::Parquet => "parquet",
+            Self::Warc => "warc",
+            Self::Graphml => "graphml",
+            Self::Dot => "dot",
+        }
+    }
+    
+    /// Whether an interrupted export in this format can be continued
+    fn resumable(&self) -> bool {
+        *self != Self::Parquet
+    }
+    
+    /// Whether the format is built from raw page results rather than processed rows
+    pub fn from_raw_storage(&self) -> bool {
+        matches!(self, Self::Warc | Self::Graphml | Self::Dot)
+    }
+    
+    /// Whether the extracted data is flattened into typed columns
+    pub fn has_data_columns(&self) -> bool {
+        matches!(self, Self::Csv | Self::Parquet)
+    }
+    
+    /// Columns written for each record, `data_columns` are the flattened data
+    /// columns of CSV and Parquet exports
+    pub fn schema(&self, data_columns: &[DataColumn]) -> Vec<SchemaField> {
+        if *self == Self::Warc {
+            return vec![
+                SchemaField::new("warcinfo", "warc-record", "Job description, first record of the file"),
+                SchemaField::new("response", "warc-record", "HTTP response carrying the rendered page"),
+                SchemaField::new("request", "warc-record", "HTTP request for the page, concurrent to its response"),
+            ];
+        }
+        if matches!(self, Self::Graphml | Self::Dot) {
+            return vec![
+                SchemaField::new("node", "graph-node", "Crawled page with its label, URL, depth and status code"),
+                SchemaField::new("edge", "graph-edge", "Link from one crawled page to another"),
+            ];
+        }
+        
+        let mut fields = vec![
+            SchemaField::new("job_id", "string", "Crawl job identifier"),
+            SchemaField::new("url", "string", "Page URL, unique within the job"),
+            SchemaField::new("data", "object", "Data extracted from the page"),
+            SchemaField::new("created_at", "timestamp", "When the page was first stored (RFC 3339)"),
+            SchemaField::new("updated_at", "timestamp", "When the page was last updated (RFC 3339)"),
+        ];
+        
+        // Tabular formats flatten the data into one column per field
+        if self.has_data_columns() {
+            fields.retain(|field| field.name != "data");
+            fields.extend(data_columns.iter().map(|column| {
+                SchemaField::new(
+                    &column.name,
+                    column.column_type.name(),
+                    "Extracted field, nested objects are dot-separated and arrays JSON encoded",
+                )
+            }));
+        }
+        
+        fields
+    }
+    
+    /// Count the records in an exported file
+    fn count_records(&self, path: &Path, compression: Option<ExportCompression>) -> Result<usize> {
+        if *self == Self::Parquet {
+            return count_parquet_rows(path);
+        }
+        
+        let reader = BufReader::new(open_export(path, compression)?);
+        match self {
+            Self::Warc => return count_responses(reader),
+            Self::Graphml | Self::Dot => return count_nodes(reader, *self),
+            _ => {},
+        }
+        
+        let count = match self {
+            Self::Json => {
+                let records: Vec<IgnoredAny> = serde_json::from_reader(reader)
+                    .context("Failed to parse JSON export")?;
+                records.len()
+            },
+            Self::Ndjson => {
+                let mut count = 0;
+                for line in reader.lines() {
+                    if !line?.trim().is_empty() {
+                        count += 1;
+                    }
+                }
+                count
+            },
+            Self::Csv => {
+                // Count line breaks outside quoted fields, minus the header
+                let mut in_quotes = false;
+                let mut lines = 0;
+                for byte in reader.bytes() {
+                    match byte? {
+                        b'"' => in_quotes = !in_quotes,
+                        b'\n' if !in_quotes => lines += 1,
+                        _ => {}
+                    }
+                }
+                lines.saturating_sub(1)
+            },
+            Self::Sql => {
+                let mut count = 0;
+                for line in reader.lines() {
+                    if line?.starts_with("INSERT INTO crawled_data ") {
+                        count += 1;
+                    }
+                }
+                count
+            },
+            Self::Parquet | Self::Warc | Self::Graphml | Self::Dot => unreachable!(),
+        };
+        
+        Ok(count)
+    }
+    
+    /// Write everything that precedes the first row
+    fn write_header<W: Write>(&self, out: &mut W, data_columns: &[DataColumn]) -> Result<()> {
+        match self {
+            Self::Json => write!(out, "[")?,
+            Self::Ndjson | Self::Parquet | Self::Warc | Self::Graphml | Self::Dot => {},
+            Self::Csv => {
+                write!(out, "job_id,url,created_at,updated_at")?;
+                for column in data_columns {
+                    write!(out, ",{}", csv_field(&column.name))?;
+                }
+                writeln!(out)?;
+            },
+            Self::Sql => write!(
+                out,
+                "CREATE TABLE IF NOT EXISTS crawled_data (
+                job_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                data JSONB NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+                updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+                PRIMARY KEY (job_id, url)
+            );\n\n"
+            )?,
+        }
+        
+        Ok(())
+    }
+    
+    /// Write a single row, `index` is the number of rows written before it
+    fn write_row<W: Write>(&self, out: &mut W, row: &PageData, index: usize, data_columns: &[DataColumn]) -> Result<()> {
+        match self {
+            Self::Json => {
+                // Same layout as serde_json's pretty printer for an array
+                let record = serde_json::to_string_pretty(row)
+                    .context("Failed to serialize JSON data")?;
+                if index > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "\n  {}", record.replace('\n', "\n  "))?;
+            },
+            Self::Ndjson => {
+                // One compact record per line, serde_json escapes newlines inside strings
+                serde_json::to_writer(&mut *out, row)
+                    .context("Failed to serialize JSON data")?;
+                writeln!(out)?;
+            },
+            Self::Csv => {
+                write!(
+                    out,
+                    "{},{},{},{}",
+                    csv_field(&row.job_id),
+                    csv_field(&row.url),
+                    row.created_at.to_rfc3339(),
+                    row.updated_at.to_rfc3339()
+                )?;
+                
+                let mut fields = BTreeMap::new();
+                flatten_data(&row.data, "", &mut fields);
+                for column in data_columns {
+                    let value = fields.get(&column.name).map(|value| text_value(value)).unwrap_or_default();
+                    write!(out, ",{}", csv_field(&value))?;
+                }
+                writeln!(out)?;
+            },
+            Self::Sql => {
+                let data_json = serde_json::to_string(&row.data)
+                    .context("Failed to serialize JSON data")?;
+                
+                writeln!(
+                    out,
+                    "INSERT INTO crawled_data (job_id, url, data, created_at, updated_at) VALUES ('{}', '{}', '{}', '{}', '{}');",
+                    row.job_id.replace('\'', "''"),
+                    row.url.replace('\'', "''"),
+                    data_json.replace('\'', "''"),
+                    row.created_at.to_rfc3339(),
+                    row.updated_at.to_rfc3339()
+                )?;
+            },
+            Self::Parquet => anyhow::bail!("Parquet rows are written in record batches"),
+            Self::Warc => anyhow::bail!("WARC records are written from raw storage"),
+            Self::Graphml | Self::Dot => anyhow::bail!("Link graphs are written from raw storage"),
+        }
+        
+        Ok(())
+    }
+    
+    /// Write everything that follows the last row
+    fn write_footer<W: Write>(&self, out: &mut W, rows: usize) -> Result<()> {
+        if *self == Self::Json {
+            if rows > 0 {
+                writeln!(out)?;
+            }
+            write!(out, "]")?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// Type of a flattened data column, inferred from its values across the job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    Int,
+    Float,
+    Bool,
+    Datetime,
+    String,
+}
+
+impl ColumnType {
+    /// Type of a single value, None for nulls and empty strings
+    fn infer(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(Self::Bool),
+            Value::Number(number) if number.is_f64() => Some(Self::Float),
+            Value::Number(_) => Some(Self::Int),
+            Value::String(text) => Self::infer_text(text),
+            Value::Array(_) | Value::Object(_) => Some(Self::String),
+        }
+    }
+    
+    /// Type of a scraped string, extracted fields are usually text even when numeric
+    fn infer_text(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        
+        // Leading zeros mark identifiers such as postal codes, not numbers
+        let numeric = !(text.len() > 1 && text.starts_with('0') && !text.starts_with("0."));
+        
+        if numeric && text.parse::<i64>().is_ok() {
+            Some(Self::Int)
+        } else if numeric && text.parse::<f64>().map_or(false, f64::is_finite) {
+            Some(Self::Float)
+        } else if text == "true" || text == "false" {
+            Some(Self::Bool)
+        } else if DateTime::parse_from_rfc3339(text).is_ok() {
+            Some(Self::Datetime)
+        } else {
+            Some(Self::String)
+        }
+    }
+    
+    /// Common type of two columns, anything but int/float mixes falls back to string
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Int, Self::Float) | (Self::Float, Self::Int) => Self::Float,
+            _ => Self::String,
+        }
+    }
+    
+    /// Type name used in export schemas
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Bool => "bool",
+            Self::Datetime => "datetime",
+            Self::String => "string",
+        }
+    }
+    
+    /// Arrow type of the column in Parquet exports
+    #[cfg(feature = "parquet")]
+    fn arrow_type(&self) -> DataType {
+        match self {
+            Self::Int => DataType::Int64,
+            Self::Float => DataType::Float64,
+            Self::Bool => DataType::Boolean,
+            Self::Datetime => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            Self::String => DataType::Utf8,
+        }
+    }
+    
+    /// Build an Arrow column, values that don't convert become nulls
+    #[cfg(feature = "parquet")]
+    fn arrow_array<'a>(&self, values: impl Iterator<Item = Option<&'a Value>>) -> ArrayRef {
+        match self {
+            Self::Int => Arc::new(values.map(|value| value.and_then(int_value)).collect::<Int64Array>()),
+            Self::Float => Arc::new(values.map(|value| value.and_then(float_value)).collect::<Float64Array>()),
+            Self::Bool => Arc::new(values.map(|value| value.and_then(bool_value)).collect::<BooleanArray>()),
+            Self::Datetime => Arc::new(
+                values.map(|value| value.and_then(datetime_value))
+                    .collect::<TimestampMicrosecondArray>()
+                    .with_timezone("UTC")
+            ),
+            Self::String => Arc::new(values.map(|value| value.map(text_value)).collect::<StringArray>()),
+        }
+    }
+}
+
+/// Flattened data column of CSV and Parquet exports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+}
+
+/// Flatten extracted data into column name/value pairs. Nested objects use
+/// dot-separated names, everything else is a leaf value.
+fn flatten_data<'a>(value: &'a Value, prefix: &str, fields: &mut BTreeMap<String, &'a Value>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let column = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_data(value, &column, fields);
+            }
+        },
+        _ => {
+            let name = if prefix.is_empty() { "data" } else { prefix };
+            fields.insert(name.to_string(), value);
+        },
+    }
+}
+
+/// Text form of a data value, arrays are kept as JSON
+pub(crate) fn text_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        _ => value.to_string(),
+    }
+}
+
+pub(crate) fn int_value(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn float_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn bool_value(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(flag) => Some(*flag),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn datetime_value(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(text) => DateTime::parse_from_rfc3339(text.trim())
+            .ok()
+            .map(|date| date.timestamp_micros()),
+        _ => None,
+    }
+}
+
+/// Data columns of the rows selected by `filter` with their inferred types
+///
+/// Without `names`, every field found in the rows is a column, in name order.
+/// Columns that only ever hold nulls are typed as strings.
+async fn collect_data_columns(
+    storage: &dyn ProcessedStorage,
+    job_id: &str,
+    filter: &RowFilter,
+    names: Option<&[String]>,
+) -> Result<Vec<DataColumn>> {
+    let mut types: BTreeMap<String, Option<ColumnType>> = BTreeMap::new();
+    
+    let mut rows = storage.stream_rows(job_id, None, filter);
+    while let Some(row) = rows.try_next().await? {
+        let mut fields = BTreeMap::new();
+        flatten_data(&row.data, "", &mut fields);
+        
+        for (name, value) in fields {
+            let column_type = types.entry(name).or_insert(None);
+            if let Some(value_type) = ColumnType::infer(value) {
+                *column_type = Some(column_type.map_or(value_type, |known| known.merge(value_type)));
+            }
+        }
+    }
+    
+    let column = |name: String, column_type: Option<ColumnType>| DataColumn {
+        name,
+        column_type: column_type.unwrap_or(ColumnType::String),
+    };
+    
+    Ok(match names {
+        Some(names) => names.iter()
+            .map(|name| column(name.clone(), types.get(name).copied().flatten()))
+            .collect(),
+        None => types.into_iter()
+            .map(|(name, column_type)| column(name, column_type))
+            .collect(),
+    })
+}
+
+/// Quote a CSV field if it contains separators, quotes or newlines
+pub fn csv_field(value: &str) -> String {
+    if value.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Description of an exported column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub description: String,
+}
+
+impl SchemaField {
+    fn new(name: &str, field_type: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Schema sidecar written next to CSV and Parquet exports as `<output>.schema.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaFile {
+    /// Export format
+    pub format: ExportFormat,
+    
+    /// Columns in file order
+    pub fields: Vec<SchemaField>,
+}
+
+impl SchemaFile {
+    /// Write the schema of an export next to it
+    pub fn save(output_path: &Path, format: ExportFormat, data_columns: &[DataColumn]) -> Result<PathBuf> {
+        let schema_path = sibling_path(output_path, "schema.json");
+        let schema = Self {
+            format,
+            fields: format.schema(data_columns),
+        };
+        
+        let contents = serde_json::to_string_pretty(&schema)
+            .context("Failed to serialize export schema")?;
+        fs::write(&schema_path, contents)
+            .context(format!("Failed to write export schema: {}", schema_path.display()))?;
+        
+        Ok(schema_path)
+    }
+}
+
+/// Write the URLs a job skipped next to an export as `<output>.skips.csv`
+pub fn save_skipped_urls(output_path: &Path, skipped: &[(String, String)]) -> Result<PathBuf> {
+    let skips_path = sibling_path(output_path, "skips.csv");
+    
+    let mut contents = String::from("url,reason\n");
+    for (url, reason) in skipped {
+        contents.push_str(&format!("{},{}\n", csv_field(url), csv_field(reason)));
+    }
+    fs::write(&skips_path, contents)
+        .context(format!("Failed to write skipped URLs: {}", skips_path.display()))?;
+    
+    Ok(skips_path)
+}
+
+/// Integrity manifest written next to each export as `<output>.manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// Name of the exported file, relative to the manifest
+    pub file_name: String,
+    
+    /// Export format
+    pub format: ExportFormat,
+    
+    /// Number of records in the export
+    pub records: usize,
+    
+    /// Size of the export in bytes
+    pub size: u64,
+    
+    /// Hex-encoded SHA-256 of the export
+    pub sha256: String,
+    
+    /// Columns of each record
+    pub schema: Vec<SchemaField>,
+    
+    /// Filter the exported rows were selected with, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    
+    /// Compression of the exported file, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<ExportCompression>,
+    
+    /// Status of the job at export time
+    pub job: JobStatus,
+    
+    /// URLs the job didn't crawl per reason, when it recorded them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub skips: BTreeMap<String, u64>,
+    
+    /// When the export finished
+    pub exported_at: DateTime<Utc>,
+}
+
+impl ExportManifest {
+    /// Build the manifest for a finished export
+    pub fn create(
+        output_path: &Path,
+        format: ExportFormat,
+        records: usize,
+        data_columns: &[DataColumn],
+        filter: &RowFilter,
+        compression: Option<ExportCompression>,
+        job: JobStatus,
+    ) -> Result<Self> {
+        let (sha256, size) = file_digest(output_path)?;
+        
+        let file_name = output_path.file_name()
+            .context(format!("Export path has no file name: {}", output_path.display()))?
+            .to_string_lossy()
+            .to_string();
+        
+        Ok(Self {
+            file_name,
+            format,
+            records,
+            size,
+            sha256,
+            schema: format.schema(data_columns),
+            filter: filter_description(filter),
+            compression,
+            job,
+            skips: BTreeMap::new(),
+            exported_at: Utc::now(),
+        })
+    }
+    
+    /// Path of the manifest for an export
+    pub fn path_for(output_path: &Path) -> PathBuf {
+        sibling_path(output_path, "manifest.json")
+    }
+    
+    /// Write the manifest next to the export
+    pub fn save(&self, output_path: &Path) -> Result<PathBuf> {
+        let manifest_path = Self::path_for(output_path);
+        
+        let contents = serde_json::to_string_pretty(self)
+            .context("Failed to serialize export manifest")?;
+        fs::write(&manifest_path, contents)
+            .context(format!("Failed to write export manifest: {}", manifest_path.display()))?;
+        
+        Ok(manifest_path)
+    }
+    
+    /// Load the manifest of an export
+    pub fn load(output_path: &Path) -> Result<Self> {
+        let manifest_path = Self::path_for(output_path);
+        
+        let contents = fs::read_to_string(&manifest_path)
+            .context(format!("Failed to read export manifest: {}", manifest_path.display()))?;
+        
+        serde_json::from_str(&contents)
+            .context(format!("Failed to parse export manifest: {}", manifest_path.display()))
+    }
+    
+    /// Check an export against the manifest, returning every mismatch found
+    pub fn verify(&self, output_path: &Path) -> Result<Vec<String>> {
+        let mut problems = Vec::new();
+        
+        let (sha256, size) = file_digest(output_path)?;
+        if size != self.size {
+            problems.push(format!("size is {} bytes, manifest says {}", size, self.size));
+        }
+        if sha256 != self.sha256 {
+            problems.push(format!("SHA-256 is {}, manifest says {}", sha256, self.sha256));
+        }
+        
+        // Only worth counting when the bytes are intact, a corrupt file may not even parse
+        if problems.is_empty() {
+            let records = self.format.count_records(output_path, self.compression)?;
+            if records != self.records {
+                problems.push(format!("contains {} records, manifest says {}", records, self.records));
+            }
+        }
+        
+        Ok(problems)
+    }
+}
+
+/// Description of a filter for manifests and checkpoints, none when every row is selected
+fn filter_description(filter: &RowFilter) -> Option<String> {
+    if filter.is_empty() {
+        None
+    } else {
+        Some(filter.to_string())
+    }
+}
+
+/// Compute the SHA-256 and size of a file
+fn file_digest(path: &Path) -> Result<(String, u64)> {
+    let mut file = File::open(path)
+        .context(format!("Failed to open export: {}", path.display()))?;
+    
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)
+        .context(format!("Failed to read export: {}", path.display()))?;
+    
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+/// Checkpoint of an export in progress, saved next to the partial output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExportProgress {
+    job_id: String,
+    format: ExportFormat,
+    
+    /// URL of the last row written, rows are exported in URL order
+    last_url: Option<String>,
+    
+    /// Number of rows written
+    records: usize,
+    
+    /// Length of the partial file at the checkpoint
+    bytes_written: u64,
+    
+    /// Data columns of a CSV export, the header can't change when resuming
+    #[serde(default)]
+    data_columns: Vec<DataColumn>,
+    
+    /// Row filter of the export, a resumed export must select the same rows
+    #[serde(default)]
+    filter: Option<String>,
+    
+    /// Compression of the partial file
+    #[serde(default)]
+    compression: Option<ExportCompression>,
+}
+
+/// Path of a file kept next to the export output
+pub(crate) fn sibling_path(output_path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = output_path.file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_default();
+    file_name.push(format!(".{}", suffix));
+    output_path.with_file_name(file_name)
+}
+
+/// Output file for one export format
+#[derive(Debug, Clone)]
+pub struct ExportTarget {
+    pub format: ExportFormat,
+    pub output_path: PathBuf,
+}
+
+impl ExportTarget {
+    /// Targets for exporting a job in each format
+    ///
+    /// With a single format the output path is used as is, with several it
+    /// only gives the stem. Without an output path files are named after the job.
+    pub fn for_formats(
+        job_id: &str,
+        formats: &[ExportFormat],
+        output: Option<&str>,
+        compression: Option<ExportCompression>,
+    ) -> Vec<Self> {
+        let extension = |format: ExportFormat| match compression {
+            Some(compression) => format!("{}.{}", format.extension(), compression.extension()),
+            None => format.extension().to_string(),
+        };
+        
+        formats.iter()
+            .map(|&format| {
+                let output_path = match output {
+                    Some(path) if formats.len() == 1 => PathBuf::from(path),
+                    Some(path) => PathBuf::from(path).with_extension(extension(format)),
+                    None => PathBuf::from(format!("{}.{}", job_id, extension(format))),
+                };
+                Self { format, output_path }
+            })
+            .collect()
+    }
+    
+    /// Whether the target is written to stdout rather than a file
+    pub fn to_stdout(&self) -> bool {
+        self.output_path.as_os_str() == "-"
+    }
+}
+
+/// Options shared by all targets of an export
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Continue an interrupted export from its last checkpoint
+    pub resume: bool,
+    
+    /// Data columns for CSV and Parquet exports, defaults to every field found in the job
+    pub data_columns: Option<Vec<String>>,
+    
+    /// Rows to export, applied by the storage query
+    pub filter: RowFilter,
+    
+    /// Compression of text and WARC exports, Parquet is compressed internally
+    pub compression: Option<ExportCompression>,
+    
+    /// Told about the rows exported from processed storage
+    pub progress: Option<Arc<dyn RowProgress>>,
+    
+    /// Threads serializing rows, 0 uses one per CPU
+    pub threads: usize,
+}
+
+/// Outcome of an export
+#[derive(Debug, Clone, Default)]
+pub struct ExportSummary {
+    /// Number of rows written to each target
+    pub records: usize,
+    
+    /// Data columns of CSV and Parquet targets
+    pub data_columns: Vec<DataColumn>,
+}
+
+/// Writer behind an export target
+enum TargetWriter {
+    Text(BufWriter<ExportWriter>),
+    #[cfg(feature = "parquet")]
+    Parquet(ArrowWriter<Destination>),
+}
+
+/// Rows of a chunk serialized for one target
+enum EncodedRows {
+    /// Text rows, a complete member when the export is compressed
+    Text(Vec<u8>),
+    #[cfg(feature = "parquet")]
+    Parquet(RecordBatch),
+}
+
+/// Chunk of rows serialized for every target, in the order of the targets
+struct EncodedChunk {
+    rows: usize,
+    last_url: Option<String>,
+    
+    /// Number of rows serialized for each target and their output
+    targets: Vec<(usize, EncodedRows)>,
+}
+
+/// What a serializer thread needs to know about a target
+#[derive(Debug, Clone)]
+struct TargetEncoding {
+    format: ExportFormat,
+    data_columns: Vec<DataColumn>,
+    compression: Option<ExportCompression>,
+    
+    /// Rows the target held when the export started
+    records: usize,
+}
+
+impl TargetEncoding {
+    /// Serialize the rows of a chunk starting at `position` that the target doesn't have yet
+    fn encode(&self, rows: &[PageData], position: usize) -> Result<(usize, EncodedRows)> {
+        // Targets that got further before an interruption skip rows they already have
+        let skip = self.records.saturating_sub(position).min(rows.len());
+        let rows = &rows[skip..];
+        
+        #[cfg(feature = "parquet")]
+        if self.format == ExportFormat::Parquet {
+            let batch = parquet_batch(parquet_schema(&self.data_columns), rows, &self.data_columns)?;
+            return Ok((rows.len(), EncodedRows::Parquet(batch)));
+        }
+        
+        let mut text = Vec::new();
+        for (offset, row) in rows.iter().enumerate() {
+            self.format.write_row(&mut text, row, position + skip + offset, &self.data_columns)
+                .context("Failed to write export row")?;
+        }
+        
+        let output = match self.compression {
+            Some(compression) => compress_member(&text, compression)?,
+            None => text,
+        };
+        
+        Ok((rows.len(), EncodedRows::Text(output)))
+    }
+}
+
+/// Compress data as one complete gzip member or zstd frame, empty data stays empty
+fn compress_member(data: &[u8], compression: ExportCompression) -> Result<Vec<u8>> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    match compression {
+        ExportCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)
+                .context("Failed to compress export rows")?;
+            encoder.finish()
+                .context("Failed to finish gzip stream")
+        },
+        ExportCompression::Zstd => zstd::encode_all(data, 0)
+            .context("Failed to compress export rows"),
+    }
+}
+
+/// Serialize a chunk of rows starting at `position` for every target
+fn encode_chunk(encodings: &[TargetEncoding], rows: Vec<PageData>, position: usize) -> Result<EncodedChunk> {
+    let targets = encodings.iter()
+        .map(|encoding| encoding.encode(&rows, position))
+        .collect::<Result<Vec<_>>>()?;
+    
+    Ok(EncodedChunk {
+        rows: rows.len(),
+        last_url: rows.last().map(|row| row.url.clone()),
+        targets,
+    })
+}
+
+/// Export target being written
+struct OpenTarget {
+    target: ExportTarget,
+    partial_path: PathBuf,
+    progress_path: PathBuf,
+    writer: TargetWriter,
+    progress: ExportProgress,
+}
+
+impl OpenTarget {
+    /// Open a target, continuing from its last checkpoint when resuming
+    fn open(
+        target: ExportTarget,
+        job_id: &str,
+        resume: bool,
+        data_columns: &[DataColumn],
+        filter: Option<String>,
+        compression: Option<ExportCompression>,
+    ) -> Result<Self> {
+        let format = target.format;
+        if compression.is_some() && format == ExportFormat::Parquet {
+            anyhow::bail!("Parquet exports are compressed internally and can't be compressed again");
+        }
+        #[cfg(not(feature = "parquet"))]
+        if format == ExportFormat::Parquet {
+            anyhow::bail!(PARQUET_DISABLED);
+        }
+        let to_stdout = target.to_stdout();
+        if to_stdout && resume {
+            anyhow::bail!("Exports to stdout can't be resumed");
+        }
+        let partial_path = sibling_path(&target.output_path, "partial");
+        let progress_path = sibling_path(&target.output_path, "progress");
+        
+        let checkpoint = if resume && format.resumable() {
+            load_progress(&progress_path, job_id, format)?
+        } else {
+            None
+        };
+        
+        let (writer, progress) = match checkpoint {
+            Some(progress) => {
+                if progress.data_columns != data_columns {
+                    anyhow::bail!(
+                        "Data columns changed since the export was interrupted, export again without --resume"
+                    );
+                }
+                if progress.filter != filter {
+                    anyhow::bail!(
+                        "Filters changed since the export was interrupted, export again without --resume"
+                    );
+                }
+                if progress.compression != compression {
+                    anyhow::bail!(
+                        "Compression changed since the export was interrupted, export again without --resume"
+                    );
+                }
+                
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .open(&partial_path)
+                    .context(format!("Failed to open partial export: {}", partial_path.display()))?;
+                
+                // Drop anything written after the last checkpoint, compressed
+                // output continues with a new member
+                file.set_len(progress.bytes_written)
+                    .context("Failed to truncate partial export")?;
+                file.seek(SeekFrom::End(0))?;
+                
+                info!("Resuming {} export of job {} after {} records", format.extension(), job_id, progress.records);
+                
+                let writer = ExportWriter::new(Destination::File(file), compression)?;
+                (TargetWriter::Text(BufWriter::new(writer)), progress)
+            },
+            None => {
+                let destination = if to_stdout {
+                    Destination::Stdout(io::stdout())
+                } else {
+                    if partial_path.exists() && !resume {
+                        warn!("Discarding interrupted export {}, use --resume to continue it", partial_path.display());
+                    }
+                    
+                    let file = File::create(&partial_path)
+                        .context(format!("Failed to create output file: {}", partial_path.display()))?;
+                    Destination::File(file)
+                };
+                
+                let writer = match format {
+                    #[cfg(feature = "parquet")]
+                    ExportFormat::Parquet => {
+                        let properties = WriterProperties::builder()
+                            .set_compression(Compression::SNAPPY)
+                            .set_max_row_group_size(PARQUET_ROW_GROUP_SIZE)
+                            .build();
+                        let writer = ArrowWriter::try_new(destination, parquet_schema(data_columns), Some(properties))
+                            .context("Failed to create Parquet writer")?;
+                        TargetWriter::Parquet(writer)
+                    },
+                    _ => {
+                        let mut writer = BufWriter::new(ExportWriter::new(destination, compression)?);
+                        format.write_header(&mut writer, data_columns)
+                            .context("Failed to write export header")?;
+                        TargetWriter::Text(writer)
+                    },
+                };
+                
+                let progress = ExportProgress {
+                    job_id: job_id.to_string(),
+                    format,
+                    last_url: None,
+                    records: 0,
+                    bytes_written: 0,
+                    data_columns: data_columns.to_vec(),
+                    filter,
+                    compression,
+                };
+                
+                (writer, progress)
+            },
+        };
+        
+        let mut open = Self {
+            target,
+            partial_path,
+            progress_path,
+            writer,
+            progress,
+        };
+        open.checkpoint()?;
+        
+        Ok(open)
+    }
+    
+    /// How serializer threads should encode rows for the target
+    fn encoding(&self) -> TargetEncoding {
+        TargetEncoding {
+            format: self.target.format,
+            data_columns: self.progress.data_columns.clone(),
+            compression: self.progress.compression,
+            records: self.progress.records,
+        }
+    }
+    
+    /// Append rows serialized for the target, `last_url` is the URL of the last of them
+    fn write_rows(&mut self, rows: usize, encoded: EncodedRows, last_url: Option<&str>) -> Result<()> {
+        if rows == 0 {
+            return Ok(());
+        }
+        
+        match (&mut self.writer, encoded) {
+            (TargetWriter::Text(writer), EncodedRows::Text(output)) => {
+                writer.flush()
+                    .context("Failed to flush export")?;
+                writer.get_mut().write_member(&output)?;
+            },
+            #[cfg(feature = "parquet")]
+            (TargetWriter::Parquet(writer), EncodedRows::Parquet(batch)) => {
+                writer.write(&batch)
+                    .context("Failed to write Parquet rows")?;
+            },
+            #[cfg(feature = "parquet")]
+            _ => anyhow::bail!("Export rows were serialized for another format"),
+        }
+        
+        self.progress.records += rows;
+        self.progress.last_url = last_url.map(str::to_string);
+        
+        Ok(())
+    }
+    
+    /// Flush the partial export to disk and record how far it got
+    fn checkpoint(&mut self) -> Result<()> {
+        // Parquet can't be appended to, there is nothing to resume from
+        let writer = match &mut self.writer {
+            TargetWriter::Text(writer) => writer,
+            #[cfg(feature = "parquet")]
+            TargetWriter::Parquet(_) => return Ok(()),
+        };
+        
+        writer.flush()
+            .context("Failed to flush export")?;
+        
+        // Output to stdout is passed on as it comes, there is nothing to resume
+        if self.target.to_stdout() {
+            return Ok(());
+        }
+        
+        writer.get_mut().end_member()?;
+        let file = writer.get_mut().file()?;
+        file.sync_data()
+            .context("Failed to sync export")?;
+        self.progress.bytes_written = file.stream_position()?;
+        
+        // Replace the progress file atomically so a checkpoint is never half written
+        let temp_path = sibling_path(&self.progress_path, "tmp");
+        let contents = serde_json::to_vec(&self.progress)
+            .context("Failed to serialize export progress")?;
+        fs::write(&temp_path, contents)
+            .context(format!("Failed to write export progress: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &self.progress_path)
+            .context(format!("Failed to write export progress: {}", self.progress_path.display()))?;
+        
+        Ok(())
+    }
+    
+    /// Complete the file and move it into place
+    fn finish(self) -> Result<()> {
+        let destination = match self.writer {
+            TargetWriter::Text(mut writer) => {
+                self.target.format.write_footer(&mut writer, self.progress.records)
+                    .context("Failed to write export footer")?;
+                writer.into_inner()
+                    .map_err(|e| e.into_error())
+                    .context("Failed to flush export")?
+                    .finish()?
+            },
+            #[cfg(feature = "parquet")]
+            TargetWriter::Parquet(writer) => {
+                writer.into_inner()
+                    .context("Failed to finish Parquet file")?
+            },
+        };
+        
+        let file = match destination {
+            Destination::File(file) => file,
+            Destination::Stdout(mut out) => {
+                out.flush()
+                    .context("Failed to flush export")?;
+                debug!("Exported {} records to stdout", self.progress.records);
+                return Ok(());
+            },
+        };
+        file.sync_all()
+            .context("Failed to sync export")?;
+        drop(file);
+        
+        // Publish the finished export in one step
+        let output_path = &self.target.output_path;
+        fs::rename(&self.partial_path, output_path)
+            .context(format!("Failed to move export into place: {}", output_path.display()))?;
+        if self.progress_path.exists() {
+            if let Err(e) = fs::remove_file(&self.progress_path) {
+                warn!("Failed to remove export progress file {}: {}", self.progress_path.display(), e);
+            }
+        }
+        
+        debug!(
+            "Exported {} records to {} file: {}",
+            self.progress.records,
+            self.target.format.extension(),
+            output_path.display()
+        );
+        
+        Ok(())
+    }
+}
+
+/// Export a job's processed data to one file per target
+///
+/// The rows selected by the filter are streamed once from a storage cursor and written to all
+/// targets in chunks. Chunks are serialized, and compressed, by several
+/// threads at once while they are written in order, with a bounded number of
+/// chunks in flight. Each target is written to `<output>.partial`, with a checkpoint in
+/// `<output>.progress` after each chunk. The output path only appears once the
+/// export is complete, so an interrupted export never leaves a truncated file
+/// behind. With `resume`, an interrupted export continues from its last
+/// checkpoint instead of starting over. A target with the output path `-` is
+/// streamed to stdout instead.
+pub async fn export_job(
+    storage: &dyn ProcessedStorage,
+    job_id: &str,
+    targets: &[ExportTarget],
+    options: &ExportOptions,
+) -> Result<ExportSummary> {
+    // Tabular formats need their columns and types up front
+    let data_columns = if targets.iter().any(|target| target.format.has_data_columns()) {
+        collect_data_columns(storage, job_id, &options.filter, options.data_columns.as_deref()).await?
+    } else {
+        Vec::new()
+    };
+    
+    let filter = filter_description(&options.filter);
+    let mut open_targets = Vec::with_capacity(targets.len());
+    for target in targets {
+        let columns: &[DataColumn] = if target.format.has_data_columns() { &data_columns } else { &[] };
+        open_targets.push(OpenTarget::open(
+            target.clone(),
+            job_id,
+            options.resume,
+            columns,
+            filter.clone(),
+            options.compression,
+        )?);
+    }
+    
+    // Read from wherever the target furthest behind stopped
+    let behind = open_targets.iter()
+        .min_by_key(|target| target.progress.records)
+        .context("No export targets given")?;
+    let mut position = behind.progress.records;
+    let start_url = behind.progress.last_url.clone();
+    
+    if let Some(progress) = &options.progress {
+        if let Some(total) = storage.count_rows(job_id, &options.filter).await? {
+            progress.set_total(total as u64);
+        }
+        progress.advance(position as u64);
+    }
+    
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+    let encodings: Arc<Vec<TargetEncoding>> = Arc::new(open_targets.iter().map(OpenTarget::encoding).collect());
+    
+    // Rows come from a single cursor and are serialized on blocking threads,
+    // at most one chunk per thread is held in memory at a time
+    let mut chunk_position = position;
+    let mut chunks = storage.stream_rows(job_id, start_url.as_deref(), &options.filter)
+        .try_chunks(EXPORT_CHUNK_SIZE)
+        .map_err(|e| e.1)
+        .map_ok(|rows| {
+            let start = chunk_position;
+            chunk_position += rows.len();
+            let encodings = encodings.clone();
+            async move {
+                tokio::task::spawn_blocking(move || encode_chunk(&encodings, rows, start))
+                    .await
+                    .context("Export serializer failed")?
+            }
+        })
+        .try_buffered(threads);
+    
+    while let Some(chunk) = chunks.try_next().await? {
+        for (target, (rows, encoded)) in open_targets.iter_mut().zip(chunk.targets) {
+            target.write_rows(rows, encoded, chunk.last_url.as_deref())?;
+        }
+        
+        position += chunk.rows;
+        
+        for target in &mut open_targets {
+            target.checkpoint()?;
+        }
+        if let Some(progress) = &options.progress {
+            progress.advance(chunk.rows as u64);
+        }
+        
+        debug!("Exported {} records of job {}", position, job_id);
+    }
+    
+    for target in open_targets {
+        target.finish()?;
+    }
+    if let Some(progress) = &options.progress {
+        progress.finish();
+    }
+    
+    Ok(ExportSummary {
+        records: position,
+        data_columns,
+    })
+}
+
+/// Error of Parquet exports in builds without them
+#[cfg(not(feature = "parquet"))]
+const PARQUET_DISABLED: &str = "Parquet exports need a build with the parquet feature";
+
+/// Number of rows of a Parquet export, from its footer
+#[cfg(feature = "parquet")]
+fn count_parquet_rows(path: &Path) -> Result<usize> {
+    let file = File::open(path)
+        .context(format!("Failed to open export: {}", path.display()))?;
+    let reader = SerializedFileReader::new(file)
+        .context("Failed to read Parquet export")?;
+    
+    Ok(reader.metadata().file_metadata().num_rows() as usize)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn count_parquet_rows(_path: &Path) -> Result<usize> {
+    anyhow::bail!(PARQUET_DISABLED)
+}
+
+/// Arrow schema of Parquet exports, data columns are nullable
+#[cfg(feature = "parquet")]
+fn parquet_schema(data_columns: &[DataColumn]) -> SchemaRef {
+    let timestamp = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+    
+    let mut fields = vec![
+        Field::new("job_id", DataType::Utf8, false),
+        Field::new("url", DataType::Utf8, false),
+        Field::new("created_at", timestamp.clone(), false),
+        Field::new("updated_at", timestamp, false),
+    ];
+    fields.extend(data_columns.iter().map(|column| {
+        Field::new(&column.name, column.column_type.arrow_type(), true)
+    }));
+    
+    Arc::new(Schema::new(fields))
+}
+
+/// Convert rows to an Arrow record batch for Parquet
+#[cfg(feature = "parquet")]
+fn parquet_batch(schema: SchemaRef, rows: &[PageData], data_columns: &[DataColumn]) -> Result<RecordBatch> {
+    let flattened: Vec<BTreeMap<String, &Value>> = rows.iter()
+        .map(|row| {
+            let mut fields = BTreeMap::new();
+            flatten_data(&row.data, "", &mut fields);
+            fields
+        })
+        .collect();
+    
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|row| row.job_id.as_str()))),
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|row| row.url.as_str()))),
+        Arc::new(TimestampMicrosecondArray::from_iter_values(
+            rows.iter().map(|row| row.created_at.timestamp_micros())
+        ).with_timezone("UTC")),
+        Arc::new(TimestampMicrosecondArray::from_iter_values(
+            rows.iter().map(|row| row.updated_at.timestamp_micros())
+        ).with_timezone("UTC")),
+    ];
+    for column in data_columns {
+        let values = flattened.iter().map(|fields| fields.get(&column.name).copied());
+        columns.push(column.column_type.arrow_array(values));
+    }
+    
+    RecordBatch::try_new(schema, columns)
+        .context("Failed to build Parquet record batch")
+}
+
+/// Load the checkpoint of an interrupted export, if there is one
+fn load_progress(progress_path: &Path, job_id: &str, format: ExportFormat) -> Result<Option<ExportProgress>> {
+    if !progress_path.exists() {
+        warn!("No interrupted {} export found, starting over", format.extension());
+        return Ok(None);
+    }
+    
+    let contents = fs::read_to_string(progress_path)
+        .context(format!("Failed to read export progress: {}", progress_path.display()))?;
+    let progress: ExportProgress = serde_json::from_str(&contents)
+        .context(format!("Failed to parse export progress: {}", progress_path.display()))?;
+    
+    if progress.job_id != job_id || progress.format != format {
+        anyhow::bail!(
+            "Interrupted export at this path is for job {} as {}, not job {} as {}",
+            progress.job_id, progress.format.extension(), job_id, format.extension()
+        );
+    }
+    
+    Ok(Some(progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "parquet")]
+    use arrow::array::Array;
+    use chrono::Utc;
+    use serde_json::json;
+    
+    fn row(url: &str) -> PageData {
+        PageData {
+            job_id: "job".to_string(),
+            url: url.to_string(),
+            data: json!({ "title": "Hello", "tags": ["a", "b"] }),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+    
+    fn render(format: ExportFormat, rows: &[PageData]) -> String {
+        let mut out = Vec::new();
+        let columns = vec![
+            DataColumn { name: "tags".to_string(), column_type: ColumnType::String },
+            DataColumn { name: "title".to_string(), column_type: ColumnType::String },
+        ];
+        format.write_header(&mut out, &columns).unwrap();
+        for (index, row) in rows.iter().enumerate() {
+            format.write_row(&mut out, row, index, &columns).unwrap();
+        }
+        format.write_footer(&mut out, rows.len()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    
+    #[test]
+    fn test_json_matches_pretty_array() {
+        let rows = vec![row("https://example.com/a"), row("https://example.com/b")];
+        
+        assert_eq!(render(ExportFormat::Json, &rows), serde_json::to_string_pretty(&rows).unwrap());
+        assert_eq!(render(ExportFormat::Json, &[]), serde_json::to_string_pretty(&Vec::<PageData>::new()).unwrap());
+    }
+    
+    #[test]
+    fn test_ndjson_one_record_per_line() {
+        let rows = vec![row("https://example.com/a"), row("https://example.com/b")];
+        let output = render(ExportFormat::Ndjson, &rows);
+        
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, row) in lines.iter().zip(&rows) {
+            let parsed: PageData = serde_json::from_str(line).unwrap();
+            assert_eq!(parsed.url, row.url);
+        }
+    }
+    
+    #[test]
+    fn test_csv_quoting() {
+        let output = render(ExportFormat::Csv, &[row("https://example.com/?a=1,2")]);
+        
+        assert!(output.starts_with("job_id,url,created_at,updated_at,tags,title\n"));
+        assert!(output.contains("job,\"https://example.com/?a=1,2\","));
+        assert!(output.trim_end().ends_with(",\"[\"\"a\"\",\"\"b\"\"]\",Hello"));
+    }
+    
+    #[test]
+    fn test_flatten_data() {
+        let mut fields = BTreeMap::new();
+        flatten_data(&json!({
+            "title": "Hello",
+            "price": { "amount": 9.5, "currency": "EUR" },
+            "in_stock": true,
+            "sku": null,
+        }), "", &mut fields);
+        
+        assert_eq!(fields.keys().collect::<Vec<_>>(), vec!["in_stock", "price.amount", "price.currency", "sku", "title"]);
+        assert_eq!(text_value(fields["price.amount"]), "9.5");
+        assert_eq!(text_value(fields["in_stock"]), "true");
+        assert_eq!(text_value(fields["sku"]), "");
+    }
+    
+    #[test]
+    fn test_column_type_inference() {
+        let infer = |values: &[Value]| {
+            values.iter()
+                .filter_map(ColumnType::infer)
+                .reduce(ColumnType::merge)
+        };
+        
+        assert_eq!(infer(&[json!(1), json!("42"), Value::Null]), Some(ColumnType::Int));
+        assert_eq!(infer(&[json!(1), json!("9.99")]), Some(ColumnType::Float));
+        assert_eq!(infer(&[json!(true), json!("false")]), Some(ColumnType::Bool));
+        assert_eq!(infer(&[json!("2024-03-01T10:00:00Z")]), Some(ColumnType::Datetime));
+        assert_eq!(infer(&[json!("08001"), json!(12)]), Some(ColumnType::String));
+        assert_eq!(infer(&[json!(1), json!(true)]), Some(ColumnType::String));
+        assert_eq!(infer(&[Value::Null, json!("")]), None);
+    }
+    
+    #[test]
+    fn test_count_records() {
+        let rows = vec![row("https://example.com/a"), row("https://example.com/b\nc"), row("https://example.com/d")];
+        let path = std::env::temp_dir().join(format!("export-count-{}.csv", std::process::id()));
+        
+        for format in [ExportFormat::Json, ExportFormat::Ndjson, ExportFormat::Csv, ExportFormat::Sql] {
+            fs::write(&path, render(format, &rows)).unwrap();
+            assert_eq!(format.count_records(&path, None).unwrap(), 3, "{:?}", format);
+        }
+        
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_compressed_resume() {
+        let path = std::env::temp_dir().join(format!("export-compressed-{}.ndjson", std::process::id()));
+        
+        for compression in [ExportCompression::Gzip, ExportCompression::Zstd] {
+            let file = File::create(&path).unwrap();
+            let mut writer = ExportWriter::new(Destination::File(file), Some(compression)).unwrap();
+            writer.write_all(b"{\"a\":1}\n").unwrap();
+            writer.end_member().unwrap();
+            let checkpoint = writer.file().unwrap().stream_position().unwrap();
+            writer.write_all(b"{\"lost\":true}\n").unwrap();
+            writer.flush().unwrap();
+            drop(writer);
+            
+            // Continue after the checkpoint like a resumed export
+            let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+            file.set_len(checkpoint).unwrap();
+            file.seek(SeekFrom::End(0)).unwrap();
+            let mut writer = ExportWriter::new(Destination::File(file), Some(compression)).unwrap();
+            writer.write_all(b"{\"b\":2}\n").unwrap();
+            writer.finish().unwrap();
+            
+            let mut contents = String::new();
+            open_export(&path, Some(compression)).unwrap().read_to_string(&mut contents).unwrap();
+            assert_eq!(contents, "{\"a\":1}\n{\"b\":2}\n", "{:?}", compression);
+            assert_eq!(ExportFormat::Ndjson.count_records(&path, Some(compression)).unwrap(), 2);
+        }
+        
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_encode_chunks() {
+        let rows = vec![row("https://example.com/a"), row("https://example.com/b"), row("https://example.com/c")];
+        let encoding = |format, compression, records| TargetEncoding {
+            format,
+            data_columns: Vec::new(),
+            compression,
+            records,
+        };
+        let text = |encoded: EncodedRows| match encoded {
+            EncodedRows::Text(output) => output,
+            EncodedRows::Parquet(_) => panic!("expected text rows"),
+        };
+        
+        // Chunks serialized apart join up to the output of a single pass
+        let json = encoding(ExportFormat::Json, None, 0);
+        let mut out = b"[".to_vec();
+        out.extend(text(json.encode(&rows[..2], 0).unwrap().1));
+        out.extend(text(json.encode(&rows[2..], 2).unwrap().1));
+        ExportFormat::Json.write_footer(&mut out, rows.len()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), serde_json::to_string_pretty(&rows).unwrap());
+        
+        // A target that got further skips the rows it already has
+        let (written, _) = encoding(ExportFormat::Ndjson, None, 2).encode(&rows[..2], 0).unwrap();
+        assert_eq!(written, 0);
+        
+        // Compressed chunks are complete members written between the encoder's own
+        let path = std::env::temp_dir().join(format!("export-chunks-{}.ndjson.gz", std::process::id()));
+        let gzip = encoding(ExportFormat::Ndjson, Some(ExportCompression::Gzip), 0);
+        let mut writer = ExportWriter::new(Destination::File(File::create(&path).unwrap()), Some(ExportCompression::Gzip)).unwrap();
+        writer.write_all(render(ExportFormat::Ndjson, &rows[..1]).as_bytes()).unwrap();
+        writer.write_member(&text(gzip.encode(&rows[1..2], 1).unwrap().1)).unwrap();
+        writer.write_member(&text(gzip.encode(&rows[2..], 2).unwrap().1)).unwrap();
+        writer.finish().unwrap();
+        
+        let mut contents = String::new();
+        open_export(&path, Some(ExportCompression::Gzip)).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, render(ExportFormat::Ndjson, &rows));
+        
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_batch() {
+        let mut rows = vec![row("https://example.com/a"), row("https://example.com/b")];
+        rows[0].data["price"] = json!("9.99");
+        let path = std::env::temp_dir().join(format!("export-count-{}.parquet", std::process::id()));
+        
+        let columns = vec![
+            DataColumn { name: "price".to_string(), column_type: ColumnType::Float },
+            DataColumn { name: "title".to_string(), column_type: ColumnType::String },
+        ];
+        let schema = parquet_schema(&columns);
+        let batch = parquet_batch(schema.clone(), &rows, &columns).unwrap();
+        
+        let prices = batch.column(4).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(prices.value(0), 9.99);
+        assert!(prices.is_null(1));
+        
+        let mut writer = ArrowWriter::try_new(File::create(&path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        
+        assert_eq!(ExportFormat::Parquet.count_records(&path, None).unwrap(), 2);
+        
+        fs::remove_file(&path).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,278 @@
+use anyhow::{Result, Context};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufWriter, Write};
+use tracing::debug;
+use url::Url;
+
+use crate::crawler::task::TaskResult;
+use crate::storage::export::{Destination, ExportCompression, ExportFormat, ExportTarget, ExportWriter};
+use crate::storage::processed::RowFilter;
+use crate::storage::raw::{JobStatus, RawStorageBackend};
+
+/// Crawled page in the link graph
+struct GraphNode {
+    url: String,
+    title: String,
+    depth: u32,
+    status_code: u16,
+}
+
+/// Page-to-page link graph of a job
+///
+/// Nodes are the crawled pages and edges the links between them, links to
+/// pages outside the crawl are left out. Repeated links count once.
+pub struct LinkGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl LinkGraph {
+    /// Build the graph from the page results of a job
+    pub fn from_results(results: &[TaskResult]) -> Self {
+        let mut results: Vec<&TaskResult> = results.iter().collect();
+        results.sort_by(|a, b| a.url.cmp(&b.url));
+        
+        let mut index = HashMap::new();
+        let mut nodes = Vec::with_capacity(results.len());
+        for result in &results {
+            let key = node_key(&result.url);
+            if index.contains_key(&key) {
+                continue;
+            }
+            index.insert(key, nodes.len());
+            nodes.push(GraphNode {
+                url: result.url.clone(),
+                title: result.title.clone(),
+                depth: result.depth,
+                status_code: result.status_code,
+            });
+        }
+        
+        let mut seen = HashSet::new();
+        let mut edges = Vec::new();
+        for result in &results {
+            let source = index[&node_key(&result.url)];
+            for link in &result.links {
+                if let Some(&target) = index.get(&node_key(link)) {
+                    if target != source && seen.insert((source, target)) {
+                        edges.push((source, target));
+                    }
+                }
+            }
+        }
+        
+        Self { nodes, edges }
+    }
+    
+    /// Number of pages in the graph
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    
+    /// Write the graph as GraphML, one node or edge per line
+    pub fn write_graphml<W: Write>(&self, out: &mut W, job_id: &str) -> Result<()> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+        writeln!(out, r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#)?;
+        writeln!(out, r#"  <key id="url" for="node" attr.name="url" attr.type="string"/>"#)?;
+        writeln!(out, r#"  <key id="depth" for="node" attr.name="depth" attr.type="int"/>"#)?;
+        writeln!(out, r#"  <key id="status_code" for="node" attr.name="status_code" attr.type="int"/>"#)?;
+        writeln!(out, r#"  <graph id="{}" edgedefault="directed">"#, xml_escape(job_id))?;
+        
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                out,
+                r#"    <node id="n{}"><data key="label">{}</data><data key="url">{}</data><data key="depth">{}</data><data key="status_code">{}</data></node>"#,
+                i,
+                xml_escape(node.label()),
+                xml_escape(&node.url),
+                node.depth,
+                node.status_code
+            )?;
+        }
+        for (i, (source, target)) in self.edges.iter().enumerate() {
+            writeln!(out, r#"    <edge id="e{}" source="n{}" target="n{}"/>"#, i, source, target)?;
+        }
+        
+        writeln!(out, "  </graph>")?;
+        writeln!(out, "</graphml>")?;
+        
+        Ok(())
+    }
+    
+    /// Write the graph in the Graphviz DOT language, one node or edge per line
+    pub fn write_dot<W: Write>(&self, out: &mut W, job_id: &str) -> Result<()> {
+        writeln!(out, "digraph \"{}\" {{", dot_escape(job_id))?;
+        
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                out,
+                "  n{} [label=\"{}\", URL=\"{}\", depth={}, status_code={}];",
+                i,
+                dot_escape(node.label()),
+                dot_escape(&node.url),
+                node.depth,
+                node.status_code
+            )?;
+        }
+        for (source, target) in &self.edges {
+            writeln!(out, "  n{} -> n{};", source, target)?;
+        }
+        
+        writeln!(out, "}}")?;
+        
+        Ok(())
+    }
+}
+
+impl GraphNode {
+    /// Page title, or the URL for untitled pages
+    fn label(&self) -> &str {
+        if self.title.trim().is_empty() {
+            &self.url
+        } else {
+            &self.title
+        }
+    }
+}
+
+/// URL used to match links to pages, without fragment and normalized by the URL parser
+fn node_key(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Escape text for XML content and attributes
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab are not allowed in XML 1.0
+            c if c.is_control() && c != '\t' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape text for a quoted DOT string
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Export the link graph of a job's pages matching `filter`
+///
+/// The whole graph is built in memory from raw storage, then written as
+/// GraphML or DOT. The file is written next to the output and moved into
+/// place when complete, or streamed when the target is stdout. Returns the
+/// number of pages in the graph.
+pub async fn export_graph(
+    raw_storage: &dyn RawStorageBackend,
+    job: &JobStatus,
+    target: &ExportTarget,
+    filter: &RowFilter,
+    compression: Option<ExportCompression>,
+) -> Result<usize> {
+    let mut results = raw_storage.list_page_results(&job.job_id).await?;
+    results.retain(|result| filter.matches(&result.url, result.crawled_at));
+    let graph = LinkGraph::from_results(&results);
+    
+    let destination = Destination::create(target)?;
+    let mut out = BufWriter::new(ExportWriter::new(destination, compression)?);
+    match target.format {
+        ExportFormat::Graphml => graph.write_graphml(&mut out, &job.job_id)?,
+        ExportFormat::Dot => graph.write_dot(&mut out, &job.job_id)?,
+        format => anyhow::bail!("Not a graph format: {}", format.extension()),
+    }
+    
+    out.into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to flush export")?
+        .finish()?
+        .publish(target)?;
+    
+    debug!(
+        "Exported link graph of {} pages and {} links to: {}",
+        graph.node_count(),
+        graph.edges.len(),
+        target.output_path.display()
+    );
+    
+    Ok(graph.node_count())
+}
+
+/// Count the nodes of an exported graph
+pub fn count_nodes<R: BufRead>(reader: R, format: ExportFormat) -> Result<usize> {
+    let mut nodes = 0;
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim_start();
+        let is_node = match format {
+            ExportFormat::Graphml => line.starts_with("<node "),
+            ExportFormat::Dot => line.starts_with('n') && line.ends_with("];"),
+            format => anyhow::bail!("Not a graph format: {}", format.extension()),
+        };
+        if is_node {
+            nodes += 1;
+        }
+    }
+    
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn page(url: &str, title: &str, links: &[&str]) -> TaskResult {
+        TaskResult {
+            title: title.to_string(),
+            links: links.iter().map(|link| link.to_string()).collect(),
+            ..TaskResult::test_page(url)
+        }
+    }
+    
+    #[test]
+    fn test_link_graph() {
+        let results = vec![
+            page("https://example.com", "Home & \"More\"", &[
+                "https://example.com/a",
+                "https://example.com/a#top",
+                "https://example.com/",
+                "https://other.com/",
+            ]),
+            page("https://example.com/a", "", &["https://example.com/"]),
+        ];
+        let graph = LinkGraph::from_results(&results);
+        
+        // The fragment and the self link collapse, the external link is dropped
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 0)]);
+        
+        let mut graphml = Vec::new();
+        graph.write_graphml(&mut graphml, "job").unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert!(graphml.contains(r#"<data key="label">Home &amp; &quot;More&quot;</data>"#));
+        assert!(graphml.contains(r#"<edge id="e1" source="n1" target="n0"/>"#));
+        assert_eq!(count_nodes(graphml.as_bytes(), ExportFormat::Graphml).unwrap(), 2);
+        
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot, "job").unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.contains(r#"n0 [label="Home & \"More\"", URL="https://example.com""#));
+        assert!(dot.contains(r#"n1 [label="https://example.com/a""#));
+        assert_eq!(count_nodes(dot.as_bytes(), ExportFormat::Dot).unwrap(), 2);
+    }
+}
\ No newline at end of file
@@ -1,8 +1,21 @@
+#[cfg(feature = "duckdb")]
+pub mod analytics;
+pub mod export;
+pub mod graph;
+pub mod models;
+pub mod naming;
+#[cfg(feature = "redis")]
 pub mod queue;
 pub mod raw;
 pub mod processed;
+pub mod progress;
+pub mod query;
+pub mod retry;
+pub mod scheduler_state;
+pub mod warc;
 
 // Re-export common types
+#[cfg(feature = "redis")]
 pub use queue::QueueManager;
 pub use raw::RawStorage;
 pub use processed::{ProcessedStorage, ProcessedStorageFactory};
\ No newline at end of file
@@ -0,0 +1,302 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::cli::config::ModelSettings;
+use crate::storage::export::{bool_value, float_value, int_value, text_value};
+
+/// Words Rust reserves, written as raw identifiers when a field is named after one
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do",
+    "final", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Type of a field of a record model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Int,
+    Float,
+    Bool,
+    Datetime,
+    String,
+    /// Any JSON value, kept as it was extracted
+    Json,
+    List(Box<FieldType>),
+}
+
+impl FieldType {
+    /// Parse a type name like `float` or `list<string>`
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if let Some(item) = name.strip_prefix("list<").and_then(|rest| rest.strip_suffix('>')) {
+            return Ok(Self::List(Box::new(Self::parse(item)?)));
+        }
+        
+        match name {
+            "int" => Ok(Self::Int),
+            "float" => Ok(Self::Float),
+            "bool" => Ok(Self::Bool),
+            "datetime" => Ok(Self::Datetime),
+            "string" => Ok(Self::String),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!(
+                "Unsupported field type: {} (expected int, float, bool, datetime, string, json or list<...>)",
+                name
+            ),
+        }
+    }
+    
+    /// Rust type of the field in generated structs
+    fn rust_type(&self) -> String {
+        match self {
+            Self::Int => "i64".to_string(),
+            Self::Float => "f64".to_string(),
+            Self::Bool => "bool".to_string(),
+            Self::Datetime => "chrono::DateTime<chrono::Utc>".to_string(),
+            Self::String => "String".to_string(),
+            Self::Json => "serde_json::Value".to_string(),
+            Self::List(item) => format!("Vec<{}>", item.rust_type()),
+        }
+    }
+    
+    /// Convert an extracted value, None when it doesn't convert
+    ///
+    /// Scraped numbers and flags are usually text, they are parsed. A list of
+    /// values given for a single one, as selectors matching several elements
+    /// extract, reads as its first value.
+    fn convert(&self, value: &Value) -> Option<Value> {
+        if let (Value::Array(values), false) = (value, matches!(self, Self::List(_) | Self::Json)) {
+            return self.convert(values.first()?);
+        }
+        
+        match self {
+            Self::Int => int_value(value).map(Value::from),
+            Self::Float => float_value(value).map(Value::from),
+            Self::Bool => bool_value(value).map(Value::from),
+            Self::Datetime => match value {
+                Value::String(text) => DateTime::parse_from_rfc3339(text.trim())
+                    .ok()
+                    .map(|date| Value::String(date.with_timezone(&Utc).to_rfc3339())),
+                _ => None,
+            },
+            Self::String => Some(Value::String(text_value(value))),
+            Self::Json => Some(value.clone()),
+            Self::List(item) => match value {
+                Value::Array(values) => values.iter().map(|value| item.convert(value)).collect::<Option<Vec<_>>>().map(Value::Array),
+                value => item.convert(value).map(|value| Value::Array(vec![value])),
+            },
+        }
+    }
+}
+
+/// Field of a record model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelField {
+    /// Dot-separated path of the field in the extracted data, like `meta.description`
+    pub path: String,
+    /// Name of the field in records and generated structs
+    pub name: String,
+    pub field_type: FieldType,
+    /// Whether pages may lack the field, a trailing `?` on the type
+    pub optional: bool,
+}
+
+/// Typed record of the extracted data of pages, declared by a profile
+///
+/// Records are built when the data is read back, from the rows of processed
+/// storage, so the raw data stays as it was extracted.
+#[derive(Debug, Clone)]
+pub struct RecordModel {
+    pub name: String,
+    pub fields: Vec<ModelField>,
+}
+
+impl RecordModel {
+    /// Model declared by a profile, checking its types and names
+    pub fn from_settings(settings: &ModelSettings) -> Result<Self> {
+        if !is_identifier(&settings.name) {
+            anyhow::bail!("Model name must be a Rust identifier: {}", settings.name);
+        }
+        if settings.fields.is_empty() {
+            anyhow::bail!("Model {} has no fields", settings.name);
+        }
+        
+        let mut fields: Vec<ModelField> = Vec::with_capacity(settings.fields.len());
+        for (path, type_name) in &settings.fields {
+            let (type_name, optional) = match type_name.trim().strip_suffix('?') {
+                Some(type_name) => (type_name, true),
+                None => (type_name.as_str(), false),
+            };
+            let field = ModelField {
+                path: path.clone(),
+                name: field_name(path),
+                field_type: FieldType::parse(type_name).context(format!("Invalid type of model field {}", path))?,
+                optional,
+            };
+            
+            if field.name.is_empty() {
+                anyhow::bail!("Invalid model field: {}", path);
+            }
+            if let Some(other) = fields.iter().find(|other| other.name == field.name) {
+                anyhow::bail!("Model fields {} and {} both become {}", other.path, path, field.name);
+            }
+            fields.push(field);
+        }
+        
+        Ok(Self { name: settings.name.clone(), fields })
+    }
+    
+    /// Typed JSON object of the extracted data of a page, with one key per
+    /// field, failing on required fields that are missing or don't convert
+    ///
+    /// Optional fields that are missing, empty or don't convert are null.
+    pub fn convert(&self, data: &Value) -> Result<Value> {
+        let mut record = Map::new();
+        for field in &self.fields {
+            let value = field.path.split('.')
+                .try_fold(data, |value, key| value.get(key))
+                .filter(|value| !matches!(value, Value::Null) && value.as_str().map_or(true, |text| !text.trim().is_empty()));
+            
+            let converted = match (value.and_then(|value| field.field_type.convert(value)), value) {
+                (Some(converted), _) => converted,
+                (None, _) if field.optional => Value::Null,
+                (None, None) => anyhow::bail!("Missing field {}", field.path),
+                (None, Some(value)) => anyhow::bail!("Field {} is not a valid {}: {}", field.path, type_name(&field.field_type), value),
+            };
+            record.insert(field.name.clone(), converted);
+        }
+        
+        Ok(Value::Object(record))
+    }
+    
+    /// Rust source of a struct the records deserialize into
+    pub fn rust_source(&self) -> String {
+        let mut source = format!(
+            "// Record model {} of the extracted data, generated by `crawler codegen`\n\n\
+             #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct {} {{\n",
+            self.name, self.name,
+        );
+        for field in &self.fields {
+            let rust_type = field.field_type.rust_type();
+            let rust_type = if field.optional { format!("Option<{}>", rust_type) } else { rust_type };
+            let name = if RUST_KEYWORDS.contains(&field.name.as_str()) {
+                format!("r#{}", field.name)
+            } else {
+                field.name.clone()
+            };
+            
+            source.push_str(&format!("    /// `{}`\n    pub {}: {},\n", field.path, name, rust_type));
+        }
+        source.push_str("}\n");
+        source
+    }
+}
+
+/// Extracted data of a page read as a typed record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypedRecord<T> {
+    pub url: String,
+    pub updated_at: DateTime<Utc>,
+    pub data: T,
+}
+
+/// Snake case name of a field from its path, `meta.og.title` is `meta_og_title`
+fn field_name(path: &str) -> String {
+    let mut name = String::with_capacity(path.len());
+    for (index, c) in path.trim().chars().enumerate() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && index > 0 && !name.ends_with('_') {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    
+    let name = name.trim_end_matches('_').to_string();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RUST_KEYWORDS.contains(&name)
+}
+
+/// Name of a field type as written in profiles
+fn type_name(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Int => "int".to_string(),
+        FieldType::Float => "float".to_string(),
+        FieldType::Bool => "bool".to_string(),
+        FieldType::Datetime => "datetime".to_string(),
+        FieldType::String => "string".to_string(),
+        FieldType::Json => "json".to_string(),
+        FieldType::List(item) => format!("list<{}>", type_name(item)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    
+    #[test]
+    fn test_record_model() {
+        let settings = ModelSettings {
+            name: "Product".to_string(),
+            fields: BTreeMap::from([
+                ("title".to_string(), "string".to_string()),
+                ("price".to_string(), "float".to_string()),
+                ("stock".to_string(), "int?".to_string()),
+                ("tags".to_string(), "list<string>".to_string()),
+                ("meta.og.type".to_string(), "string?".to_string()),
+                ("publishedAt".to_string(), "datetime?".to_string()),
+            ]),
+        };
+        let model = RecordModel::from_settings(&settings).unwrap();
+        
+        let data = json!({
+            "title": ["Blue kettle", "Related kettle"],
+            "price": " 24.90 ",
+            "stock": "",
+            "tags": "kitchen",
+            "meta": {"og": {"type": "product"}},
+            "publishedAt": "2024-07-01T10:00:00+02:00",
+        });
+        assert_eq!(model.convert(&data).unwrap(), json!({
+            "meta_og_type": "product",
+            "price": 24.9,
+            "published_at": "2024-07-01T08:00:00+00:00",
+            "stock": null,
+            "tags": ["kitchen"],
+            "title": "Blue kettle",
+        }));
+        
+        assert!(model.convert(&json!({"title": "Kettle", "tags": []})).is_err());
+        assert!(model.convert(&json!({"title": "Kettle", "price": "cheap", "tags": []})).is_err());
+        
+        let source = model.rust_source();
+        assert!(source.contains("pub struct Product {\n"));
+        assert!(source.contains("    /// `meta.og.type`\n    pub meta_og_type: Option<String>,\n"));
+        assert!(source.contains("    pub published_at: Option<chrono::DateTime<chrono::Utc>>,\n"));
+        assert!(source.contains("    pub tags: Vec<String>,\n"));
+        
+        let fields = |fields: &[(&str, &str)]| ModelSettings {
+            name: "Product".to_string(),
+            fields: fields.iter().map(|(path, field_type)| (path.to_string(), field_type.to_string())).collect(),
+        };
+        assert!(RecordModel::from_settings(&fields(&[("price", "money")])).is_err());
+        assert!(RecordModel::from_settings(&fields(&[("og.title", "string"), ("og_title", "string")])).is_err());
+        assert!(RecordModel::from_settings(&ModelSettings { name: "type".to_string(), ..fields(&[("a", "int")]) }).is_err());
+    }
+}
\ No newline at end of file
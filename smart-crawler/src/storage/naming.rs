@@ -0,0 +1,142 @@
+use sha2::{Digest, Sha256};
+
+/// Length of the job ID hash appended to sanitized identifiers
+const HASH_LEN: usize = 16;
+
+/// MongoDB limit on `<database>.<collection>` namespaces
+const MONGODB_MAX_NAMESPACE: usize = 120;
+
+/// Identifiers a storage backend accepts for the tables, collections or
+/// directories it creates per job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamingRules {
+    /// Longest identifier, in bytes
+    pub max_len: usize,
+    
+    /// Whether dashes can be kept, they are replaced with underscores otherwise
+    pub allow_dash: bool,
+}
+
+impl NamingRules {
+    /// Unquoted PostgreSQL identifiers, longer names are silently truncated
+    pub const POSTGRES: Self = Self { max_len: 63, allow_dash: false };
+    
+    /// SQLite has no practical limit, names are kept as short as in PostgreSQL
+    pub const SQLITE: Self = Self { max_len: 63, allow_dash: false };
+    
+    /// Directory names on common filesystems
+    pub const FILESYSTEM: Self = Self { max_len: 255, allow_dash: true };
+    
+    /// MongoDB collections, the namespace includes the database name
+    pub fn mongodb(database: &str) -> Self {
+        Self {
+            max_len: MONGODB_MAX_NAMESPACE.saturating_sub(database.len() + 1),
+            allow_dash: true,
+        }
+    }
+    
+    /// Whether a name can be used as it is: lowercase letters, digits,
+    /// underscores and, when allowed, dashes, within the length limit
+    pub fn accepts(&self, name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= self.max_len
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || (c == '-' && self.allow_dash))
+    }
+    
+    /// Identifier of a job's table, collection or directory: `<prefix>_<job>_<suffix>`
+    ///
+    /// Job IDs of lowercase letters, digits and dashes, like the generated
+    /// UUIDs, are used as they are so existing data keeps its name. Other IDs
+    /// are reduced to the same characters, cut to fit the length limit and
+    /// followed by a hash of the real ID, which stays in the job_id column
+    /// or field of the stored records.
+    pub fn job_identifier(&self, prefix: &str, job_id: &str, suffix: &str) -> String {
+        let join = |job: &str| {
+            [prefix, job, suffix].iter()
+                .filter(|part| !part.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join("_")
+        };
+        
+        let plain = !job_id.is_empty()
+            && job_id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if plain {
+            let job = if self.allow_dash { job_id.to_string() } else { job_id.replace('-', "_") };
+            let name = join(&job);
+            if name.len() <= self.max_len {
+                return name;
+            }
+        }
+        
+        let hash = format!("{:x}", Sha256::digest(job_id.as_bytes()));
+        let hash = &hash[..HASH_LEN];
+        
+        // Room left for the readable part once the prefix, suffix, hash and separators are in
+        let fixed = join(hash).len() + 1;
+        let room = self.max_len.saturating_sub(fixed);
+        let readable: String = job_id.chars()
+            .map(|c| match c {
+                'a'..='z' | '0'..='9' => c,
+                'A'..='Z' => c.to_ascii_lowercase(),
+                '-' if self.allow_dash => c,
+                _ => '_',
+            })
+            .take(room)
+            .collect();
+        let readable = readable.trim_matches(|c| c == '_' || c == '-');
+        
+        if readable.is_empty() {
+            join(hash)
+        } else {
+            join(&format!("{}_{}", readable, hash))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_job_identifier() {
+        let uuid = "0b6f2d7e-5a7c-4f6e-9a51-3c2d1e0f9b8a";
+        
+        // Generated IDs keep the names they always had
+        assert_eq!(
+            NamingRules::POSTGRES.job_identifier("crawled", uuid, "pages"),
+            "crawled_0b6f2d7e_5a7c_4f6e_9a51_3c2d1e0f9b8a_pages"
+        );
+        assert_eq!(
+            NamingRules::mongodb("crawler").job_identifier("raw", uuid, "pages"),
+            format!("raw_{}_pages", uuid)
+        );
+        assert_eq!(NamingRules::FILESYSTEM.job_identifier("", uuid, ""), uuid);
+        
+        // Other IDs are sanitized and hashed so different IDs don't collide
+        let unusual = NamingRules::POSTGRES.job_identifier("crawled", "Shop \"A\"; DROP", "pages");
+        assert!(unusual.starts_with("crawled_shop__a___drop_"));
+        assert!(unusual.ends_with("_pages"));
+        assert_ne!(unusual, NamingRules::POSTGRES.job_identifier("crawled", "Shop \"B\"; DROP", "pages"));
+        assert_eq!(NamingRules::FILESYSTEM.job_identifier("", "../..", "").len(), HASH_LEN);
+        
+        // Long IDs are cut to the length limit
+        let long = "a".repeat(100);
+        let name = NamingRules::POSTGRES.job_identifier("crawled", &long, "pages");
+        assert_eq!(name.len(), 63);
+        assert!(name.starts_with("crawled_aaa"));
+        assert_ne!(name, NamingRules::POSTGRES.job_identifier("crawled", &"a".repeat(101), "pages"));
+    }
+    
+    #[test]
+    fn test_accepts() {
+        assert!(NamingRules::FILESYSTEM.accepts("shop-details"));
+        assert!(NamingRules::POSTGRES.accepts("crawled_pages"));
+        assert!(!NamingRules::POSTGRES.accepts("shop-details"));
+        assert!(!NamingRules::FILESYSTEM.accepts(""));
+        assert!(!NamingRules::FILESYSTEM.accepts("../general"));
+        assert!(!NamingRules::FILESYSTEM.accepts("sites/general"));
+        assert!(!NamingRules::FILESYSTEM.accepts("General"));
+        assert!(!NamingRules::POSTGRES.accepts(&"a".repeat(64)));
+    }
+}
\ No newline at end of file
@@ -1,18 +1,42 @@
 use anyhow::{Result, Context};
+use async_stream::try_stream;
 use async_trait::async_trait;
-use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
-use sqlx::types::Json;
+use futures::stream::{BoxStream, StreamExt};
+use regex::Regex;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use sqlx::{Column, Executor, Pool, types::Json};
+#[cfg(feature = "postgres")]
+use sqlx::{Postgres, postgres::PgPoolOptions};
+#[cfg(feature = "sqlite")]
+use sqlx::{Row, Sqlite, TypeInfo, ValueRef, sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow}};
 use serde::{Serialize, Deserialize};
 use serde_json; // Add this import
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "sqlite")]
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::fs;
 use std::io::Write;
-use tracing::{debug, error};
-use chrono::{DateTime, Utc};
+use tracing::{debug, error, instrument};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use tokio::sync::Mutex;
+use url::Url;
 
-use crate::cli::config::ProcessedDataSettings;
+use crate::cli::config::{ProcessedDataSettings, VersionRetention};
 use crate::crawler::task::TaskResult;
+use crate::storage::naming::NamingRules;
+use crate::storage::query::{check_query, json_cell, QueryResult, QUERY_TIMEOUT};
+use crate::storage::retry::connect_with_retry;
+
+/// Pool size of the SQL backends when the settings don't give one
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+const SQL_MAX_CONNECTIONS: u32 = 5;
 
 /// Trait for processed data storage
 #[async_trait]
@@ -26,17 +50,45 @@ pub trait ProcessedStorage: Send + Sync {
     /// List all pages for a job
     async fn list_pages(&self, job_id: &str) -> Result<Vec<String>>;
     
-    /// Export job data as JSON
-    async fn export_as_json(&self, job_id: &str, output_path: &Path) -> Result<()>;
-    
-    /// Export job data as CSV
-    async fn export_as_csv(&self, job_id: &str, output_path: &Path) -> Result<()>;
+    /// Stream the page data rows of a job matching `filter` with URLs after `after_url`,
+    /// ordered by URL. Rows are read from a cursor as they are consumed rather than
+    /// loaded up front.
+    fn stream_rows<'a>(
+        &'a self,
+        job_id: &'a str,
+        after_url: Option<&'a str>,
+        filter: &'a RowFilter,
+    ) -> BoxStream<'a, Result<PageData>>;
     
-    /// Export job data as SQL
-    async fn export_as_sql(&self, job_id: &str, output_path: &Path) -> Result<()>;
+    /// Number of page data rows of a job matching `filter`, None when the
+    /// backend can't count them without reading the rows
+    async fn count_rows(&self, _job_id: &str, _filter: &RowFilter) -> Result<Option<usize>> {
+        Ok(None)
+    }
     
     /// Delete a job and all its data
     async fn delete_job(&self, job_id: &str) -> Result<()>;
+    
+    /// Write out pages buffered by backends that insert in batches
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Run a read-only SELECT over the pages of a job, which it reads as the
+    /// `pages` table, returning at most `max_rows` rows
+    async fn query(&self, _job_id: &str, _sql: &str, _max_rows: usize) -> Result<QueryResult> {
+        anyhow::bail!("SQL queries aren't supported by this processed storage backend")
+    }
+    
+    /// Stored versions of a URL across all jobs, newest first, at most `limit`
+    async fn page_history(&self, _url: &str, _limit: usize) -> Result<Vec<PageVersion>> {
+        anyhow::bail!("Page history isn't supported by this processed storage backend")
+    }
+    
+    /// Delete the page versions the retention policy doesn't keep, returning how many
+    async fn prune_versions(&self, _retention: &VersionRetention) -> Result<usize> {
+        Ok(0)
+    }
 }
 
 /// Factory for creating a ProcessedStorage implementation
@@ -46,18 +98,28 @@ impl ProcessedStorageFactory {
     /// Create a new ProcessedStorage instance based on the settings
     pub async fn create(settings: &ProcessedDataSettings) -> Result<Arc<dyn ProcessedStorage>> {
         match settings.storage_type.as_str() {
+            #[cfg(feature = "postgres")]
             "postgresql" => {
                 let storage = PostgresStorage::new(settings).await?;
                 Ok(Arc::new(storage))
             },
+            #[cfg(feature = "sqlite")]
             "sqlite" => {
-                // For future implementation
-                anyhow::bail!("SQLite storage is not yet implemented");
+                let storage = SqliteStorage::new(settings).await?;
+                Ok(Arc::new(storage))
             },
             "filesystem" => {
-                // For future implementation
-                anyhow::bail!("Filesystem storage is not yet implemented");
+                let storage = FilesystemStorage::new(settings)?;
+                Ok(Arc::new(storage))
+            },
+            "clickhouse" => {
+                let storage = ClickHouseStorage::new(settings).await?;
+                Ok(Arc::new(storage))
             },
+            #[cfg(not(feature = "postgres"))]
+            "postgresql" => anyhow::bail!("PostgreSQL storage needs a build with the postgres feature"),
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => anyhow::bail!("SQLite storage needs a build with the sqlite feature"),
             _ => {
                 anyhow::bail!("Unsupported processed data storage type: {}", settings.storage_type);
             }
@@ -70,21 +132,120 @@ impl ProcessedStorageFactory {
     }
 }
 
+/// Error of history reads when versions aren't kept
+const NOT_VERSIONED: &str = "Page history needs `versioned: true` in the processed storage settings";
+
 /// PostgreSQL implementation of ProcessedStorage
+#[cfg(feature = "postgres")]
 pub struct PostgresStorage {
     /// PostgreSQL connection pool
     pool: Pool<Postgres>,
     
+    /// Pool for page reads and exports, the primary pool without a read replica
+    read_pool: Pool<Postgres>,
+    
     /// Schema name
     schema: String,
     
     /// Table prefix
     table_prefix: String,
+    
+    /// Table keeping every version of the pages of all jobs, in versioned mode
+    versions_table: Option<PgTable>,
 }
 
-/// Page data record for database storage
-#[derive(Debug, Serialize, Deserialize)]
-struct PageData {
+/// Page data record as stored and exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageData {
+    pub job_id: String,
+    pub url: String,
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Selection of the rows to read, applied by the storage query where the backend supports it
+#[derive(Debug, Clone, Default)]
+pub struct RowFilter {
+    /// Only pages whose URL matches this regular expression
+    pub include_url: Option<Regex>,
+    
+    /// Only pages updated at or after this time
+    pub since: Option<DateTime<Utc>>,
+    
+    /// Only pages updated before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl RowFilter {
+    /// Build a filter from command line values, timestamps are RFC 3339 or a date at midnight UTC
+    pub fn parse(include_url: Option<&str>, since: Option<&str>, until: Option<&str>) -> Result<Self> {
+        let include_url = include_url
+            .map(|pattern| Regex::new(pattern).context(format!("Invalid URL pattern: {}", pattern)))
+            .transpose()?;
+        let since = since.map(parse_timestamp).transpose()?;
+        let until = until.map(parse_timestamp).transpose()?;
+        
+        if let (Some(since), Some(until)) = (since, until) {
+            if since >= until {
+                anyhow::bail!("Filter start {} is not before its end {}", since.to_rfc3339(), until.to_rfc3339());
+            }
+        }
+        
+        Ok(Self { include_url, since, until })
+    }
+    
+    /// Whether the filter selects every row
+    pub fn is_empty(&self) -> bool {
+        self.include_url.is_none() && self.since.is_none() && self.until.is_none()
+    }
+    
+    /// Check a page against the filter
+    pub fn matches(&self, url: &str, updated_at: DateTime<Utc>) -> bool {
+        self.include_url.as_ref().map_or(true, |pattern| pattern.is_match(url))
+            && self.since.map_or(true, |since| updated_at >= since)
+            && self.until.map_or(true, |until| updated_at < until)
+    }
+}
+
+impl fmt::Display for RowFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(pattern) = &self.include_url {
+            parts.push(format!("url matches /{}/", pattern));
+        }
+        if let Some(since) = self.since {
+            parts.push(format!("since {}", since.to_rfc3339()));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("until {}", until.to_rfc3339()));
+        }
+        
+        if parts.is_empty() {
+            write!(f, "all pages")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a `YYYY-MM-DD` date
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .context(format!("Invalid timestamp, expected RFC 3339 or YYYY-MM-DD: {}", value))?;
+    Ok(date.and_hms_opt(0, 0, 0)
+        .context(format!("Invalid date: {}", value))?
+        .and_utc())
+}
+
+/// Row shape shared by the SQL backends
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+#[derive(sqlx::FromRow)]
+struct PageRow {
     job_id: String,
     url: String,
     data: Json<serde_json::Value>,
@@ -92,36 +253,245 @@ struct PageData {
     updated_at: DateTime<Utc>,
 }
 
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+impl From<PageRow> for PageData {
+    fn from(row: PageRow) -> Self {
+        Self {
+            job_id: row.job_id,
+            url: row.url,
+            data: row.data.0,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Page data as stored by one crawl, kept in versioned mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageVersion {
+    pub job_id: String,
+    pub url: String,
+    pub data: serde_json::Value,
+    pub stored_at: DateTime<Utc>,
+}
+
+/// Version row shape shared by the SQL backends
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+#[derive(sqlx::FromRow)]
+struct VersionRow {
+    id: i64,
+    job_id: String,
+    url: String,
+    data: Json<serde_json::Value>,
+    stored_at: DateTime<Utc>,
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+impl From<VersionRow> for PageVersion {
+    fn from(row: VersionRow) -> Self {
+        Self {
+            job_id: row.job_id,
+            url: row.url,
+            data: row.data.0,
+            stored_at: row.stored_at,
+        }
+    }
+}
+
+/// Which versions of a page the retention policy keeps, for versions ordered oldest first
+fn retained_versions(versions: &[PageVersion], retention: &VersionRetention) -> Vec<bool> {
+    let mut keep = vec![true; versions.len()];
+    
+    // Of consecutive identical versions, the first shows when the data changed
+    if retention.changed_only {
+        for index in 1..versions.len() {
+            keep[index] = versions[index].data != versions[index - 1].data;
+        }
+    }
+    
+    let mut recent = 0;
+    let mut weeks = HashSet::new();
+    for index in (0..versions.len()).rev().filter(|index| keep[*index]) {
+        let week = versions[index].stored_at.iso_week();
+        let week = (week.year(), week.week());
+        
+        let latest = retention.keep_last.map_or(!retention.keep_weekly, |keep_last| recent < keep_last);
+        if latest {
+            recent += 1;
+            weeks.insert(week);
+        } else if !(retention.keep_weekly && weeks.insert(week)) {
+            keep[index] = false;
+        }
+    }
+    
+    keep
+}
+
+/// Name of the table or directory holding the page versions of all jobs
+fn versions_identifier(table_prefix: &str) -> String {
+    format!("{}_page_versions", table_prefix)
+}
+
+/// Quote an identifier for PostgreSQL, doubling embedded quotes
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Statements on a job's pages table. `{table}` is replaced by the quoted,
+/// schema-qualified table name; every value is bound as a parameter.
+#[cfg(feature = "postgres")]
+const PG_CREATE_PAGES: &str = "CREATE TABLE IF NOT EXISTS {table} (
+    job_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    data JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, url)
+)";
+
+#[cfg(feature = "postgres")]
+const PG_UPSERT_PAGE: &str = "INSERT INTO {table} (job_id, url, data, created_at, updated_at)
+    VALUES ($1, $2, $3, NOW(), NOW())
+    ON CONFLICT (job_id, url) DO UPDATE
+    SET data = $3, updated_at = NOW()";
+
+#[cfg(feature = "postgres")]
+const PG_SELECT_PAGE_DATA: &str = "SELECT data FROM {table} WHERE job_id = $1 AND url = $2";
 
+#[cfg(feature = "postgres")]
+const PG_SELECT_URLS: &str = "SELECT url FROM {table} WHERE job_id = $1 ORDER BY url";
 
+// PostgreSQL regular expressions cover the common subset of the Rust syntax
+#[cfg(feature = "postgres")]
+const PG_SELECT_ROWS: &str = "SELECT job_id, url, data, created_at, updated_at
+    FROM {table}
+    WHERE job_id = $1 AND ($2::TEXT IS NULL OR url > $2)
+        AND ($3::TEXT IS NULL OR url ~ $3)
+        AND ($4::TIMESTAMPTZ IS NULL OR updated_at >= $4)
+        AND ($5::TIMESTAMPTZ IS NULL OR updated_at < $5)
+    ORDER BY url";
+
+#[cfg(feature = "postgres")]
+const PG_COUNT_ROWS: &str = "SELECT COUNT(*) FROM {table}
+    WHERE job_id = $1 AND ($2::TEXT IS NULL OR url ~ $2)
+        AND ($3::TIMESTAMPTZ IS NULL OR updated_at >= $3)
+        AND ($4::TIMESTAMPTZ IS NULL OR updated_at < $4)";
+
+#[cfg(feature = "postgres")]
+const PG_DROP_PAGES: &str = "DROP TABLE IF EXISTS {table}";
+
+// User queries read the job's rows as `pages`, `{select}` is what is read from their result
+#[cfg(feature = "postgres")]
+const PG_QUERY: &str = "WITH pages AS (SELECT job_id, url, data, created_at, updated_at FROM {table} WHERE job_id = $1)
+    SELECT {select} FROM ({query}) AS query LIMIT $2";
+
+// Versions of all jobs share a table, `{index}` is replaced by an index name
+#[cfg(feature = "postgres")]
+const PG_CREATE_VERSIONS: &str = "CREATE TABLE IF NOT EXISTS {table} (
+    id BIGSERIAL PRIMARY KEY,
+    job_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    data JSONB NOT NULL,
+    stored_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+)";
+
+#[cfg(feature = "postgres")]
+const PG_CREATE_VERSIONS_INDEX: &str = "CREATE INDEX IF NOT EXISTS {index} ON {table} (url, stored_at)";
+
+#[cfg(feature = "postgres")]
+const PG_INSERT_VERSION: &str = "INSERT INTO {table} (job_id, url, data, stored_at) VALUES ($1, $2, $3, NOW())";
+
+#[cfg(feature = "postgres")]
+const PG_SELECT_HISTORY: &str = "SELECT id, job_id, url, data, stored_at FROM {table}
+    WHERE url = $1
+    ORDER BY stored_at DESC, id DESC
+    LIMIT $2";
+
+#[cfg(feature = "postgres")]
+const PG_DELETE_VERSIONS: &str = "DELETE FROM {table} WHERE job_id = $1";
+
+#[cfg(feature = "postgres")]
+const PG_SELECT_VERSIONED_URLS: &str = "SELECT DISTINCT url FROM {table}";
+
+#[cfg(feature = "postgres")]
+const PG_SELECT_VERSIONS: &str = "SELECT id, job_id, url, data, stored_at FROM {table}
+    WHERE url = $1
+    ORDER BY stored_at, id";
+
+#[cfg(feature = "postgres")]
+const PG_DELETE_VERSION_IDS: &str = "DELETE FROM {table} WHERE id = ANY($1)";
+
+/// Table in a PostgreSQL schema, the pages of a job or the page versions
+#[cfg(feature = "postgres")]
+struct PgTable {
+    schema: String,
+    name: String,
+}
+
+#[cfg(feature = "postgres")]
+impl PgTable {
+    /// Schema-qualified, quoted table name
+    fn qualified(&self) -> String {
+        format!("{}.{}", quote_identifier(&self.schema), quote_identifier(&self.name))
+    }
+    
+    /// Statement text for the table, from one of the `PG_*` templates
+    fn sql(&self, template: &str) -> String {
+        template.replace("{table}", &self.qualified())
+    }
+}
 
+#[cfg(feature = "postgres")]
 impl PostgresStorage {
     /// Create a new PostgreSQL storage instance
     pub async fn new(settings: &ProcessedDataSettings) -> Result<Self> {
-        // Create connection pool
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&settings.connection_string)
-            .await
-            .context(format!("Failed to connect to PostgreSQL: {}", settings.connection_string))?;
+        let pool = Self::connect(settings, &settings.connection_string).await?;
+        let read_pool = match &settings.read_connection_string {
+            Some(connection_string) => {
+                let read_pool = Self::connect(settings, connection_string).await?;
+                debug!("Reading pages from PostgreSQL replica");
+                read_pool
+            }
+            None => pool.clone(),
+        };
         
         let storage = Self {
             pool,
+            read_pool,
             schema: settings.schema_name.clone(),
             table_prefix: settings.table_prefix.clone(),
+            versions_table: settings.versioned.then(|| PgTable {
+                schema: settings.schema_name.clone(),
+                name: versions_identifier(&settings.table_prefix),
+            }),
         };
         
         // Ensure schema exists
         storage.ensure_schema().await?;
+        if let Some(table) = &storage.versions_table {
+            storage.ensure_versions_table(table).await?;
+        }
         
         debug!("Connected to PostgreSQL database");
         
         Ok(storage)
     }
     
+    /// Create a connection pool, broken connections are replaced when next acquired
+    async fn connect(settings: &ProcessedDataSettings, connection_string: &str) -> Result<Pool<Postgres>> {
+        connect_with_retry(&settings.pool, "PostgreSQL", || async move {
+            PgPoolOptions::new()
+                .max_connections(settings.pool.max_connections.unwrap_or(SQL_MAX_CONNECTIONS))
+                .acquire_timeout(Duration::from_secs(settings.pool.acquire_timeout))
+                .connect(connection_string)
+                .await
+                .context(format!("Failed to connect to PostgreSQL: {}", connection_string))
+        }).await
+    }
+    
     /// Ensure the schema exists
     async fn ensure_schema(&self) -> Result<()> {
-        let query = format!("CREATE SCHEMA IF NOT EXISTS {}", self.schema);
+        let query = format!("CREATE SCHEMA IF NOT EXISTS {}", quote_identifier(&self.schema));
         
         sqlx::query(&query)
             .execute(&self.pool)
@@ -134,59 +504,88 @@ impl PostgresStorage {
     }
     
     /// Ensure the pages table exists for a job
-    async fn ensure_pages_table(&self, job_id: &str) -> Result<()> {
-        let table_name = self.get_pages_table_name(job_id);
+    async fn ensure_pages_table(&self, table: &PgTable) -> Result<()> {
+        sqlx::query(&table.sql(PG_CREATE_PAGES))
+            .execute(&self.pool)
+            .await
+            .context(format!("Failed to create pages table: {}", table.name))?;
         
-        let query = format!(
-            "CREATE TABLE IF NOT EXISTS {}.{} (
-                job_id TEXT NOT NULL,
-                url TEXT NOT NULL,
-                data JSONB NOT NULL,
-                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                PRIMARY KEY (job_id, url)
-            )",
-            self.schema, table_name
-        );
+        debug!("Ensured pages table exists: {}", table.name);
         
-        sqlx::query(&query)
+        Ok(())
+    }
+    
+    /// Ensure the page versions table and its URL index exist
+    async fn ensure_versions_table(&self, table: &PgTable) -> Result<()> {
+        sqlx::query(&table.sql(PG_CREATE_VERSIONS))
             .execute(&self.pool)
             .await
-            .context(format!("Failed to create pages table: {}", table_name))?;
+            .context(format!("Failed to create page versions table: {}", table.name))?;
         
-        debug!("Ensured pages table exists: {}", table_name);
+        let index = quote_identifier(&format!("{}_url", table.name));
+        sqlx::query(&table.sql(PG_CREATE_VERSIONS_INDEX).replace("{index}", &index))
+            .execute(&self.pool)
+            .await
+            .context(format!("Failed to create page versions index: {}", table.name))?;
+        
+        debug!("Ensured page versions table exists: {}", table.name);
         
         Ok(())
     }
     
-    /// Get the name of the pages table for a job
-    fn get_pages_table_name(&self, job_id: &str) -> String {
-        format!("{}_{}_pages", self.table_prefix, job_id.replace('-', "_"))
+    /// Get the pages table of a job
+    fn pages_table(&self, job_id: &str) -> PgTable {
+        PgTable {
+            schema: self.schema.clone(),
+            name: NamingRules::POSTGRES.job_identifier(&self.table_prefix, job_id, "pages"),
+        }
+    }
+    
+    /// Check whether a table exists in the schema, as seen by the read pool
+    async fn table_exists(&self, table: &PgTable) -> Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT FROM pg_tables WHERE schemaname = $1 AND tablename = $2)"
+        )
+        .bind(&table.schema)
+        .bind(&table.name)
+        .fetch_one(&self.read_pool)
+        .await
+        .context("Failed to check if table exists")?;
+        
+        Ok(exists)
     }
 }
 
+#[cfg(feature = "postgres")]
 #[async_trait]
 impl ProcessedStorage for PostgresStorage {
+    #[instrument(skip_all, fields(backend = "postgresql", job_id = %job_id, url = %url))]
     async fn store_page_data(&self, job_id: &str, url: &str, data: serde_json::Value) -> Result<()> {
         // Ensure the pages table exists
-        self.ensure_pages_table(job_id).await?;
-        
-        let table_name = self.get_pages_table_name(job_id);
+        let table = self.pages_table(job_id);
+        self.ensure_pages_table(&table).await?;
         
-        // Insert or update the page data
-        let query = format!(
-            "INSERT INTO {}.{} (job_id, url, data, created_at, updated_at)
-             VALUES ($1, $2, $3, NOW(), NOW())
-             ON CONFLICT (job_id, url) DO UPDATE
-             SET data = $3, updated_at = NOW()",
-            self.schema, table_name
-        );
-        
-        sqlx::query(&query)
+        // Insert or update the page data, adding a version in versioned mode
+        let mut transaction = self.pool.begin()
+            .await
+            .context("Failed to start page data transaction")?;
+        sqlx::query(&table.sql(PG_UPSERT_PAGE))
             .bind(job_id)
             .bind(url)
-            .bind(&Json(data))
-            .execute(&self.pool)
+            .bind(&Json(&data))
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to store page data in PostgreSQL")?;
+        if let Some(versions_table) = &self.versions_table {
+            sqlx::query(&versions_table.sql(PG_INSERT_VERSION))
+                .bind(job_id)
+                .bind(url)
+                .bind(&Json(&data))
+                .execute(&mut *transaction)
+                .await
+                .context("Failed to store page version in PostgreSQL")?;
+        }
+        transaction.commit()
             .await
             .context("Failed to store page data in PostgreSQL")?;
         
@@ -196,37 +595,16 @@ impl ProcessedStorage for PostgresStorage {
     }
     
     async fn get_page_data(&self, job_id: &str, url: &str) -> Result<Option<serde_json::Value>> {
-        let table_name = self.get_pages_table_name(job_id);
-        
-        // Check if the table exists
-        let table_exists = sqlx::query_scalar::<_, bool>(
-            &format!(
-                "SELECT EXISTS (
-                    SELECT FROM pg_tables
-                    WHERE schemaname = $1 AND tablename = $2
-                )",
-            )
-        )
-        .bind(&self.schema)
-        .bind(&table_name)
-        .fetch_one(&self.pool)
-        .await
-        .context("Failed to check if table exists")?;
-        
-        if !table_exists {
+        let table = self.pages_table(job_id);
+        if !self.table_exists(&table).await? {
             return Ok(None);
         }
         
         // Query the page data
-        let query = format!(
-            "SELECT data FROM {}.{} WHERE job_id = $1 AND url = $2",
-            self.schema, table_name
-        );
-        
-        let result: Option<Json<serde_json::Value>> = sqlx::query_scalar(&query)
+        let result: Option<Json<serde_json::Value>> = sqlx::query_scalar(&table.sql(PG_SELECT_PAGE_DATA))
             .bind(job_id)
             .bind(url)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.read_pool)
             .await
             .context("Failed to query page data from PostgreSQL")?;
         
@@ -235,320 +613,1811 @@ impl ProcessedStorage for PostgresStorage {
     }
     
     async fn list_pages(&self, job_id: &str) -> Result<Vec<String>> {
-        let table_name = self.get_pages_table_name(job_id);
-        
-        // Check if the table exists
-        let table_exists = sqlx::query_scalar::<_, bool>(
-            &format!(
-                "SELECT EXISTS (
-                    SELECT FROM pg_tables
-                    WHERE schemaname = $1 AND tablename = $2
-                )",
-            )
-        )
-        .bind(&self.schema)
-        .bind(&table_name)
-        .fetch_one(&self.pool)
-        .await
-        .context("Failed to check if table exists")?;
-        
-        if !table_exists {
+        let table = self.pages_table(job_id);
+        if !self.table_exists(&table).await? {
             return Ok(Vec::new());
         }
         
         // Query all page URLs
-        let query = format!(
-            "SELECT url FROM {}.{} WHERE job_id = $1 ORDER BY url",
-            self.schema, table_name
-        );
-        
-        let results: Vec<String> = sqlx::query_scalar(&query)
+        let results: Vec<String> = sqlx::query_scalar(&table.sql(PG_SELECT_URLS))
             .bind(job_id)
-            .fetch_all(&self.pool)
+            .fetch_all(&self.read_pool)
             .await
             .context("Failed to query page URLs from PostgreSQL")?;
         
         Ok(results)
     }
     
-    async fn export_as_json(&self, job_id: &str, output_path: &Path) -> Result<()> {
-        let table_name = self.get_pages_table_name(job_id);
-        
-        // Check if the table exists
-        let table_exists = sqlx::query_scalar::<_, bool>(
-            &format!(
-                "SELECT EXISTS (
-                    SELECT FROM pg_tables
-                    WHERE schemaname = $1 AND tablename = $2
-                )",
-            )
-        )
-        .bind(&self.schema)
-        .bind(&table_name)
-        .fetch_one(&self.pool)
-        .await
-        .context("Failed to check if table exists")?;
-        
-        if !table_exists {
-            // Create an empty JSON array if no data
-            let file = fs::File::create(output_path)
-                .context(format!("Failed to create output file: {}", output_path.display()))?;
+    fn stream_rows<'a>(
+        &'a self,
+        job_id: &'a str,
+        after_url: Option<&'a str>,
+        filter: &'a RowFilter,
+    ) -> BoxStream<'a, Result<PageData>> {
+        Box::pin(try_stream! {
+            let table = self.pages_table(job_id);
+            if !self.table_exists(&table).await? {
+                return;
+            }
             
-            serde_json::to_writer_pretty(file, &Vec::<serde_json::Value>::new())
-                .context("Failed to write empty JSON array to file")?;
+            let query = table.sql(PG_SELECT_ROWS);
+            let mut rows = sqlx::query_as::<_, PageRow>(&query)
+                .bind(job_id)
+                .bind(after_url)
+                .bind(filter.include_url.as_ref().map(|pattern| pattern.as_str()))
+                .bind(filter.since)
+                .bind(filter.until)
+                .fetch(&self.read_pool);
             
-            return Ok(());
+            while let Some(row) = rows.next().await {
+                let row = row.context("Failed to query page data from PostgreSQL")?;
+                yield PageData::from(row);
+            }
+        })
+    }
+    
+    async fn count_rows(&self, job_id: &str, filter: &RowFilter) -> Result<Option<usize>> {
+        let table = self.pages_table(job_id);
+        if !self.table_exists(&table).await? {
+            return Ok(Some(0));
         }
         
-        // Query all page data
-        let query = format!(
-            "SELECT json_build_object(
-                'job_id', job_id,
-                'url', url,
-                'data', data,
-                'created_at', created_at,
-                'updated_at', updated_at
-            ) AS json_data
-            FROM {}.{}
-            WHERE job_id = $1
-            ORDER BY url",
-            self.schema, table_name
-        );
-        
-        let results: Vec<serde_json::Value> = sqlx::query_scalar(&query)
+        let rows: i64 = sqlx::query_scalar(&table.sql(PG_COUNT_ROWS))
             .bind(job_id)
-            .fetch_all(&self.pool)
+            .bind(filter.include_url.as_ref().map(|pattern| pattern.as_str()))
+            .bind(filter.since)
+            .bind(filter.until)
+            .fetch_one(&self.read_pool)
             .await
-            .context("Failed to query page data from PostgreSQL")?;
+            .context("Failed to count page data in PostgreSQL")?;
+        
+        Ok(Some(rows as usize))
+    }
+    
+    async fn delete_job(&self, job_id: &str) -> Result<()> {
+        let table = self.pages_table(job_id);
         
-        // Write to file
-        let file = fs::File::create(output_path)
-            .context(format!("Failed to create output file: {}", output_path.display()))?;
+        // Drop the table, a job that stored nothing has none
+        sqlx::query(&table.sql(PG_DROP_PAGES))
+            .execute(&self.pool)
+            .await
+            .context(format!("Failed to drop table: {}", table.name))?;
         
-        serde_json::to_writer_pretty(file, &results)
-            .context("Failed to write JSON data to file")?;
+        if let Some(versions_table) = &self.versions_table {
+            sqlx::query(&versions_table.sql(PG_DELETE_VERSIONS))
+                .bind(job_id)
+                .execute(&self.pool)
+                .await
+                .context(format!("Failed to delete page versions of job: {}", job_id))?;
+        }
         
-        debug!("Exported {} records to JSON file: {}", results.len(), output_path.display());
+        debug!("Deleted job data: {}", job_id);
         
         Ok(())
     }
     
-    async fn export_as_csv(&self, job_id: &str, output_path: &Path) -> Result<()> {
-        let table_name = self.get_pages_table_name(job_id);
-        
-        // Check if the table exists
-        let table_exists = sqlx::query_scalar::<_, bool>(
-            &format!(
-                "SELECT EXISTS (
-                    SELECT FROM pg_tables
-                    WHERE schemaname = $1 AND tablename = $2
-                )",
-            )
-        )
-        .bind(&self.schema)
-        .bind(&table_name)
-        .fetch_one(&self.pool)
-        .await
-        .context("Failed to check if table exists")?;
-        
-        if !table_exists {
-            // Create an empty CSV file if no data
-            let mut file = fs::File::create(output_path)
-                .context(format!("Failed to create output file: {}", output_path.display()))?;
-            
-            // Write header row
-            writeln!(file, "job_id,url,created_at,updated_at")
-                .context("Failed to write CSV header to file")?;
-            
-            return Ok(());
+    async fn query(&self, job_id: &str, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        let sql = check_query(sql)?;
+        let table = self.pages_table(job_id);
+        if !self.table_exists(&table).await? {
+            anyhow::bail!("Job {} has no processed data", job_id);
         }
         
-        // Query all page data
-        let query = format!(
-            "SELECT job_id, url, created_at, updated_at
-            FROM {}.{}
-            WHERE job_id = $1
-            ORDER BY url",
-            self.schema, table_name
-        );
+        // Rows come back as JSON objects so values of any type can be shown
+        let template = table.sql(PG_QUERY);
+        let columns_query = template.replace("{select}", "*").replace("{query}", sql);
+        let rows_query = template.replace("{select}", "row_to_json(query)::TEXT").replace("{query}", sql);
         
-        #[derive(sqlx::FromRow)]
-        struct CsvRow {
-            job_id: String,
-            url: String,
-            created_at: DateTime<Utc>,
-            updated_at: DateTime<Utc>,
-        }
+        // A read-only transaction on the read pool, rolled back once the rows are read
+        let mut transaction = self.read_pool.begin()
+            .await
+            .context("Failed to start query transaction")?;
+        sqlx::query("SET TRANSACTION READ ONLY")
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to make query transaction read-only")?;
+        sqlx::query(&format!("SET LOCAL statement_timeout = {}", QUERY_TIMEOUT.as_millis()))
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to set query timeout")?;
         
-        let results = sqlx::query_as::<_, CsvRow>(&query)
+        let columns: Vec<String> = (&mut *transaction).describe(&columns_query)
+            .await
+            .context("Invalid query")?
+            .columns()
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect();
+        let rows: Vec<String> = sqlx::query_scalar(&rows_query)
             .bind(job_id)
-            .fetch_all(&self.pool)
+            .bind(max_rows as i64 + 1)
+            .fetch_all(&mut *transaction)
             .await
-            .context("Failed to query page data from PostgreSQL")?;
+            .context("Query failed")?;
+        transaction.rollback()
+            .await
+            .context("Failed to end query transaction")?;
         
-        // Write to CSV file
-        let mut file = fs::File::create(output_path)
-            .context(format!("Failed to create output file: {}", output_path.display()))?;
-        
-        // Write header row
-        writeln!(file, "job_id,url,created_at,updated_at")
-            .context("Failed to write CSV header to file")?;
-        let results_length = results.len(); 
-        // Write data rows
-        for row in results {
-            writeln!(
-                file,
-                "{},{},{},{}",
-                row.job_id,
-                row.url,
-                row.created_at.to_rfc3339(),
-                row.updated_at.to_rfc3339()
-            )
-            .context("Failed to write CSV row to file")?;
-        }
-        
-        debug!("Exported {} records to CSV file: {}", results_length, output_path.display());
+        let rows = rows.iter()
+            .map(|row| {
+                let values: serde_json::Map<String, serde_json::Value> = serde_json::from_str(row)
+                    .context("Failed to parse query row")?;
+                Ok(columns.iter().map(|column| json_cell(values.get(column))).collect())
+            })
+            .collect::<Result<Vec<_>>>()?;
         
-        Ok(())
+        Ok(QueryResult { columns, rows, truncated: false }.limit(max_rows))
     }
     
-    async fn export_as_sql(&self, job_id: &str, output_path: &Path) -> Result<()> {
-        let table_name = self.get_pages_table_name(job_id);
+    async fn page_history(&self, url: &str, limit: usize) -> Result<Vec<PageVersion>> {
+        let table = self.versions_table.as_ref().context(NOT_VERSIONED)?;
         
-        // Check if the table exists
-        let table_exists = sqlx::query_scalar::<_, bool>(
-            &format!(
-                "SELECT EXISTS (
-                    SELECT FROM pg_tables
-                    WHERE schemaname = $1 AND tablename = $2
-                )",
-            )
-        )
-        .bind(&self.schema)
-        .bind(&table_name)
-        .fetch_one(&self.pool)
-        .await
-        .context("Failed to check if table exists")?;
+        let rows = sqlx::query_as::<_, VersionRow>(&table.sql(PG_SELECT_HISTORY))
+            .bind(url)
+            .bind(limit as i64)
+            .fetch_all(&self.read_pool)
+            .await
+            .context("Failed to query page history from PostgreSQL")?;
         
-        if !table_exists {
-            // Create an empty SQL file if no data
-            let mut file = fs::File::create(output_path)
-                .context(format!("Failed to create output file: {}", output_path.display()))?;
-            
-            // Write table creation statement
-            write!(
-                file,
-                "CREATE TABLE IF NOT EXISTS crawled_data (
-                    job_id TEXT NOT NULL,
-                    url TEXT NOT NULL,
-                    data JSONB NOT NULL,
-                    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
-                    updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
-                    PRIMARY KEY (job_id, url)
-                );\n"
-            )
-            .context("Failed to write SQL create table statement to file")?;
-            
-            return Ok(());
+        Ok(rows.into_iter().map(PageVersion::from).collect())
+    }
+    
+    async fn prune_versions(&self, retention: &VersionRetention) -> Result<usize> {
+        let Some(table) = &self.versions_table else {
+            return Ok(0);
+        };
+        if retention.keeps_all() {
+            return Ok(0);
         }
         
-        // Query all page data
-        let query = format!(
-            "SELECT job_id, url, data, created_at, updated_at
-            FROM {}.{}
-            WHERE job_id = $1
-            ORDER BY url",
-            self.schema, table_name
-        );
-        
-        #[derive(sqlx::FromRow)]
-        struct SqlRow {
-            job_id: String,
-            url: String,
-            data: Json<serde_json::Value>,
-            created_at: DateTime<Utc>,
-            updated_at: DateTime<Utc>,
-        }
-
-        let results = sqlx::query_as::<_, SqlRow>(&query)
-            .bind(job_id)
+        // URLs are pruned one at a time, only the versions of one page are held in memory
+        let urls: Vec<String> = sqlx::query_scalar(&table.sql(PG_SELECT_VERSIONED_URLS))
             .fetch_all(&self.pool)
             .await
-            .context("Failed to query page data from PostgreSQL")?;
-        
-        // Write to SQL file
-        let mut file = fs::File::create(output_path)
-            .context(format!("Failed to create output file: {}", output_path.display()))?;
+            .context("Failed to query versioned pages from PostgreSQL")?;
         
-        // Write table creation statement
-        write!(
-            file,
-            "CREATE TABLE IF NOT EXISTS crawled_data (
-                job_id TEXT NOT NULL,
-                url TEXT NOT NULL,
-                data JSONB NOT NULL,
-                created_at TIMESTAMP WITH TIME ZONE NOT NULL,
-                updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
-                PRIMARY KEY (job_id, url)
-            );\n\n"
-        )
-        .context("Failed to write SQL create table statement to file")?;
-        let result_count = results.len();
-        // Write data insert statements
-        for row in results {
-            let data_json = serde_json::to_string(&row.data.0)
-                .context("Failed to serialize JSON data")?;
+        let mut pruned = 0;
+        for url in urls {
+            let rows = sqlx::query_as::<_, VersionRow>(&table.sql(PG_SELECT_VERSIONS))
+                .bind(&url)
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to query page versions from PostgreSQL")?;
+            let ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+            let versions: Vec<PageVersion> = rows.into_iter().map(PageVersion::from).collect();
             
-            writeln!(
-                file,
-                "INSERT INTO crawled_data (job_id, url, data, created_at, updated_at) VALUES ('{}', '{}', '{}', '{}', '{}');",
-                row.job_id.replace('\'', "''"),
-                row.url.replace('\'', "''"),
-                data_json.replace('\'', "''"),
-                row.created_at.to_rfc3339(),
-                row.updated_at.to_rfc3339()
-            )
-            .context("Failed to write SQL insert statement to file")?;
+            let expired: Vec<i64> = retained_versions(&versions, retention).into_iter()
+                .zip(ids)
+                .filter(|(keep, _)| !keep)
+                .map(|(_, id)| id)
+                .collect();
+            if expired.is_empty() {
+                continue;
+            }
+            
+            sqlx::query(&table.sql(PG_DELETE_VERSION_IDS))
+                .bind(&expired)
+                .execute(&self.pool)
+                .await
+                .context(format!("Failed to prune page versions of: {}", url))?;
+            pruned += expired.len();
         }
         
-        debug!("Exported {} records to SQL file: {}", result_count, output_path.display());
-        
-        Ok(())
+        Ok(pruned)
     }
+}
+
+/// SQLite implementation of ProcessedStorage
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    /// SQLite connection pool
+    pool: Pool<Sqlite>,
     
-    async fn delete_job(&self, job_id: &str) -> Result<()> {
-        let table_name = self.get_pages_table_name(job_id);
-        
-        // Check if the table exists
-        let table_exists = sqlx::query_scalar::<_, bool>(
-            &format!(
-                "SELECT EXISTS (
-                    SELECT FROM pg_tables
-                    WHERE schemaname = $1 AND tablename = $2
-                )",
-            )
-        )
-        .bind(&self.schema)
-        .bind(&table_name)
-        .fetch_one(&self.pool)
-        .await
+    /// Table prefix
+    table_prefix: String,
+    
+    /// Quoted table keeping every version of the pages of all jobs, in versioned mode
+    versions_table: Option<String>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    /// Create a new SQLite storage instance
+    pub async fn new(settings: &ProcessedDataSettings) -> Result<Self> {
+        // Create the database file if it doesn't exist yet
+        let options = SqliteConnectOptions::from_str(&settings.connection_string)
+            .context(format!("Failed to parse SQLite connection string: {}", settings.connection_string))?
+            .create_if_missing(true);
+        
+        let pool = connect_with_retry(&settings.pool, "SQLite", || {
+            let options = options.clone();
+            async move {
+                SqlitePoolOptions::new()
+                    .max_connections(settings.pool.max_connections.unwrap_or(SQL_MAX_CONNECTIONS))
+                    .acquire_timeout(Duration::from_secs(settings.pool.acquire_timeout))
+                    .connect_with(options)
+                    .await
+                    .context(format!("Failed to connect to SQLite: {}", settings.connection_string))
+            }
+        }).await?;
+        
+        debug!("Connected to SQLite database");
+        
+        let storage = Self {
+            pool,
+            table_prefix: settings.table_prefix.clone(),
+            versions_table: settings.versioned.then(|| quote_identifier(&versions_identifier(&settings.table_prefix))),
+        };
+        
+        if let Some(table) = &storage.versions_table {
+            let index = quote_identifier(&format!("{}_url", versions_identifier(&settings.table_prefix)));
+            for statement in [SQLITE_CREATE_VERSIONS, SQLITE_CREATE_VERSIONS_INDEX] {
+                sqlx::query(&statement.replace("{table}", table).replace("{index}", &index))
+                    .execute(&storage.pool)
+                    .await
+                    .context(format!("Failed to create page versions table: {}", table))?;
+            }
+        }
+        
+        Ok(storage)
+    }
+    
+    /// Ensure the pages table exists for a job
+    async fn ensure_pages_table(&self, job_id: &str) -> Result<()> {
+        let table_name = self.get_pages_table_name(job_id);
+        
+        let query = format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                job_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                data TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (job_id, url)
+            )",
+            table_name
+        );
+        
+        sqlx::query(&query)
+            .execute(&self.pool)
+            .await
+            .context(format!("Failed to create pages table: {}", table_name))?;
+        
+        debug!("Ensured pages table exists: {}", table_name);
+        
+        Ok(())
+    }
+    
+    /// Check whether a table exists
+    async fn table_exists(&self, table_name: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)"
+        )
+        .bind(table_name)
+        .fetch_one(&self.pool)
+        .await
         .context("Failed to check if table exists")?;
         
-        if !table_exists {
-            // Table doesn't exist, nothing to delete
-            return Ok(());
+        Ok(exists)
+    }
+    
+    /// Get the name of the pages table for a job
+    fn get_pages_table_name(&self, job_id: &str) -> String {
+        NamingRules::SQLITE.job_identifier(&self.table_prefix, job_id, "pages")
+    }
+}
+
+// Versions of all jobs share a table, `{index}` is replaced by an index name
+#[cfg(feature = "sqlite")]
+const SQLITE_CREATE_VERSIONS: &str = "CREATE TABLE IF NOT EXISTS {table} (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    data TEXT NOT NULL,
+    stored_at TEXT NOT NULL
+)";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_CREATE_VERSIONS_INDEX: &str = "CREATE INDEX IF NOT EXISTS {index} ON {table} (url, stored_at)";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_INSERT_VERSION: &str = "INSERT INTO {table} (job_id, url, data, stored_at) VALUES (?1, ?2, ?3, ?4)";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_SELECT_HISTORY: &str = "SELECT id, job_id, url, data, stored_at FROM {table}
+    WHERE url = ?1
+    ORDER BY stored_at DESC, id DESC
+    LIMIT ?2";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_DELETE_VERSIONS: &str = "DELETE FROM {table} WHERE job_id = ?1";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_SELECT_VERSIONED_URLS: &str = "SELECT DISTINCT url FROM {table}";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_SELECT_VERSIONS: &str = "SELECT id, job_id, url, data, stored_at FROM {table}
+    WHERE url = ?1
+    ORDER BY stored_at, id";
+
+#[cfg(feature = "sqlite")]
+const SQLITE_DELETE_VERSION: &str = "DELETE FROM {table} WHERE id = ?1";
+
+// Only URLs are read to count rows, the URL pattern is checked on them
+#[cfg(feature = "sqlite")]
+const SQLITE_SELECT_ROW_URLS: &str = "SELECT url FROM {table}
+    WHERE job_id = ?1
+        AND (?2 IS NULL OR updated_at >= ?2)
+        AND (?3 IS NULL OR updated_at < ?3)";
+
+// User queries read the job's rows as `pages`
+#[cfg(feature = "sqlite")]
+const SQLITE_QUERY: &str = "WITH pages AS (SELECT job_id, url, data, created_at, updated_at FROM {table} WHERE job_id = ?1)
+    SELECT * FROM ({query}) AS query LIMIT ?2";
+
+/// Text of a query value, by the type SQLite stored it with
+#[cfg(feature = "sqlite")]
+fn sqlite_cell(row: &SqliteRow, index: usize) -> Result<Option<String>> {
+    let raw = row.try_get_raw(index).context("Failed to read query value")?;
+    if raw.is_null() {
+        return Ok(None);
+    }
+    
+    let value = match raw.type_info().name() {
+        "INTEGER" => row.try_get::<i64, _>(index)?.to_string(),
+        "REAL" => row.try_get::<f64, _>(index)?.to_string(),
+        "BLOB" => format!("<{} bytes>", row.try_get::<Vec<u8>, _>(index)?.len()),
+        _ => row.try_get::<String, _>(index)?,
+    };
+    
+    Ok(Some(value))
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait]
+impl ProcessedStorage for SqliteStorage {
+    #[instrument(skip_all, fields(backend = "sqlite", job_id = %job_id, url = %url))]
+    async fn store_page_data(&self, job_id: &str, url: &str, data: serde_json::Value) -> Result<()> {
+        // Ensure the pages table exists
+        self.ensure_pages_table(job_id).await?;
+        
+        let table_name = self.get_pages_table_name(job_id);
+        let now = Utc::now();
+        
+        // Insert or update the page data
+        let query = format!(
+            "INSERT INTO \"{}\" (job_id, url, data, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)
+             ON CONFLICT (job_id, url) DO UPDATE
+             SET data = excluded.data, updated_at = excluded.updated_at",
+            table_name
+        );
+        
+        let mut transaction = self.pool.begin()
+            .await
+            .context("Failed to start page data transaction")?;
+        sqlx::query(&query)
+            .bind(job_id)
+            .bind(url)
+            .bind(Json(&data))
+            .bind(now)
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to store page data in SQLite")?;
+        if let Some(versions_table) = &self.versions_table {
+            sqlx::query(&SQLITE_INSERT_VERSION.replace("{table}", versions_table))
+                .bind(job_id)
+                .bind(url)
+                .bind(Json(&data))
+                .bind(now)
+                .execute(&mut *transaction)
+                .await
+                .context("Failed to store page version in SQLite")?;
+        }
+        transaction.commit()
+            .await
+            .context("Failed to store page data in SQLite")?;
+        
+        debug!("Stored processed data for URL: {}", url);
+        
+        Ok(())
+    }
+    
+    async fn get_page_data(&self, job_id: &str, url: &str) -> Result<Option<serde_json::Value>> {
+        let table_name = self.get_pages_table_name(job_id);
+        
+        if !self.table_exists(&table_name).await? {
+            return Ok(None);
+        }
+        
+        let query = format!(
+            "SELECT data FROM \"{}\" WHERE job_id = ?1 AND url = ?2",
+            table_name
+        );
+        
+        let result: Option<Json<serde_json::Value>> = sqlx::query_scalar(&query)
+            .bind(job_id)
+            .bind(url)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to query page data from SQLite")?;
+        
+        Ok(result.map(|json| json.0))
+    }
+    
+    async fn list_pages(&self, job_id: &str) -> Result<Vec<String>> {
+        let table_name = self.get_pages_table_name(job_id);
+        
+        if !self.table_exists(&table_name).await? {
+            return Ok(Vec::new());
+        }
+        
+        let query = format!(
+            "SELECT url FROM \"{}\" WHERE job_id = ?1 ORDER BY url",
+            table_name
+        );
+        
+        let results: Vec<String> = sqlx::query_scalar(&query)
+            .bind(job_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query page URLs from SQLite")?;
+        
+        Ok(results)
+    }
+    
+    fn stream_rows<'a>(
+        &'a self,
+        job_id: &'a str,
+        after_url: Option<&'a str>,
+        filter: &'a RowFilter,
+    ) -> BoxStream<'a, Result<PageData>> {
+        Box::pin(try_stream! {
+            let table_name = self.get_pages_table_name(job_id);
+            if !self.table_exists(&table_name).await? {
+                return;
+            }
+            
+            // SQLite has no built-in REGEXP, the URL pattern is checked on the rows read
+            let query = format!(
+                "SELECT job_id, url, data, created_at, updated_at
+                FROM \"{}\"
+                WHERE job_id = ?1 AND (?2 IS NULL OR url > ?2)
+                    AND (?3 IS NULL OR updated_at >= ?3)
+                    AND (?4 IS NULL OR updated_at < ?4)
+                ORDER BY url",
+                table_name
+            );
+            
+            let mut rows = sqlx::query_as::<_, PageRow>(&query)
+                .bind(job_id)
+                .bind(after_url)
+                .bind(filter.since)
+                .bind(filter.until)
+                .fetch(&self.pool);
+            
+            while let Some(row) = rows.next().await {
+                let row = row.context("Failed to query page data from SQLite")?;
+                if filter.matches(&row.url, row.updated_at) {
+                    yield PageData::from(row);
+                }
+            }
+        })
+    }
+    
+    async fn count_rows(&self, job_id: &str, filter: &RowFilter) -> Result<Option<usize>> {
+        let table_name = self.get_pages_table_name(job_id);
+        if !self.table_exists(&table_name).await? {
+            return Ok(Some(0));
         }
         
-        // Drop the table
-        let query = format!("DROP TABLE {}.{}", self.schema, table_name);
+        let query = SQLITE_SELECT_ROW_URLS.replace("{table}", &quote_identifier(&table_name));
+        let mut urls = sqlx::query_scalar::<_, String>(&query)
+            .bind(job_id)
+            .bind(filter.since)
+            .bind(filter.until)
+            .fetch(&self.pool);
+        
+        let mut rows = 0;
+        while let Some(url) = urls.next().await {
+            let url = url.context("Failed to count page data in SQLite")?;
+            if filter.include_url.as_ref().map_or(true, |pattern| pattern.is_match(&url)) {
+                rows += 1;
+            }
+        }
+        
+        Ok(Some(rows))
+    }
+    
+    async fn delete_job(&self, job_id: &str) -> Result<()> {
+        let table_name = self.get_pages_table_name(job_id);
+        
+        let query = format!("DROP TABLE IF EXISTS \"{}\"", table_name);
         
         sqlx::query(&query)
             .execute(&self.pool)
             .await
             .context(format!("Failed to drop table: {}", table_name))?;
         
+        if let Some(versions_table) = &self.versions_table {
+            sqlx::query(&SQLITE_DELETE_VERSIONS.replace("{table}", versions_table))
+                .bind(job_id)
+                .execute(&self.pool)
+                .await
+                .context(format!("Failed to delete page versions of job: {}", job_id))?;
+        }
+        
         debug!("Deleted job data: {}", job_id);
         
         Ok(())
     }
+    
+    async fn query(&self, job_id: &str, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        let sql = check_query(sql)?;
+        let table_name = self.get_pages_table_name(job_id);
+        if !self.table_exists(&table_name).await? {
+            anyhow::bail!("Job {} has no processed data", job_id);
+        }
+        let query = SQLITE_QUERY
+            .replace("{table}", &quote_identifier(&table_name))
+            .replace("{query}", sql);
+        
+        // The connection refuses writes while the query runs
+        let mut connection = self.pool.acquire()
+            .await
+            .context("Failed to acquire SQLite connection")?;
+        sqlx::query("PRAGMA query_only = ON")
+            .execute(&mut *connection)
+            .await
+            .context("Failed to make SQLite connection read-only")?;
+        
+        let run = async {
+            let columns: Vec<String> = (&mut *connection).describe(&query)
+                .await
+                .context("Invalid query")?
+                .columns()
+                .iter()
+                .map(|column| column.name().to_string())
+                .collect();
+            let rows = sqlx::query(&query)
+                .bind(job_id)
+                .bind(max_rows as i64 + 1)
+                .fetch_all(&mut *connection)
+                .await
+                .context("Query failed")?;
+            Ok::<_, anyhow::Error>((columns, rows))
+        };
+        
+        let (columns, rows) = match tokio::time::timeout(QUERY_TIMEOUT, run).await {
+            Ok(result) => {
+                sqlx::query("PRAGMA query_only = OFF")
+                    .execute(&mut *connection)
+                    .await
+                    .context("Failed to make SQLite connection writable again")?;
+                result?
+            },
+            Err(_) => {
+                // The connection may still be busy with the query, close it rather than reuse it
+                drop(connection.detach());
+                anyhow::bail!("Query timed out after {} seconds", QUERY_TIMEOUT.as_secs());
+            },
+        };
+        
+        let rows = rows.iter()
+            .map(|row| (0..columns.len()).map(|index| sqlite_cell(row, index)).collect())
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(QueryResult { columns, rows, truncated: false }.limit(max_rows))
+    }
+    
+    async fn page_history(&self, url: &str, limit: usize) -> Result<Vec<PageVersion>> {
+        let table = self.versions_table.as_ref().context(NOT_VERSIONED)?;
+        
+        let rows = sqlx::query_as::<_, VersionRow>(&SQLITE_SELECT_HISTORY.replace("{table}", table))
+            .bind(url)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query page history from SQLite")?;
+        
+        Ok(rows.into_iter().map(PageVersion::from).collect())
+    }
+    
+    async fn prune_versions(&self, retention: &VersionRetention) -> Result<usize> {
+        let Some(table) = &self.versions_table else {
+            return Ok(0);
+        };
+        if retention.keeps_all() {
+            return Ok(0);
+        }
+        
+        let urls: Vec<String> = sqlx::query_scalar(&SQLITE_SELECT_VERSIONED_URLS.replace("{table}", table))
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query versioned pages from SQLite")?;
+        
+        let mut pruned = 0;
+        for url in urls {
+            let rows = sqlx::query_as::<_, VersionRow>(&SQLITE_SELECT_VERSIONS.replace("{table}", table))
+                .bind(&url)
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to query page versions from SQLite")?;
+            let ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+            let versions: Vec<PageVersion> = rows.into_iter().map(PageVersion::from).collect();
+            
+            let expired: Vec<i64> = retained_versions(&versions, retention).into_iter()
+                .zip(ids)
+                .filter(|(keep, _)| !keep)
+                .map(|(_, id)| id)
+                .collect();
+            if expired.is_empty() {
+                continue;
+            }
+            
+            // SQLite can't bind a list, the deletes share a transaction instead
+            let mut transaction = self.pool.begin()
+                .await
+                .context("Failed to start prune transaction")?;
+            for id in &expired {
+                sqlx::query(&SQLITE_DELETE_VERSION.replace("{table}", table))
+                    .bind(id)
+                    .execute(&mut *transaction)
+                    .await
+                    .context(format!("Failed to prune page versions of: {}", url))?;
+            }
+            transaction.commit()
+                .await
+                .context(format!("Failed to prune page versions of: {}", url))?;
+            pruned += expired.len();
+        }
+        
+        Ok(pruned)
+    }
+}
+
+/// Filesystem implementation of ProcessedStorage
+///
+/// Each job gets a directory with one JSON file per page and an `index.jsonl`
+/// mapping URLs to page files, a line appended for each new URL. Writes are
+/// serialized within the process and the index of a job is kept in memory
+/// once written to, so a job directory should only be written by one crawler
+/// process at a time.
+/// In versioned mode, every version of a URL is also appended to a JSON Lines
+/// file named after the URL hash, in a directory shared by all jobs.
+pub struct FilesystemStorage {
+    /// Base directory for all jobs
+    base_dir: PathBuf,
+    
+    /// Job directory prefix
+    table_prefix: String,
+    
+    /// Directory of the page version files, in versioned mode
+    versions_dir: Option<PathBuf>,
+    
+    /// URL indexes of the jobs written to by this process, by job ID. The
+    /// lock also serializes writes.
+    indexes: Mutex<HashMap<String, BTreeMap<String, String>>>,
+}
+
+/// Index file name inside a job directory
+const INDEX_FILE: &str = "index.jsonl";
+
+/// Line of a job's index file
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    url: String,
+    file: String,
+}
+
+impl FilesystemStorage {
+    /// Create a new filesystem storage instance
+    pub fn new(settings: &ProcessedDataSettings) -> Result<Self> {
+        let base_dir = PathBuf::from(&settings.connection_string);
+        
+        fs::create_dir_all(&base_dir)
+            .context(format!("Failed to create storage directory: {}", base_dir.display()))?;
+        
+        debug!("Using filesystem storage at: {}", base_dir.display());
+        
+        let versions_dir = settings.versioned.then(|| base_dir.join(versions_identifier(&settings.table_prefix)));
+        if let Some(versions_dir) = &versions_dir {
+            fs::create_dir_all(versions_dir)
+                .context(format!("Failed to create page versions directory: {}", versions_dir.display()))?;
+        }
+        
+        Ok(Self {
+            base_dir,
+            table_prefix: settings.table_prefix.clone(),
+            versions_dir,
+            indexes: Mutex::new(HashMap::new()),
+        })
+    }
+    
+    /// Versions file of a URL
+    fn versions_file(versions_dir: &Path, url: &str) -> PathBuf {
+        versions_dir.join(format!("{:x}.jsonl", Sha256::digest(url.as_bytes())))
+    }
+    
+    /// Rewrite a versions file with the versions to keep, returning how many were removed
+    fn rewrite_versions(path: &Path, versions: Vec<PageVersion>, keep: &[bool]) -> Result<usize> {
+        let kept: Vec<PageVersion> = versions.into_iter()
+            .zip(keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(version, _)| version)
+            .collect();
+        let removed = keep.len() - kept.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+        
+        if kept.is_empty() {
+            fs::remove_file(path)
+                .context(format!("Failed to delete page versions file: {}", path.display()))?;
+            return Ok(removed);
+        }
+        
+        let mut contents = String::new();
+        for version in &kept {
+            contents.push_str(&serde_json::to_string(version).context("Failed to serialize page version")?);
+            contents.push('\n');
+        }
+        fs::write(path, contents)
+            .context(format!("Failed to write page versions file: {}", path.display()))?;
+        
+        Ok(removed)
+    }
+    
+    /// Read the versions in a versions file, oldest first
+    fn read_versions(path: &Path) -> Result<Vec<PageVersion>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        
+        fs::read_to_string(path)
+            .context(format!("Failed to read page versions file: {}", path.display()))?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line)
+                .context(format!("Failed to parse page versions file: {}", path.display())))
+            .collect()
+    }
+    
+    /// Get the directory for a job
+    fn job_dir(&self, job_id: &str) -> PathBuf {
+        self.base_dir.join(NamingRules::FILESYSTEM.job_identifier(&self.table_prefix, job_id, ""))
+    }
+    
+    /// Load the URL index for a job
+    fn load_index(&self, job_id: &str) -> Result<BTreeMap<String, String>> {
+        Self::read_index(&self.job_dir(job_id))
+    }
+    
+    /// Read the URL index in a job directory
+    fn read_index(job_dir: &Path) -> Result<BTreeMap<String, String>> {
+        let index_path = job_dir.join(INDEX_FILE);
+        
+        if !index_path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        
+        fs::read_to_string(&index_path)
+            .context(format!("Failed to read index file: {}", index_path.display()))?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<IndexEntry>(line)
+                .map(|entry| (entry.url, entry.file))
+                .context(format!("Failed to parse index file: {}", index_path.display())))
+            .collect()
+    }
+    
+    /// Read a page file
+    fn read_page(job_dir: &Path, file_name: &str) -> Result<PageData> {
+        let page_path = job_dir.join(file_name);
+        
+        let contents = fs::read_to_string(&page_path)
+            .context(format!("Failed to read page file: {}", page_path.display()))?;
+        
+        serde_json::from_str(&contents)
+            .context(format!("Failed to parse page file: {}", page_path.display()))
+    }
+    
+    /// Write a page to its file, keeping the creation time of a page stored
+    /// before, and append its URL to the index when it is new
+    fn write_page(job_dir: &Path, file_name: &str, is_new: bool, mut page: PageData) -> Result<PageData> {
+        fs::create_dir_all(job_dir)
+            .context(format!("Failed to create job directory: {}", job_dir.display()))?;
+        
+        if !is_new {
+            page.created_at = Self::read_page(job_dir, file_name)?.created_at;
+        }
+        
+        let page_path = job_dir.join(file_name);
+        let contents = serde_json::to_string_pretty(&page)
+            .context("Failed to serialize page data")?;
+        fs::write(&page_path, contents)
+            .context(format!("Failed to write page file: {}", page_path.display()))?;
+        
+        // The page file is written first, so an indexed URL always has one
+        if is_new {
+            let index_path = job_dir.join(INDEX_FILE);
+            let entry = IndexEntry { url: page.url.clone(), file: file_name.to_string() };
+            let mut line = serde_json::to_string(&entry)
+                .context("Failed to serialize index entry")?;
+            line.push('\n');
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&index_path)
+                .and_then(|mut file| file.write_all(line.as_bytes()))
+                .context(format!("Failed to write index file: {}", index_path.display()))?;
+        }
+        
+        Ok(page)
+    }
+    
+    /// Append a version of a page to the versions file of its URL
+    fn append_version(versions_dir: &Path, version: &PageVersion) -> Result<()> {
+        let path = Self::versions_file(versions_dir, &version.url);
+        let mut line = serde_json::to_string(version)
+            .context("Failed to serialize page version")?;
+        line.push('\n');
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .context(format!("Failed to write page versions file: {}", path.display()))
+    }
+}
+
+#[async_trait]
+impl ProcessedStorage for FilesystemStorage {
+    #[instrument(skip_all, fields(backend = "filesystem", job_id = %job_id, url = %url))]
+    async fn store_page_data(&self, job_id: &str, url: &str, data: serde_json::Value) -> Result<()> {
+        let mut indexes = self.indexes.lock().await;
+        let job_dir = self.job_dir(job_id);
+        
+        // The index is read once per job, later pages only append to it
+        let index = match indexes.entry(job_id.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let dir = job_dir.clone();
+                let index = tokio::task::spawn_blocking(move || Self::read_index(&dir))
+                    .await
+                    .context("Index reader failed")??;
+                entry.insert(index)
+            },
+        };
+        
+        // Reuse the existing file for known URLs
+        let (file_name, is_new) = match index.get(url) {
+            Some(file_name) => (file_name.clone(), false),
+            None => (format!("{:08}.json", index.len() + 1), true),
+        };
+        
+        let now = Utc::now();
+        let page = PageData {
+            job_id: job_id.to_string(),
+            url: url.to_string(),
+            data,
+            created_at: now,
+            updated_at: now,
+        };
+        let versions_dir = self.versions_dir.clone();
+        let written_file = file_name.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let page = Self::write_page(&job_dir, &written_file, is_new, page)?;
+            if let Some(versions_dir) = &versions_dir {
+                let version = PageVersion {
+                    job_id: page.job_id,
+                    url: page.url,
+                    data: page.data,
+                    stored_at: now,
+                };
+                Self::append_version(versions_dir, &version)?;
+            }
+            Ok(())
+        })
+        .await
+        .context("Page writer failed")??;
+        
+        if is_new {
+            index.insert(url.to_string(), file_name);
+        }
+        
+        debug!("Stored processed data for URL: {}", url);
+        
+        Ok(())
+    }
+    
+    async fn get_page_data(&self, job_id: &str, url: &str) -> Result<Option<serde_json::Value>> {
+        let index = self.load_index(job_id)?;
+        
+        match index.get(url) {
+            Some(file_name) => Ok(Some(Self::read_page(&self.job_dir(job_id), file_name)?.data)),
+            None => Ok(None),
+        }
+    }
+    
+    async fn list_pages(&self, job_id: &str) -> Result<Vec<String>> {
+        let index = self.load_index(job_id)?;
+        
+        Ok(index.into_keys().collect())
+    }
+    
+    fn stream_rows<'a>(
+        &'a self,
+        job_id: &'a str,
+        after_url: Option<&'a str>,
+        filter: &'a RowFilter,
+    ) -> BoxStream<'a, Result<PageData>> {
+        Box::pin(try_stream! {
+            let index = self.load_index(job_id)?;
+            
+            // Only the index is held in memory, page files are read one at a time.
+            // The URL pattern is checked against the index before reading a page.
+            let file_names: Vec<String> = match after_url {
+                Some(after_url) => index.range::<str, _>((Bound::Excluded(after_url), Bound::Unbounded))
+                    .filter(|(url, _)| filter.include_url.as_ref().map_or(true, |pattern| pattern.is_match(url)))
+                    .map(|(_, file_name)| file_name.clone())
+                    .collect(),
+                None => index.into_iter()
+                    .filter(|(url, _)| filter.include_url.as_ref().map_or(true, |pattern| pattern.is_match(url)))
+                    .map(|(_, file_name)| file_name)
+                    .collect(),
+            };
+            
+            let job_dir = self.job_dir(job_id);
+            for file_name in file_names {
+                let page = Self::read_page(&job_dir, &file_name)?;
+                if filter.matches(&page.url, page.updated_at) {
+                    yield page;
+                }
+            }
+        })
+    }
+    
+    async fn count_rows(&self, job_id: &str, filter: &RowFilter) -> Result<Option<usize>> {
+        // Timestamps are only in the page files, counting by them means reading every page
+        if filter.since.is_some() || filter.until.is_some() {
+            return Ok(None);
+        }
+        
+        let index = self.load_index(job_id)?;
+        let rows = index.keys()
+            .filter(|url| filter.include_url.as_ref().map_or(true, |pattern| pattern.is_match(url)))
+            .count();
+        
+        Ok(Some(rows))
+    }
+    
+    async fn delete_job(&self, job_id: &str) -> Result<()> {
+        let mut indexes = self.indexes.lock().await;
+        indexes.remove(job_id);
+        
+        let job_dir = self.job_dir(job_id);
+        if job_dir.exists() {
+            fs::remove_dir_all(&job_dir)
+                .context(format!("Failed to delete job directory: {}", job_dir.display()))?;
+        }
+        
+        // Versions files hold every job crawling the URL, only the job's lines are removed
+        if let Some(versions_dir) = &self.versions_dir {
+            let entries = fs::read_dir(versions_dir)
+                .context(format!("Failed to read page versions directory: {}", versions_dir.display()))?;
+            for entry in entries {
+                let path = entry.context("Failed to read page versions directory")?.path();
+                let versions = Self::read_versions(&path)?;
+                let keep: Vec<bool> = versions.iter().map(|version| version.job_id != job_id).collect();
+                Self::rewrite_versions(&path, versions, &keep)?;
+            }
+        }
+        
+        debug!("Deleted job data: {}", job_id);
+        
+        Ok(())
+    }
+    
+    async fn page_history(&self, url: &str, limit: usize) -> Result<Vec<PageVersion>> {
+        let versions_dir = self.versions_dir.as_ref().context(NOT_VERSIONED)?;
+        
+        let mut versions = Self::read_versions(&Self::versions_file(versions_dir, url))?;
+        versions.reverse();
+        versions.truncate(limit);
+        
+        Ok(versions)
+    }
+    
+    async fn prune_versions(&self, retention: &VersionRetention) -> Result<usize> {
+        let Some(versions_dir) = &self.versions_dir else {
+            return Ok(0);
+        };
+        if retention.keeps_all() {
+            return Ok(0);
+        }
+        
+        let _guard = self.indexes.lock().await;
+        
+        let mut pruned = 0;
+        let entries = fs::read_dir(versions_dir)
+            .context(format!("Failed to read page versions directory: {}", versions_dir.display()))?;
+        for entry in entries {
+            let path = entry.context("Failed to read page versions directory")?.path();
+            let versions = Self::read_versions(&path)?;
+            let keep = retained_versions(&versions, retention);
+            pruned += Self::rewrite_versions(&path, versions, &keep)?;
+        }
+        
+        Ok(pruned)
+    }
+}
+
+/// ClickHouse implementation of ProcessedStorage, through its HTTP interface
+///
+/// All jobs share one `<prefix>_pages` table ordered by job and URL. Pages are
+/// buffered and inserted in batches, and besides the JSON document each row
+/// holds the data flattened to `path -> value` pairs for analytical queries.
+/// The ReplacingMergeTree engine keeps the latest version of a page, so
+/// `created_at` is when that version was stored, and reads use FINAL to skip
+/// older versions not merged away yet.
+pub struct ClickHouseStorage {
+    /// HTTP client
+    client: reqwest::Client,
+    
+    /// HTTP endpoint, without credentials
+    endpoint: Url,
+    
+    /// User and password from the connection string
+    credentials: Option<(String, String)>,
+    
+    /// Database name
+    database: String,
+    
+    /// Pages table shared by all jobs
+    table: String,
+    
+    /// Pages waiting to be inserted
+    batch: Mutex<ClickHouseBatch>,
+    
+    /// Pages buffered before an insert
+    batch_size: usize,
+    
+    /// Age after which a partial batch is inserted
+    flush_interval: Duration,
+}
+
+/// Pages buffered for the next ClickHouse insert
+struct ClickHouseBatch {
+    rows: Vec<ClickHouseRow>,
+    started: Instant,
+}
+
+/// Pages table row, as sent and received in the JSONEachRow format
+#[derive(Debug, Serialize, Deserialize)]
+struct ClickHouseRow {
+    job_id: String,
+    url: String,
+    data: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    fields: BTreeMap<String, String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl ClickHouseRow {
+    fn into_page(self) -> Result<PageData> {
+        let data = serde_json::from_str(&self.data)
+            .context(format!("Failed to parse page data of {} from ClickHouse", self.url))?;
+        
+        Ok(PageData {
+            job_id: self.job_id,
+            url: self.url,
+            data,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+// Statements take values as `{name:Type}` query parameters, sent apart from the statement text
+const CH_CREATE_DATABASE: &str = "CREATE DATABASE IF NOT EXISTS {database}";
+
+const CH_CREATE_PAGES: &str = "CREATE TABLE IF NOT EXISTS {table} (
+        job_id String,
+        url String,
+        data String,
+        fields Map(String, String),
+        created_at DateTime64(3, 'UTC'),
+        updated_at DateTime64(3, 'UTC')
+    )
+    ENGINE = ReplacingMergeTree(updated_at)
+    ORDER BY (job_id, url)";
+
+const CH_INSERT_PAGES: &str = "INSERT INTO {table} FORMAT JSONEachRow";
+
+const CH_SELECT_PAGE_DATA: &str = "SELECT data FROM {table} FINAL
+    WHERE job_id = {job_id:String} AND url = {url:String}
+    FORMAT JSONEachRow";
+
+const CH_SELECT_URLS: &str = "SELECT url FROM {table} FINAL
+    WHERE job_id = {job_id:String}
+    ORDER BY url
+    FORMAT JSONEachRow";
+
+// An empty pattern matches every URL, ClickHouse uses RE2 like most of the Rust syntax
+const CH_SELECT_ROWS: &str = "SELECT job_id, url, data, created_at, updated_at
+    FROM {table} FINAL
+    WHERE job_id = {job_id:String} AND url > {after_url:String}
+        AND match(url, {include_url:String})
+        AND ({since:Nullable(DateTime64(3, 'UTC'))} IS NULL OR updated_at >= {since:Nullable(DateTime64(3, 'UTC'))})
+        AND ({until:Nullable(DateTime64(3, 'UTC'))} IS NULL OR updated_at < {until:Nullable(DateTime64(3, 'UTC'))})
+    ORDER BY url
+    FORMAT JSONEachRow";
+
+const CH_COUNT_ROWS: &str = "SELECT toString(count()) AS rows
+    FROM {table} FINAL
+    WHERE job_id = {job_id:String}
+        AND match(url, {include_url:String})
+        AND ({since:Nullable(DateTime64(3, 'UTC'))} IS NULL OR updated_at >= {since:Nullable(DateTime64(3, 'UTC'))})
+        AND ({until:Nullable(DateTime64(3, 'UTC'))} IS NULL OR updated_at < {until:Nullable(DateTime64(3, 'UTC'))})
+    FORMAT JSONEachRow";
+
+const CH_DELETE_JOB: &str = "ALTER TABLE {table} DELETE WHERE job_id = {job_id:String}";
+
+// User queries read the job's rows as `pages`, the first output line holds the column names
+const CH_QUERY: &str = "WITH pages AS (
+        SELECT job_id, url, data, fields, created_at, updated_at FROM {table} FINAL WHERE job_id = {job_id:String}
+    )
+    SELECT * FROM ({query}) AS query
+    LIMIT {max_rows:UInt64}
+    FORMAT JSONCompactEachRowWithNames";
+
+/// Format a time as a ClickHouse query parameter, `\N` standing for NULL
+fn clickhouse_time(time: Option<DateTime<Utc>>) -> String {
+    match time {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        None => "\\N".to_string(),
+    }
+}
+
+/// Flatten a JSON document to `path -> value` pairs, paths join object keys
+/// and array indexes with dots and nulls are left out
+fn flatten_json(value: &serde_json::Value) -> BTreeMap<String, String> {
+    fn walk(value: &serde_json::Value, path: String, fields: &mut BTreeMap<String, String>) {
+        let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+        match value {
+            serde_json::Value::Null => {},
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    walk(value, child(key), fields);
+                }
+            },
+            serde_json::Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    walk(item, child(&index.to_string()), fields);
+                }
+            },
+            serde_json::Value::String(text) => {
+                fields.insert(path, text.clone());
+            },
+            other => {
+                fields.insert(path, other.to_string());
+            },
+        }
+    }
+    
+    let mut fields = BTreeMap::new();
+    walk(value, String::new(), &mut fields);
+    fields
+}
+
+impl ClickHouseStorage {
+    /// Create a new ClickHouse storage instance
+    pub async fn new(settings: &ProcessedDataSettings) -> Result<Self> {
+        let mut endpoint = Url::parse(&settings.connection_string)
+            .context(format!("Failed to parse ClickHouse connection string: {}", settings.connection_string))?;
+        
+        // Credentials go in ClickHouse headers rather than the URL
+        let credentials = if endpoint.username().is_empty() {
+            None
+        } else {
+            Some((endpoint.username().to_string(), endpoint.password().unwrap_or_default().to_string()))
+        };
+        let _ = endpoint.set_username("");
+        let _ = endpoint.set_password(None);
+        
+        if settings.clickhouse.batch_size == 0 {
+            anyhow::bail!("ClickHouse batch_size must be at least 1");
+        }
+        if settings.versioned {
+            anyhow::bail!("Versioned processed storage isn't supported by ClickHouse");
+        }
+        
+        let mut client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(settings.pool.acquire_timeout));
+        if let Some(max_connections) = settings.pool.max_connections {
+            client = client.pool_max_idle_per_host(max_connections as usize);
+        }
+        let client = client.build()
+            .context("Failed to create ClickHouse HTTP client")?;
+        
+        let storage = Self {
+            client,
+            endpoint,
+            credentials,
+            database: quote_identifier(&settings.schema_name),
+            table: format!(
+                "{}.{}",
+                quote_identifier(&settings.schema_name),
+                quote_identifier(&format!("{}_pages", settings.table_prefix))
+            ),
+            batch: Mutex::new(ClickHouseBatch { rows: Vec::new(), started: Instant::now() }),
+            batch_size: settings.clickhouse.batch_size,
+            flush_interval: Duration::from_millis(settings.clickhouse.flush_interval),
+        };
+        
+        // Test connection
+        let storage_ref = &storage;
+        connect_with_retry(&settings.pool, "ClickHouse", || async move {
+            storage_ref.execute("SELECT 1", &[], None).await.map(|_| ())
+        }).await?;
+        
+        storage.ensure_pages_table(settings.clickhouse.ttl_days).await?;
+        
+        debug!("Connected to ClickHouse at {}", storage.endpoint);
+        
+        Ok(storage)
+    }
+    
+    /// Statement text for the pages table, from one of the `CH_*` templates
+    fn sql(&self, template: &str) -> String {
+        template.replace("{database}", &self.database).replace("{table}", &self.table)
+    }
+    
+    /// Run a statement with its query parameters, `body` holding the rows of inserts
+    async fn execute(&self, query: &str, params: &[(&str, String)], body: Option<String>) -> Result<reqwest::Response> {
+        self.execute_with(query, params, &[], body).await
+    }
+    
+    /// Run a statement with extra ClickHouse settings
+    async fn execute_with(
+        &self,
+        query: &str,
+        params: &[(&str, String)],
+        settings: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<reqwest::Response> {
+        let mut request = self.client.post(self.endpoint.clone())
+            .query(&[
+                ("query", query),
+                ("date_time_input_format", "best_effort"),
+                ("date_time_output_format", "iso"),
+                ("mutations_sync", "1"),
+                ("materialize_ttl_after_modify", "0"),
+            ])
+            .query(settings)
+            .query(&params.iter().map(|(name, value)| (format!("param_{}", name), value)).collect::<Vec<_>>())
+            .body(body.unwrap_or_default());
+        if let Some((user, password)) = &self.credentials {
+            request = request
+                .header("X-ClickHouse-User", user)
+                .header("X-ClickHouse-Key", password);
+        }
+        
+        let response = request.send()
+            .await
+            .context("Failed to reach ClickHouse")?;
+        
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            anyhow::bail!("ClickHouse query failed ({}): {}", status, message.trim());
+        }
+        
+        Ok(response)
+    }
+    
+    /// Run a query and parse its JSONEachRow output
+    async fn fetch<T: serde::de::DeserializeOwned>(&self, query: &str, params: &[(&str, String)]) -> Result<Vec<T>> {
+        let body = self.execute(query, params, None).await?
+            .text()
+            .await
+            .context("Failed to read ClickHouse response")?;
+        
+        body.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).context("Failed to parse ClickHouse row"))
+            .collect()
+    }
+    
+    /// Ensure the pages table exists with the configured retention
+    async fn ensure_pages_table(&self, ttl_days: Option<u32>) -> Result<()> {
+        self.execute(&self.sql(CH_CREATE_DATABASE), &[], None).await
+            .context(format!("Failed to create database: {}", self.database))?;
+        self.execute(&self.sql(CH_CREATE_PAGES), &[], None).await
+            .context(format!("Failed to create pages table: {}", self.table))?;
+        
+        // Only the table metadata changes, existing parts expire as they are merged
+        if let Some(days) = ttl_days {
+            let query = format!("ALTER TABLE {} MODIFY TTL toDateTime(updated_at) + INTERVAL {} DAY", self.table, days);
+            self.execute(&query, &[], None).await
+                .context(format!("Failed to set retention of pages table: {}", self.table))?;
+        }
+        
+        debug!("Ensured pages table exists: {}", self.table);
+        
+        Ok(())
+    }
+    
+    /// Insert the buffered pages, keeping them buffered if the insert fails
+    #[instrument(skip_all, fields(backend = "clickhouse", rows = batch.rows.len()))]
+    async fn insert_batch(&self, batch: &mut ClickHouseBatch) -> Result<()> {
+        if batch.rows.is_empty() {
+            return Ok(());
+        }
+        
+        let mut body = String::new();
+        for row in &batch.rows {
+            body.push_str(&serde_json::to_string(row).context("Failed to serialize page data")?);
+            body.push('\n');
+        }
+        
+        self.execute(&self.sql(CH_INSERT_PAGES), &[], Some(body)).await
+            .context("Failed to insert page data into ClickHouse")?;
+        
+        debug!("Inserted {} pages into ClickHouse", batch.rows.len());
+        batch.rows.clear();
+        
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ProcessedStorage for ClickHouseStorage {
+    #[instrument(skip_all, fields(backend = "clickhouse", job_id = %job_id, url = %url))]
+    async fn store_page_data(&self, job_id: &str, url: &str, data: serde_json::Value) -> Result<()> {
+        let now = Utc::now();
+        let row = ClickHouseRow {
+            job_id: job_id.to_string(),
+            url: url.to_string(),
+            fields: flatten_json(&data),
+            data: data.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        
+        let mut batch = self.batch.lock().await;
+        if batch.rows.is_empty() {
+            batch.started = Instant::now();
+        }
+        batch.rows.push(row);
+        
+        if batch.rows.len() >= self.batch_size || batch.started.elapsed() >= self.flush_interval {
+            self.insert_batch(&mut batch).await?;
+        }
+        
+        debug!("Stored processed data for URL: {}", url);
+        
+        Ok(())
+    }
+    
+    async fn get_page_data(&self, job_id: &str, url: &str) -> Result<Option<serde_json::Value>> {
+        // Pages still buffered by this process are read back like stored ones
+        self.flush().await?;
+        
+        #[derive(Deserialize)]
+        struct DataRow {
+            data: String,
+        }
+        
+        let rows: Vec<DataRow> = self.fetch(
+            &self.sql(CH_SELECT_PAGE_DATA),
+            &[("job_id", job_id.to_string()), ("url", url.to_string())],
+        ).await.context("Failed to query page data from ClickHouse")?;
+        
+        rows.into_iter()
+            .next()
+            .map(|row| serde_json::from_str(&row.data).context("Failed to parse page data from ClickHouse"))
+            .transpose()
+    }
+    
+    async fn list_pages(&self, job_id: &str) -> Result<Vec<String>> {
+        self.flush().await?;
+        
+        #[derive(Deserialize)]
+        struct UrlRow {
+            url: String,
+        }
+        
+        let rows: Vec<UrlRow> = self.fetch(&self.sql(CH_SELECT_URLS), &[("job_id", job_id.to_string())])
+            .await
+            .context("Failed to query page URLs from ClickHouse")?;
+        
+        Ok(rows.into_iter().map(|row| row.url).collect())
+    }
+    
+    fn stream_rows<'a>(
+        &'a self,
+        job_id: &'a str,
+        after_url: Option<&'a str>,
+        filter: &'a RowFilter,
+    ) -> BoxStream<'a, Result<PageData>> {
+        Box::pin(try_stream! {
+            self.flush().await?;
+            
+            let params = [
+                ("job_id", job_id.to_string()),
+                ("after_url", after_url.unwrap_or_default().to_string()),
+                ("include_url", filter.include_url.as_ref().map(|pattern| pattern.as_str().to_string()).unwrap_or_default()),
+                ("since", clickhouse_time(filter.since)),
+                ("until", clickhouse_time(filter.until)),
+            ];
+            let mut response = self.execute(&self.sql(CH_SELECT_ROWS), &params, None).await
+                .context("Failed to query page data from ClickHouse")?;
+            
+            // Rows are parsed as their lines arrive rather than after the whole response
+            let mut buffer = Vec::new();
+            while let Some(chunk) = response.chunk().await.context("Failed to read page data from ClickHouse")? {
+                buffer.extend_from_slice(&chunk);
+                while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=end).collect();
+                    let row: ClickHouseRow = serde_json::from_slice(&line)
+                        .context("Failed to parse ClickHouse row")?;
+                    yield row.into_page()?;
+                }
+            }
+        })
+    }
+    
+    async fn count_rows(&self, job_id: &str, filter: &RowFilter) -> Result<Option<usize>> {
+        self.flush().await?;
+        
+        // 64-bit integers are quoted in JSON output, the count is read as text
+        #[derive(Deserialize)]
+        struct CountRow {
+            rows: String,
+        }
+        
+        let params = [
+            ("job_id", job_id.to_string()),
+            ("include_url", filter.include_url.as_ref().map(|pattern| pattern.as_str().to_string()).unwrap_or_default()),
+            ("since", clickhouse_time(filter.since)),
+            ("until", clickhouse_time(filter.until)),
+        ];
+        let rows: Vec<CountRow> = self.fetch(&self.sql(CH_COUNT_ROWS), &params)
+            .await
+            .context("Failed to count page data in ClickHouse")?;
+        
+        rows.first()
+            .map(|row| row.rows.parse().context("Failed to parse ClickHouse row count"))
+            .transpose()
+    }
+    
+    async fn delete_job(&self, job_id: &str) -> Result<()> {
+        // Drop the job's buffered pages so they aren't inserted after the delete
+        let mut batch = self.batch.lock().await;
+        batch.rows.retain(|row| row.job_id != job_id);
+        
+        self.execute(&self.sql(CH_DELETE_JOB), &[("job_id", job_id.to_string())], None).await
+            .context(format!("Failed to delete job data: {}", job_id))?;
+        
+        debug!("Deleted job data: {}", job_id);
+        
+        Ok(())
+    }
+    
+    async fn flush(&self) -> Result<()> {
+        let mut batch = self.batch.lock().await;
+        self.insert_batch(&mut batch).await
+    }
+    
+    async fn query(&self, job_id: &str, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        let sql = check_query(sql)?;
+        self.flush().await?;
+        
+        // readonly=2 refuses writes but still takes the settings sent with every statement
+        let timeout = QUERY_TIMEOUT.as_secs().to_string();
+        let body = self.execute_with(
+            &self.sql(CH_QUERY).replace("{query}", sql),
+            &[("job_id", job_id.to_string()), ("max_rows", (max_rows + 1).to_string())],
+            &[("readonly", "2"), ("max_execution_time", timeout.as_str())],
+            None,
+        ).await
+            .context("Query failed")?
+            .text()
+            .await
+            .context("Failed to read ClickHouse response")?;
+        
+        let mut lines = body.lines().filter(|line| !line.is_empty());
+        let columns: Vec<String> = match lines.next() {
+            Some(line) => serde_json::from_str(line).context("Failed to parse query columns")?,
+            None => Vec::new(),
+        };
+        let rows = lines
+            .map(|line| {
+                let values: Vec<serde_json::Value> = serde_json::from_str(line)
+                    .context("Failed to parse ClickHouse row")?;
+                Ok(values.iter().map(|value| json_cell(Some(value))).collect())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(QueryResult { columns, rows, truncated: false }.limit(max_rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_row_filter() {
+        let filter = RowFilter::parse(Some("/products/"), Some("2024-03-01"), Some("2024-03-02T00:00:00+00:00")).unwrap();
+        let time = |value: &str| parse_timestamp(value).unwrap();
+        
+        assert!(filter.matches("https://example.com/products/1", time("2024-03-01T00:00:00Z")));
+        assert!(filter.matches("https://example.com/products/1", time("2024-03-01T23:59:59Z")));
+        assert!(!filter.matches("https://example.com/products/1", time("2024-03-02T00:00:00Z")));
+        assert!(!filter.matches("https://example.com/products/1", time("2024-02-29T23:59:59Z")));
+        assert!(!filter.matches("https://example.com/about", time("2024-03-01T12:00:00Z")));
+        
+        assert!(RowFilter::default().is_empty());
+        assert!(RowFilter::parse(None, Some("yesterday"), None).is_err());
+        assert!(RowFilter::parse(None, Some("2024-03-02"), Some("2024-03-01")).is_err());
+    }
+    
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn test_pg_statements() {
+        assert_eq!(quote_identifier("pages"), "\"pages\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        
+        // Hostile job IDs end up as a sanitized, quoted identifier
+        let table = PgTable {
+            schema: "crawl\"ed".to_string(),
+            name: NamingRules::POSTGRES.job_identifier("crawled", "x\"; DROP TABLE pages; --", "pages"),
+        };
+        let qualified = table.qualified();
+        assert!(qualified.starts_with("\"crawl\"\"ed\".\"crawled_x___drop_table_pages"));
+        assert!(!qualified.contains(';'));
+        
+        for template in [
+            PG_CREATE_PAGES, PG_UPSERT_PAGE, PG_SELECT_PAGE_DATA, PG_SELECT_URLS, PG_SELECT_ROWS, PG_DROP_PAGES,
+            PG_CREATE_VERSIONS, PG_INSERT_VERSION, PG_SELECT_HISTORY, PG_DELETE_VERSIONS, PG_COUNT_ROWS,
+        ] {
+            let sql = table.sql(template);
+            assert!(sql.contains(&qualified));
+            assert!(!sql.contains("{table}"));
+            assert!(!sql.contains('\''));
+        }
+    }
+    
+    /// Runs against the PostgreSQL at CRAWLER_TEST_POSTGRES_URL, or a local one
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server"]
+    async fn test_pg_hostile_values() {
+        let connection_string = std::env::var("CRAWLER_TEST_POSTGRES_URL").unwrap_or_else(|_| "postgres://postgres@127.0.0.1:5432/postgres".to_string());
+        let settings: ProcessedDataSettings = serde_json::from_value(serde_json::json!({
+            "storage_type": "postgresql",
+            "connection_string": connection_string,
+            "schema_name": "crawler_test",
+            "table_prefix": "crawled",
+            "versioned": true,
+        })).unwrap();
+        let storage = PostgresStorage::new(&settings).await.unwrap();
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let other_url = "https://example.com/about";
+        
+        // Values go to the server as bound parameters, so a hostile URL is stored as is
+        let url = "https://example.com/'); DROP TABLE pages; --";
+        let data = serde_json::json!({ "title": "'; DELETE FROM pages; --" });
+        storage.store_page_data(&job_id, url, data.clone()).await.unwrap();
+        storage.store_page_data(&job_id, other_url, serde_json::json!({ "title": "About" })).await.unwrap();
+        
+        let pages = storage.list_pages(&job_id).await;
+        let stored = storage.get_page_data(&job_id, url).await;
+        storage.delete_job(&job_id).await.unwrap();
+        
+        let mut pages = pages.unwrap();
+        pages.sort();
+        assert_eq!(pages, vec![url.to_string(), other_url.to_string()]);
+        assert_eq!(stored.unwrap(), Some(data));
+    }
+    
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_round_trip() {
+        use futures::TryStreamExt;
+        
+        // The journal files sit next to the database
+        let dir = std::env::temp_dir().join(format!("processed-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let settings = ProcessedDataSettings {
+            storage_type: "sqlite".to_string(),
+            connection_string: format!("sqlite://{}", dir.join("pages.db").display()),
+            schema_name: String::new(),
+            table_prefix: "crawled".to_string(),
+            read_connection_string: None,
+            versioned: true,
+            version_retention: Default::default(),
+            clickhouse: Default::default(),
+            pool: Default::default(),
+        };
+        let storage = SqliteStorage::new(&settings).await.unwrap();
+        let filter = RowFilter::parse(None, None, None).unwrap();
+        let job_id = "x\"; DROP TABLE pages; --";
+        let (a, b) = ("https://example.com/a", "https://example.com/b?q=');--");
+        
+        assert_eq!(storage.get_page_data(job_id, a).await.unwrap(), None);
+        storage.store_page_data(job_id, b, serde_json::json!({"title": "B"})).await.unwrap();
+        storage.store_page_data(job_id, a, serde_json::json!({"title": "A", "tags": ["x", "y"]})).await.unwrap();
+        storage.store_page_data(job_id, b, serde_json::json!({"title": "B2"})).await.unwrap();
+        
+        assert_eq!(storage.list_pages(job_id).await.unwrap(), vec![a, b]);
+        assert_eq!(storage.get_page_data(job_id, a).await.unwrap(), Some(serde_json::json!({"title": "A", "tags": ["x", "y"]})));
+        assert_eq!(storage.get_page_data(job_id, b).await.unwrap(), Some(serde_json::json!({"title": "B2"})));
+        assert_eq!(storage.count_rows(job_id, &filter).await.unwrap(), Some(2));
+        
+        let rows: Vec<PageData> = storage.stream_rows(job_id, Some(a), &filter).try_collect().await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].url, b);
+        assert!(rows[0].created_at <= rows[0].updated_at);
+        assert_eq!(storage.page_history(b, 10).await.unwrap().len(), 2);
+        
+        storage.delete_job(job_id).await.unwrap();
+        assert!(storage.list_pages(job_id).await.unwrap().is_empty());
+        assert!(storage.page_history(b, 10).await.unwrap().is_empty());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_retained_versions() {
+        let start = DateTime::parse_from_rfc3339("2026-03-02T12:00:00Z").unwrap().with_timezone(&Utc);
+        let version = |day: i64, price: &str| PageVersion {
+            job_id: format!("job-{}", day),
+            url: "https://example.com/item".to_string(),
+            data: serde_json::json!({ "price": price }),
+            stored_at: start + chrono::Duration::days(day),
+        };
+        // Two weeks of crawls, then one on each of the next two days
+        let versions = vec![
+            version(0, "10"), version(2, "10"), version(4, "11"),
+            version(7, "11"), version(9, "12"), version(11, "12"),
+            version(14, "12"), version(15, "13"),
+        ];
+        let kept = |retention: VersionRetention| -> Vec<i64> {
+            retained_versions(&versions, &retention).iter()
+                .zip(&versions)
+                .filter(|(keep, _)| **keep)
+                .map(|(_, version)| (version.stored_at - start).num_days())
+                .collect()
+        };
+        
+        assert_eq!(kept(VersionRetention::default()), vec![0, 2, 4, 7, 9, 11, 14, 15]);
+        assert_eq!(kept(VersionRetention { changed_only: true, ..Default::default() }), vec![0, 4, 9, 15]);
+        assert_eq!(kept(VersionRetention { keep_last: Some(2), ..Default::default() }), vec![14, 15]);
+        assert_eq!(kept(VersionRetention { keep_weekly: true, ..Default::default() }), vec![4, 11, 15]);
+        assert_eq!(
+            kept(VersionRetention { keep_last: Some(3), keep_weekly: true, ..Default::default() }),
+            vec![4, 11, 14, 15]
+        );
+        assert_eq!(
+            kept(VersionRetention { keep_last: Some(1), keep_weekly: true, changed_only: true }),
+            vec![4, 9, 15]
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_filesystem_page_history() {
+        let dir = std::env::temp_dir().join(format!("page-history-{}", uuid::Uuid::new_v4()));
+        let settings = ProcessedDataSettings {
+            storage_type: "filesystem".to_string(),
+            connection_string: dir.to_string_lossy().into_owned(),
+            schema_name: String::new(),
+            table_prefix: "crawled".to_string(),
+            read_connection_string: None,
+            versioned: true,
+            version_retention: Default::default(),
+            clickhouse: Default::default(),
+            pool: Default::default(),
+        };
+        let storage = FilesystemStorage::new(&settings).unwrap();
+        let url = "https://example.com/item";
+        
+        storage.store_page_data("job-1", url, serde_json::json!({"price": "10"})).await.unwrap();
+        storage.store_page_data("job-1", url, serde_json::json!({"price": "11"})).await.unwrap();
+        storage.store_page_data("job-2", url, serde_json::json!({"price": "12"})).await.unwrap();
+        
+        // The job keeps its latest version, the history has all of them
+        assert_eq!(storage.get_page_data("job-1", url).await.unwrap(), Some(serde_json::json!({"price": "11"})));
+        let prices = |versions: Vec<PageVersion>| -> Vec<String> {
+            versions.iter().map(|version| format!("{}:{}", version.job_id, version.data["price"])).collect()
+        };
+        assert_eq!(
+            prices(storage.page_history(url, 10).await.unwrap()),
+            vec!["job-2:\"12\"", "job-1:\"11\"", "job-1:\"10\""]
+        );
+        assert_eq!(storage.page_history(url, 1).await.unwrap().len(), 1);
+        
+        storage.delete_job("job-1").await.unwrap();
+        assert_eq!(prices(storage.page_history(url, 10).await.unwrap()), vec!["job-2:\"12\""]);
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_filesystem_round_trip() {
+        use futures::TryStreamExt;
+        
+        let dir = std::env::temp_dir().join(format!("processed-{}", uuid::Uuid::new_v4()));
+        let settings = ProcessedDataSettings {
+            storage_type: "filesystem".to_string(),
+            connection_string: dir.to_string_lossy().into_owned(),
+            schema_name: String::new(),
+            table_prefix: "crawled".to_string(),
+            read_connection_string: None,
+            versioned: false,
+            version_retention: Default::default(),
+            clickhouse: Default::default(),
+            pool: Default::default(),
+        };
+        let storage = FilesystemStorage::new(&settings).unwrap();
+        let filter = RowFilter::parse(None, None, None).unwrap();
+        let (a, b) = ("https://example.com/a", "https://example.com/b");
+        
+        storage.store_page_data("job", b, serde_json::json!({"title": "B"})).await.unwrap();
+        storage.store_page_data("job", a, serde_json::json!({"title": "A"})).await.unwrap();
+        let stored: Vec<PageData> = storage.stream_rows("job", Some(a), &filter).try_collect().await.unwrap();
+        storage.store_page_data("job", b, serde_json::json!({"title": "B2"})).await.unwrap();
+        
+        // Each URL is indexed once, an update only rewrites its page file
+        let index = fs::read_to_string(storage.job_dir("job").join(INDEX_FILE)).unwrap();
+        assert_eq!(index.lines().count(), 2);
+        
+        // Another instance reads what the first one wrote
+        let reopened = FilesystemStorage::new(&settings).unwrap();
+        assert_eq!(reopened.list_pages("job").await.unwrap(), vec![a, b]);
+        assert_eq!(reopened.get_page_data("job", b).await.unwrap(), Some(serde_json::json!({"title": "B2"})));
+        assert_eq!(reopened.get_page_data("job", "https://example.com/c").await.unwrap(), None);
+        assert_eq!(reopened.count_rows("job", &filter).await.unwrap(), Some(2));
+        
+        let rows: Vec<PageData> = reopened.stream_rows("job", Some(a), &filter).try_collect().await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].url, b);
+        assert_eq!(rows[0].created_at, stored[0].created_at);
+        assert!(rows[0].updated_at >= stored[0].updated_at);
+        
+        // Pages stored after the job was deleted start a new index
+        storage.delete_job("job").await.unwrap();
+        assert!(reopened.list_pages("job").await.unwrap().is_empty());
+        storage.store_page_data("job", a, serde_json::json!({"title": "A"})).await.unwrap();
+        assert_eq!(storage.list_pages("job").await.unwrap(), vec![a]);
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_flatten_json() {
+        let data = serde_json::json!({
+            "title": "Widget",
+            "price": 9.5,
+            "tags": ["new", "sale"],
+            "seller": { "name": "Shop", "rating": null },
+            "in_stock": true,
+        });
+        
+        let fields = flatten_json(&data);
+        assert_eq!(fields.get("title").map(String::as_str), Some("Widget"));
+        assert_eq!(fields.get("price").map(String::as_str), Some("9.5"));
+        assert_eq!(fields.get("tags.1").map(String::as_str), Some("sale"));
+        assert_eq!(fields.get("seller.name").map(String::as_str), Some("Shop"));
+        assert_eq!(fields.get("in_stock").map(String::as_str), Some("true"));
+        assert!(!fields.contains_key("seller.rating"));
+        assert_eq!(fields.len(), 6);
+        
+        assert_eq!(clickhouse_time(None), "\\N");
+        assert_eq!(clickhouse_time(Some(parse_timestamp("2024-03-01").unwrap())), "2024-03-01 00:00:00.000");
+    }
 }
\ No newline at end of file
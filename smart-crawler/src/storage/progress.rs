@@ -0,0 +1,16 @@
+use std::fmt;
+
+/// Receives the progress of a long storage operation, like an export
+///
+/// Operations call `set_total` once the storage has counted the rows they go
+/// through, backends that can't count them cheaply leave it out.
+pub trait RowProgress: Send + Sync + fmt::Debug {
+    /// Rows the operation goes through
+    fn set_total(&self, rows: u64);
+    
+    /// Rows done since the last call
+    fn advance(&self, rows: u64);
+    
+    /// The operation is over
+    fn finish(&self);
+}
\ No newline at end of file
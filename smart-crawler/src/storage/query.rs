@@ -0,0 +1,211 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::storage::export::csv_field;
+
+/// Longest a query over processed storage may run
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Rows of a query, every value as text
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+    
+    /// Whether the query returned more rows than were kept
+    pub truncated: bool,
+}
+
+/// How query results are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl QueryFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format.to_lowercase().as_str() {
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!("Unsupported query output format: {}", format),
+        }
+    }
+}
+
+/// Check a query is a single SELECT, returning it without a trailing semicolon
+///
+/// Backends also run queries read-only, this gives a clear error before
+/// reaching them. Semicolons can't appear anywhere else in the query.
+pub fn check_query(sql: &str) -> Result<&str> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    
+    let keyword = sql.split_whitespace().next().unwrap_or_default().to_lowercase();
+    if keyword != "select" && keyword != "with" {
+        anyhow::bail!("Only SELECT queries are allowed");
+    }
+    if sql.contains(';') {
+        anyhow::bail!("Only a single statement is allowed");
+    }
+    
+    Ok(sql)
+}
+
+/// Text of a JSON value as a query cell, strings without their quotes
+pub fn json_cell(value: Option<&serde_json::Value>) -> Option<String> {
+    match value {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(text)) => Some(text.clone()),
+        Some(other) => Some(other.to_string()),
+    }
+}
+
+impl QueryResult {
+    /// Keep the first `max_rows` rows, of a query asked for one more to tell if there are others
+    pub fn limit(mut self, max_rows: usize) -> Self {
+        if self.rows.len() > max_rows {
+            self.rows.truncate(max_rows);
+            self.truncated = true;
+        }
+        self
+    }
+    
+    /// Render the rows, NULL values are empty in CSV and null in JSON
+    pub fn render(&self, format: QueryFormat) -> String {
+        match format {
+            QueryFormat::Table => self.render_table(),
+            QueryFormat::Csv => {
+                let mut output = self.columns.iter()
+                    .map(|column| csv_field(column))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                output.push('\n');
+                for row in &self.rows {
+                    let line = row.iter()
+                        .map(|value| csv_field(value.as_deref().unwrap_or_default()))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+                output
+            },
+            QueryFormat::Json => {
+                // Objects are written by hand to keep the column order
+                let objects: Vec<String> = self.rows.iter()
+                    .map(|row| {
+                        let fields: Vec<String> = self.columns.iter()
+                            .zip(row)
+                            .map(|(column, value)| format!(
+                                "{}: {}",
+                                serde_json::Value::from(column.as_str()),
+                                value.as_deref().map_or(serde_json::Value::Null, serde_json::Value::from)
+                            ))
+                            .collect();
+                        format!("  {{{}}}", fields.join(", "))
+                    })
+                    .collect();
+                if objects.is_empty() {
+                    "[]\n".to_string()
+                } else {
+                    format!("[\n{}\n]\n", objects.join(",\n"))
+                }
+            },
+        }
+    }
+    
+    /// Aligned text table, long values are cut
+    fn render_table(&self) -> String {
+        const MAX_WIDTH: usize = 60;
+        
+        let cell = |value: &Option<String>| -> String {
+            let value = value.as_deref().unwrap_or("NULL").replace('\n', " ");
+            if value.chars().count() > MAX_WIDTH {
+                format!("{}...", value.chars().take(MAX_WIDTH - 3).collect::<String>())
+            } else {
+                value
+            }
+        };
+        let rows: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().map(cell).collect())
+            .collect();
+        
+        let mut widths: Vec<usize> = self.columns.iter().map(|column| column.chars().count()).collect();
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+        
+        let line = |values: &[String]| -> String {
+            values.iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{:<width$}", value, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+        
+        let mut output = line(&self.columns);
+        output.push('\n');
+        for row in &rows {
+            output.push_str(&line(row));
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "({} row{}{})\n",
+            rows.len(),
+            if rows.len() == 1 { "" } else { "s" },
+            if self.truncated { ", more were cut by the limit" } else { "" }
+        ));
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_check_query() {
+        assert_eq!(check_query("  SELECT url FROM pages;\n").unwrap(), "SELECT url FROM pages");
+        assert!(check_query("with recent as (select * from pages) select count(*) from recent").is_ok());
+        
+        assert!(check_query("DELETE FROM pages").is_err());
+        assert!(check_query("SELECT 1; DROP TABLE pages").is_err());
+        assert!(check_query("").is_err());
+    }
+    
+    #[test]
+    fn test_render() {
+        let result = QueryResult {
+            columns: vec!["url".to_string(), "title".to_string()],
+            rows: vec![
+                vec![Some("https://example.com/a".to_string()), Some("Shoes, \"red\"".to_string())],
+                vec![Some("https://example.com/b".to_string()), None],
+            ],
+            truncated: false,
+        };
+        
+        assert_eq!(
+            result.render(QueryFormat::Csv),
+            "url,title\nhttps://example.com/a,\"Shoes, \"\"red\"\"\"\nhttps://example.com/b,\n"
+        );
+        assert_eq!(
+            result.render(QueryFormat::Json),
+            "[\n  {\"url\": \"https://example.com/a\", \"title\": \"Shoes, \\\"red\\\"\"},\n  {\"url\": \"https://example.com/b\", \"title\": null}\n]\n"
+        );
+        
+        let table = result.render(QueryFormat::Table);
+        assert!(table.starts_with("url                    title\n"));
+        assert!(table.contains("https://example.com/b  NULL\n"));
+        assert!(table.ends_with("(2 rows)\n"));
+        
+        let limited = result.clone().limit(1);
+        assert!(limited.truncated);
+        assert_eq!(limited.rows.len(), 1);
+    }
+}
\ No newline at end of file
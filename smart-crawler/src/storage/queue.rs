@@ -1,13 +1,119 @@
 use anyhow::{Result, Context};
-use redis::{Client, aio::MultiplexedConnection};
-use tracing::{debug, error};
-use tokio::sync::Mutex;
-use std::sync::Arc;
+use chrono::{DateTime, TimeZone, Utc};
+use redis::{Client, Script, aio::ConnectionManager};
+use serde::{Serialize, Deserialize};
+use tracing::{debug, error, instrument, warn};
+use tokio::sync::{Mutex, MutexGuard};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use crate::cli::config::QueueSettings;
-use crate::crawler::task::CrawlTask;
+use crate::crawler::schedule::CrawlSchedule;
+use crate::crawler::task::{CrawlTask, DeadLetter, RecordPreview};
+use crate::storage::retry::connect_with_retry;
+
+/// How many tasks from the head of the queue a pop looks through for one
+/// whose domain is ready
+const FRONTIER_WINDOW: usize = 100;
+
+/// Record previews kept per job, newest first
+const PREVIEW_RECORDS: usize = 20;
+
+// Pops the task of highest priority, nearest the head of the queue among
+// equals, whose domain is past its next request time, and reserves the
+// domain's following slot for the worker. The task is moved to the processing
+// set and leased in the same step, so it can't be lost between the two.
+const POP_READY: &str = r#"
+local tasks = redis.call('LRANGE', KEYS[1], -tonumber(ARGV[3]), -1)
+local now = tonumber(ARGV[1])
+local best, best_url, best_host, best_priority
+for i = #tasks, 1, -1 do
+    local ok, task = pcall(cjson.decode, tasks[i])
+    local host = ok and type(task.url) == 'string' and string.match(task.url, '^%a[%w+.-]*://([^/?#]*)')
+    if host then
+        host = string.gsub(host, '^.*@', '')
+        host = string.lower((string.gsub(host, ':%d*$', '')))
+    end
+    local next_at = 0
+    if host then
+        next_at = tonumber(redis.call('HGET', KEYS[2], host) or '0')
+    end
+    if next_at <= now then
+        local priority = ok and tonumber(task.priority) or 0
+        if not best or priority > best_priority then
+            best, best_url, best_host, best_priority = tasks[i], task.url, host, priority
+        end
+    end
+end
+if not best then
+    return false
+end
+redis.call('LREM', KEYS[1], -1, best)
+if best_host then
+    local delay = tonumber(redis.call('HGET', KEYS[3], best_host) or ARGV[2])
+    redis.call('HSET', KEYS[2], best_host, now + delay)
+    redis.call('EXPIRE', KEYS[2], ARGV[4])
+end
+if type(best_url) == 'string' then
+    redis.call('SADD', KEYS[4], best_url)
+    if redis.call('TTL', KEYS[4]) == -1 then
+        redis.call('EXPIRE', KEYS[4], ARGV[4])
+    end
+    local lease = '{"worker_id":' .. cjson.encode(ARGV[5]) .. ',"task":' .. best .. ',"leased_at":' .. ARGV[7] .. '}'
+    redis.call('HSET', KEYS[5], best_url, lease)
+    redis.call('ZADD', KEYS[6], ARGV[6], best_url)
+    redis.call('EXPIRE', KEYS[5], ARGV[4])
+    redis.call('EXPIRE', KEYS[6], ARGV[4])
+end
+return best
+"#;
+
+// Adds the cost of a captcha solve to a job's spend unless it would go over
+// the limit, 0 being none. A job without a counter yet starts from the spend
+// of its status.
+const RESERVE_SPEND: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    redis.call('SET', KEYS[1], ARGV[4])
+end
+local limit = tonumber(ARGV[2])
+if limit > 0 and tonumber(redis.call('GET', KEYS[1])) + tonumber(ARGV[1]) > limit + 1e-9 then
+    return false
+end
+local spent = redis.call('INCRBYFLOAT', KEYS[1], ARGV[1])
+redis.call('EXPIRE', KEYS[1], ARGV[3])
+return spent
+";
+
+/// Task counters of a job's queue
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueCounts {
+    pub pending: usize,
+    pub processing: usize,
+    pub completed: usize,
+    pub failed: usize,
+    
+    /// Workers still sending heartbeats
+    pub workers: usize,
+}
+
+/// Task popped by a worker, held until the worker completes or fails it
+#[derive(Debug, Serialize, Deserialize)]
+struct Lease {
+    worker_id: String,
+    task: CrawlTask,
+    
+    /// Unix time the task was popped
+    leased_at: i64,
+}
 
 /// Queue manager for task distribution
+///
+/// Workers register with heartbeats and every popped task is leased to its
+/// worker. Leases are renewed by the heartbeats of the worker holding them,
+/// a lease that expires or whose worker stopped sending heartbeats is
+/// reclaimed and its task returned to the queue. Tasks are delivered at
+/// least once, a worker that was only slow may see its task crawled again.
 pub struct QueueManager {
     /// Redis client
     client: Client,
@@ -15,8 +121,31 @@ pub struct QueueManager {
     /// Task TTL in seconds
     task_ttl: u64,
     
-    /// Connection pool
-    conn_pool: Arc<Mutex<MultiplexedConnection>>,
+    /// Seconds between worker heartbeats
+    heartbeat_interval: u64,
+    
+    /// Seconds without a heartbeat before a worker counts as dead
+    worker_timeout: u64,
+    
+    /// Seconds a task stays leased without being renewed
+    lease_timeout: u64,
+    
+    /// Seconds a task may stay processing before it is requeued, 0 never
+    stale_after: u64,
+    
+    /// Connection pool, connections are handed out in turn and reconnect
+    /// on their own after a failure
+    conn_pool: Vec<Mutex<ConnectionManager>>,
+    
+    /// Next connection to hand out
+    next_conn: AtomicUsize,
+    
+    /// Longest wait for a free connection
+    acquire_timeout: Duration,
+    
+    pop_ready: Script,
+    
+    reserve_spend: Script,
 }
 
 impl QueueManager {
@@ -25,24 +154,535 @@ impl QueueManager {
         let client = Client::open(config.redis_url.clone())
             .context(format!("Failed to connect to Redis at {}", config.redis_url))?;
         
-        let conn = client.get_multiplexed_async_connection().await
-            .context("Failed to get Redis connection")?;
+        let leases = &config.leases;
+        if leases.heartbeat_interval == 0
+            || leases.heartbeat_interval >= leases.worker_timeout
+            || leases.heartbeat_interval >= leases.lease_timeout
+        {
+            anyhow::bail!(
+                "Worker heartbeat interval ({}s) must be positive and shorter than the worker timeout ({}s) and lease timeout ({}s)",
+                leases.heartbeat_interval, leases.worker_timeout, leases.lease_timeout
+            );
+        }
         
-        let conn_pool = Arc::new(Mutex::new(conn));
+        let pool_size = config.pool.max_connections.unwrap_or(1).max(1);
+        let mut conn_pool = Vec::with_capacity(pool_size as usize);
+        for _ in 0..pool_size {
+            let conn = connect_with_retry(&config.pool, "Redis", || {
+                let client = client.clone();
+                async move {
+                    ConnectionManager::new(client).await
+                        .context("Failed to get Redis connection")
+                }
+            }).await?;
+            conn_pool.push(Mutex::new(conn));
+        }
         
         Ok(Self {
             client,
             task_ttl: config.task_ttl,
+            heartbeat_interval: leases.heartbeat_interval,
+            worker_timeout: leases.worker_timeout,
+            lease_timeout: leases.lease_timeout,
+            stale_after: leases.stale_after,
             conn_pool,
+            next_conn: AtomicUsize::new(0),
+            acquire_timeout: Duration::from_secs(config.pool.acquire_timeout),
+            pop_ready: Script::new(POP_READY),
+            reserve_spend: Script::new(RESERVE_SPEND),
         })
     }
     
+    /// Take the next connection of the pool
+    async fn conn(&self) -> Result<MutexGuard<'_, ConnectionManager>> {
+        let index = self.next_conn.fetch_add(1, Ordering::Relaxed) % self.conn_pool.len();
+        tokio::time::timeout(self.acquire_timeout, self.conn_pool[index].lock())
+            .await
+            .context("Timed out waiting for a Redis connection")
+    }
+    
     /// Connect to an existing queue
     pub async fn connect(config: &QueueSettings) -> Result<Self> {
         Self::new(config).await
     }
     
-    /// Push a task to the queue
+    /// Time between the heartbeats workers should send
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_interval)
+    }
+    
+    /// Register a worker of a job or keep it registered, renewing the lease
+    /// of the task it is working on
+    #[instrument(skip_all, fields(job_id = %job_id, worker_id = %worker_id))]
+    pub async fn heartbeat(&self, worker_id: &str, job_id: &str, leased_url: Option<&str>) -> Result<()> {
+        let worker_key = format!("crawler:worker:{}", worker_id);
+        let workers_key = format!("crawler:workers:{}", job_id);
+        let leases_key = format!("crawler:leases:{}", job_id);
+        let job_heartbeat_key = format!("crawler:job_heartbeat:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        // The worker counts as alive for as long as its key exists
+        redis::cmd("SET")
+            .arg(&worker_key)
+            .arg(job_id)
+            .arg("EX")
+            .arg(self.worker_timeout)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to store worker heartbeat")?;
+        
+        redis::cmd("SADD")
+            .arg(&workers_key)
+            .arg(worker_id)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to register worker")?;
+        
+        redis::cmd("EXPIRE")
+            .arg(&workers_key)
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to set TTL on workers")?;
+        
+        // Time of the latest heartbeat of any of the job's workers, outliving
+        // the workers so jobs left without any can be told apart
+        redis::cmd("SET")
+            .arg(&job_heartbeat_key)
+            .arg(Utc::now().timestamp())
+            .arg("EX")
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to store job heartbeat")?;
+        
+        if let Some(url) = leased_url {
+            // XX only renews, a lease that was already reclaimed stays gone
+            redis::cmd("ZADD")
+                .arg(&leases_key)
+                .arg("XX")
+                .arg(self.lease_expiry())
+                .arg(url)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to renew task lease")?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Remove a worker that is stopping
+    pub async fn deregister_worker(&self, worker_id: &str, job_id: &str) -> Result<()> {
+        let mut conn = self.conn().await?;
+        
+        redis::cmd("DEL")
+            .arg(format!("crawler:worker:{}", worker_id))
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to delete worker heartbeat")?;
+        
+        redis::cmd("SREM")
+            .arg(format!("crawler:workers:{}", job_id))
+            .arg(worker_id)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to deregister worker")?;
+        
+        debug!("Deregistered worker {} of job: {}", worker_id, job_id);
+        
+        Ok(())
+    }
+    
+    /// Time of the latest heartbeat of any worker of a job, None when no worker
+    /// sent one since the job's queue keys last expired
+    pub async fn last_heartbeat(&self, job_id: &str) -> Result<Option<DateTime<Utc>>> {
+        let mut conn = self.conn().await?;
+        
+        let seconds: Option<i64> = redis::cmd("GET")
+            .arg(format!("crawler:job_heartbeat:{}", job_id))
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read job heartbeat")?;
+        
+        Ok(seconds.and_then(|seconds| Utc.timestamp_opt(seconds, 0).single()))
+    }
+    
+    /// Get the number of workers of a job still sending heartbeats, dropping
+    /// the registrations of dead ones
+    pub async fn get_worker_count(&self, job_id: &str) -> Result<usize> {
+        let workers_key = format!("crawler:workers:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let workers: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(&workers_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read workers")?;
+        
+        let mut alive = 0;
+        for worker_id in &workers {
+            let exists: bool = redis::cmd("EXISTS")
+                .arg(format!("crawler:worker:{}", worker_id))
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to check worker heartbeat")?;
+            
+            if exists {
+                alive += 1;
+            } else {
+                redis::cmd("SREM")
+                    .arg(&workers_key)
+                    .arg(worker_id)
+                    .query_async::<_, ()>(&mut *conn)
+                    .await
+                    .context("Failed to deregister worker")?;
+            }
+        }
+        
+        Ok(alive)
+    }
+    
+    /// Return the tasks of expired leases and of dead workers to the queue
+    ///
+    /// Several workers may reclaim at once, only the one removing a lease
+    /// requeues its task.
+    #[instrument(skip_all, fields(job_id = %job_id))]
+    pub async fn reclaim_expired_tasks(&self, job_id: &str) -> Result<usize> {
+        let queue_key = format!("crawler:queue:{}", job_id);
+        let processing_key = format!("crawler:processing:{}", job_id);
+        let leases_key = format!("crawler:leases:{}", job_id);
+        let leased_tasks_key = format!("crawler:leased_tasks:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let leases: Vec<(String, i64)> = redis::cmd("ZRANGE")
+            .arg(&leases_key)
+            .arg(0)
+            .arg(-1)
+            .arg("WITHSCORES")
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read task leases")?;
+        
+        let now = Utc::now().timestamp();
+        let mut reclaimed = 0;
+        for (url, expires_at) in leases {
+            let lease_json: Option<String> = redis::cmd("HGET")
+                .arg(&leased_tasks_key)
+                .arg(&url)
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to read leased task")?;
+            let Some(lease_json) = lease_json else {
+                continue;
+            };
+            let lease: Lease = serde_json::from_str(&lease_json)
+                .context("Failed to deserialize leased task")?;
+            
+            if expires_at > now {
+                let alive: bool = redis::cmd("EXISTS")
+                    .arg(format!("crawler:worker:{}", lease.worker_id))
+                    .query_async(&mut *conn)
+                    .await
+                    .context("Failed to check worker heartbeat")?;
+                if alive {
+                    continue;
+                }
+            }
+            
+            let removed: usize = redis::cmd("ZREM")
+                .arg(&leases_key)
+                .arg(&url)
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to remove task lease")?;
+            if removed == 0 {
+                continue;
+            }
+            
+            redis::cmd("HDEL")
+                .arg(&leased_tasks_key)
+                .arg(&url)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to remove leased task")?;
+            
+            redis::cmd("SREM")
+                .arg(&processing_key)
+                .arg(&url)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to remove URL from processing set")?;
+            
+            let mut task = lease.task;
+            task.attempt += 1;
+            let task_json = serde_json::to_string(&task)
+                .context("Failed to serialize task")?;
+            
+            // Pushed to the end tasks are popped from, so it is picked up next
+            redis::cmd("RPUSH")
+                .arg(&queue_key)
+                .arg(&task_json)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to push task to Redis queue")?;
+            
+            warn!("Returned task of worker {} to the queue: {}", lease.worker_id, url);
+            reclaimed += 1;
+        }
+        
+        Ok(reclaimed)
+    }
+    
+    /// Requeue tasks that have been processing for longer than `stale_after`
+    ///
+    /// This catches what leases don't, workers still sending heartbeats while
+    /// stuck on a task and URLs in the processing set without a lease. Those
+    /// are requeued as seeds once they have been seen without a lease for
+    /// that long, the age they had before is unknown.
+    #[instrument(skip_all, fields(job_id = %job_id))]
+    pub async fn requeue_stale_tasks(&self, job_id: &str) -> Result<usize> {
+        if self.stale_after == 0 {
+            return Ok(0);
+        }
+        
+        let queue_key = format!("crawler:queue:{}", job_id);
+        let processing_key = format!("crawler:processing:{}", job_id);
+        let leases_key = format!("crawler:leases:{}", job_id);
+        let leased_tasks_key = format!("crawler:leased_tasks:{}", job_id);
+        let unleased_key = format!("crawler:unleased:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let processing: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(&processing_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read processing set")?;
+        
+        let now = Utc::now().timestamp();
+        let stale = |since: i64| now - since >= self.stale_after as i64;
+        let mut requeued = 0;
+        for url in &processing {
+            let lease_json: Option<String> = redis::cmd("HGET")
+                .arg(&leased_tasks_key)
+                .arg(url)
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to read leased task")?;
+            
+            let mut task = match lease_json {
+                Some(lease_json) => {
+                    redis::cmd("HDEL")
+                        .arg(&unleased_key)
+                        .arg(url)
+                        .query_async::<_, ()>(&mut *conn)
+                        .await
+                        .context("Failed to update unleased tasks")?;
+                    
+                    let lease: Lease = serde_json::from_str(&lease_json)
+                        .context("Failed to deserialize leased task")?;
+                    if !stale(lease.leased_at) {
+                        continue;
+                    }
+                    
+                    let removed: usize = redis::cmd("ZREM")
+                        .arg(&leases_key)
+                        .arg(url)
+                        .query_async(&mut *conn)
+                        .await
+                        .context("Failed to remove task lease")?;
+                    if removed == 0 {
+                        continue;
+                    }
+                    
+                    redis::cmd("HDEL")
+                        .arg(&leased_tasks_key)
+                        .arg(url)
+                        .query_async::<_, ()>(&mut *conn)
+                        .await
+                        .context("Failed to remove leased task")?;
+                    
+                    lease.task
+                },
+                None => {
+                    // Tasks are added to the processing set just before their lease
+                    let seen: Option<i64> = redis::cmd("HGET")
+                        .arg(&unleased_key)
+                        .arg(url)
+                        .query_async(&mut *conn)
+                        .await
+                        .context("Failed to read unleased tasks")?;
+                    
+                    match seen {
+                        Some(seen) if stale(seen) => {},
+                        Some(_) => continue,
+                        None => {
+                            redis::cmd("HSETNX")
+                                .arg(&unleased_key)
+                                .arg(url)
+                                .arg(now)
+                                .query_async::<_, ()>(&mut *conn)
+                                .await
+                                .context("Failed to record unleased task")?;
+                            continue;
+                        },
+                    }
+                    
+                    let removed: usize = redis::cmd("HDEL")
+                        .arg(&unleased_key)
+                        .arg(url)
+                        .query_async(&mut *conn)
+                        .await
+                        .context("Failed to update unleased tasks")?;
+                    if removed == 0 {
+                        continue;
+                    }
+                    
+                    seed_task(job_id, url)
+                },
+            };
+            
+            redis::cmd("SREM")
+                .arg(&processing_key)
+                .arg(url)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to remove URL from processing set")?;
+            
+            task.attempt += 1;
+            let task_json = serde_json::to_string(&task)
+                .context("Failed to serialize task")?;
+            
+            redis::cmd("RPUSH")
+                .arg(&queue_key)
+                .arg(&task_json)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to push task to Redis queue")?;
+            
+            warn!("Requeued task stuck processing: {}", url);
+            requeued += 1;
+        }
+        
+        // Forget URLs that left the processing set on their own
+        let unleased: Vec<String> = redis::cmd("HKEYS")
+            .arg(&unleased_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read unleased tasks")?;
+        let finished: Vec<&String> = unleased.iter()
+            .filter(|url| !processing.contains(*url))
+            .collect();
+        if !finished.is_empty() {
+            redis::cmd("HDEL")
+                .arg(&unleased_key)
+                .arg(&finished)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to update unleased tasks")?;
+        }
+        
+        redis::cmd("EXPIRE")
+            .arg(&unleased_key)
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to set TTL on unleased tasks")?;
+        
+        Ok(requeued)
+    }
+    
+    /// Requeue the URLs of the processing set that have no lease, as seeds
+    ///
+    /// Only safe while no worker runs the job, a live worker leases its task
+    /// just after adding it to the processing set.
+    #[instrument(skip_all, fields(job_id = %job_id))]
+    pub async fn requeue_unleased_tasks(&self, job_id: &str) -> Result<usize> {
+        let queue_key = format!("crawler:queue:{}", job_id);
+        let processing_key = format!("crawler:processing:{}", job_id);
+        let leased_tasks_key = format!("crawler:leased_tasks:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let processing: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(&processing_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read processing set")?;
+        
+        let mut requeued = 0;
+        for url in &processing {
+            let leased: bool = redis::cmd("HEXISTS")
+                .arg(&leased_tasks_key)
+                .arg(url)
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to read leased task")?;
+            if leased {
+                continue;
+            }
+            
+            let removed: usize = redis::cmd("SREM")
+                .arg(&processing_key)
+                .arg(url)
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to remove URL from processing set")?;
+            if removed == 0 {
+                continue;
+            }
+            
+            let task_json = serde_json::to_string(&seed_task(job_id, url))
+                .context("Failed to serialize task")?;
+            
+            redis::cmd("RPUSH")
+                .arg(&queue_key)
+                .arg(&task_json)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to push task to Redis queue")?;
+            
+            requeued += 1;
+        }
+        
+        redis::cmd("DEL")
+            .arg(format!("crawler:unleased:{}", job_id))
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to clear unleased tasks")?;
+        
+        Ok(requeued)
+    }
+    
+    /// Unix time a lease taken or renewed now expires at
+    fn lease_expiry(&self) -> i64 {
+        Utc::now().timestamp() + self.lease_timeout as i64
+    }
+    
+    /// Drop the lease of a task that is done
+    async fn release_lease(&self, conn: &mut ConnectionManager, job_id: &str, url: &str) -> Result<()> {
+        redis::cmd("ZREM")
+            .arg(format!("crawler:leases:{}", job_id))
+            .arg(url)
+            .query_async::<_, ()>(conn)
+            .await
+            .context("Failed to remove task lease")?;
+        
+        redis::cmd("HDEL")
+            .arg(format!("crawler:leased_tasks:{}", job_id))
+            .arg(url)
+            .query_async::<_, ()>(conn)
+            .await
+            .context("Failed to remove leased task")?;
+        
+        Ok(())
+    }
+    
+    /// Push a task to the queue, at the head when its priority is above 0
+    #[instrument(skip_all, fields(job_id = %task.job_id, url = %task.url))]
     pub async fn push_task(&self, task: &CrawlTask) -> Result<()> {
         let task_json = serde_json::to_string(task)
             .context("Failed to serialize task")?;
@@ -50,7 +690,7 @@ impl QueueManager {
         let queue_key = format!("crawler:queue:{}", task.job_id);
         let processing_key = format!("crawler:processing:{}", task.job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         // Check if the task is already in processing
         let in_processing: bool = redis::cmd("SISMEMBER")
@@ -65,8 +705,8 @@ impl QueueManager {
             return Ok(());
         }
         
-        // Add task to the queue
-        redis::cmd("LPUSH")
+        // Add task to the queue, pops start from the head on the right
+        redis::cmd(if task.priority > 0 { "RPUSH" } else { "LPUSH" })
             .arg(&queue_key)
             .arg(&task_json)
             .query_async::<_, ()>(&mut *conn)
@@ -94,49 +734,48 @@ impl QueueManager {
         Ok(())
     }
     
-    /// Pop a task from the queue
-    pub async fn pop_task(&self, job_id: &str) -> Result<Option<CrawlTask>> {
+    /// Pop a task from the queue, leasing it to a worker
+    ///
+    /// Tasks of a domain still within its delay since the last request are
+    /// left in the queue, the first other task near the head is popped
+    /// instead. Among the ready tasks near the head, the one of highest
+    /// priority goes first. None while every task near the head has to wait.
+    #[instrument(skip_all, fields(job_id = %job_id, worker_id = %worker_id))]
+    pub async fn pop_task(&self, job_id: &str, worker_id: &str, politeness_delay: u64) -> Result<Option<CrawlTask>> {
         let queue_key = format!("crawler:queue:{}", job_id);
         let processing_key = format!("crawler:processing:{}", job_id);
+        let leases_key = format!("crawler:leases:{}", job_id);
+        let leased_tasks_key = format!("crawler:leased_tasks:{}", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
-        // Get a task from the queue
-        let task_json: Option<String> = redis::cmd("RPOP")
-            .arg(&queue_key)
-            .query_async(&mut *conn)
+        self.promote_due_retries(&mut conn, job_id).await?;
+        
+        // Get a task whose domain is ready, skipping the ones still cooling down,
+        // and lease it to the worker. The domain times and delays are the ones
+        // of the scheduler state.
+        let task_json: Option<String> = self.pop_ready
+            .key(&queue_key)
+            .key(format!("crawler:domain_next:{}", job_id))
+            .key(format!("crawler:domain_delays:{}", job_id))
+            .key(&processing_key)
+            .key(&leased_tasks_key)
+            .key(&leases_key)
+            .arg(Utc::now().timestamp_millis())
+            .arg(politeness_delay)
+            .arg(FRONTIER_WINDOW)
+            .arg(self.task_ttl)
+            .arg(worker_id)
+            .arg(self.lease_expiry())
+            .arg(Utc::now().timestamp())
+            .invoke_async(&mut *conn)
             .await
             .context("Failed to pop task from Redis queue")?;
         
         if let Some(task_json) = task_json {
-            // Parse the task
             let task: CrawlTask = serde_json::from_str(&task_json)
                 .context("Failed to deserialize task")?;
             
-            // Add the URL to the processing set
-            redis::cmd("SADD")
-                .arg(&processing_key)
-                .arg(&task.url)
-                .query_async::<_, ()>(&mut *conn)
-                .await
-                .context("Failed to add URL to processing set")?;
-            
-            // Set TTL on the processing set if not already set
-            let ttl: i64 = redis::cmd("TTL")
-                .arg(&processing_key)
-                .query_async(&mut *conn)
-                .await
-                .unwrap_or(-1);
-            
-            if ttl == -1 {
-                redis::cmd("EXPIRE")
-                    .arg(&processing_key)
-                    .arg(self.task_ttl)
-                    .query_async::<_, ()>(&mut *conn)
-                    .await
-                    .context("Failed to set TTL on processing set")?;
-            }
-            
             debug!("Popped task from queue: {}", task.url);
             
             Ok(Some(task))
@@ -145,12 +784,86 @@ impl QueueManager {
         }
     }
     
+    /// Reserve the cost of a captcha solve in a job's spend, returning the
+    /// new total or None when the solve would go over `limit`
+    ///
+    /// `spent` is the spend of the job's status, used when the job has no
+    /// counter yet. Workers reserve before solving, so concurrent solves
+    /// can't go over the limit together.
+    pub async fn reserve_captcha_spend(&self, job_id: &str, cost: f64, limit: f64, spent: f64) -> Result<Option<f64>> {
+        let mut conn = self.conn().await?;
+        let total: Option<String> = self.reserve_spend
+            .key(format!("crawler:captcha_spend:{}", job_id))
+            .arg(cost)
+            .arg(limit)
+            .arg(self.task_ttl)
+            .arg(spent)
+            .invoke_async(&mut *conn)
+            .await
+            .context("Failed to reserve captcha spend")?;
+        
+        total.map(|total| total.parse().context("Invalid captcha spend")).transpose()
+    }
+    
+    /// Give back the cost of a captcha solve that failed
+    pub async fn release_captcha_spend(&self, job_id: &str, cost: f64) -> Result<f64> {
+        let mut conn = self.conn().await?;
+        let total: String = redis::cmd("INCRBYFLOAT")
+            .arg(format!("crawler:captcha_spend:{}", job_id))
+            .arg(-cost)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to release captcha spend")?;
+        
+        total.parse().context("Invalid captcha spend")
+    }
+    
+    /// Move the retries that are due to the head of the queue
+    async fn promote_due_retries(&self, conn: &mut ConnectionManager, job_id: &str) -> Result<()> {
+        let retry_key = format!("crawler:retry:{}", job_id);
+        let queue_key = format!("crawler:queue:{}", job_id);
+        
+        let due: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+            .arg(&retry_key)
+            .arg("-inf")
+            .arg(Utc::now().timestamp_millis())
+            .arg("LIMIT")
+            .arg(0)
+            .arg(FRONTIER_WINDOW)
+            .query_async(conn)
+            .await
+            .context("Failed to read due task retries")?;
+        
+        for task_json in due {
+            // Only the worker that removes the retry queues it
+            let removed: usize = redis::cmd("ZREM")
+                .arg(&retry_key)
+                .arg(&task_json)
+                .query_async(conn)
+                .await
+                .context("Failed to remove task retry")?;
+            if removed == 0 {
+                continue;
+            }
+            
+            redis::cmd("RPUSH")
+                .arg(&queue_key)
+                .arg(&task_json)
+                .query_async::<_, ()>(conn)
+                .await
+                .context("Failed to push task to Redis queue")?;
+        }
+        
+        Ok(())
+    }
+    
     /// Mark a task as completed
+    #[instrument(skip_all, fields(job_id = %job_id, url = %url))]
     pub async fn complete_task(&self, job_id: &str, url: &str) -> Result<()> {
         let processing_key = format!("crawler:processing:{}", job_id);
         let completed_key = format!("crawler:completed:{}", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         // Remove the URL from the processing set
         redis::cmd("SREM")
@@ -160,6 +873,8 @@ impl QueueManager {
             .await
             .context("Failed to remove URL from processing set")?;
         
+        self.release_lease(&mut conn, job_id, url).await?;
+        
         // Add the URL to the completed set
         redis::cmd("SADD")
             .arg(&completed_key)
@@ -189,37 +904,122 @@ impl QueueManager {
         Ok(())
     }
     
+    /// Return a task that was interrupted to the front of its queue
+    ///
+    /// Used when the process shuts down mid-task, the attempt isn't counted
+    /// since the task didn't fail.
+    #[instrument(skip_all, fields(job_id = %task.job_id, url = %task.url))]
+    pub async fn requeue_task(&self, task: &CrawlTask) -> Result<()> {
+        let task_json = serde_json::to_string(task)
+            .context("Failed to serialize task")?;
+        
+        let mut conn = self.conn().await?;
+        
+        redis::cmd("SREM")
+            .arg(format!("crawler:processing:{}", task.job_id))
+            .arg(&task.url)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to remove URL from processing set")?;
+        
+        self.release_lease(&mut conn, &task.job_id, &task.url).await?;
+        
+        redis::cmd("RPUSH")
+            .arg(format!("crawler:queue:{}", task.job_id))
+            .arg(&task_json)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to push task to Redis queue")?;
+        
+        debug!("Requeued interrupted task: {}", task.url);
+        
+        Ok(())
+    }
+    
     /// Mark a task as failed
-    pub async fn fail_task(&self, job_id: &str, url: &str, error: &str) -> Result<()> {
-        let processing_key = format!("crawler:processing:{}", job_id);
-        let failed_key = format!("crawler:failed:{}", job_id);
-        let error_key = format!("crawler:errors:{}:{}", job_id, url);
+    ///
+    /// With a retry delay the task is scheduled for another attempt once the
+    /// delay is over, without one it is moved to the job's dead-letter list
+    /// with its error.
+    #[instrument(skip_all, fields(job_id = %task.job_id, url = %task.url))]
+    pub async fn fail_task(&self, task: &CrawlTask, error: &str, error_type: &str, retry_in: Option<Duration>) -> Result<()> {
+        let retry_key = format!("crawler:retry:{}", task.job_id);
+        let processing_key = format!("crawler:processing:{}", task.job_id);
+        let failed_key = format!("crawler:failed:{}", task.job_id);
+        let dead_letter_key = format!("crawler:dead_letter:{}", task.job_id);
+        let error_key = format!("crawler:errors:{}:{}", task.job_id, task.url);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         // Remove the URL from the processing set
         redis::cmd("SREM")
             .arg(&processing_key)
-            .arg(url)
+            .arg(&task.url)
             .query_async::<_, ()>(&mut *conn)
             .await
             .context("Failed to remove URL from processing set")?;
         
+        self.release_lease(&mut conn, &task.job_id, &task.url).await?;
+        
+        // Store the error message
+        redis::cmd("SET")
+            .arg(&error_key)
+            .arg(error)
+            .arg("EX")
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to store error message")?;
+        
+        if let Some(retry_in) = retry_in {
+            let mut retry = task.clone();
+            retry.attempt += 1;
+            let task_json = serde_json::to_string(&retry)
+                .context("Failed to serialize task")?;
+            
+            // Scheduled by the time it is due, pops move it to the queue then
+            redis::cmd("ZADD")
+                .arg(&retry_key)
+                .arg(Utc::now().timestamp_millis() + retry_in.as_millis() as i64)
+                .arg(&task_json)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to schedule task retry")?;
+            
+            redis::cmd("EXPIRE")
+                .arg(&retry_key)
+                .arg(self.task_ttl)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to set TTL on task retries")?;
+            
+            debug!("Retrying failed task in {} ms (attempt {}): {}", retry_in.as_millis(), retry.attempt + 1, task.url);
+            
+            return Ok(());
+        }
+        
+        let dead_letter_json = serde_json::to_string(&DeadLetter {
+            task: task.clone(),
+            error: error.to_string(),
+            error_type: error_type.to_string(),
+            failed_at: Utc::now(),
+        }).context("Failed to serialize dead letter")?;
+        
         // Add the URL to the failed set
         redis::cmd("SADD")
             .arg(&failed_key)
-            .arg(url)
+            .arg(&task.url)
             .query_async::<_, ()>(&mut *conn)
             .await
             .context("Failed to add URL to failed set")?;
         
-        // Store the error message
-        redis::cmd("SET")
-            .arg(&error_key)
-            .arg(error)
+        // Keep the full task so it can be retried at the same depth
+        redis::cmd("RPUSH")
+            .arg(&dead_letter_key)
+            .arg(&dead_letter_json)
             .query_async::<_, ()>(&mut *conn)
             .await
-            .context("Failed to store error message")?;
+            .context("Failed to add task to dead-letter list")?;
         
         // Set TTLs
         let ttl: i64 = redis::cmd("TTL")
@@ -235,25 +1035,195 @@ impl QueueManager {
                 .query_async::<_, ()>(&mut *conn)
                 .await
                 .context("Failed to set TTL on failed set")?;
+        }
+        
+        redis::cmd("EXPIRE")
+            .arg(&dead_letter_key)
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to set TTL on dead-letter list")?;
+        
+        debug!("Moved failed task to the dead-letter list: {}", task.url);
+        
+        Ok(())
+    }
+    
+    /// Get the dead-letter list of a job, oldest first
+    pub async fn list_dead_letters(&self, job_id: &str) -> Result<Vec<DeadLetter>> {
+        let dead_letter_key = format!("crawler:dead_letter:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let entries: Vec<String> = redis::cmd("LRANGE")
+            .arg(&dead_letter_key)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read dead-letter list")?;
+        
+        entries.iter()
+            .map(|entry| serde_json::from_str(entry).context("Failed to deserialize dead letter"))
+            .collect()
+    }
+    
+    /// Keep the preview of a page just crawled, dropping the oldest past
+    /// the previews kept per job
+    pub async fn push_preview(&self, job_id: &str, preview: &RecordPreview) -> Result<()> {
+        let preview_key = format!("crawler:preview:{}", job_id);
+        let preview_json = serde_json::to_string(preview)
+            .context("Failed to serialize record preview")?;
+        
+        let mut conn = self.conn().await?;
+        
+        redis::cmd("LPUSH")
+            .arg(&preview_key)
+            .arg(&preview_json)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to add record preview")?;
+        
+        redis::cmd("LTRIM")
+            .arg(&preview_key)
+            .arg(0)
+            .arg(PREVIEW_RECORDS - 1)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to trim record previews")?;
+        
+        redis::cmd("EXPIRE")
+            .arg(&preview_key)
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to set TTL on record previews")?;
+        
+        Ok(())
+    }
+    
+    /// Previews of the pages a job crawled last, newest first
+    pub async fn recent_previews(&self, job_id: &str, count: usize) -> Result<Vec<RecordPreview>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let mut conn = self.conn().await?;
+        
+        let entries: Vec<String> = redis::cmd("LRANGE")
+            .arg(format!("crawler:preview:{}", job_id))
+            .arg(0)
+            .arg(count.min(PREVIEW_RECORDS) - 1)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read record previews")?;
+        
+        entries.iter()
+            .map(|entry| serde_json::from_str(entry).context("Failed to deserialize record preview"))
+            .collect()
+    }
+    
+    /// Move failed tasks of a job back into the queue, all of them or only
+    /// the one of a URL
+    pub async fn requeue_failed(&self, job_id: &str, url: Option<&str>) -> Result<usize> {
+        let queue_key = format!("crawler:queue:{}", job_id);
+        let failed_key = format!("crawler:failed:{}", job_id);
+        let dead_letter_key = format!("crawler:dead_letter:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let entries: Vec<String> = redis::cmd("LRANGE")
+            .arg(&dead_letter_key)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read dead-letter list")?;
+        
+        let mut tasks = Vec::new();
+        let mut listed = HashSet::new();
+        for entry in &entries {
+            let dead_letter: DeadLetter = serde_json::from_str(entry)
+                .context("Failed to deserialize dead letter")?;
+            listed.insert(dead_letter.task.url.clone());
+            if url.is_some_and(|url| url != dead_letter.task.url) {
+                continue;
+            }
             
-            redis::cmd("EXPIRE")
-                .arg(&error_key)
-                .arg(self.task_ttl)
+            // Entries removed in the meantime were requeued by someone else
+            let removed: usize = redis::cmd("LREM")
+                .arg(&dead_letter_key)
+                .arg(1)
+                .arg(entry)
+                .query_async(&mut *conn)
+                .await
+                .context("Failed to remove task from dead-letter list")?;
+            if removed > 0 {
+                tasks.push(dead_letter.task);
+            }
+        }
+        
+        // URLs failed before the dead-letter list existed are retried as seeds
+        let failed: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(&failed_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read failed set")?;
+        for failed_url in failed {
+            if !listed.contains(&failed_url) && url.map_or(true, |url| url == failed_url) {
+                tasks.push(seed_task(job_id, &failed_url));
+            }
+        }
+        
+        for task in &mut tasks {
+            task.attempt += 1;
+            
+            let task_json = serde_json::to_string(&task)
+                .context("Failed to serialize task")?;
+            
+            redis::cmd("LPUSH")
+                .arg(&queue_key)
+                .arg(&task_json)
                 .query_async::<_, ()>(&mut *conn)
                 .await
-                .context("Failed to set TTL on error message")?;
+                .context("Failed to push task to Redis queue")?;
+            
+            redis::cmd("SREM")
+                .arg(&failed_key)
+                .arg(&task.url)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to remove URL from failed set")?;
+            
+            redis::cmd("DEL")
+                .arg(format!("crawler:errors:{}:{}", job_id, task.url))
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to delete error message")?;
         }
         
-        debug!("Marked task as failed: {}", url);
+        debug!("Requeued {} failed tasks for job: {}", tasks.len(), job_id);
         
-        Ok(())
+        Ok(tasks.len())
+    }
+    
+    /// Get all task counters of a job
+    pub async fn get_counts(&self, job_id: &str) -> Result<QueueCounts> {
+        Ok(QueueCounts {
+            pending: self.get_pending_count(job_id).await?,
+            processing: self.get_processing_count(job_id).await?,
+            completed: self.get_completed_count(job_id).await?,
+            failed: self.get_failed_count(job_id).await?,
+            workers: self.get_worker_count(job_id).await?,
+        })
     }
     
     /// Get the number of pending tasks for a job
     pub async fn get_pending_count(&self, job_id: &str) -> Result<usize> {
         let queue_key = format!("crawler:queue:{}", job_id);
+        let retry_key = format!("crawler:retry:{}", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         let count: usize = redis::cmd("LLEN")
             .arg(&queue_key)
@@ -261,14 +1231,21 @@ impl QueueManager {
             .await
             .context("Failed to get queue length")?;
         
-        Ok(count)
+        // Retries waiting out their delay are still to be crawled
+        let retries: usize = redis::cmd("ZCARD")
+            .arg(&retry_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to get task retry count")?;
+        
+        Ok(count + retries)
     }
     
     /// Get the number of processing tasks for a job
     pub async fn get_processing_count(&self, job_id: &str) -> Result<usize> {
         let processing_key = format!("crawler:processing:{}", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         let count: usize = redis::cmd("SCARD")
             .arg(&processing_key)
@@ -283,7 +1260,7 @@ impl QueueManager {
     pub async fn get_completed_count(&self, job_id: &str) -> Result<usize> {
         let completed_key = format!("crawler:completed:{}", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         let count: usize = redis::cmd("SCARD")
             .arg(&completed_key)
@@ -298,7 +1275,7 @@ impl QueueManager {
     pub async fn get_failed_count(&self, job_id: &str) -> Result<usize> {
         let failed_key = format!("crawler:failed:{}", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         let count: usize = redis::cmd("SCARD")
             .arg(&failed_key)
@@ -309,15 +1286,210 @@ impl QueueManager {
         Ok(count)
     }
     
+    /// Pause or resume task distribution for a job
+    pub async fn set_paused(&self, job_id: &str, paused: bool) -> Result<()> {
+        let paused_key = format!("crawler:paused:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        if paused {
+            redis::cmd("SET")
+                .arg(&paused_key)
+                .arg(1)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to set paused flag")?;
+        } else {
+            redis::cmd("DEL")
+                .arg(&paused_key)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .context("Failed to clear paused flag")?;
+        }
+        
+        debug!("Set paused={} for job: {}", paused, job_id);
+        
+        Ok(())
+    }
+    
+    /// Check whether a job is paused
+    pub async fn is_paused(&self, job_id: &str) -> Result<bool> {
+        let paused_key = format!("crawler:paused:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let paused: bool = redis::cmd("EXISTS")
+            .arg(&paused_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to check paused flag")?;
+        
+        Ok(paused)
+    }
+    
+    /// Flag a job as cancelled so workers abandon it
+    pub async fn set_cancelled(&self, job_id: &str) -> Result<()> {
+        let cancelled_key = format!("crawler:cancelled:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        // The flag outlives clear_job so workers still see it, the TTL cleans it up
+        redis::cmd("SET")
+            .arg(&cancelled_key)
+            .arg(1)
+            .arg("EX")
+            .arg(self.task_ttl)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to set cancelled flag")?;
+        
+        debug!("Flagged job as cancelled: {}", job_id);
+        
+        Ok(())
+    }
+    
+    /// Check whether a job has been cancelled
+    pub async fn is_cancelled(&self, job_id: &str) -> Result<bool> {
+        let cancelled_key = format!("crawler:cancelled:{}", job_id);
+        
+        let mut conn = self.conn().await?;
+        
+        let cancelled: bool = redis::cmd("EXISTS")
+            .arg(&cancelled_key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to check cancelled flag")?;
+        
+        Ok(cancelled)
+    }
+    
+    /// Store a new crawl schedule
+    pub async fn add_schedule(&self, schedule: &CrawlSchedule) -> Result<()> {
+        let schedule_json = serde_json::to_string(schedule)
+            .context("Failed to serialize schedule")?;
+        
+        let mut conn = self.conn().await?;
+        
+        redis::cmd("SET")
+            .arg(format!("crawler:schedule:{}", schedule.id))
+            .arg(&schedule_json)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to store schedule")?;
+        
+        redis::cmd("SADD")
+            .arg("crawler:schedules")
+            .arg(&schedule.id)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to add schedule")?;
+        
+        Ok(())
+    }
+    
+    /// Store the runs of a schedule, unless it was removed meanwhile
+    pub async fn update_schedule(&self, schedule: &CrawlSchedule) -> Result<()> {
+        let schedule_json = serde_json::to_string(schedule)
+            .context("Failed to serialize schedule")?;
+        
+        let mut conn = self.conn().await?;
+        
+        redis::cmd("SET")
+            .arg(format!("crawler:schedule:{}", schedule.id))
+            .arg(&schedule_json)
+            .arg("XX")
+            .query_async::<_, Option<String>>(&mut *conn)
+            .await
+            .context("Failed to update schedule")?;
+        
+        Ok(())
+    }
+    
+    /// Every crawl schedule, oldest first
+    pub async fn list_schedules(&self) -> Result<Vec<CrawlSchedule>> {
+        let mut conn = self.conn().await?;
+        
+        let ids: Vec<String> = redis::cmd("SMEMBERS")
+            .arg("crawler:schedules")
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to list schedules")?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let entries: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(ids.iter().map(|id| format!("crawler:schedule:{}", id)).collect::<Vec<_>>())
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to read schedules")?;
+        
+        let mut schedules = entries.iter()
+            .flatten()
+            .map(|entry| serde_json::from_str(entry).context("Failed to deserialize schedule"))
+            .collect::<Result<Vec<CrawlSchedule>>>()?;
+        schedules.sort_by_key(|schedule| schedule.created_at);
+        
+        Ok(schedules)
+    }
+    
+    /// Remove a crawl schedule, returning whether it existed
+    pub async fn remove_schedule(&self, id: &str) -> Result<bool> {
+        let mut conn = self.conn().await?;
+        
+        let removed: usize = redis::cmd("DEL")
+            .arg(format!("crawler:schedule:{}", id))
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to remove schedule")?;
+        
+        redis::cmd("SREM")
+            .arg("crawler:schedules")
+            .arg(id)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to remove schedule")?;
+        
+        Ok(removed > 0)
+    }
+    
+    /// Claim the run of a schedule due at a time, false when another
+    /// process already did
+    pub async fn claim_schedule_run(&self, id: &str, due: DateTime<Utc>) -> Result<bool> {
+        let mut conn = self.conn().await?;
+        
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(format!("crawler:schedule_run:{}:{}", id, due.timestamp()))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.task_ttl)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to claim schedule run")?;
+        
+        Ok(claimed.is_some())
+    }
+    
     /// Clear all data for a job
     pub async fn clear_job(&self, job_id: &str) -> Result<()> {
         let queue_key = format!("crawler:queue:{}", job_id);
         let processing_key = format!("crawler:processing:{}", job_id);
         let completed_key = format!("crawler:completed:{}", job_id);
         let failed_key = format!("crawler:failed:{}", job_id);
+        let dead_letter_key = format!("crawler:dead_letter:{}", job_id);
+        let paused_key = format!("crawler:paused:{}", job_id);
+        let leases_key = format!("crawler:leases:{}", job_id);
+        let leased_tasks_key = format!("crawler:leased_tasks:{}", job_id);
+        let workers_key = format!("crawler:workers:{}", job_id);
+        let unleased_key = format!("crawler:unleased:{}", job_id);
+        let retry_key = format!("crawler:retry:{}", job_id);
+        let job_heartbeat_key = format!("crawler:job_heartbeat:{}", job_id);
+        let preview_key = format!("crawler:preview:{}", job_id);
+        let captcha_spend_key = format!("crawler:captcha_spend:{}", job_id);
         let error_pattern = format!("crawler:errors:{}:*", job_id);
         
-        let mut conn = self.conn_pool.lock().await;
+        let mut conn = self.conn().await?;
         
         // Delete the queue
         redis::cmd("DEL")
@@ -331,6 +1503,16 @@ impl QueueManager {
             .arg(&processing_key)
             .arg(&completed_key)
             .arg(&failed_key)
+            .arg(&dead_letter_key)
+            .arg(&paused_key)
+            .arg(&leases_key)
+            .arg(&leased_tasks_key)
+            .arg(&workers_key)
+            .arg(&unleased_key)
+            .arg(&retry_key)
+            .arg(&job_heartbeat_key)
+            .arg(&preview_key)
+            .arg(&captcha_spend_key)
             .query_async::<_, ()>(&mut *conn)
             .await
             .context("Failed to delete sets")?;
@@ -354,4 +1536,117 @@ impl QueueManager {
         
         Ok(())
     }
+}
+
+/// Task for a URL whose original task is lost, crawled again from the top
+fn seed_task(job_id: &str, url: &str) -> CrawlTask {
+    CrawlTask {
+        job_id: job_id.to_string(),
+        url: url.to_string(),
+        depth: 0,
+        parent_url: None,
+        priority: 0,
+        attempt: 0,
+        feed_entry: None,
+        seed: None,
+        recrawl_of: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Runs against the Redis at CRAWLER_TEST_REDIS_URL, or a local one
+    #[tokio::test]
+    #[ignore = "needs a Redis server"]
+    async fn test_pop_skips_domains_in_delay() {
+        let redis_url = std::env::var("CRAWLER_TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let settings: QueueSettings = serde_json::from_value(serde_json::json!({
+            "redis_url": redis_url,
+            "task_ttl": 60,
+        })).unwrap();
+        let queue = QueueManager::new(&settings).await.unwrap();
+        let job_id = uuid::Uuid::new_v4().to_string();
+        
+        for url in ["https://a.example.com/1", "https://a.example.com/2", "https://b.example.com/1"] {
+            queue.push_task(&seed_task(&job_id, url)).await.unwrap();
+        }
+        let mut popped = Vec::new();
+        while let Some(task) = queue.pop_task(&job_id, "worker", 60_000).await.unwrap() {
+            popped.push(task.url);
+        }
+        queue.clear_job(&job_id).await.unwrap();
+        
+        // The second page of a.example.com waits out the delay the first one started
+        assert_eq!(popped, vec!["https://a.example.com/1", "https://b.example.com/1"]);
+    }
+    
+    /// Runs against the Redis at CRAWLER_TEST_REDIS_URL, or a local one
+    #[tokio::test]
+    #[ignore = "needs a Redis server"]
+    async fn test_pop_leases_task() {
+        let redis_url = std::env::var("CRAWLER_TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let settings: QueueSettings = serde_json::from_value(serde_json::json!({
+            "redis_url": redis_url,
+            "task_ttl": 60,
+        })).unwrap();
+        let queue = QueueManager::new(&settings).await.unwrap();
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let url = "https://a.example.com/\"quoted\"";
+        
+        queue.push_task(&seed_task(&job_id, url)).await.unwrap();
+        let task = queue.pop_task(&job_id, "worker-1", 0).await.unwrap().unwrap();
+        assert_eq!(task.url, url);
+        
+        // The popped task is processing and leased as soon as the pop returns
+        let (processing, lease, expiry): (bool, String, Option<i64>) = {
+            let mut conn = queue.conn().await.unwrap();
+            redis::pipe()
+                .cmd("SISMEMBER").arg(format!("crawler:processing:{}", job_id)).arg(url)
+                .cmd("HGET").arg(format!("crawler:leased_tasks:{}", job_id)).arg(url)
+                .cmd("ZSCORE").arg(format!("crawler:leases:{}", job_id)).arg(url)
+                .query_async(&mut *conn)
+                .await
+                .unwrap()
+        };
+        queue.clear_job(&job_id).await.unwrap();
+        
+        assert!(processing);
+        let lease: Lease = serde_json::from_str(&lease).unwrap();
+        assert_eq!(lease.worker_id, "worker-1");
+        assert_eq!(lease.task.url, url);
+        assert!(expiry.is_some_and(|expiry| expiry > Utc::now().timestamp()));
+    }
+    
+    /// Runs against the Redis at CRAWLER_TEST_REDIS_URL, or a local one
+    #[tokio::test]
+    #[ignore = "needs a Redis server"]
+    async fn test_concurrent_captcha_spend() {
+        let redis_url = std::env::var("CRAWLER_TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let settings: QueueSettings = serde_json::from_value(serde_json::json!({
+            "redis_url": redis_url,
+            "task_ttl": 60,
+            "pool": { "max_connections": 2 },
+        })).unwrap();
+        let queue = QueueManager::new(&settings).await.unwrap();
+        let job_id = uuid::Uuid::new_v4().to_string();
+        
+        // Two workers solving at once with room for one more solve
+        let (first, second) = tokio::join!(
+            queue.reserve_captcha_spend(&job_id, 0.003, 0.006, 0.003),
+            queue.reserve_captcha_spend(&job_id, 0.003, 0.006, 0.003),
+        );
+        let reserved: Vec<_> = [first.unwrap(), second.unwrap()].into_iter().flatten().collect();
+        assert_eq!(reserved.len(), 1);
+        assert!((reserved[0] - 0.006).abs() < 1e-9);
+        
+        // A failed solve gives its room back
+        let total = queue.release_captcha_spend(&job_id, 0.003).await.unwrap();
+        assert!((total - 0.003).abs() < 1e-9);
+        assert!(queue.reserve_captcha_spend(&job_id, 0.003, 0.006, 0.0).await.unwrap().is_some());
+        assert!(queue.reserve_captcha_spend(&job_id, 0.003, 0.0, 0.0).await.unwrap().is_some());
+        
+        queue.clear_job(&job_id).await.unwrap();
+    }
 }
\ No newline at end of file
@@ -1,27 +1,167 @@
 use anyhow::{Result, Context};
+#[cfg(feature = "mongodb")]
 use futures::StreamExt;
 use async_trait::async_trait;
+#[cfg(feature = "s3")]
+use aws_config::BehaviorVersion;
+#[cfg(feature = "s3")]
+use aws_sdk_s3::config::{Credentials, Region};
+#[cfg(feature = "s3")]
+use aws_sdk_s3::primitives::ByteStream;
+#[cfg(feature = "s3")]
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier};
+#[cfg(feature = "mongodb")]
 use mongodb::{Client, Database, Collection, options::ClientOptions};
-use mongodb::bson::{doc, Document};
+#[cfg(feature = "mongodb")]
+use mongodb::bson::{doc, Binary, Document, spec::BinarySubtype};
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::debug;
+use std::time::Duration;
+use tracing::{debug, instrument};
 use chrono::{DateTime, Utc}; // Make sure to add this
 
-use crate::cli::config::RawDataSettings;
-use crate::crawler::task::TaskResult;
+use crate::cli::config::{CrawlerSettings, JobPriority, RawDataSettings};
+#[cfg(feature = "s3")]
+use crate::cli::config::S3Settings;
+use crate::crawler::recrawl::RecrawlSummary;
+use crate::crawler::task::{QuarantinedPage, TaskResult, TaskTrace};
+use crate::storage::naming::NamingRules;
+#[cfg(feature = "mongodb")]
+use crate::storage::retry::connect_with_retry;
 
 // Define the JobStatus struct here to avoid circular dependencies
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobStatus {
     pub job_id: String,
     pub seed_url: String,
-    pub state: String,  // "pending", "running", "completed", "failed"
+    pub state: String,  // "pending", "running", "stalled", "completed", "failed"
     pub pages_crawled: usize,
     pub pages_total: usize,
     pub started_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub errors: Vec<String>,
+    #[serde(default)]
+    pub captcha_spend: f64,
+    #[serde(default)]
+    pub limits: Option<JobLimits>, // None for jobs started before limits were recorded
+    #[serde(default)]
+    pub stop_reason: Option<String>, // Why the job was paused or failed automatically
+    #[serde(default)]
+    pub pages_per_minute: f64,
+    #[serde(default)]
+    pub avg_page_ms: u64, // Average time to fetch, extract and store a page
+    #[serde(default)]
+    pub eta_seconds: Option<u64>, // Time left to crawl the pending pages at the current rate
+    #[serde(default)]
+    pub config_hash: Option<String>, // Configuration the job was started with, idle workers of other jobs only help when theirs matches
+    #[serde(default)]
+    pub parent_job_id: Option<String>, // Job whose extracted URLs seeded this one, like the previous pipeline stage
+    #[serde(default)]
+    pub tags: Vec<String>, // Labels given by whoever started the job
+    #[serde(default)]
+    pub seeds: Vec<SeedProgress>, // Pages found from each seed URL, in the order they were given
+    #[serde(default)]
+    pub recrawl: Option<RecrawlSummary>, // Job a recrawl fetches the pages of again, and how many changed
+}
+
+/// Pages of a job found from one of its seed URLs, following links from it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedProgress {
+    pub url: String,
+    pub pages_crawled: usize,
+    pub pages_total: usize,
+}
+
+/// Weight of the latest page in the crawl rate, older pages fade out
+const RATE_SMOOTHING: f64 = 0.2;
+
+impl JobStatus {
+    /// Count a crawled page, found from the seed at index `seed`, updating the
+    /// crawl rate, average page duration and ETA
+    ///
+    /// The rate is a moving average of the time between pages, so it follows
+    /// the current pace rather than the whole history of the job. Pending
+    /// pages are bounded by the page limit when the job has one.
+    pub fn record_page(&mut self, seed: Option<usize>, duration: Duration, pending: usize, now: DateTime<Utc>) {
+        let previous = if self.pages_crawled == 0 { self.started_at } else { self.updated_at };
+        let minutes = (now - previous).num_milliseconds().max(1) as f64 / 60_000.0;
+        
+        let minutes_per_page = if self.pages_per_minute > 0.0 {
+            RATE_SMOOTHING * minutes + (1.0 - RATE_SMOOTHING) / self.pages_per_minute
+        } else {
+            minutes
+        };
+        self.pages_per_minute = 1.0 / minutes_per_page;
+        
+        let total_ms = self.avg_page_ms as u128 * self.pages_crawled as u128 + duration.as_millis();
+        self.pages_crawled += 1;
+        if let Some(seed) = seed.and_then(|seed| self.seeds.get_mut(seed)) {
+            seed.pages_crawled += 1;
+        }
+        self.avg_page_ms = (total_ms / self.pages_crawled as u128) as u64;
+        
+        let mut remaining = pending;
+        if let Some(limits) = &self.limits {
+            remaining = remaining.min((limits.max_pages as usize).saturating_sub(self.pages_crawled));
+        }
+        self.eta_seconds = Some((remaining as f64 * minutes_per_page * 60.0).round() as u64);
+        self.updated_at = now;
+    }
+}
+
+/// Limits of a job, kept in its status so running workers pick up changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobLimits {
+    pub max_pages: u32,
+    pub max_depth: u32,
+    pub concurrency: usize, // Number of workers taking tasks
+    #[serde(default = "default_weight")]
+    pub weight: u32, // Share of the idle workers of other jobs the job gets, 0 for none
+    #[serde(default)]
+    pub priority: JobPriority,
+}
+
+fn default_weight() -> u32 {
+    JobLimits::DEFAULT_WEIGHT
+}
+
+impl JobLimits {
+    /// Most workers used when the profile doesn't say otherwise, fewer on
+    /// machines with fewer cores
+    pub const DEFAULT_CONCURRENCY: usize = 4;
+    
+    /// Most workers a job runs
+    pub const MAX_CONCURRENCY: usize = 16;
+    
+    /// Weight of jobs started without one
+    pub const DEFAULT_WEIGHT: u32 = 1;
+    
+    /// Limits from the crawler settings of a profile
+    pub fn from_settings(settings: &CrawlerSettings) -> Self {
+        Self {
+            max_pages: settings.max_pages,
+            max_depth: settings.max_depth,
+            concurrency: settings.concurrency.clamp(1, Self::MAX_CONCURRENCY),
+            weight: Self::DEFAULT_WEIGHT,
+            priority: settings.priority,
+        }
+    }
+}
+
+/// Binary asset captured for a job (favicon, og:image, page payload)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAsset {
+    pub job_id: String,
+    pub hash: String, // SHA-256 of the content, identical assets are stored once
+    pub url: String,
+    pub content_type: String,
+    pub fetched_at: DateTime<Utc>,
+    #[serde(skip)]
+    pub content: Vec<u8>,
 }
 
 /// Trait for raw data storage
@@ -33,12 +173,37 @@ pub trait RawStorageBackend: Send + Sync {
     /// Get a page result by URL
     async fn get_page_result(&self, job_id: &str, url: &str) -> Result<Option<TaskResult>>;
     
+    /// Get all page results for a job
+    async fn list_page_results(&self, job_id: &str) -> Result<Vec<TaskResult>>;
+    
     /// Store job status
     async fn store_job_status(&self, status: &JobStatus) -> Result<()>;
     
     /// Get job status
     async fn get_job_status(&self, job_id: &str) -> Result<JobStatus>;
     
+    /// Store a debug trace for a failed task
+    async fn store_task_trace(&self, trace: &TaskTrace) -> Result<()>;
+    
+    /// Get all debug traces for a job
+    async fn list_task_traces(&self, job_id: &str) -> Result<Vec<TaskTrace>>;
+    
+    /// Store a captured asset
+    async fn store_asset(&self, asset: &StoredAsset) -> Result<()>;
+    
+    /// Get a captured asset by content hash
+    async fn get_asset(&self, job_id: &str, hash: &str) -> Result<Option<StoredAsset>>;
+    
+    /// Store a page that failed validation, replacing an earlier copy of the URL
+    async fn store_quarantined_page(&self, page: &QuarantinedPage) -> Result<()>;
+    
+    /// Get all quarantined pages for a job
+    async fn list_quarantined_pages(&self, job_id: &str) -> Result<Vec<QuarantinedPage>>;
+    
+    /// Remove quarantined pages expiring before the cutoff (all of them
+    /// without one), returning how many were removed
+    async fn purge_quarantine(&self, job_id: &str, before: Option<DateTime<Utc>>) -> Result<usize>;
+    
     /// List all jobs
     async fn list_jobs(&self) -> Result<Vec<JobStatus>>;
     
@@ -53,14 +218,24 @@ impl RawStorage {
     /// Create a new RawStorage instance based on the settings
     pub async fn create(settings: &RawDataSettings) -> Result<Arc<dyn RawStorageBackend>> {
         match settings.storage_type.as_str() {
+            #[cfg(feature = "mongodb")]
             "mongodb" => {
                 let storage = MongoDBStorage::new(settings).await?;
                 Ok(Arc::new(storage))
             },
             "filesystem" => {
-                // For future implementation
-                anyhow::bail!("Filesystem storage is not yet implemented");
+                let storage = FilesystemRawStorage::new(settings)?;
+                Ok(Arc::new(storage))
             },
+            #[cfg(feature = "s3")]
+            "s3" => {
+                let storage = S3Storage::new(settings).await?;
+                Ok(Arc::new(storage))
+            },
+            #[cfg(not(feature = "mongodb"))]
+            "mongodb" => anyhow::bail!("MongoDB storage needs a build with the mongodb feature"),
+            #[cfg(not(feature = "s3"))]
+            "s3" => anyhow::bail!("S3 storage needs a build with the s3 feature"),
             _ => {
                 anyhow::bail!("Unsupported raw data storage type: {}", settings.storage_type);
             }
@@ -74,6 +249,7 @@ impl RawStorage {
 }
 
 /// MongoDB implementation of RawStorage
+#[cfg(feature = "mongodb")]
 pub struct MongoDBStorage {
     /// MongoDB client
     client: Client,
@@ -81,42 +257,97 @@ pub struct MongoDBStorage {
     /// MongoDB database
     database: Database,
     
+    /// Database for listings and exports, the primary database without a read replica
+    read_database: Database,
+    
     /// Collection prefix
     collection_prefix: String,
 }
 
+#[cfg(feature = "mongodb")]
 impl MongoDBStorage {
     /// Create a new MongoDB storage instance
     pub async fn new(settings: &RawDataSettings) -> Result<Self> {
-        // Parse connection options
-        let client_options = ClientOptions::parse(&settings.connection_string)
-            .await
-            .context(format!("Failed to parse MongoDB connection string: {}", settings.connection_string))?;
-        
-        // Create the client
-        let client = Client::with_options(client_options)
-            .context("Failed to create MongoDB client")?;
-        
-        // Get the database
+        let client = Self::connect(settings, &settings.connection_string).await?;
         let database = client.database(&settings.database_name);
         
-        // Test connection
-        database.list_collection_names(None)
-            .await
-            .context("Failed to connect to MongoDB")?;
+        let read_database = match &settings.read_connection_string {
+            Some(connection_string) => {
+                let read_client = Self::connect(settings, connection_string).await?;
+                debug!("Reading listings from MongoDB replica");
+                read_client.database(&settings.database_name)
+            }
+            None => database.clone(),
+        };
         
         debug!("Connected to MongoDB database: {}", settings.database_name);
         
         Ok(Self {
             client,
             database,
+            read_database,
             collection_prefix: settings.collection_prefix.clone(),
         })
     }
     
+    /// Create a client and check the database is reachable
+    async fn connect(settings: &RawDataSettings, connection_string: &str) -> Result<Client> {
+        // Parse connection options
+        let mut client_options = ClientOptions::parse(connection_string)
+            .await
+            .context(format!("Failed to parse MongoDB connection string: {}", connection_string))?;
+        if let Some(max_connections) = settings.pool.max_connections {
+            client_options.max_pool_size = Some(max_connections);
+        }
+        client_options.server_selection_timeout = Some(Duration::from_secs(settings.pool.acquire_timeout));
+        
+        // Create the client, it reconnects on its own once the server is back
+        let client = Client::with_options(client_options)
+            .context("Failed to create MongoDB client")?;
+        
+        // Test connection
+        let test_database = client.database(&settings.database_name);
+        let test_database = &test_database;
+        connect_with_retry(&settings.pool, "MongoDB", || async move {
+            test_database.list_collection_names(None)
+                .await
+                .context("Failed to connect to MongoDB")
+        }).await?;
+        
+        Ok(client)
+    }
+    
+    /// Get the same collection on the read database, for reads that can lag
+    /// behind the latest writes
+    fn for_reads(&self, collection: Collection<Document>) -> Collection<Document> {
+        self.read_database.collection(collection.name())
+    }
+    
+    /// Get a per-job collection, named safely whatever the job ID
+    fn job_collection(&self, job_id: &str, suffix: &str) -> Collection<Document> {
+        let name = NamingRules::mongodb(self.database.name())
+            .job_identifier(&self.collection_prefix, job_id, suffix);
+        self.database.collection(&name)
+    }
+    
     /// Get the collection for page results
     fn pages_collection(&self, job_id: &str) -> Collection<Document> {
-        self.database.collection(&format!("{}_{}_pages", self.collection_prefix, job_id))
+        self.job_collection(job_id, "pages")
+    }
+    
+    /// Get the collection for failed task traces
+    fn traces_collection(&self, job_id: &str) -> Collection<Document> {
+        self.job_collection(job_id, "traces")
+    }
+    
+    /// Get the collection for captured assets
+    fn assets_collection(&self, job_id: &str) -> Collection<Document> {
+        self.job_collection(job_id, "assets")
+    }
+    
+    /// Get the collection for quarantined pages
+    fn quarantine_collection(&self, job_id: &str) -> Collection<Document> {
+        self.job_collection(job_id, "quarantine")
     }
     
     /// Get the collection for job status
@@ -125,8 +356,10 @@ impl MongoDBStorage {
     }
 }
 
+#[cfg(feature = "mongodb")]
 #[async_trait]
 impl RawStorageBackend for MongoDBStorage {
+    #[instrument(skip_all, fields(backend = "mongodb", job_id = %result.job_id, url = %result.url))]
     async fn store_page_result(&self, result: &TaskResult) -> Result<()> {
         let collection = self.pages_collection(&result.job_id);
         
@@ -151,7 +384,7 @@ impl RawStorageBackend for MongoDBStorage {
     }
     
     async fn get_page_result(&self, job_id: &str, url: &str) -> Result<Option<TaskResult>> {
-        let collection = self.pages_collection(job_id);
+        let collection = self.for_reads(self.pages_collection(job_id));
         
         // Create filter
         let filter = doc! {
@@ -174,6 +407,25 @@ impl RawStorageBackend for MongoDBStorage {
         }
     }
     
+    async fn list_page_results(&self, job_id: &str) -> Result<Vec<TaskResult>> {
+        let collection = self.for_reads(self.pages_collection(job_id));
+        
+        let mut cursor = collection.find(None, None).await
+            .context("Failed to query MongoDB for page results")?;
+        
+        let mut results = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let doc = doc.context("Failed to get document from cursor")?;
+            let task_result: TaskResult = mongodb::bson::from_document(doc)
+                .context("Failed to convert BSON document to TaskResult")?;
+            
+            results.push(task_result);
+        }
+        
+        Ok(results)
+    }
+    
+    #[instrument(skip_all, fields(backend = "mongodb", job_id = %status.job_id))]
     async fn store_job_status(&self, status: &JobStatus) -> Result<()> {
         let collection = self.jobs_collection();
         
@@ -219,8 +471,153 @@ impl RawStorageBackend for MongoDBStorage {
         }
     }
     
+    async fn store_task_trace(&self, trace: &TaskTrace) -> Result<()> {
+        let collection = self.traces_collection(&trace.job_id);
+        
+        // Convert to BSON document
+        let doc = mongodb::bson::to_document(trace)
+            .context("Failed to convert TaskTrace to BSON document")?;
+        
+        // Keep every trace, a URL can fail more than once
+        collection.insert_one(doc, None)
+            .await
+            .context("Failed to store task trace in MongoDB")?;
+        
+        debug!("Stored failure trace for URL: {}", trace.url);
+        
+        Ok(())
+    }
+    
+    async fn list_task_traces(&self, job_id: &str) -> Result<Vec<TaskTrace>> {
+        let collection = self.for_reads(self.traces_collection(job_id));
+        
+        let mut cursor = collection.find(None, None).await
+            .context("Failed to query MongoDB for task traces")?;
+        
+        let mut traces = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let doc = doc.context("Failed to get document from cursor")?;
+            let trace: TaskTrace = mongodb::bson::from_document(doc)
+                .context("Failed to convert BSON document to TaskTrace")?;
+            
+            traces.push(trace);
+        }
+        
+        Ok(traces)
+    }
+    
+    async fn store_asset(&self, asset: &StoredAsset) -> Result<()> {
+        let collection = self.assets_collection(&asset.job_id);
+        
+        // Convert to BSON document, the content is stored as binary
+        let mut doc = mongodb::bson::to_document(asset)
+            .context("Failed to convert StoredAsset to BSON document")?;
+        doc.insert("content", Binary {
+            subtype: BinarySubtype::Generic,
+            bytes: asset.content.clone(),
+        });
+        
+        // Create filter for upsert
+        let filter = doc! {
+            "hash": &asset.hash,
+        };
+        
+        // Upsert the document
+        collection.replace_one(filter, doc, mongodb::options::ReplaceOptions::builder().upsert(true).build())
+            .await
+            .context("Failed to store asset in MongoDB")?;
+        
+        debug!("Stored asset: {}", asset.url);
+        
+        Ok(())
+    }
+    
+    async fn get_asset(&self, job_id: &str, hash: &str) -> Result<Option<StoredAsset>> {
+        let collection = self.for_reads(self.assets_collection(job_id));
+        
+        // Create filter
+        let filter = doc! {
+            "hash": hash,
+        };
+        
+        // Find the document
+        let result = collection.find_one(filter, None).await
+            .context("Failed to query MongoDB for asset")?;
+        
+        // Convert to StoredAsset if found
+        if let Some(doc) = result {
+            let content = doc.get_binary_generic("content")
+                .context("Asset document has no content")?
+                .clone();
+            let mut asset: StoredAsset = mongodb::bson::from_document(doc)
+                .context("Failed to convert BSON document to StoredAsset")?;
+            asset.content = content;
+            
+            Ok(Some(asset))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    async fn store_quarantined_page(&self, page: &QuarantinedPage) -> Result<()> {
+        let collection = self.quarantine_collection(&page.result.job_id);
+        
+        // Convert to BSON document
+        let doc = mongodb::bson::to_document(page)
+            .context("Failed to convert QuarantinedPage to BSON document")?;
+        
+        let filter = doc! {
+            "result.url": &page.result.url,
+        };
+        
+        collection.replace_one(filter, doc, mongodb::options::ReplaceOptions::builder().upsert(true).build())
+            .await
+            .context("Failed to store quarantined page in MongoDB")?;
+        
+        debug!("Quarantined page: {}", page.result.url);
+        
+        Ok(())
+    }
+    
+    async fn list_quarantined_pages(&self, job_id: &str) -> Result<Vec<QuarantinedPage>> {
+        let collection = self.for_reads(self.quarantine_collection(job_id));
+        
+        let mut cursor = collection.find(None, None).await
+            .context("Failed to query MongoDB for quarantined pages")?;
+        
+        let mut pages = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let doc = doc.context("Failed to get document from cursor")?;
+            let page: QuarantinedPage = mongodb::bson::from_document(doc)
+                .context("Failed to convert BSON document to QuarantinedPage")?;
+            
+            pages.push(page);
+        }
+        
+        Ok(pages)
+    }
+    
+    async fn purge_quarantine(&self, job_id: &str, before: Option<DateTime<Utc>>) -> Result<usize> {
+        let collection = self.quarantine_collection(job_id);
+        
+        // Timestamps are stored as strings, so compare them after loading
+        let mut purged = 0;
+        for page in self.list_quarantined_pages(job_id).await? {
+            if !page.purged_by(before) {
+                continue;
+            }
+            
+            collection.delete_one(doc! { "result.url": &page.result.url }, None)
+                .await
+                .context("Failed to delete quarantined page from MongoDB")?;
+            purged += 1;
+        }
+        
+        Ok(purged)
+    }
+    
     async fn list_jobs(&self) -> Result<Vec<JobStatus>> {
-        let collection = self.jobs_collection();
+        let collection = self.for_reads(self.jobs_collection());
         
         // Find all job documents
         let mut cursor = collection.find(None, None).await
@@ -258,8 +655,945 @@ impl RawStorageBackend for MongoDBStorage {
         pages_collection.drop(None).await
             .context("Failed to drop pages collection from MongoDB")?;
         
+        // Delete failure traces
+        let traces_collection = self.traces_collection(job_id);
+        traces_collection.drop(None).await
+            .context("Failed to drop traces collection from MongoDB")?;
+        
+        // Delete captured assets
+        let assets_collection = self.assets_collection(job_id);
+        assets_collection.drop(None).await
+            .context("Failed to drop assets collection from MongoDB")?;
+        
+        // Delete quarantined pages
+        let quarantine_collection = self.quarantine_collection(job_id);
+        quarantine_collection.drop(None).await
+            .context("Failed to drop quarantine collection from MongoDB")?;
+        
+        debug!("Deleted job and all its data: {}", job_id);
+        
+        Ok(())
+    }
+}
+
+/// Filesystem implementation of RawStorage
+///
+/// Layout: `<base>/<prefix>/<job_id>/status.json`, page results under
+/// `pages/<sha256(url)>.json`, failure traces under `traces/`, captured
+/// assets under `assets/<sha256(content)>` with a `.json` metadata file and
+/// quarantined pages under `quarantine/<sha256(url)>.json`.
+pub struct FilesystemRawStorage {
+    /// Root directory for all jobs
+    root_dir: PathBuf,
+}
+
+impl FilesystemRawStorage {
+    /// Create a new filesystem raw storage instance
+    pub fn new(settings: &RawDataSettings) -> Result<Self> {
+        let root_dir = PathBuf::from(&settings.connection_string).join(&settings.collection_prefix);
+        
+        fs::create_dir_all(&root_dir)
+            .context(format!("Failed to create raw storage directory: {}", root_dir.display()))?;
+        
+        debug!("Using filesystem raw storage at: {}", root_dir.display());
+        
+        Ok(Self { root_dir })
+    }
+    
+    /// Get the directory for a job
+    fn job_dir(&self, job_id: &str) -> PathBuf {
+        self.root_dir.join(NamingRules::FILESYSTEM.job_identifier("", job_id, ""))
+    }
+    
+    /// Hash a URL into a file name
+    fn url_file_name(url: &str) -> String {
+        format!("{:x}.json", Sha256::digest(url.as_bytes()))
+    }
+    
+    /// Write a value as JSON, replacing the file atomically
+    fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context(format!("Failed to create directory: {}", parent.display()))?;
+        }
+        
+        let temp_path = path.with_extension("json.tmp");
+        let contents = serde_json::to_vec(value)
+            .context("Failed to serialize raw data")?;
+        fs::write(&temp_path, contents)
+            .context(format!("Failed to write file: {}", temp_path.display()))?;
+        fs::rename(&temp_path, path)
+            .context(format!("Failed to replace file: {}", path.display()))?;
+        
+        Ok(())
+    }
+    
+    /// Read a JSON file if it exists
+    fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        
+        let contents = fs::read(path)
+            .context(format!("Failed to read file: {}", path.display()))?;
+        let value = serde_json::from_slice(&contents)
+            .context(format!("Failed to parse file: {}", path.display()))?;
+        
+        Ok(Some(value))
+    }
+    
+    /// Read every JSON file in a directory
+    fn read_json_dir<T: DeserializeOwned>(dir: &Path) -> Result<Vec<T>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut values = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "json") {
+                if let Some(value) = Self::read_json(&path)? {
+                    values.push(value);
+                }
+            }
+        }
+        
+        Ok(values)
+    }
+}
+
+#[async_trait]
+impl RawStorageBackend for FilesystemRawStorage {
+    #[instrument(skip_all, fields(backend = "filesystem", job_id = %result.job_id, url = %result.url))]
+    async fn store_page_result(&self, result: &TaskResult) -> Result<()> {
+        let path = self.job_dir(&result.job_id)
+            .join("pages")
+            .join(Self::url_file_name(&result.url));
+        
+        Self::write_json(&path, result)?;
+        
+        debug!("Stored page result for URL: {}", result.url);
+        
+        Ok(())
+    }
+    
+    async fn get_page_result(&self, job_id: &str, url: &str) -> Result<Option<TaskResult>> {
+        let path = self.job_dir(job_id)
+            .join("pages")
+            .join(Self::url_file_name(url));
+        
+        Self::read_json(&path)
+    }
+    
+    async fn list_page_results(&self, job_id: &str) -> Result<Vec<TaskResult>> {
+        Self::read_json_dir(&self.job_dir(job_id).join("pages"))
+    }
+    
+    #[instrument(skip_all, fields(backend = "filesystem", job_id = %status.job_id))]
+    async fn store_job_status(&self, status: &JobStatus) -> Result<()> {
+        let path = self.job_dir(&status.job_id).join("status.json");
+        
+        Self::write_json(&path, status)?;
+        
+        debug!("Stored status for job: {}", status.job_id);
+        
+        Ok(())
+    }
+    
+    async fn get_job_status(&self, job_id: &str) -> Result<JobStatus> {
+        let path = self.job_dir(job_id).join("status.json");
+        
+        match Self::read_json(&path)? {
+            Some(status) => Ok(status),
+            None => anyhow::bail!("Job not found: {}", job_id),
+        }
+    }
+    
+    async fn store_task_trace(&self, trace: &TaskTrace) -> Result<()> {
+        // Prefix with the capture time so repeated failures of a URL are all kept
+        let file_name = format!(
+            "{}-{}",
+            trace.captured_at.timestamp_millis(),
+            Self::url_file_name(&trace.url)
+        );
+        let path = self.job_dir(&trace.job_id).join("traces").join(file_name);
+        
+        Self::write_json(&path, trace)?;
+        
+        debug!("Stored failure trace for URL: {}", trace.url);
+        
+        Ok(())
+    }
+    
+    async fn list_task_traces(&self, job_id: &str) -> Result<Vec<TaskTrace>> {
+        let mut traces: Vec<TaskTrace> = Self::read_json_dir(&self.job_dir(job_id).join("traces"))?;
+        traces.sort_by(|a, b| a.captured_at.cmp(&b.captured_at));
+        
+        Ok(traces)
+    }
+    
+    async fn store_asset(&self, asset: &StoredAsset) -> Result<()> {
+        let assets_dir = self.job_dir(&asset.job_id).join("assets");
+        let content_path = assets_dir.join(&asset.hash);
+        
+        // Write the content before the metadata so a listed asset is always complete
+        fs::create_dir_all(&assets_dir)
+            .context(format!("Failed to create directory: {}", assets_dir.display()))?;
+        let temp_path = content_path.with_extension("tmp");
+        fs::write(&temp_path, &asset.content)
+            .context(format!("Failed to write file: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &content_path)
+            .context(format!("Failed to replace file: {}", content_path.display()))?;
+        
+        Self::write_json(&content_path.with_extension("json"), asset)?;
+        
+        debug!("Stored asset: {}", asset.url);
+        
+        Ok(())
+    }
+    
+    async fn get_asset(&self, job_id: &str, hash: &str) -> Result<Option<StoredAsset>> {
+        let content_path = self.job_dir(job_id).join("assets").join(hash);
+        
+        let mut asset: StoredAsset = match Self::read_json(&content_path.with_extension("json"))? {
+            Some(asset) => asset,
+            None => return Ok(None),
+        };
+        asset.content = fs::read(&content_path)
+            .context(format!("Failed to read file: {}", content_path.display()))?;
+        
+        Ok(Some(asset))
+    }
+    
+    async fn store_quarantined_page(&self, page: &QuarantinedPage) -> Result<()> {
+        let path = self.job_dir(&page.result.job_id)
+            .join("quarantine")
+            .join(Self::url_file_name(&page.result.url));
+        
+        Self::write_json(&path, page)?;
+        
+        debug!("Quarantined page: {}", page.result.url);
+        
+        Ok(())
+    }
+    
+    async fn list_quarantined_pages(&self, job_id: &str) -> Result<Vec<QuarantinedPage>> {
+        Self::read_json_dir(&self.job_dir(job_id).join("quarantine"))
+    }
+    
+    async fn purge_quarantine(&self, job_id: &str, before: Option<DateTime<Utc>>) -> Result<usize> {
+        let quarantine_dir = self.job_dir(job_id).join("quarantine");
+        
+        let mut purged = 0;
+        for page in self.list_quarantined_pages(job_id).await? {
+            if !page.purged_by(before) {
+                continue;
+            }
+            
+            let path = quarantine_dir.join(Self::url_file_name(&page.result.url));
+            fs::remove_file(&path)
+                .context(format!("Failed to delete file: {}", path.display()))?;
+            purged += 1;
+        }
+        
+        Ok(purged)
+    }
+    
+    async fn list_jobs(&self) -> Result<Vec<JobStatus>> {
+        let mut jobs = Vec::new();
+        
+        for entry in fs::read_dir(&self.root_dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if let Some(status) = Self::read_json(&path.join("status.json"))? {
+                    jobs.push(status);
+                }
+            }
+        }
+        
+        Ok(jobs)
+    }
+    
+    async fn delete_job(&self, job_id: &str) -> Result<()> {
+        let job_dir = self.job_dir(job_id);
+        
+        if job_dir.exists() {
+            fs::remove_dir_all(&job_dir)
+                .context(format!("Failed to delete job directory: {}", job_dir.display()))?;
+        }
+        
+        debug!("Deleted job and all its data: {}", job_id);
+        
+        Ok(())
+    }
+}
+
+/// Minimum part size accepted by S3 for multipart uploads
+#[cfg(feature = "s3")]
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// S3-compatible implementation of RawStorage
+///
+/// Uses the same layout as the filesystem backend, rooted at
+/// `<collection_prefix>/` inside the bucket.
+#[cfg(feature = "s3")]
+pub struct S3Storage {
+    /// S3 client
+    client: aws_sdk_s3::Client,
+    
+    /// Bucket holding the raw data
+    bucket: String,
+    
+    /// Key prefix for all jobs, empty or ending with a slash
+    prefix: String,
+    
+    /// Size in bytes from which multipart uploads are used
+    multipart_threshold: usize,
+    
+    /// Multipart part size in bytes
+    part_size: usize,
+}
+
+#[cfg(feature = "s3")]
+impl S3Storage {
+    /// Create a new S3 storage instance
+    pub async fn new(settings: &RawDataSettings) -> Result<Self> {
+        let s3: &S3Settings = settings.s3.as_ref()
+            .context("S3 raw storage requires an `s3` section in raw_data settings")?;
+        
+        if s3.part_size < MIN_PART_SIZE {
+            anyhow::bail!("S3 part_size must be at least {} bytes", MIN_PART_SIZE);
+        }
+        
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = &s3.region {
+            loader = loader.region(Region::new(region.clone()));
+        }
+        if let (Some(access_key_id), Some(secret_access_key)) = (&s3.access_key_id, &s3.secret_access_key) {
+            loader = loader.credentials_provider(Credentials::new(
+                access_key_id.clone(),
+                secret_access_key.clone(),
+                None,
+                None,
+                "smart-crawler",
+            ));
+        }
+        let shared_config = loader.load().await;
+        
+        let mut builder = aws_sdk_s3::config::Builder::from(&shared_config);
+        if let Some(endpoint) = &s3.endpoint {
+            // S3-compatible stores generally need path-style addressing
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+        let client = aws_sdk_s3::Client::from_conf(builder.build());
+        
+        // Test connection
+        client.head_bucket()
+            .bucket(&s3.bucket)
+            .send()
+            .await
+            .context(format!("Failed to access S3 bucket: {}", s3.bucket))?;
+        
+        debug!("Connected to S3 bucket: {}", s3.bucket);
+        
+        Ok(Self::with_client(client, s3, &settings.collection_prefix))
+    }
+    
+    /// Storage in the settings' bucket through a connected client
+    fn with_client(client: aws_sdk_s3::Client, s3: &S3Settings, collection_prefix: &str) -> Self {
+        // Without a prefix, jobs sit at the root of the bucket
+        let prefix = match collection_prefix.trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("{}/", prefix),
+        };
+        
+        Self {
+            client,
+            bucket: s3.bucket.clone(),
+            prefix,
+            multipart_threshold: s3.multipart_threshold,
+            part_size: s3.part_size,
+        }
+    }
+    
+    /// Get the key prefix for a job, named like the job directories of the filesystem backend
+    fn job_prefix(&self, job_id: &str) -> String {
+        format!("{}{}/", self.prefix, NamingRules::FILESYSTEM.job_identifier("", job_id, ""))
+    }
+    
+    /// Hash a URL into an object name
+    fn url_object_name(url: &str) -> String {
+        format!("{:x}.json", Sha256::digest(url.as_bytes()))
+    }
+    
+    /// Upload a value as JSON
+    async fn put_json<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let body = serde_json::to_vec(value)
+            .context("Failed to serialize raw data")?;
+        
+        self.put_object(key, body, "application/json").await
+    }
+    
+    /// Upload an object, switching to multipart uploads for large bodies
+    async fn put_object(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        if body.len() >= self.multipart_threshold {
+            return self.put_multipart(key, body, content_type).await;
+        }
+        
+        self.client.put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .context(format!("Failed to upload S3 object: {}", key))?;
+        
+        Ok(())
+    }
+    
+    /// Upload a large object in parts
+    async fn put_multipart(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        let upload = self.client.create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .send()
+            .await
+            .context(format!("Failed to start multipart upload: {}", key))?;
+        
+        let upload_id = upload.upload_id()
+            .context("Multipart upload response has no upload id")?
+            .to_string();
+        
+        let mut parts = Vec::new();
+        for (index, chunk) in body.chunks(self.part_size).enumerate() {
+            let part_number = (index + 1) as i32;
+            
+            let part = self.client.upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(chunk.to_vec()))
+                .send()
+                .await;
+            
+            match part {
+                Ok(part) => {
+                    parts.push(CompletedPart::builder()
+                        .set_e_tag(part.e_tag().map(|tag| tag.to_string()))
+                        .part_number(part_number)
+                        .build());
+                },
+                Err(e) => {
+                    // Don't leave orphaned parts behind, they are billed until aborted
+                    let _ = self.client.abort_multipart_upload()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .upload_id(&upload_id)
+                        .send()
+                        .await;
+                    return Err(e).context(format!("Failed to upload part {} of {}", part_number, key));
+                }
+            }
+        }
+        
+        self.client.complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send()
+            .await
+            .context(format!("Failed to complete multipart upload: {}", key))?;
+        
+        debug!("Uploaded {} bytes in parts to: {}", body.len(), key);
+        
+        Ok(())
+    }
+    
+    /// Download and parse a JSON object if it exists
+    async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let body = match self.get_object(key).await? {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+        
+        let value = serde_json::from_slice(&body)
+            .context(format!("Failed to parse S3 object: {}", key))?;
+        
+        Ok(Some(value))
+    }
+    
+    /// Download an object if it exists
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let output = match self.client.get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().map_or(false, |e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(e).context(format!("Failed to download S3 object: {}", key)),
+        };
+        
+        let body = output.body.collect()
+            .await
+            .context(format!("Failed to read S3 object: {}", key))?
+            .into_bytes();
+        
+        Ok(Some(body.to_vec()))
+    }
+    
+    /// List object keys (or common prefixes when a delimiter is given) under a prefix
+    async fn list_keys(&self, prefix: &str, delimiter: Option<&str>) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        
+        loop {
+            let output = self.client.list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_delimiter(delimiter.map(|d| d.to_string()))
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .context(format!("Failed to list S3 objects under: {}", prefix))?;
+            
+            if delimiter.is_some() {
+                keys.extend(output.common_prefixes().iter().filter_map(|p| p.prefix().map(|p| p.to_string())));
+            } else {
+                keys.extend(output.contents().iter().filter_map(|o| o.key().map(|k| k.to_string())));
+            }
+            
+            match output.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+        
+        Ok(keys)
+    }
+    
+    /// Download every JSON object under a prefix
+    async fn get_json_prefix<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<T>> {
+        let mut values = Vec::new();
+        
+        for key in self.list_keys(prefix, None).await? {
+            if let Some(value) = self.get_json(&key).await? {
+                values.push(value);
+            }
+        }
+        
+        Ok(values)
+    }
+}
+
+#[cfg(feature = "s3")]
+#[async_trait]
+impl RawStorageBackend for S3Storage {
+    #[instrument(skip_all, fields(backend = "s3", job_id = %result.job_id, url = %result.url))]
+    async fn store_page_result(&self, result: &TaskResult) -> Result<()> {
+        let key = format!("{}pages/{}", self.job_prefix(&result.job_id), Self::url_object_name(&result.url));
+        
+        self.put_json(&key, result).await?;
+        
+        debug!("Stored page result for URL: {}", result.url);
+        
+        Ok(())
+    }
+    
+    async fn get_page_result(&self, job_id: &str, url: &str) -> Result<Option<TaskResult>> {
+        let key = format!("{}pages/{}", self.job_prefix(job_id), Self::url_object_name(url));
+        
+        self.get_json(&key).await
+    }
+    
+    async fn list_page_results(&self, job_id: &str) -> Result<Vec<TaskResult>> {
+        self.get_json_prefix(&format!("{}pages/", self.job_prefix(job_id))).await
+    }
+    
+    #[instrument(skip_all, fields(backend = "s3", job_id = %status.job_id))]
+    async fn store_job_status(&self, status: &JobStatus) -> Result<()> {
+        let key = format!("{}status.json", self.job_prefix(&status.job_id));
+        
+        self.put_json(&key, status).await?;
+        
+        debug!("Stored status for job: {}", status.job_id);
+        
+        Ok(())
+    }
+    
+    async fn get_job_status(&self, job_id: &str) -> Result<JobStatus> {
+        let key = format!("{}status.json", self.job_prefix(job_id));
+        
+        match self.get_json(&key).await? {
+            Some(status) => Ok(status),
+            None => anyhow::bail!("Job not found: {}", job_id),
+        }
+    }
+    
+    async fn store_task_trace(&self, trace: &TaskTrace) -> Result<()> {
+        let key = format!(
+            "{}traces/{}-{}",
+            self.job_prefix(&trace.job_id),
+            trace.captured_at.timestamp_millis(),
+            Self::url_object_name(&trace.url)
+        );
+        
+        self.put_json(&key, trace).await?;
+        
+        debug!("Stored failure trace for URL: {}", trace.url);
+        
+        Ok(())
+    }
+    
+    async fn list_task_traces(&self, job_id: &str) -> Result<Vec<TaskTrace>> {
+        let mut traces: Vec<TaskTrace> = self.get_json_prefix(&format!("{}traces/", self.job_prefix(job_id))).await?;
+        traces.sort_by(|a, b| a.captured_at.cmp(&b.captured_at));
+        
+        Ok(traces)
+    }
+    
+    async fn store_asset(&self, asset: &StoredAsset) -> Result<()> {
+        let key = format!("{}assets/{}", self.job_prefix(&asset.job_id), asset.hash);
+        
+        // Upload the content before the metadata so a listed asset is always complete
+        self.put_object(&key, asset.content.clone(), &asset.content_type).await?;
+        self.put_json(&format!("{}.json", key), asset).await?;
+        
+        debug!("Stored asset: {}", asset.url);
+        
+        Ok(())
+    }
+    
+    async fn get_asset(&self, job_id: &str, hash: &str) -> Result<Option<StoredAsset>> {
+        let key = format!("{}assets/{}", self.job_prefix(job_id), hash);
+        
+        let mut asset: StoredAsset = match self.get_json(&format!("{}.json", key)).await? {
+            Some(asset) => asset,
+            None => return Ok(None),
+        };
+        asset.content = self.get_object(&key).await?
+            .context(format!("Asset content is missing: {}", key))?;
+        
+        Ok(Some(asset))
+    }
+    
+    async fn store_quarantined_page(&self, page: &QuarantinedPage) -> Result<()> {
+        let key = format!(
+            "{}quarantine/{}",
+            self.job_prefix(&page.result.job_id),
+            Self::url_object_name(&page.result.url)
+        );
+        
+        self.put_json(&key, page).await?;
+        
+        debug!("Quarantined page: {}", page.result.url);
+        
+        Ok(())
+    }
+    
+    async fn list_quarantined_pages(&self, job_id: &str) -> Result<Vec<QuarantinedPage>> {
+        self.get_json_prefix(&format!("{}quarantine/", self.job_prefix(job_id))).await
+    }
+    
+    async fn purge_quarantine(&self, job_id: &str, before: Option<DateTime<Utc>>) -> Result<usize> {
+        let mut purged = 0;
+        for page in self.list_quarantined_pages(job_id).await? {
+            if !page.purged_by(before) {
+                continue;
+            }
+            
+            let key = format!("{}quarantine/{}", self.job_prefix(job_id), Self::url_object_name(&page.result.url));
+            self.client.delete_object()
+                .bucket(&self.bucket)
+                .key(&key)
+                .send()
+                .await
+                .context(format!("Failed to delete S3 object: {}", key))?;
+            purged += 1;
+        }
+        
+        Ok(purged)
+    }
+    
+    async fn list_jobs(&self) -> Result<Vec<JobStatus>> {
+        let mut jobs = Vec::new();
+        
+        for job_prefix in self.list_keys(&self.prefix, Some("/")).await? {
+            if let Some(status) = self.get_json(&format!("{}status.json", job_prefix)).await? {
+                jobs.push(status);
+            }
+        }
+        
+        Ok(jobs)
+    }
+    
+    async fn delete_job(&self, job_id: &str) -> Result<()> {
+        let keys = self.list_keys(&self.job_prefix(job_id), None).await?;
+        
+        // DeleteObjects accepts at most 1000 keys per request
+        for batch in keys.chunks(1000) {
+            let objects = batch.iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<Result<Vec<_>, _>>()
+                .context("Failed to build S3 delete request")?;
+            
+            self.client.delete_objects()
+                .bucket(&self.bucket)
+                .delete(Delete::builder().set_objects(Some(objects)).build()
+                    .context("Failed to build S3 delete request")?)
+                .send()
+                .await
+                .context(format!("Failed to delete S3 objects for job: {}", job_id))?;
+        }
+        
         debug!("Deleted job and all its data: {}", job_id);
         
         Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn job_status(job_id: &str, started_at: DateTime<Utc>) -> JobStatus {
+        JobStatus {
+            job_id: job_id.to_string(),
+            seed_url: "https://example.com".to_string(),
+            state: "running".to_string(),
+            pages_crawled: 0,
+            pages_total: 1,
+            started_at,
+            updated_at: started_at,
+            errors: Vec::new(),
+            captcha_spend: 0.0,
+            limits: None,
+            stop_reason: None,
+            pages_per_minute: 0.0,
+            avg_page_ms: 0,
+            eta_seconds: None,
+            config_hash: None,
+            parent_job_id: None,
+            tags: Vec::new(),
+            seeds: vec![SeedProgress { url: "https://example.com".to_string(), pages_crawled: 0, pages_total: 1 }],
+            recrawl: None,
+        }
+    }
+    
+    #[test]
+    fn test_record_page() {
+        let start = Utc::now();
+        let mut status = job_status("job", start);
+        
+        status.record_page(Some(0), Duration::from_millis(500), 10, start + chrono::Duration::seconds(6));
+        assert_eq!(status.pages_crawled, 1);
+        assert_eq!(status.seeds[0].pages_crawled, 1);
+        assert!((status.pages_per_minute - 10.0).abs() < 1e-9);
+        assert_eq!(status.avg_page_ms, 500);
+        assert_eq!(status.eta_seconds, Some(60));
+        
+        // Pending pages past the page limit won't be crawled
+        status.limits = Some(JobLimits { max_pages: 3, max_depth: 2, concurrency: 1, weight: 1, priority: JobPriority::Normal });
+        status.record_page(None, Duration::from_millis(1500), 5, start + chrono::Duration::seconds(12));
+        assert!((status.pages_per_minute - 10.0).abs() < 1e-9);
+        assert_eq!(status.avg_page_ms, 1000);
+        assert_eq!(status.eta_seconds, Some(6));
+        
+        // A slower page lowers the rate gradually
+        status.record_page(None, Duration::from_millis(1000), 0, start + chrono::Duration::seconds(42));
+        assert!((status.pages_per_minute - 1.0 / 0.18).abs() < 1e-9);
+        assert_eq!(status.eta_seconds, Some(0));
+        assert_eq!(status.seeds[0].pages_crawled, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_filesystem_round_trip() {
+        let dir = std::env::temp_dir().join(format!("raw-{}", uuid::Uuid::new_v4()));
+        let settings = RawDataSettings {
+            storage_type: "filesystem".to_string(),
+            connection_string: dir.to_string_lossy().into_owned(),
+            database_name: String::new(),
+            collection_prefix: "crawler".to_string(),
+            read_connection_string: None,
+            s3: None,
+            pool: Default::default(),
+        };
+        let storage = FilesystemRawStorage::new(&settings).unwrap();
+        let job_id = "../job";
+        let now = Utc::now();
+        
+        let mut status = job_status(job_id, now);
+        storage.store_job_status(&status).await.unwrap();
+        status.state = "completed".to_string();
+        storage.store_job_status(&status).await.unwrap();
+        assert_eq!(storage.get_job_status(job_id).await.unwrap().state, "completed");
+        assert!(storage.get_job_status("other").await.is_err());
+        let jobs = storage.list_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_id, job_id);
+        
+        // Job IDs can't reach outside the storage directory
+        assert_eq!(storage.job_dir(job_id).parent(), Some(storage.root_dir.as_path()));
+        
+        let page = TaskResult {
+            job_id: job_id.to_string(),
+            raw_content: "<p>Blue kettle</p>".to_string(),
+            ..TaskResult::test_page("https://example.com/kettles/blue")
+        };
+        storage.store_page_result(&page).await.unwrap();
+        let stored = storage.get_page_result(job_id, &page.url).await.unwrap().unwrap();
+        assert_eq!(stored.raw_content, page.raw_content);
+        assert!(storage.get_page_result(job_id, "https://example.com/").await.unwrap().is_none());
+        assert_eq!(storage.list_page_results(job_id).await.unwrap().len(), 1);
+        
+        for seconds in [2, 1] {
+            let trace = TaskTrace {
+                job_id: job_id.to_string(),
+                url: page.url.clone(),
+                error: format!("failed after {}s", seconds),
+                screenshot: None,
+                html: None,
+                console_logs: Vec::new(),
+                request_headers: Default::default(),
+                proxy: None,
+                captured_at: now + chrono::Duration::seconds(seconds),
+            };
+            storage.store_task_trace(&trace).await.unwrap();
+        }
+        let errors: Vec<String> = storage.list_task_traces(job_id).await.unwrap().into_iter().map(|trace| trace.error).collect();
+        assert_eq!(errors, vec!["failed after 1s", "failed after 2s"]);
+        
+        let asset = StoredAsset {
+            job_id: job_id.to_string(),
+            hash: format!("{:x}", Sha256::digest(b"PNG")),
+            url: "https://example.com/kettle.png".to_string(),
+            content_type: "image/png".to_string(),
+            fetched_at: now,
+            content: b"PNG".to_vec(),
+        };
+        storage.store_asset(&asset).await.unwrap();
+        let stored = storage.get_asset(job_id, &asset.hash).await.unwrap().unwrap();
+        assert_eq!((stored.url, stored.content), (asset.url, asset.content));
+        assert!(storage.get_asset(job_id, "missing").await.unwrap().is_none());
+        
+        for (url, days) in [("https://example.com/a", -1), ("https://example.com/b", 1)] {
+            let quarantined = QuarantinedPage {
+                result: TaskResult { job_id: job_id.to_string(), ..TaskResult::test_page(url) },
+                missing_fields: vec!["price".to_string()],
+                quarantined_at: now,
+                expires_at: now + chrono::Duration::days(days),
+            };
+            storage.store_quarantined_page(&quarantined).await.unwrap();
+        }
+        assert_eq!(storage.purge_quarantine(job_id, Some(now)).await.unwrap(), 1);
+        let quarantined = storage.list_quarantined_pages(job_id).await.unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].result.url, "https://example.com/b");
+        
+        storage.delete_job(job_id).await.unwrap();
+        assert!(storage.list_jobs().await.unwrap().is_empty());
+        assert!(storage.list_page_results(job_id).await.unwrap().is_empty());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[cfg(feature = "s3")]
+    fn s3_settings(bucket: &str) -> S3Settings {
+        S3Settings {
+            bucket: bucket.to_string(),
+            region: Some("us-east-1".to_string()),
+            endpoint: None,
+            access_key_id: None,
+            secret_access_key: None,
+            multipart_threshold: MIN_PART_SIZE,
+            part_size: MIN_PART_SIZE,
+        }
+    }
+    
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_s3_keys() {
+        // Building a client doesn't connect, keys are computed without a bucket
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .build();
+        let storage = |prefix: &str| S3Storage::with_client(aws_sdk_s3::Client::from_conf(config.clone()), &s3_settings("bucket"), prefix);
+        
+        let uuid = "4f1c2b9e-0d7a-4c57-9a43-3f2a8e6b1c0d";
+        assert_eq!(storage("crawler").job_prefix(uuid), format!("crawler/{}/", uuid));
+        assert_eq!(storage("/crawler/raw/").job_prefix(uuid), format!("crawler/raw/{}/", uuid));
+        assert_eq!(storage("").job_prefix(uuid), format!("{}/", uuid));
+        assert_eq!(storage("/").prefix, "");
+        
+        // Job IDs don't add key segments
+        let prefix = storage("crawler").job_prefix("../other/job");
+        assert!(prefix.starts_with("crawler/"));
+        assert_eq!(prefix.matches('/').count(), 2);
+        assert!(!prefix.contains(".."));
+    }
+    
+    /// Runs against the S3-compatible store at CRAWLER_TEST_S3_ENDPOINT, or a
+    /// local MinIO, in the bucket CRAWLER_TEST_S3_BUCKET
+    #[cfg(feature = "s3")]
+    #[tokio::test]
+    #[ignore = "needs an S3-compatible store"]
+    async fn test_s3_round_trip() {
+        let env = |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let settings = RawDataSettings {
+            storage_type: "s3".to_string(),
+            connection_string: String::new(),
+            database_name: String::new(),
+            collection_prefix: format!("test-{}", uuid::Uuid::new_v4()),
+            read_connection_string: None,
+            s3: Some(S3Settings {
+                endpoint: Some(env("CRAWLER_TEST_S3_ENDPOINT", "http://127.0.0.1:9000")),
+                access_key_id: Some(env("CRAWLER_TEST_S3_ACCESS_KEY_ID", "minioadmin")),
+                secret_access_key: Some(env("CRAWLER_TEST_S3_SECRET_ACCESS_KEY", "minioadmin")),
+                ..s3_settings(&env("CRAWLER_TEST_S3_BUCKET", "crawler-test"))
+            }),
+            pool: Default::default(),
+        };
+        let storage = S3Storage::new(&settings).await.unwrap();
+        let job_id = "../job";
+        
+        storage.store_job_status(&job_status(job_id, Utc::now())).await.unwrap();
+        assert_eq!(storage.get_job_status(job_id).await.unwrap().job_id, job_id);
+        let jobs = storage.list_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        
+        let page = TaskResult {
+            job_id: job_id.to_string(),
+            raw_content: "<p>Blue kettle</p>".to_string(),
+            ..TaskResult::test_page("https://example.com/kettles/blue")
+        };
+        storage.store_page_result(&page).await.unwrap();
+        let stored = storage.get_page_result(job_id, &page.url).await.unwrap().unwrap();
+        assert_eq!(stored.raw_content, page.raw_content);
+        assert_eq!(storage.list_page_results(job_id).await.unwrap().len(), 1);
+        
+        // Larger than the multipart threshold, uploaded in two parts
+        let content = vec![7u8; MIN_PART_SIZE + 1];
+        let asset = StoredAsset {
+            job_id: job_id.to_string(),
+            hash: format!("{:x}", Sha256::digest(&content)),
+            url: "https://example.com/kettle.mp4".to_string(),
+            content_type: "video/mp4".to_string(),
+            fetched_at: Utc::now(),
+            content,
+        };
+        storage.store_asset(&asset).await.unwrap();
+        let stored = storage.get_asset(job_id, &asset.hash).await.unwrap().unwrap();
+        assert_eq!(stored.content, asset.content);
+        
+        let result = storage.delete_job(job_id).await;
+        let remaining = storage.list_keys(&storage.prefix, None).await.unwrap();
+        result.unwrap();
+        assert!(remaining.is_empty());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::cli::config::{PoolSettings, RetrySettings};
+
+/// Delay before a connection retry, doubled on each attempt up to the cap
+pub fn backoff(settings: &PoolSettings, attempt: u32) -> Duration {
+    let delay = settings.retry_backoff.saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)));
+    Duration::from_millis(delay.min(settings.max_backoff))
+}
+
+/// Delay before retrying a task that failed its `attempt`, counted from 0,
+/// or None when it has no attempts left. `random` from 0 to 1 sets the jitter.
+pub fn task_retry_delay(settings: &RetrySettings, attempt: u32, random: f64) -> Option<Duration> {
+    if attempt.saturating_add(1) >= settings.max_attempts {
+        return None;
+    }
+    
+    let delay = settings.base_delay
+        .saturating_mul(2u64.saturating_pow(attempt))
+        .min(settings.max_delay.max(settings.base_delay));
+    let jitter = settings.jitter.clamp(0.0, 1.0) * random.clamp(0.0, 1.0);
+    Some(Duration::from_millis((delay as f64 * (1.0 - jitter)) as u64))
+}
+
+/// Connect to a storage backend, retrying failed attempts with exponential backoff
+///
+/// Only the first connection is retried here. Once connected, the pools
+/// reconnect lazily when a connection is lost.
+pub async fn connect_with_retry<T, F, Fut>(settings: &PoolSettings, backend: &str, mut connect: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match connect().await {
+            Ok(connection) => return Ok(connection),
+            Err(e) if attempt < settings.connect_retries => {
+                attempt += 1;
+                let delay = backoff(settings, attempt);
+                warn!(
+                    "Failed to connect to {} ({:#}), retry {} of {} in {} ms",
+                    backend, e, attempt, settings.connect_retries, delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_backoff() {
+        let settings = PoolSettings {
+            retry_backoff: 500,
+            max_backoff: 3000,
+            ..PoolSettings::default()
+        };
+        
+        assert_eq!(backoff(&settings, 1), Duration::from_millis(500));
+        assert_eq!(backoff(&settings, 2), Duration::from_millis(1000));
+        assert_eq!(backoff(&settings, 3), Duration::from_millis(2000));
+        assert_eq!(backoff(&settings, 4), Duration::from_millis(3000));
+        assert_eq!(backoff(&settings, 100), Duration::from_millis(3000));
+    }
+    
+    #[test]
+    fn test_task_retry_delay() {
+        let settings = RetrySettings {
+            max_attempts: 4,
+            base_delay: 1000,
+            max_delay: 3000,
+            jitter: 0.5,
+        };
+        
+        assert_eq!(task_retry_delay(&settings, 0, 0.0), Some(Duration::from_millis(1000)));
+        assert_eq!(task_retry_delay(&settings, 1, 0.0), Some(Duration::from_millis(2000)));
+        assert_eq!(task_retry_delay(&settings, 2, 0.0), Some(Duration::from_millis(3000)));
+        assert_eq!(task_retry_delay(&settings, 3, 0.0), None);
+        
+        // Jitter only ever shortens the delay
+        assert_eq!(task_retry_delay(&settings, 1, 1.0), Some(Duration::from_millis(1000)));
+        assert_eq!(task_retry_delay(&settings, 1, 0.5), Some(Duration::from_millis(1500)));
+        
+        let no_retries = RetrySettings { max_attempts: 1, ..settings };
+        assert_eq!(task_retry_delay(&no_retries, 0, 0.0), None);
+    }
+    
+    #[tokio::test]
+    async fn test_connect_with_retry() {
+        let settings = PoolSettings {
+            connect_retries: 2,
+            retry_backoff: 1,
+            ..PoolSettings::default()
+        };
+        
+        let mut attempts = 0;
+        let connected = connect_with_retry(&settings, "test", || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt < 3 {
+                    anyhow::bail!("refused");
+                }
+                Ok(attempt)
+            }
+        }).await.unwrap();
+        assert_eq!(connected, 3);
+        
+        let mut attempts = 0;
+        let failed: Result<()> = connect_with_retry(&settings, "test", || {
+            attempts += 1;
+            async { anyhow::bail!("refused") }
+        }).await;
+        assert!(failed.is_err());
+        assert_eq!(attempts, 3);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,576 @@
+use anyhow::Result;
+#[cfg(feature = "redis")]
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+#[cfg(feature = "redis")]
+use redis::{Client, Script, aio::ConnectionManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(feature = "redis")]
+use crate::cli::config::QueueSettings;
+#[cfg(feature = "redis")]
+use crate::crawler::simhash;
+#[cfg(feature = "redis")]
+use crate::storage::retry::connect_with_retry;
+
+// Takes a token from the bucket of a domain and returns how long to wait for
+// it. The bucket may go negative, later requests wait for the tokens owed.
+#[cfg(feature = "redis")]
+const TAKE_TOKEN: &str = r"
+local now = tonumber(ARGV[2])
+local rate = tonumber(ARGV[3]) / 1000
+local burst = tonumber(ARGV[4])
+local tokens = tonumber(redis.call('HGET', KEYS[1], ARGV[1] .. ':tokens') or burst)
+local at = tonumber(redis.call('HGET', KEYS[1], ARGV[1] .. ':at') or now)
+tokens = math.min(burst, tokens + math.max(0, now - at) * rate) - 1
+redis.call('HSET', KEYS[1], ARGV[1] .. ':tokens', tostring(tokens), ARGV[1] .. ':at', now)
+redis.call('EXPIRE', KEYS[1], ARGV[5])
+if tokens >= 0 then
+    return 0
+end
+return math.ceil(-tokens / rate)
+";
+
+/// Requests to a domain across jobs, for schedulers deciding when to crawl it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DomainStats {
+    pub domain: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub avg_latency_ms: u64,
+    
+    /// Delay between requests the domain was last adapted to, in milliseconds
+    pub delay_ms: u64,
+    
+    pub last_request_at: Option<DateTime<Utc>>,
+    
+    /// When the domain last blocked a request and how, like "captcha"
+    pub last_block_at: Option<DateTime<Utc>>,
+    pub last_block_reason: Option<String>,
+}
+
+impl DomainStats {
+    /// Read the statistics of a domain from the fields they are stored as
+    pub fn from_fields(domain: &str, fields: &HashMap<String, String>) -> Self {
+        let number = |name: &str| fields.get(name).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+        let time = |name: &str| fields.get(name)
+            .and_then(|value| value.parse::<i64>().ok())
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single());
+        
+        let requests = number("requests");
+        let errors = number("errors");
+        Self {
+            domain: domain.to_string(),
+            requests,
+            errors,
+            error_rate: if requests > 0 { errors as f64 / requests as f64 } else { 0.0 },
+            avg_latency_ms: number("latency_ms").checked_div(requests).unwrap_or(0),
+            delay_ms: number("delay_ms"),
+            last_request_at: time("last_request_at"),
+            last_block_at: time("last_block_at"),
+            last_block_reason: fields.get("last_block_reason").cloned(),
+        }
+    }
+}
+
+/// Crawl decisions shared by all workers of a job: the URLs already seen,
+/// the pages crawled per domain, and the current delay and rate limit
+/// bucket of each domain
+#[async_trait]
+pub trait SchedulerState: Send + Sync {
+    /// Record a URL as seen, false when it already was
+    async fn mark_seen(&self, job_id: &str, url: &str) -> Result<bool>;
+    
+    /// Number of URLs seen
+    async fn seen_count(&self, job_id: &str) -> Result<usize>;
+    
+    /// Add a crawled page to the counter of a domain
+    async fn count_page(&self, job_id: &str, domain: &str) -> Result<()>;
+    
+    /// Pages crawled per domain, most crawled first
+    async fn domain_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>>;
+    
+    /// Delay between requests to a domain in milliseconds, None until it is adapted
+    async fn domain_delay(&self, job_id: &str, domain: &str) -> Result<Option<u64>>;
+    
+    /// Change the delay between requests to a domain
+    async fn set_domain_delay(&self, job_id: &str, domain: &str, delay_ms: u64) -> Result<()>;
+    
+    /// Take a request from the token bucket of a domain, refilled at
+    /// `rate` requests per second up to `burst`. Returns the milliseconds
+    /// to wait before sending it.
+    async fn take_token(&self, job_id: &str, domain: &str, rate: f64, burst: u32) -> Result<u64>;
+    
+    /// Add a request to the statistics of a domain, which are kept across jobs
+    async fn record_domain_request(&self, domain: &str, latency_ms: u64, success: bool, delay_ms: u64) -> Result<()>;
+    
+    /// Record that a domain blocked a request
+    async fn record_domain_block(&self, domain: &str, reason: &str) -> Result<()>;
+    
+    /// Statistics of every domain requested recently, most requested first
+    async fn domain_stats(&self) -> Result<Vec<DomainStats>>;
+    
+    /// Record a URL the scheduler didn't crawl and why, keeping the first
+    /// reason of a URL skipped more than once
+    async fn record_skip(&self, job_id: &str, url: &str, reason: &str) -> Result<()>;
+    
+    /// Number of URLs skipped for each reason, most common first
+    async fn skip_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>>;
+    
+    /// URLs skipped and their reasons, by URL
+    async fn skipped_urls(&self, job_id: &str) -> Result<Vec<(String, String)>>;
+    
+    /// URL of a page recorded with a SimHash fingerprint at most
+    /// `max_distance` bits from `fingerprint`, recording the page under `url`
+    /// when there is none
+    async fn near_duplicate(&self, job_id: &str, url: &str, fingerprint: u64, max_distance: u32) -> Result<Option<String>>;
+    
+    /// Forget everything about a job
+    async fn clear(&self, job_id: &str) -> Result<()>;
+}
+
+/// Scheduler state kept in Redis next to the queue, so it survives restarts
+#[cfg(feature = "redis")]
+pub struct RedisSchedulerState {
+    /// Multiplexed connection, cloned for each command
+    conn: ConnectionManager,
+    
+    /// Capacity and false positive rate of the bloom filter of seen URLs,
+    /// None keeps them in a set
+    bloom: Option<(u64, f64)>,
+    
+    /// Time to live of the state in seconds, renewed as it changes
+    ttl: u64,
+    
+    /// Time to live of domain statistics in seconds, renewed with each request
+    stats_ttl: u64,
+    
+    take_token: Script,
+}
+
+#[cfg(feature = "redis")]
+impl RedisSchedulerState {
+    /// Connect to the Redis server of the queue
+    pub async fn new(config: &QueueSettings) -> Result<Self> {
+        let filter = &config.seen_filter;
+        let bloom = match filter.filter_type.as_str() {
+            "set" => None,
+            "bloom" => {
+                if filter.capacity == 0 || !(filter.false_positive_rate > 0.0 && filter.false_positive_rate < 1.0) {
+                    anyhow::bail!("Bloom filter needs a positive capacity and a false positive rate between 0 and 1");
+                }
+                Some((filter.capacity, filter.false_positive_rate))
+            },
+            other => anyhow::bail!("Unsupported seen URL filter type: {}", other),
+        };
+        
+        let client = Client::open(config.redis_url.clone())
+            .context(format!("Failed to connect to Redis at {}", config.redis_url))?;
+        
+        let conn = connect_with_retry(&config.pool, "Redis", || {
+            let client = client.clone();
+            async move {
+                ConnectionManager::new(client).await
+                    .context("Failed to get Redis connection")
+            }
+        }).await?;
+        
+        Ok(Self {
+            conn,
+            bloom,
+            ttl: config.task_ttl,
+            stats_ttl: config.domain_stats_ttl,
+            take_token: Script::new(TAKE_TOKEN),
+        })
+    }
+    
+    /// Renew the time to live of a key
+    async fn expire(&self, key: &str) -> Result<()> {
+        redis::cmd("EXPIRE")
+            .arg(key)
+            .arg(self.ttl)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to set TTL on scheduler state")
+    }
+    
+    /// Index the statistics of a domain and renew their time to live
+    async fn touch_domain_stats(&self, domain: &str) -> Result<()> {
+        redis::cmd("EXPIRE")
+            .arg(format!("crawler:domain_stats:{}", domain))
+            .arg(self.stats_ttl)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to set TTL on domain statistics")?;
+        
+        redis::cmd("SADD")
+            .arg("crawler:domain_stats")
+            .arg(domain)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to index domain statistics")?;
+        
+        redis::cmd("EXPIRE")
+            .arg("crawler:domain_stats")
+            .arg(self.stats_ttl)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to set TTL on domain statistics")
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl SchedulerState for RedisSchedulerState {
+    async fn mark_seen(&self, job_id: &str, url: &str) -> Result<bool> {
+        if let Some((capacity, error_rate)) = self.bloom {
+            let bloom_key = format!("crawler:seen_bloom:{}", job_id);
+            
+            // Creates the filter with its sizing on the first URL
+            let added: Vec<i64> = redis::cmd("BF.INSERT")
+                .arg(&bloom_key)
+                .arg("CAPACITY")
+                .arg(capacity)
+                .arg("ERROR")
+                .arg(error_rate)
+                .arg("ITEMS")
+                .arg(url)
+                .query_async(&mut self.conn.clone())
+                .await
+                .context("Failed to add URL to bloom filter, is the RedisBloom module loaded?")?;
+            let added = added.first().is_some_and(|added| *added == 1);
+            
+            if added {
+                self.expire(&bloom_key).await?;
+            }
+            
+            return Ok(added);
+        }
+        
+        let seen_key = format!("crawler:seen:{}", job_id);
+        
+        let added: usize = redis::cmd("SADD")
+            .arg(&seen_key)
+            .arg(url)
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to add URL to seen set")?;
+        
+        if added > 0 {
+            self.expire(&seen_key).await?;
+        }
+        
+        Ok(added > 0)
+    }
+    
+    async fn seen_count(&self, job_id: &str) -> Result<usize> {
+        if self.bloom.is_some() {
+            return redis::cmd("BF.CARD")
+                .arg(format!("crawler:seen_bloom:{}", job_id))
+                .query_async(&mut self.conn.clone())
+                .await
+                .context("Failed to get bloom filter size");
+        }
+        
+        redis::cmd("SCARD")
+            .arg(format!("crawler:seen:{}", job_id))
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to get seen set size")
+    }
+    
+    async fn count_page(&self, job_id: &str, domain: &str) -> Result<()> {
+        let pages_key = format!("crawler:domain_pages:{}", job_id);
+        
+        redis::cmd("HINCRBY")
+            .arg(&pages_key)
+            .arg(domain)
+            .arg(1)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to count domain page")?;
+        
+        self.expire(&pages_key).await
+    }
+    
+    async fn domain_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>> {
+        let mut counts: Vec<(String, u64)> = redis::cmd("HGETALL")
+            .arg(format!("crawler:domain_pages:{}", job_id))
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to read domain page counts")?;
+        
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        
+        Ok(counts)
+    }
+    
+    async fn domain_delay(&self, job_id: &str, domain: &str) -> Result<Option<u64>> {
+        redis::cmd("HGET")
+            .arg(format!("crawler:domain_delays:{}", job_id))
+            .arg(domain)
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to read domain delay")
+    }
+    
+    async fn set_domain_delay(&self, job_id: &str, domain: &str, delay_ms: u64) -> Result<()> {
+        let delays_key = format!("crawler:domain_delays:{}", job_id);
+        
+        redis::cmd("HSET")
+            .arg(&delays_key)
+            .arg(domain)
+            .arg(delay_ms)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to store domain delay")?;
+        
+        self.expire(&delays_key).await
+    }
+    
+    async fn take_token(&self, job_id: &str, domain: &str, rate: f64, burst: u32) -> Result<u64> {
+        // The script runs atomically, so workers on other machines share the bucket
+        self.take_token
+            .key(format!("crawler:rate_limit:{}", job_id))
+            .arg(domain)
+            .arg(Utc::now().timestamp_millis())
+            .arg(rate)
+            .arg(burst.max(1))
+            .arg(self.ttl)
+            .invoke_async(&mut self.conn.clone())
+            .await
+            .context("Failed to take domain rate limit token")
+    }
+    
+    async fn record_domain_request(&self, domain: &str, latency_ms: u64, success: bool, delay_ms: u64) -> Result<()> {
+        let stats_key = format!("crawler:domain_stats:{}", domain);
+        
+        let counters = [("requests", 1), ("errors", u64::from(!success)), ("latency_ms", latency_ms)];
+        for (field, increment) in counters {
+            redis::cmd("HINCRBY")
+                .arg(&stats_key)
+                .arg(field)
+                .arg(increment)
+                .query_async::<_, ()>(&mut self.conn.clone())
+                .await
+                .context("Failed to record domain request")?;
+        }
+        
+        redis::cmd("HSET")
+            .arg(&stats_key)
+            .arg("delay_ms")
+            .arg(delay_ms)
+            .arg("last_request_at")
+            .arg(Utc::now().timestamp())
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to record domain request")?;
+        
+        self.touch_domain_stats(domain).await
+    }
+    
+    async fn record_domain_block(&self, domain: &str, reason: &str) -> Result<()> {
+        redis::cmd("HSET")
+            .arg(format!("crawler:domain_stats:{}", domain))
+            .arg("last_block_at")
+            .arg(Utc::now().timestamp())
+            .arg("last_block_reason")
+            .arg(reason)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to record domain block")?;
+        
+        self.touch_domain_stats(domain).await
+    }
+    
+    async fn domain_stats(&self) -> Result<Vec<DomainStats>> {
+        let domains: Vec<String> = redis::cmd("SMEMBERS")
+            .arg("crawler:domain_stats")
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to read domains with statistics")?;
+        
+        let mut stats = Vec::with_capacity(domains.len());
+        for domain in domains {
+            let fields: HashMap<String, String> = redis::cmd("HGETALL")
+                .arg(format!("crawler:domain_stats:{}", domain))
+                .query_async(&mut self.conn.clone())
+                .await
+                .context("Failed to read domain statistics")?;
+            
+            // Statistics expire on their own, drop the domain from the index with them
+            if fields.is_empty() {
+                redis::cmd("SREM")
+                    .arg("crawler:domain_stats")
+                    .arg(&domain)
+                    .query_async::<_, ()>(&mut self.conn.clone())
+                    .await
+                    .context("Failed to update domains with statistics")?;
+                continue;
+            }
+            
+            stats.push(DomainStats::from_fields(&domain, &fields));
+        }
+        
+        stats.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.domain.cmp(&b.domain)));
+        
+        Ok(stats)
+    }
+    
+    async fn record_skip(&self, job_id: &str, url: &str, reason: &str) -> Result<()> {
+        let skipped_key = format!("crawler:skipped:{}", job_id);
+        let counts_key = format!("crawler:skip_counts:{}", job_id);
+        
+        let added: bool = redis::cmd("HSETNX")
+            .arg(&skipped_key)
+            .arg(url)
+            .arg(reason)
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to record skipped URL")?;
+        if !added {
+            return Ok(());
+        }
+        
+        redis::cmd("HINCRBY")
+            .arg(&counts_key)
+            .arg(reason)
+            .arg(1)
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to count skipped URL")?;
+        
+        self.expire(&skipped_key).await?;
+        self.expire(&counts_key).await
+    }
+    
+    async fn skip_counts(&self, job_id: &str) -> Result<Vec<(String, u64)>> {
+        let mut counts: Vec<(String, u64)> = redis::cmd("HGETALL")
+            .arg(format!("crawler:skip_counts:{}", job_id))
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to read skip counts")?;
+        
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        
+        Ok(counts)
+    }
+    
+    async fn skipped_urls(&self, job_id: &str) -> Result<Vec<(String, String)>> {
+        let mut skipped: Vec<(String, String)> = redis::cmd("HGETALL")
+            .arg(format!("crawler:skipped:{}", job_id))
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to read skipped URLs")?;
+        
+        skipped.sort();
+        
+        Ok(skipped)
+    }
+    
+    async fn near_duplicate(&self, job_id: &str, url: &str, fingerprint: u64, max_distance: u32) -> Result<Option<String>> {
+        // Pages are kept in a set per band of their fingerprint, "<fingerprint> <url>"
+        let band_keys: Vec<String> = simhash::bands(fingerprint, max_distance).into_iter()
+            .map(|(index, bits)| format!("crawler:simhash:{}:{}:{:x}", job_id, index, bits))
+            .collect();
+        
+        for band_key in &band_keys {
+            let pages: Vec<String> = redis::cmd("SMEMBERS")
+                .arg(band_key)
+                .query_async(&mut self.conn.clone())
+                .await
+                .context("Failed to read page fingerprints")?;
+            
+            for page in pages {
+                let Some((other, other_url)) = page.split_once(' ') else {
+                    continue;
+                };
+                let Ok(other) = u64::from_str_radix(other, 16) else {
+                    continue;
+                };
+                if other_url != url && simhash::hamming_distance(fingerprint, other) <= max_distance {
+                    return Ok(Some(other_url.to_string()));
+                }
+            }
+        }
+        
+        let page = format!("{:016x} {}", fingerprint, url);
+        for band_key in &band_keys {
+            redis::cmd("SADD")
+                .arg(band_key)
+                .arg(&page)
+                .query_async::<_, ()>(&mut self.conn.clone())
+                .await
+                .context("Failed to record page fingerprint")?;
+            self.expire(band_key).await?;
+        }
+        
+        Ok(None)
+    }
+    
+    async fn clear(&self, job_id: &str) -> Result<()> {
+        let fingerprint_keys: Vec<String> = redis::cmd("KEYS")
+            .arg(format!("crawler:simhash:{}:*", job_id))
+            .query_async(&mut self.conn.clone())
+            .await
+            .context("Failed to get page fingerprint keys")?;
+        
+        if !fingerprint_keys.is_empty() {
+            redis::cmd("DEL")
+                .arg(&fingerprint_keys)
+                .query_async::<_, ()>(&mut self.conn.clone())
+                .await
+                .context("Failed to delete page fingerprints")?;
+        }
+        
+        // The next request times of domains are set by the queue as it pops tasks
+        redis::cmd("DEL")
+            .arg(format!("crawler:seen:{}", job_id))
+            .arg(format!("crawler:seen_bloom:{}", job_id))
+            .arg(format!("crawler:domain_pages:{}", job_id))
+            .arg(format!("crawler:domain_delays:{}", job_id))
+            .arg(format!("crawler:domain_next:{}", job_id))
+            .arg(format!("crawler:rate_limit:{}", job_id))
+            .arg(format!("crawler:skipped:{}", job_id))
+            .arg(format!("crawler:skip_counts:{}", job_id))
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .context("Failed to delete scheduler state")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_domain_stats_from_fields() {
+        let fields: HashMap<String, String> = [
+            ("requests", "4"),
+            ("errors", "1"),
+            ("latency_ms", "2000"),
+            ("delay_ms", "1500"),
+            ("last_block_at", "1700000000"),
+            ("last_block_reason", "captcha"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        
+        let stats = DomainStats::from_fields("example.com", &fields);
+        assert_eq!(stats.requests, 4);
+        assert_eq!(stats.error_rate, 0.25);
+        assert_eq!(stats.avg_latency_ms, 500);
+        assert_eq!(stats.delay_ms, 1500);
+        assert_eq!(stats.last_request_at, None);
+        assert_eq!(stats.last_block_at.map(|at| at.timestamp()), Some(1_700_000_000));
+        assert_eq!(stats.last_block_reason.as_deref(), Some("captcha"));
+        
+        // A domain that only blocked has no requests to average
+        let stats = DomainStats::from_fields("example.org", &HashMap::new());
+        assert_eq!((stats.requests, stats.error_rate, stats.avg_latency_ms), (0, 0.0, 0));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,265 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use tracing::{debug, warn};
+use url::Url;
+use uuid::Uuid;
+
+use crate::crawler::task::TaskResult;
+use crate::storage::export::{Destination, ExportCompression, ExportTarget, ExportWriter};
+use crate::storage::processed::RowFilter;
+use crate::storage::raw::{JobStatus, RawStorageBackend};
+
+/// Writes records in the WARC 1.1 format
+pub struct WarcWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> WarcWriter<W> {
+    /// Create a new WARC writer
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+    
+    /// Write a single record, Content-Length is added from the block
+    pub fn write_record(&mut self, headers: &[(&str, String)], block: &[u8]) -> Result<()> {
+        write!(self.out, "WARC/1.1\r\n")?;
+        for (name, value) in headers {
+            write!(self.out, "{}: {}\r\n", name, value)?;
+        }
+        write!(self.out, "Content-Length: {}\r\n\r\n", block.len())?;
+        self.out.write_all(block)?;
+        write!(self.out, "\r\n\r\n")?;
+        
+        Ok(())
+    }
+    
+    /// Write the request and response records for a crawled page
+    pub fn write_page(&mut self, result: &TaskResult) -> Result<()> {
+        let url = Url::parse(&result.url)
+            .context(format!("Invalid page URL: {}", result.url))?;
+        let date = warc_date(&result.crawled_at);
+        let response_id = record_id();
+        
+        // The browser service returns the rendered document as UTF-8 text
+        let content_type = if result.content_type.contains("charset=") {
+            result.content_type.clone()
+        } else {
+            format!("{}; charset=utf-8", result.content_type)
+        };
+        let body = result.raw_content.as_bytes();
+        let reason = StatusCode::from_u16(result.status_code)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or("");
+        
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            result.status_code, reason, content_type, body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        
+        self.write_record(
+            &[
+                ("WARC-Type", "response".to_string()),
+                ("WARC-Record-ID", response_id.clone()),
+                ("WARC-Date", date.clone()),
+                ("WARC-Target-URI", result.url.clone()),
+                ("Content-Type", "application/http; msgtype=response".to_string()),
+            ],
+            &response,
+        )?;
+        
+        let mut target = url.path().to_string();
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(query);
+        }
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => String::new(),
+        };
+        let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", target, host);
+        
+        self.write_record(
+            &[
+                ("WARC-Type", "request".to_string()),
+                ("WARC-Record-ID", record_id()),
+                ("WARC-Date", date),
+                ("WARC-Target-URI", result.url.clone()),
+                ("WARC-Concurrent-To", response_id),
+                ("Content-Type", "application/http; msgtype=request".to_string()),
+            ],
+            request.as_bytes(),
+        )
+    }
+    
+    /// Get a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+    
+    /// Get the underlying writer back
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// New WARC record identifier
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", Uuid::new_v4())
+}
+
+/// Format a timestamp as a WARC-Date
+fn warc_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Export a job's raw page results matching `filter` as a WARC file
+///
+/// Pages are filtered on their URL and crawl time. Each page becomes a
+/// response record holding the rendered document and a request record
+/// describing the fetch, preceded by one warcinfo record for the job.
+/// Compressed files get one member per page, as WARC readers expect. The file
+/// is written next to the output and moved into place when complete, or
+/// streamed when the target is stdout. Returns the number of pages written.
+pub async fn export_warc(
+    raw_storage: &dyn RawStorageBackend,
+    job: &JobStatus,
+    target: &ExportTarget,
+    filter: &RowFilter,
+    compression: Option<ExportCompression>,
+) -> Result<usize> {
+    let mut results = raw_storage.list_page_results(&job.job_id).await?;
+    results.retain(|result| filter.matches(&result.url, result.crawled_at));
+    results.sort_by(|a, b| a.crawled_at.cmp(&b.crawled_at));
+    
+    let destination = Destination::create(target)?;
+    let mut warc = WarcWriter::new(BufWriter::new(ExportWriter::new(destination, compression)?));
+    
+    let mut info_headers = vec![
+        ("WARC-Type", "warcinfo".to_string()),
+        ("WARC-Record-ID", record_id()),
+        ("WARC-Date", warc_date(&Utc::now())),
+    ];
+    if !target.to_stdout() {
+        let file_name = target.output_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        info_headers.push(("WARC-Filename", file_name));
+    }
+    info_headers.push(("Content-Type", "application/warc-fields".to_string()));
+    let info = format!(
+        "software: smart-crawler/{}\r\nformat: WARC File Format 1.1\r\nconformsTo: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\nisPartOf: {}\r\ndescription: Crawl of {}\r\n",
+        env!("CARGO_PKG_VERSION"),
+        job.job_id,
+        job.seed_url
+    );
+    warc.write_record(&info_headers, info.as_bytes())
+        .context("Failed to write WARC record")?;
+    
+    let mut pages = 0;
+    for result in &results {
+        match warc.write_page(result) {
+            Ok(()) => pages += 1,
+            Err(e) => warn!("Skipping page in WARC export {}: {}", result.url, e),
+        }
+        
+        if compression.is_some() {
+            let out = warc.get_mut();
+            out.flush().context("Failed to flush export")?;
+            out.get_mut().end_member()?;
+        }
+    }
+    
+    warc.into_inner()
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to flush export")?
+        .finish()?
+        .publish(target)?;
+    
+    debug!("Exported {} pages to WARC file: {}", pages, target.output_path.display());
+    
+    Ok(pages)
+}
+
+/// Count the response records in a WARC file
+pub fn count_responses<R: BufRead>(mut reader: R) -> Result<usize> {
+    let mut responses = 0;
+    loop {
+        // Read the record header
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with("WARC/") {
+            anyhow::bail!("Malformed WARC record header: {}", line.trim());
+        }
+        
+        let mut content_length = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                anyhow::bail!("Truncated WARC record header");
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "warc-type" if value.trim() == "response" => responses += 1,
+                    "content-length" => content_length = value.trim().parse::<u64>().ok(),
+                    _ => {}
+                }
+            }
+        }
+        
+        // Skip the block, the trailing blank lines are consumed above
+        let content_length = content_length.context("WARC record has no Content-Length")?;
+        let skipped = io::copy(&mut (&mut reader).take(content_length), &mut io::sink())?;
+        if skipped != content_length {
+            anyhow::bail!("Truncated WARC record block");
+        }
+    }
+    
+    Ok(responses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::BufReader;
+    
+    #[test]
+    fn test_warc_records() {
+        let result = TaskResult {
+            title: "Page".to_string(),
+            // Looks like a record header but sits inside a block
+            raw_content: "<html>\r\nWARC-Type: response\r\n</html>".to_string(),
+            ..TaskResult::test_page("https://example.com:8443/page?id=1")
+        };
+        
+        let mut warc = WarcWriter::new(Vec::new());
+        warc.write_page(&result).unwrap();
+        warc.write_page(&result).unwrap();
+        let output = String::from_utf8(warc.into_inner()).unwrap();
+        
+        assert!(output.starts_with("WARC/1.1\r\nWARC-Type: response\r\n"));
+        assert!(output.contains("HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n"));
+        assert!(output.contains("GET /page?id=1 HTTP/1.1\r\nHost: example.com:8443\r\n"));
+        
+        let path = std::env::temp_dir().join(format!("export-{}.warc", std::process::id()));
+        fs::write(&path, output).unwrap();
+        assert_eq!(count_responses(BufReader::new(File::open(&path).unwrap())).unwrap(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+}
\ No newline at end of file
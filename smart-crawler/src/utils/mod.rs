@@ -1,6 +1,9 @@
 pub mod logging;
 pub mod metrics;
+pub mod shutdown;
+pub mod telemetry;
 
 // Re-export common functions and types
 pub use logging::{init_logging, default_log_file};
-pub use metrics::{MetricsCollector, Metrics, RequestTimer};
\ No newline at end of file
+pub use metrics::{MetricsCollector, Metrics, RequestTimer};
+pub use telemetry::{init_tracing, trace_headers};
\ No newline at end of file
@@ -0,0 +1,107 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Shutdown state of the process, shared by the servers and workers
+struct Shutdown {
+    /// Set once SIGINT or SIGTERM is received
+    requested: watch::Sender<bool>,
+    
+    /// Number of workers still running
+    workers: watch::Sender<usize>,
+}
+
+static SHUTDOWN: OnceLock<Shutdown> = OnceLock::new();
+
+/// Shutdown state, installing the signal handler on first use
+///
+/// Commands that never ask about shutdown keep the default signal handling
+/// and still stop at once on Ctrl+C.
+fn state() -> &'static Shutdown {
+    SHUTDOWN.get_or_init(|| {
+        tokio::spawn(async {
+            wait_for_signal().await;
+            info!("Shutdown requested, finishing in-flight tasks");
+            if let Some(state) = SHUTDOWN.get() {
+                state.requested.send_replace(true);
+            }
+            
+            // A second signal doesn't wait for the workers
+            wait_for_signal().await;
+            warn!("Shutdown forced, in-flight tasks return to the queue when their leases expire");
+            std::process::exit(130);
+        });
+        
+        Shutdown {
+            requested: watch::channel(false).0,
+            workers: watch::channel(0).0,
+        }
+    })
+}
+
+/// Wait for SIGINT, or SIGTERM on Unix as sent by Kubernetes
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    result = tokio::signal::ctrl_c() => {
+                        if let Err(e) = result {
+                            error!("Failed to wait for shutdown signal: {}", e);
+                        }
+                    },
+                    _ = terminate.recv() => {},
+                }
+                return;
+            },
+            Err(e) => error!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Failed to wait for shutdown signal: {}", e);
+    }
+}
+
+/// Whether a shutdown signal was received
+pub fn is_requested() -> bool {
+    *state().requested.borrow()
+}
+
+/// Wait until a shutdown signal is received
+pub async fn requested() {
+    let mut requested = state().requested.subscribe();
+    // The sender lives in a static, so this only returns once requested
+    let _ = requested.wait_for(|requested| *requested).await;
+}
+
+/// Wait until a shutdown signal is received and `grace` has passed since
+pub async fn grace_over(grace: Duration) {
+    requested().await;
+    tokio::time::sleep(grace).await;
+}
+
+/// Count a worker as running until the returned guard is dropped
+pub fn worker_running() -> WorkerGuard {
+    state().workers.send_modify(|workers| *workers += 1);
+    WorkerGuard
+}
+
+/// Wait until every running worker has stopped
+pub async fn wait_for_workers() {
+    let mut workers = state().workers.subscribe();
+    let _ = workers.wait_for(|workers| *workers == 0).await;
+}
+
+/// Keeps a worker counted as running, see `worker_running`
+pub struct WorkerGuard;
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        state().workers.send_modify(|workers| *workers -= 1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+#[cfg(feature = "otel")]
+use anyhow::Context;
+#[cfg(feature = "otel")]
+use opentelemetry::propagation::Injector;
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Shuts down the span exporter when dropped, sending the spans still buffered
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    exporting: bool,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if self.exporting {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Initialize logging on stderr, so command output can be piped
+///
+/// With the otel feature, spans of the crawler are also exported over OTLP
+/// when `OTEL_EXPORTER_OTLP_ENDPOINT` is set. The exporter reads the other
+/// standard `OTEL_*` variables, the service name defaults to smart-crawler.
+pub fn init_tracing() -> Result<TelemetryGuard> {
+    let registry = tracing_subscriber::registry()
+        .with(fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(EnvFilter::from_default_env()));
+    
+    #[cfg(feature = "otel")]
+    if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some() {
+        use opentelemetry::KeyValue;
+        use opentelemetry_sdk::{propagation::TraceContextPropagator, trace, Resource};
+        use tracing_subscriber::filter::Targets;
+        
+        let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "smart-crawler".to_string());
+        let resource = Resource::default().merge(&Resource::new(vec![KeyValue::new("service.name", service_name)]));
+        
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+            .with_trace_config(trace::config().with_resource(resource))
+            .install_batch(opentelemetry_sdk::runtime::Tokio)
+            .context("Failed to start the OTLP span exporter")?;
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        
+        // Only the crawler's own spans, whatever the log level
+        registry
+            .with(tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(Targets::new().with_target("smart_crawler", tracing::Level::INFO)))
+            .init();
+        
+        return Ok(TelemetryGuard { exporting: true });
+    }
+    
+    registry.init();
+    
+    Ok(TelemetryGuard {
+        #[cfg(feature = "otel")]
+        exporting: false,
+    })
+}
+
+/// Trace context headers of the current span, so services called over HTTP
+/// can join the trace. Empty when spans aren't exported.
+pub fn trace_headers() -> Vec<(String, String)> {
+    #[allow(unused_mut)]
+    let mut headers = Vec::new();
+    
+    #[cfg(feature = "otel")]
+    {
+        struct HeaderInjector<'a>(&'a mut Vec<(String, String)>);
+        
+        impl Injector for HeaderInjector<'_> {
+            fn set(&mut self, key: &str, value: String) {
+                self.0.push((key.to_string(), value));
+            }
+        }
+        
+        let context = tracing::Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut HeaderInjector(&mut headers));
+        });
+    }
+    
+    headers
+}
\ No newline at end of file